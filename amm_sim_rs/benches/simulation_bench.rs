@@ -35,7 +35,7 @@ fn benchmark_price_process(c: &mut Criterion) {
 }
 
 fn benchmark_trade_info_encoding(c: &mut Criterion) {
-    use amm_sim_rs::types::trade_info::TradeInfo;
+    use amm_sim_rs::types::trade_info::{TradeInfo, TRADE_CALLDATA_LEN};
     use amm_sim_rs::types::wad::Wad;
 
     let trade = TradeInfo::new(
@@ -43,16 +43,17 @@ fn benchmark_trade_info_encoding(c: &mut Criterion) {
         Wad::from_f64(1.5),
         Wad::from_f64(1.5),
         100,
+        0,
         Wad::from_f64(1001.5),
         Wad::from_f64(998.5),
     );
 
-    let mut buffer = [0u8; 196];
+    let mut buffer = [0u8; TRADE_CALLDATA_LEN];
 
     c.bench_function("trade_info_encode", |bench| {
         bench.iter(|| {
             trade.encode_calldata(&mut buffer);
-            black_box(&buffer)
+            black_box(buffer)
         })
     });
 }
@@ -60,10 +61,10 @@ fn benchmark_trade_info_encoding(c: &mut Criterion) {
 fn benchmark_retail_trader(c: &mut Criterion) {
     use amm_sim_rs::market::RetailTrader;
 
-    let mut trader = RetailTrader::new(5.0, 2.0, 0.5, Some(42));
+    let mut trader = RetailTrader::new(5.0, 2.0, 0.5, 0.5, 0.0, Some(42));
 
     c.bench_function("retail_generate_orders", |bench| {
-        bench.iter(|| black_box(trader.generate_orders()))
+        bench.iter(|| black_box(trader.generate_orders(0)))
     });
 }
 
@@ -4,12 +4,23 @@
 //! into separate buckets rather than being reinvested into liquidity.
 //! This means fees count toward PnL but don't inflate the k constant.
 
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::amm::native::{NativeFees, NativeStrategy};
+use crate::evm::strategy::StrategyState;
 use crate::evm::EVMStrategy;
+use crate::types::result::GasBreakdown;
 use crate::types::trade_info::TradeInfo;
-use crate::types::wad::Wad;
+use crate::types::wad::{Wad, MAX_FEE, WAD};
 
 /// Fee quote (bid and ask fees).
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct FeeQuote {
     pub bid_fee: Wad, // Fee when AMM buys X
     pub ask_fee: Wad, // Fee when AMM sells X
@@ -25,11 +36,142 @@ impl FeeQuote {
     }
 }
 
+/// Default reserve floor: small enough never to bind in normal operation,
+/// large enough to keep `k / reserve` finite.
+pub const DEFAULT_MIN_RESERVE: f64 = 1e-9;
+
+/// Default relative tolerance of the debug-build check that trades leave
+/// `k` unchanged; well above f64 rounding, far below any real math error.
+pub const DEFAULT_INVARIANT_TOLERANCE: f64 = 1e-9;
+
+/// Quote token of a pool unless set otherwise; it is the router's
+/// numeraire at rate 1.
+pub const DEFAULT_QUOTE_TOKEN: &str = "Y";
+
+/// Which side of a trade the fee is charged on.
+///
+/// - `OnInput`: the fee is skimmed from the token the trader pays in
+///   (Uniswap-style, the default).
+/// - `OnOutput`: the full input reaches the curve and the fee is skimmed
+///   from the token the trader receives.
+///
+/// Either way the fee goes to the separate fee bucket of that token.
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FeeModel {
+    #[default]
+    OnInput,
+    OnOutput,
+}
+
+/// How often the strategy is asked for new fees.
+///
+/// - `PerTrade`: after every trade (the default).
+/// - `PerStep`: once per step, with the step's last trade, when the
+///   engine calls `CFMM::flush_fee_update`; fees hold within a step.
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FeeUpdateFrequency {
+    #[default]
+    PerTrade,
+    PerStep,
+}
+
+/// How quotes round the amounts they return.
+///
+/// - `Native`: amounts as the math produces them (f64 round-to-nearest;
+///   Wad math truncates every division).
+/// - `AgainstTrader`: rounded at WAD granularity in the pool's favor, as
+///   on-chain pools do: what the trader receives rounds down, what it
+///   pays and the fee round up. Exact under Wad math; under f64 math the
+///   amounts are quantized after the fact, which only reaches below the
+///   curve's own f64 error for small amounts.
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RoundingMode {
+    #[default]
+    Native,
+    AgainstTrader,
+}
+
+/// Which side of the pool a depth curve walks, from the trader's view.
+///
+/// - `Buy`: the trader buys X (the AMM sells it).
+/// - `Sell`: the trader sells X (the AMM buys it).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthSide {
+    Buy,
+    Sell,
+}
+
 /// Result of a trade execution.
 #[derive(Debug, Clone)]
 pub struct TradeResult {
     pub trade_info: TradeInfo,
+    /// Fee charged, in the input token (fee-on-input) or output token (fee-on-output)
+    pub fee_amount: f64,
+    /// Realized price of the fill: Y paid or received by the trader per X
+    pub price: f64,
+    /// Reserves (x, y) before the trade
+    pub pre_reserves: (f64, f64),
+    /// Reserves (x, y) after the trade
+    pub post_reserves: (f64, f64),
+}
+
+/// Projected outcome of a trade, computed without mutating the pool.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TradePreview {
+    /// True if AMM buys X (trader sells X)
+    pub is_buy: bool,
+    /// Amount of X traded
+    pub amount_x: f64,
+    /// Amount of Y paid (AMM sells X) or received (AMM buys X) by the trader
+    pub amount_y: f64,
+    /// Fee charged
     pub fee_amount: f64,
+    /// Whether the fee is denominated in X (otherwise Y)
+    pub fee_in_x: bool,
+    /// Reserve of X after the trade
+    pub reserve_x: f64,
+    /// Reserve of Y after the trade
+    pub reserve_y: f64,
+    /// Spot price (Y per X) before the trade
+    pub spot_price_before: f64,
+    /// Spot price (Y per X) after the trade
+    pub spot_price_after: f64,
+}
+
+impl TradePreview {
+    /// Relative change in spot price caused by the trade.
+    pub fn price_impact(&self) -> f64 {
+        if self.spot_price_before == 0.0 {
+            return 0.0;
+        }
+        self.spot_price_after / self.spot_price_before - 1.0
+    }
+}
+
+/// Evolving state of a pool and its strategy, for engine checkpoints.
+///
+/// Settings (fee model, tiers, caps, ...) are not included: a snapshot is
+/// restored onto a pool configured the same way.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolState {
+    reserve_x: f64,
+    reserve_y: f64,
+    current_fees: FeeQuote,
+    accumulated_fees_x: f64,
+    accumulated_fees_y: f64,
+    swap_failures: u64,
+    last_swap_error: Option<String>,
+    fee_violations: u64,
+    drained: bool,
+    oracle_price: Option<Wad>,
+    fee_history: VecDeque<(u64, Wad, Wad)>,
+    pending_fee_update: Option<(TradeInfo, bool)>,
+    strategy: Option<StrategyState>,
+    native: Option<NativeFees>,
+    exact_reserves: Option<(Wad, Wad)>,
 }
 
 /// Constant Function Market Maker with dynamic fees.
@@ -40,12 +182,17 @@ pub struct TradeResult {
 pub struct CFMM {
     /// Strategy name
     pub name: String,
-    /// EVM strategy for fee decisions
-    strategy: EVMStrategy,
+    /// EVM strategy for fee decisions (None = native rule or fixed fees)
+    strategy: Option<EVMStrategy>,
+    /// Built-in fee rule used when there is no EVM strategy
+    native: Option<NativeFees>,
     /// Current X reserves
     reserve_x: f64,
     /// Current Y reserves
     reserve_y: f64,
+    /// Reserves as exact Wad amounts when high-precision bookkeeping is
+    /// enabled; `reserve_x`/`reserve_y` then mirror them
+    exact_reserves: Option<(Wad, Wad)>,
     /// Current fee quote
     current_fees: FeeQuote,
     /// Whether initialized
@@ -54,31 +201,414 @@ pub struct CFMM {
     accumulated_fees_x: f64,
     /// Accumulated fees in Y (collected separately, not in reserves)
     accumulated_fees_y: f64,
+    /// Allowed fee tiers (sorted ascending); strategy fees snap to the nearest
+    fee_tiers: Option<Vec<Wad>>,
+    /// Lowest and highest fee a strategy may charge
+    fee_bounds: (Wad, Wad),
+    /// Number of afterSwap calls that failed (fees left unchanged)
+    swap_failures: u64,
+    /// Most recent afterSwap failure
+    last_swap_error: Option<String>,
+    /// Reject strategy fees outside `fee_bounds` instead of clamping them
+    strict_fees: bool,
+    /// Number of strategy fee quotes with a fee outside `fee_bounds`
+    fee_violations: u64,
+    /// Which token fees are charged in
+    fee_model: FeeModel,
+    /// Quote with integer Wad math instead of f64
+    wad_math: bool,
+    /// Direction quoted amounts are rounded in
+    rounding_mode: RoundingMode,
+    /// Trades that would leave either reserve below this are rejected
+    min_reserve: f64,
+    /// Cap on a single trade as a fraction of the reserve it is measured
+    /// against; larger trades are clamped (None = uncapped)
+    max_trade_fraction: Option<f64>,
+    /// Whether a trade was ever rejected by the reserve floor
+    drained: bool,
+    /// Oracle price passed to the strategy before each afterSwap
+    oracle_price: Option<Wad>,
+    /// Whether trades currently come from arbitrageurs (notify the
+    /// strategy via afterArbitrage instead of afterSwap)
+    arbitrage_flow: bool,
+    /// Most recent fee updates as (timestamp, bid_fee, ask_fee)
+    fee_history: VecDeque<(u64, Wad, Wad)>,
+    /// Maximum fee history length (0 = recording disabled)
+    fee_history_capacity: usize,
+    /// Relative change in `k` a trade may cause before the debug-build
+    /// invariant check panics
+    invariant_tolerance: f64,
+    /// When the strategy is asked for new fees
+    fee_update_frequency: FeeUpdateFrequency,
+    /// Last trade since the previous fee update and whether it was
+    /// arbitrage flow (`PerStep` only)
+    pending_fee_update: Option<(TradeInfo, bool)>,
+    /// Fees were set by `pin_fees`; the strategy is not consulted
+    fees_pinned: bool,
+    /// Token the Y side is denominated in
+    quote_token: String,
+    /// Value of one quote token in the router's common numeraire
+    quote_rate: f64,
+    /// Trades executed so far, shared with the other pools of a run (see
+    /// `set_trade_counter`); numbers each trade's `TradeInfo::sequence`
+    trade_counter: Arc<AtomicU64>,
 }
 
 impl CFMM {
     /// Create a new CFMM with the given strategy and reserves.
     pub fn new(strategy: EVMStrategy, reserve_x: f64, reserve_y: f64) -> Self {
         let name = strategy.name().to_string();
+        Self::with_strategy(name, Some(strategy), reserve_x, reserve_y, FeeQuote::symmetric(Wad::from_bps(30)))
+    }
+
+    /// Create a CFMM charging constant fees, without an EVM strategy.
+    ///
+    /// `initialize` only applies the fee rules (e.g. tiers) to `fee_quote`
+    /// and `update_fees` is a no-op, so no bytecode is needed.
+    pub fn fixed_fee(name: String, reserve_x: f64, reserve_y: f64, fee_quote: FeeQuote) -> Self {
+        Self::with_strategy(name, None, reserve_x, reserve_y, fee_quote)
+    }
+
+    /// Create a CFMM whose fees come from a built-in rule, without an EVM
+    /// strategy.
+    pub fn native(name: String, reserve_x: f64, reserve_y: f64, strategy: NativeStrategy) -> Self {
+        let mut amm = Self::with_strategy(name, None, reserve_x, reserve_y, FeeQuote::symmetric(Wad::from_bps(30)));
+        amm.native = Some(NativeFees::new(strategy));
+        amm
+    }
+
+    /// Built-in fee rule of a native pool.
+    pub fn native_strategy(&self) -> Option<NativeStrategy> {
+        self.native.as_ref().map(NativeFees::strategy)
+    }
+
+    fn with_strategy(
+        name: String,
+        strategy: Option<EVMStrategy>,
+        reserve_x: f64,
+        reserve_y: f64,
+        current_fees: FeeQuote,
+    ) -> Self {
         Self {
             name,
             strategy,
+            native: None,
             reserve_x,
             reserve_y,
-            current_fees: FeeQuote::symmetric(Wad::from_bps(30)),
+            exact_reserves: None,
+            current_fees,
             initialized: false,
             accumulated_fees_x: 0.0,
             accumulated_fees_y: 0.0,
+            fee_tiers: None,
+            fee_bounds: (Wad::zero(), Wad::new(MAX_FEE)),
+            swap_failures: 0,
+            last_swap_error: None,
+            strict_fees: false,
+            fee_violations: 0,
+            fee_model: FeeModel::OnInput,
+            wad_math: false,
+            rounding_mode: RoundingMode::Native,
+            min_reserve: DEFAULT_MIN_RESERVE,
+            max_trade_fraction: None,
+            drained: false,
+            oracle_price: None,
+            arbitrage_flow: false,
+            fee_history: VecDeque::new(),
+            fee_history_capacity: 0,
+            invariant_tolerance: DEFAULT_INVARIANT_TOLERANCE,
+            fee_update_frequency: FeeUpdateFrequency::PerTrade,
+            pending_fee_update: None,
+            fees_pinned: false,
+            quote_token: DEFAULT_QUOTE_TOKEN.to_string(),
+            quote_rate: 1.0,
+            trade_counter: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Denominate the Y side in `token`, worth `rate` units of the
+    /// router's numeraire each (`DEFAULT_QUOTE_TOKEN` at rate 1 by
+    /// default). Reserves, quotes and trades stay in the pool's own token;
+    /// only the router converts.
+    pub fn set_quote_token(&mut self, token: impl Into<String>, rate: f64) {
+        self.quote_token = token.into();
+        self.quote_rate = rate;
+    }
+
+    /// Number trades from `counter`, shared with the other pools of the
+    /// run so sequence numbers are global; a pool starts with its own.
+    pub fn set_trade_counter(&mut self, counter: Arc<AtomicU64>) {
+        self.trade_counter = counter;
+    }
+
+    /// Token the Y side is denominated in.
+    pub fn quote_token(&self) -> &str {
+        &self.quote_token
+    }
+
+    /// Value of one quote token in the router's numeraire.
+    pub fn quote_rate(&self) -> f64 {
+        self.quote_rate
+    }
+
+    /// Set the reserve floor below which trades are rejected.
+    pub fn set_min_reserve(&mut self, min_reserve: f64) {
+        self.min_reserve = min_reserve.max(0.0);
+    }
+
+    /// Get the reserve floor.
+    pub fn min_reserve(&self) -> f64 {
+        self.min_reserve
+    }
+
+    /// Cap every trade at `fraction` of the relevant reserve (None removes
+    /// the cap).
+    ///
+    /// The X traded by `execute_buy_x` and `execute_sell_x` is capped at
+    /// `fraction * reserve_x`, the Y paid into `execute_buy_x_with_y` at
+    /// `fraction * reserve_y`. Larger trades are filled only up to the cap.
+    pub fn set_max_trade_fraction(&mut self, fraction: Option<f64>) {
+        self.max_trade_fraction = fraction;
+    }
+
+    /// Get the per-trade cap as a fraction of reserves.
+    pub fn max_trade_fraction(&self) -> Option<f64> {
+        self.max_trade_fraction
+    }
+
+    /// The part of `amount_x` a single trade may move (see
+    /// `set_max_trade_fraction`).
+    pub fn capped_x(&self, amount_x: f64) -> f64 {
+        match self.max_trade_fraction {
+            Some(fraction) => amount_x.min(fraction * self.reserve_x),
+            None => amount_x,
+        }
+    }
+
+    /// The part of `amount_y` a single trade may pay in (see
+    /// `set_max_trade_fraction`).
+    pub fn capped_y(&self, amount_y: f64) -> f64 {
+        match self.max_trade_fraction {
+            Some(fraction) => amount_y.min(fraction * self.reserve_y),
+            None => amount_y,
+        }
+    }
+
+    /// Set how often the strategy is asked for new fees.
+    pub fn set_fee_update_frequency(&mut self, frequency: FeeUpdateFrequency) {
+        self.fee_update_frequency = frequency;
+    }
+
+    /// Get how often the strategy is asked for new fees.
+    pub fn fee_update_frequency(&self) -> FeeUpdateFrequency {
+        self.fee_update_frequency
+    }
+
+    /// Set the relative tolerance of the debug-build invariant check.
+    pub fn set_invariant_tolerance(&mut self, tolerance: f64) {
+        self.invariant_tolerance = tolerance.max(0.0);
+    }
+
+    /// Get the relative tolerance of the debug-build invariant check.
+    pub fn invariant_tolerance(&self) -> f64 {
+        self.invariant_tolerance
+    }
+
+    /// Set the oracle price fed to the strategy before each afterSwap
+    /// (None disables the oracle call).
+    pub fn set_oracle_price(&mut self, price: Option<f64>) {
+        self.oracle_price = price.map(Wad::from_f64);
+    }
+
+    /// Mark subsequent trades as arbitrage (true) or regular (false) flow.
+    pub fn set_arbitrage_flow(&mut self, arbitrage_flow: bool) {
+        self.arbitrage_flow = arbitrage_flow;
+    }
+
+    /// Record the fees after each update, keeping the most recent `capacity`
+    /// entries (0 disables recording and clears the history).
+    pub fn set_fee_history_capacity(&mut self, capacity: usize) {
+        self.fee_history_capacity = capacity;
+        if capacity == 0 {
+            self.fee_history = VecDeque::new();
+        } else {
+            while self.fee_history.len() > capacity {
+                self.fee_history.pop_front();
+            }
+        }
+    }
+
+    /// Recorded fee updates as (timestamp, bid_fee, ask_fee), oldest first.
+    pub fn fee_history(&self) -> &VecDeque<(u64, Wad, Wad)> {
+        &self.fee_history
+    }
+
+    /// Whether a trade was ever rejected for hitting the reserve floor.
+    pub fn drained(&self) -> bool {
+        self.drained
+    }
+
+    /// Route all quotes (and so all trades) through the integer Wad path.
+    ///
+    /// Slower, but the curve math is exact integer arithmetic and so
+    /// bit-reproducible across platforms and comparable to on-chain math.
+    pub fn set_wad_math(&mut self, wad_math: bool) {
+        self.wad_math = wad_math;
+    }
+
+    /// Whether quotes use integer Wad math.
+    pub fn wad_math(&self) -> bool {
+        self.wad_math
+    }
+
+    /// Keep the reserves as exact Wad amounts instead of f64.
+    ///
+    /// Each trade then moves the reserves by its Wad-converted amounts with
+    /// integer arithmetic, so offsetting trades cancel exactly and long runs
+    /// do not accumulate f64 rounding; quotes still work from the f64 mirror
+    /// unless Wad math is on. Enabling snaps the reserves to Wad precision.
+    pub fn set_exact_reserves(&mut self, exact_reserves: bool) {
+        self.exact_reserves = exact_reserves
+            .then(|| (Wad::from_f64(self.reserve_x), Wad::from_f64(self.reserve_y)));
+        self.sync_reserves();
+    }
+
+    /// Whether reserves are kept as exact Wad amounts.
+    pub fn exact_reserves(&self) -> bool {
+        self.exact_reserves.is_some()
+    }
+
+    /// Refresh the f64 reserves from the exact ones, if kept.
+    fn sync_reserves(&mut self) {
+        if let Some((x, y)) = self.exact_reserves {
+            self.reserve_x = x.to_f64();
+            self.reserve_y = y.to_f64();
         }
     }
 
+    /// Round quoted amounts natively or against the trader.
+    pub fn set_rounding_mode(&mut self, rounding_mode: RoundingMode) {
+        self.rounding_mode = rounding_mode;
+    }
+
+    /// Get the rounding mode.
+    pub fn rounding_mode(&self) -> RoundingMode {
+        self.rounding_mode
+    }
+
+    /// Charge fees on the input or the output side of each trade.
+    pub fn set_fee_model(&mut self, fee_model: FeeModel) {
+        self.fee_model = fee_model;
+    }
+
+    /// Get the fee model.
+    pub fn fee_model(&self) -> FeeModel {
+        self.fee_model
+    }
+
+    /// Restrict fees to a discrete set of tiers.
+    ///
+    /// Strategy fees are clamped to the valid range, then snapped to the
+    /// nearest tier in ratio terms (e.g. 17 bps with tiers {5, 30} snaps
+    /// to 30, since 30/17 < 17/5). An empty set disables snapping.
+    pub fn set_fee_tiers(&mut self, mut tiers: Vec<Wad>) {
+        tiers.sort();
+        self.fee_tiers = if tiers.is_empty() { None } else { Some(tiers) };
+    }
+
+    /// Restrict fees to `[min, max]` instead of `[0, MAX_FEE]`; a bound
+    /// left as None keeps its default. Bounds are themselves clamped to
+    /// `[-MAX_FEE, MAX_FEE]`, and also apply to tier-snapped fees.
+    ///
+    /// A negative `min` allows maker rebates: below zero the pool pays the
+    /// trader the fee instead, out of its accumulated fees (which go
+    /// negative once the rebates exceed what it has collected).
+    pub fn set_fee_bounds(&mut self, min: Option<Wad>, max: Option<Wad>) {
+        let (lowest, highest) = (Wad::new(-MAX_FEE), Wad::new(MAX_FEE));
+        self.fee_bounds = (
+            min.unwrap_or(Wad::zero()).clamp(lowest, highest),
+            max.unwrap_or(highest).clamp(lowest, highest),
+        );
+    }
+
+    /// Reject strategy fees outside the fee bounds instead of clamping
+    /// them: `initialize` fails, and a trade's fee update leaves the
+    /// current fees in place. Violations are counted either way.
+    pub fn set_strict_fees(&mut self, strict_fees: bool) {
+        self.strict_fees = strict_fees;
+    }
+
+    /// Whether out-of-bounds strategy fees are rejected.
+    pub fn strict_fees(&self) -> bool {
+        self.strict_fees
+    }
+
+    /// Number of strategy fee quotes so far with a fee outside the bounds.
+    pub fn fee_violations(&self) -> u64 {
+        self.fee_violations
+    }
+
+    /// Count a strategy fee quote with either fee outside the fee bounds;
+    /// returns whether it is to be rejected.
+    fn check_fee_bounds(&mut self, bid_fee: Wad, ask_fee: Wad) -> bool {
+        let (min, max) = self.fee_bounds;
+        let outside = |fee: Wad| fee < min || fee > max;
+        if !outside(bid_fee) && !outside(ask_fee) {
+            return false;
+        }
+        self.fee_violations += 1;
+        self.strict_fees
+    }
+
+    /// Clamp a fee into the pool's fee bounds.
+    #[inline]
+    fn bounded_fee(&self, fee: Wad) -> Wad {
+        let (min, max) = self.fee_bounds;
+        fee.clamp(min, max)
+    }
+
+    /// Apply the AMM's fee rules to a strategy-returned fee.
+    fn apply_fee_rules(&self, fee: Wad) -> Wad {
+        let fee = self.bounded_fee(fee);
+        match &self.fee_tiers {
+            Some(tiers) => self.bounded_fee(snap_to_tier(fee, tiers)),
+            None => fee,
+        }
+    }
+
+    /// Hold the fees at `fees` (after the fee rules) from now on: trades
+    /// no longer ask the strategy for updates. Pinning again replaces the
+    /// held fees; `reset` releases them.
+    pub fn pin_fees(&mut self, fees: FeeQuote) {
+        self.current_fees = FeeQuote::new(self.apply_fee_rules(fees.bid_fee), self.apply_fee_rules(fees.ask_fee));
+        self.fees_pinned = true;
+    }
+
     /// Initialize the AMM and get starting fees from strategy.
     pub fn initialize(&mut self) -> Result<(), crate::evm::strategy::EVMError> {
         let initial_x = Wad::from_f64(self.reserve_x);
         let initial_y = Wad::from_f64(self.reserve_y);
 
-        let (bid_fee, ask_fee) = self.strategy.after_initialize(initial_x, initial_y)?;
-        self.current_fees = FeeQuote::new(bid_fee.clamp_fee(), ask_fee.clamp_fee());
+        let (bid_fee, ask_fee) = match (&mut self.strategy, &mut self.native) {
+            (Some(strategy), _) => strategy.after_initialize(initial_x, initial_y)?,
+            (None, Some(native)) => {
+                let fees = native.after_initialize(self.reserve_x, self.reserve_y);
+                (fees.bid_fee, fees.ask_fee)
+            }
+            (None, None) => (self.current_fees.bid_fee, self.current_fees.ask_fee),
+        };
+        let from_strategy = self.strategy.is_some() || self.native.is_some();
+        if from_strategy && self.check_fee_bounds(bid_fee, ask_fee) {
+            let (min, max) = self.fee_bounds;
+            return Err(crate::evm::strategy::EVMError::InvalidReturnData(format!(
+                "initial fees ({}, {}) outside [{}, {}]",
+                bid_fee.to_f64(),
+                ask_fee.to_f64(),
+                min.to_f64(),
+                max.to_f64()
+            )));
+        }
+        self.current_fees = FeeQuote::new(self.apply_fee_rules(bid_fee), self.apply_fee_rules(ask_fee));
         self.initialized = true;
 
         Ok(())
@@ -102,216 +632,862 @@ impl CFMM {
         self.reserve_x * self.reserve_y
     }
 
+    /// Add (or, with negative deltas, withdraw) liquidity outside of a trade.
+    ///
+    /// No fees are charged and the strategy is not notified. Returns false
+    /// and leaves the pool untouched if either reserve would end at or
+    /// below `min_reserve` (or zero) or a delta is not finite.
+    pub fn add_liquidity(&mut self, delta_x: f64, delta_y: f64) -> bool {
+        let (x, y) = (self.reserve_x + delta_x, self.reserve_y + delta_y);
+        let floor = self.min_reserve.max(0.0);
+        if !(x.is_finite() && y.is_finite()) || x <= floor || y <= floor {
+            return false;
+        }
+        match &mut self.exact_reserves {
+            Some((exact_x, exact_y)) => {
+                *exact_x = *exact_x + Wad::from_f64(delta_x);
+                *exact_y = *exact_y + Wad::from_f64(delta_y);
+                self.sync_reserves();
+            }
+            None => {
+                self.reserve_x = x;
+                self.reserve_y = y;
+            }
+        }
+        true
+    }
+
+    /// Get the no-arbitrage band of fair prices `(lower, upper)`.
+    ///
+    /// Mirrors the arbitrageur's closed forms (γ = 1 - f; the band is the
+    /// same under either fee model):
+    /// - Selling X to the AMM is profitable iff p < γ_bid · spot
+    /// - Buying X from the AMM is profitable iff p > spot / γ_ask
+    ///
+    /// Inside the band no arbitrage trade is profitable.
+    pub fn no_arb_band(&self) -> (f64, f64) {
+        let spot = self.spot_price();
+        let gamma_bid = (1.0 - self.current_fees.bid_fee.to_f64()).max(0.0);
+        let gamma_ask = (1.0 - self.current_fees.ask_fee.to_f64()).max(0.0);
+
+        let lower = spot * gamma_bid;
+        let upper = if gamma_ask > 0.0 {
+            spot / gamma_ask
+        } else {
+            f64::INFINITY
+        };
+        (lower, upper)
+    }
+
     /// Get current fees.
     pub fn fees(&self) -> FeeQuote {
         self.current_fees
     }
 
+    /// Number of afterSwap calls that failed so far.
+    pub fn swap_failures(&self) -> u64 {
+        self.swap_failures
+    }
+
+    /// Most recent afterSwap failure message, if any.
+    pub fn last_swap_error(&self) -> Option<&str> {
+        self.last_swap_error.as_deref()
+    }
+
+    /// Gas used by the strategy, by call type and trade side.
+    pub fn strategy_gas(&self) -> GasBreakdown {
+        self.strategy
+            .as_ref()
+            .map(EVMStrategy::gas_breakdown)
+            .unwrap_or_default()
+    }
+
+    /// Latest signal the strategy returned alongside its fees (see
+    /// `StrategyOutput`), as a float; None without an EVM strategy or when
+    /// it returned only fees.
+    pub fn strategy_signal(&self) -> Option<f64> {
+        self.strategy.as_ref()?.signal().map(Wad::to_f64)
+    }
+
     /// Get accumulated fees (collected separately from reserves).
     pub fn accumulated_fees(&self) -> (f64, f64) {
         (self.accumulated_fees_x, self.accumulated_fees_y)
     }
 
+    /// Snapshot reserves, fees, counters and the strategy's storage.
+    pub fn save_state(&self) -> PoolState {
+        PoolState {
+            reserve_x: self.reserve_x,
+            reserve_y: self.reserve_y,
+            current_fees: self.current_fees,
+            accumulated_fees_x: self.accumulated_fees_x,
+            accumulated_fees_y: self.accumulated_fees_y,
+            swap_failures: self.swap_failures,
+            last_swap_error: self.last_swap_error.clone(),
+            fee_violations: self.fee_violations,
+            drained: self.drained,
+            oracle_price: self.oracle_price,
+            fee_history: self.fee_history.clone(),
+            pending_fee_update: self.pending_fee_update,
+            strategy: self.strategy.as_ref().map(EVMStrategy::save_state),
+            native: self.native.clone(),
+            exact_reserves: self.exact_reserves,
+        }
+    }
+
+    /// Restore a snapshot taken by `save_state` onto this initialized
+    /// pool, which must have the same kind of fee source (EVM strategy,
+    /// native rule or fixed fees).
+    pub fn load_state(&mut self, state: PoolState) -> Result<(), String> {
+        match (&mut self.strategy, state.strategy) {
+            (Some(strategy), Some(saved)) => strategy.load_state(saved).map_err(|e| e.to_string())?,
+            (None, None) => {}
+            _ => return Err(format!("{}: strategy presence does not match", self.name)),
+        }
+        if self.native_strategy() != state.native.as_ref().map(NativeFees::strategy) {
+            return Err(format!("{}: native strategy does not match", self.name));
+        }
+        if self.exact_reserves.is_some() != state.exact_reserves.is_some() {
+            return Err(format!("{}: exact reserve tracking does not match", self.name));
+        }
+        self.native = state.native;
+        self.reserve_x = state.reserve_x;
+        self.reserve_y = state.reserve_y;
+        self.exact_reserves = state.exact_reserves;
+        self.current_fees = state.current_fees;
+        self.accumulated_fees_x = state.accumulated_fees_x;
+        self.accumulated_fees_y = state.accumulated_fees_y;
+        self.swap_failures = state.swap_failures;
+        self.last_swap_error = state.last_swap_error;
+        self.fee_violations = state.fee_violations;
+        self.drained = state.drained;
+        self.oracle_price = state.oracle_price;
+        self.fee_history = state.fee_history;
+        self.pending_fee_update = state.pending_fee_update;
+        Ok(())
+    }
+
     /// Fast quote for AMM buying X (trader selling X).
     ///
-    /// Returns (y_out, fee_amount) or (0, 0) if invalid.
+    /// Returns (y_out, fee_amount) or (0, 0) if invalid, including when the
+    /// trade would take reserve_y below the floor. The fee is in X under
+    /// fee-on-input and in Y under fee-on-output.
     #[inline]
     pub fn quote_buy_x(&self, amount_x: f64) -> (f64, f64) {
-        if amount_x <= 0.0 {
+        let (quote, reserve_y_after) = self.unfloored_quote_buy_x(amount_x);
+        self.apply_floor(quote, reserve_y_after)
+    }
+
+    /// `quote_buy_x` before the reserve floor, with the Y reserve it
+    /// would leave.
+    #[inline]
+    fn unfloored_quote_buy_x(&self, amount_x: f64) -> ((f64, f64), f64) {
+        let quote = self.round_quote(self.curve_quote_buy_x(amount_x), false);
+        let drawn_y = match self.fee_model {
+            FeeModel::OnInput => quote.0,
+            FeeModel::OnOutput => quote.0 + quote.1,
+        };
+        (quote, self.reserve_y - drawn_y)
+    }
+
+    /// Fast quote for AMM selling X (trader buying X).
+    ///
+    /// Returns (total_y_in, fee_amount) or (0, 0) if invalid, including when
+    /// the trade would take reserve_x below the floor. The fee is in Y under
+    /// fee-on-input and in X under fee-on-output.
+    #[inline]
+    pub fn quote_sell_x(&self, amount_x: f64) -> (f64, f64) {
+        let (quote, reserve_x_after) = self.unfloored_quote_sell_x(amount_x);
+        self.apply_floor(quote, reserve_x_after)
+    }
+
+    /// `quote_sell_x` before the reserve floor, with the X reserve it
+    /// would leave.
+    #[inline]
+    fn unfloored_quote_sell_x(&self, amount_x: f64) -> ((f64, f64), f64) {
+        let quote = self.round_quote(self.curve_quote_sell_x(amount_x), true);
+        let drawn_x = match self.fee_model {
+            FeeModel::OnInput => amount_x,
+            FeeModel::OnOutput => amount_x + quote.1,
+        };
+        (quote, self.reserve_x - drawn_x)
+    }
+
+    /// Fast quote for Y input to X output.
+    ///
+    /// Returns (x_out, fee_amount) or (0, 0) if invalid, including when the
+    /// trade would take reserve_x below the floor. The fee is in Y under
+    /// fee-on-input and in X under fee-on-output.
+    #[inline]
+    pub fn quote_x_for_y(&self, amount_y: f64) -> (f64, f64) {
+        let (quote, reserve_x_after) = self.unfloored_quote_x_for_y(amount_y);
+        self.apply_floor(quote, reserve_x_after)
+    }
+
+    /// `quote_x_for_y` before the reserve floor, with the X reserve it
+    /// would leave.
+    #[inline]
+    fn unfloored_quote_x_for_y(&self, amount_y: f64) -> ((f64, f64), f64) {
+        let quote = self.round_quote(self.curve_quote_x_for_y(amount_y), false);
+        let drawn_x = match self.fee_model {
+            FeeModel::OnInput => quote.0,
+            FeeModel::OnOutput => quote.0 + quote.1,
+        };
+        (quote, self.reserve_x - drawn_x)
+    }
+
+    /// Round a quote's amount (paid by the trader when `trader_pays`,
+    /// received otherwise) and fee per the rounding mode.
+    #[inline]
+    fn round_quote(&self, (amount, fee): (f64, f64), trader_pays: bool) -> (f64, f64) {
+        match self.rounding_mode {
+            RoundingMode::Native => (amount, fee),
+            RoundingMode::AgainstTrader => {
+                let amount = if trader_pays { wad_ceil(amount) } else { wad_floor(amount) };
+                (amount, wad_ceil(fee))
+            }
+        }
+    }
+
+    /// Reject a valid quote that would leave a reserve below `min_reserve`.
+    #[inline]
+    fn apply_floor(&self, quote: (f64, f64), reserve_after: f64) -> (f64, f64) {
+        if self.below_floor(quote, reserve_after) {
+            return (0.0, 0.0);
+        }
+        quote
+    }
+
+    /// Whether the floor rejects a valid quote leaving `reserve_after`.
+    #[inline]
+    fn below_floor(&self, quote: (f64, f64), reserve_after: f64) -> bool {
+        quote.0 > 0.0 && reserve_after < self.min_reserve
+    }
+
+    /// Whether both reserves are positive and their product finite; an
+    /// empty pool quotes nothing rather than dividing by a zero invariant.
+    #[inline]
+    pub fn has_liquidity(&self) -> bool {
+        let k = self.reserve_x * self.reserve_y;
+        self.reserve_x > 0.0 && self.reserve_y > 0.0 && k.is_finite() && k > 0.0
+    }
+
+    /// Curve quote for AMM buying X, before the reserve floor.
+    #[inline]
+    fn curve_quote_buy_x(&self, amount_x: f64) -> (f64, f64) {
+        if amount_x <= 0.0 || !self.has_liquidity() {
             return (0.0, 0.0);
         }
+        if self.wad_math {
+            return wad_pair_to_f64(self.quote_buy_x_wad(Wad::from_f64(amount_x)));
+        }
 
         let fee = self.current_fees.bid_fee.to_f64();
-        let gamma = (1.0 - fee).clamp(0.0, 1.0);
+        // Above 1 when the fee is a rebate
+        let gamma = (1.0 - fee).max(0.0);
         if gamma <= 0.0 {
             return (0.0, 0.0);
         }
-        let net_x = amount_x * gamma;
 
         let k = self.reserve_x * self.reserve_y;
-        let new_rx = self.reserve_x + net_x;
-        let new_ry = k / new_rx;
-        let y_out = self.reserve_y - new_ry;
+        match self.fee_model {
+            FeeModel::OnInput => {
+                let net_x = amount_x * gamma;
+                let new_rx = self.reserve_x + net_x;
+                let new_ry = k / new_rx;
+                let y_out = self.reserve_y - new_ry;
 
-        if y_out > 0.0 {
-            (y_out, amount_x * fee)
-        } else {
-            (0.0, 0.0)
+                if y_out > 0.0 {
+                    (y_out, amount_x * fee)
+                } else {
+                    (0.0, 0.0)
+                }
+            }
+            FeeModel::OnOutput => {
+                let new_rx = self.reserve_x + amount_x;
+                let new_ry = k / new_rx;
+                let gross_y = self.reserve_y - new_ry;
+
+                if gross_y > 0.0 {
+                    (gross_y * gamma, gross_y * fee)
+                } else {
+                    (0.0, 0.0)
+                }
+            }
         }
     }
 
-    /// Fast quote for AMM selling X (trader buying X).
-    ///
-    /// Returns (total_y_in, fee_amount) or (0, 0) if invalid.
+    /// Curve quote for AMM selling X, before the reserve floor.
     #[inline]
-    pub fn quote_sell_x(&self, amount_x: f64) -> (f64, f64) {
-        if amount_x <= 0.0 || amount_x >= self.reserve_x {
+    fn curve_quote_sell_x(&self, amount_x: f64) -> (f64, f64) {
+        if amount_x <= 0.0 || amount_x >= self.reserve_x || !self.has_liquidity() {
             return (0.0, 0.0);
         }
+        if self.wad_math {
+            return wad_pair_to_f64(self.quote_sell_x_wad(Wad::from_f64(amount_x)));
+        }
 
         let k = self.reserve_x * self.reserve_y;
         let fee = self.current_fees.ask_fee.to_f64();
-        let gamma = (1.0 - fee).clamp(0.0, 1.0);
+        let gamma = (1.0 - fee).max(0.0);
         if gamma <= 0.0 {
             return (0.0, 0.0);
         }
 
-        let new_rx = self.reserve_x - amount_x;
-        let new_ry = k / new_rx;
-        let net_y = new_ry - self.reserve_y;
+        match self.fee_model {
+            FeeModel::OnInput => {
+                let new_rx = self.reserve_x - amount_x;
+                let new_ry = k / new_rx;
+                let net_y = new_ry - self.reserve_y;
 
-        if net_y <= 0.0 {
-            return (0.0, 0.0);
-        }
+                if net_y <= 0.0 {
+                    return (0.0, 0.0);
+                }
+
+                let total_y = net_y / gamma;
+                (total_y, total_y - net_y)
+            }
+            FeeModel::OnOutput => {
+                // The pool releases gross X; the fee is skimmed before the trader
+                let gross_x = amount_x / gamma;
+                if gross_x >= self.reserve_x {
+                    return (0.0, 0.0);
+                }
+
+                let new_rx = self.reserve_x - gross_x;
+                let new_ry = k / new_rx;
+                let total_y = new_ry - self.reserve_y;
 
-        let total_y = net_y / gamma;
-        (total_y, total_y - net_y)
+                if total_y <= 0.0 {
+                    return (0.0, 0.0);
+                }
+
+                (total_y, gross_x - amount_x)
+            }
+        }
     }
 
-    /// Fast quote for Y input to X output.
-    ///
-    /// Returns (x_out, fee_amount) or (0, 0) if invalid.
+    /// Curve quote for Y input to X output, before the reserve floor.
     #[inline]
-    pub fn quote_x_for_y(&self, amount_y: f64) -> (f64, f64) {
-        if amount_y <= 0.0 {
+    fn curve_quote_x_for_y(&self, amount_y: f64) -> (f64, f64) {
+        if amount_y <= 0.0 || !self.has_liquidity() {
             return (0.0, 0.0);
         }
+        if self.wad_math {
+            return wad_pair_to_f64(self.quote_x_for_y_wad(Wad::from_f64(amount_y)));
+        }
 
         let k = self.reserve_x * self.reserve_y;
         let fee = self.current_fees.ask_fee.to_f64();
-        let gamma = (1.0 - fee).clamp(0.0, 1.0);
+        let gamma = (1.0 - fee).max(0.0);
         if gamma <= 0.0 {
             return (0.0, 0.0);
         }
 
-        let net_y = amount_y * gamma;
-        let new_ry = self.reserve_y + net_y;
-        let new_rx = k / new_ry;
-        let x_out = self.reserve_x - new_rx;
+        match self.fee_model {
+            FeeModel::OnInput => {
+                let net_y = amount_y * gamma;
+                let new_ry = self.reserve_y + net_y;
+                let new_rx = k / new_ry;
+                let x_out = self.reserve_x - new_rx;
 
-        if x_out > 0.0 {
-            (x_out, amount_y * fee)
+                if x_out > 0.0 {
+                    (x_out, amount_y * fee)
+                } else {
+                    (0.0, 0.0)
+                }
+            }
+            FeeModel::OnOutput => {
+                let new_ry = self.reserve_y + amount_y;
+                let new_rx = k / new_ry;
+                let gross_x = self.reserve_x - new_rx;
+
+                if gross_x > 0.0 {
+                    (gross_x * gamma, gross_x * fee)
+                } else {
+                    (0.0, 0.0)
+                }
+            }
+        }
+    }
+
+    /// Wad quote for AMM buying X (trader selling X).
+    ///
+    /// Integer counterpart of `quote_buy_x`: outputs round down, in the
+    /// pool's favor. Returns (y_out, fee_amount) or (0, 0) if invalid.
+    pub fn quote_buy_x_wad(&self, amount_x: Wad) -> (Wad, Wad) {
+        let zero = (Wad::zero(), Wad::zero());
+        let fee = self.bounded_fee(self.current_fees.bid_fee);
+        let gamma = Wad::one() - fee;
+        let (rx, ry) = self.reserves_wad();
+        if !amount_x.is_positive() || !rx.is_positive() || !ry.is_positive() {
+            return zero;
+        }
+
+        // y_out = y * Δx / (x + Δx) for the X that reaches the curve
+        let (y_out, fee_amount) = match self.fee_model {
+            FeeModel::OnInput => {
+                let net_x = amount_x.wmul(gamma);
+                (ry.mul_div(net_x, rx + net_x), amount_x - net_x)
+            }
+            FeeModel::OnOutput => {
+                let gross_y = ry.mul_div(amount_x, rx + amount_x);
+                let y_out = gross_y.wmul(gamma);
+                (y_out, gross_y - y_out)
+            }
+        };
+        if y_out.is_positive() {
+            (y_out, fee_amount)
         } else {
-            (0.0, 0.0)
+            zero
         }
     }
 
-    /// Execute trade where AMM buys X (trader sells X for Y).
-    pub fn execute_buy_x(&mut self, amount_x: f64, timestamp: u64) -> Option<TradeResult> {
-        let (y_out, fee_amount) = self.quote_buy_x(amount_x);
-        if y_out <= 0.0 {
-            return None;
+    /// Wad quote for AMM selling X (trader buying X).
+    ///
+    /// Integer counterpart of `quote_sell_x`: the Y owed truncates, in the
+    /// trader's favor, unless rounding against the trader. Returns
+    /// (total_y_in, fee_amount) or (0, 0) if invalid.
+    pub fn quote_sell_x_wad(&self, amount_x: Wad) -> (Wad, Wad) {
+        let zero = (Wad::zero(), Wad::zero());
+        let fee = self.bounded_fee(self.current_fees.ask_fee);
+        let gamma = Wad::one() - fee;
+        let (rx, ry) = self.reserves_wad();
+        if !amount_x.is_positive() || !gamma.is_positive() || !rx.is_positive() || !ry.is_positive() {
+            return zero;
         }
 
-        // Update reserves - fees go to separate bucket, not into liquidity
-        let net_x = amount_x - fee_amount;
-        self.reserve_x += net_x;
-        self.accumulated_fees_x += fee_amount;
-        self.reserve_y -= y_out;
+        let round_up = self.rounding_mode == RoundingMode::AgainstTrader;
+        let mul_div = |a: Wad, b: Wad, c: Wad| if round_up { a.mul_div_up(b, c) } else { a.mul_div(b, c) };
+        let wdiv = |a: Wad, b: Wad| if round_up { a.wdiv_up(b) } else { a.wdiv(b) };
 
-        let trade_info = TradeInfo::new(
-            true, // is_buy (AMM buys X)
-            Wad::from_f64(amount_x),
-            Wad::from_f64(y_out),
-            timestamp,
-            Wad::from_f64(self.reserve_x),
-            Wad::from_f64(self.reserve_y),
-        );
+        // Δy = y * Δx / (x - Δx) for the X leaving the curve
+        let (total_y, fee_amount) = match self.fee_model {
+            FeeModel::OnInput => {
+                if amount_x >= rx {
+                    return zero;
+                }
+                let net_y = mul_div(ry, amount_x, rx - amount_x);
+                let total_y = wdiv(net_y, gamma);
+                (total_y, total_y - net_y)
+            }
+            FeeModel::OnOutput => {
+                let gross_x = wdiv(amount_x, gamma);
+                if gross_x >= rx {
+                    return zero;
+                }
+                (mul_div(ry, gross_x, rx - gross_x), gross_x - amount_x)
+            }
+        };
+        if total_y.is_positive() {
+            (total_y, fee_amount)
+        } else {
+            zero
+        }
+    }
 
-        // Update fees from strategy
-        self.update_fees(&trade_info);
+    /// Wad quote for Y input to X output.
+    ///
+    /// Integer counterpart of `quote_x_for_y`: outputs round down, in the
+    /// pool's favor. Returns (x_out, fee_amount) or (0, 0) if invalid.
+    pub fn quote_x_for_y_wad(&self, amount_y: Wad) -> (Wad, Wad) {
+        let zero = (Wad::zero(), Wad::zero());
+        let fee = self.bounded_fee(self.current_fees.ask_fee);
+        let gamma = Wad::one() - fee;
+        let (rx, ry) = self.reserves_wad();
+        if !amount_y.is_positive() || !rx.is_positive() || !ry.is_positive() {
+            return zero;
+        }
 
-        Some(TradeResult {
-            trade_info,
-            fee_amount,
-        })
+        // x_out = x * Δy / (y + Δy) for the Y that reaches the curve
+        let (x_out, fee_amount) = match self.fee_model {
+            FeeModel::OnInput => {
+                let net_y = amount_y.wmul(gamma);
+                (rx.mul_div(net_y, ry + net_y), amount_y - net_y)
+            }
+            FeeModel::OnOutput => {
+                let gross_x = rx.mul_div(amount_y, ry + amount_y);
+                let x_out = gross_x.wmul(gamma);
+                (x_out, gross_x - x_out)
+            }
+        };
+        if x_out.is_positive() {
+            (x_out, fee_amount)
+        } else {
+            zero
+        }
     }
 
-    /// Execute trade where AMM sells X (trader buys X with Y).
-    pub fn execute_sell_x(&mut self, amount_x: f64, timestamp: u64) -> Option<TradeResult> {
-        let (total_y, fee_amount) = self.quote_sell_x(amount_x);
-        if total_y <= 0.0 {
+    /// Current reserves as Wad.
+    fn reserves_wad(&self) -> (Wad, Wad) {
+        self.exact_reserves
+            .unwrap_or_else(|| (Wad::from_f64(self.reserve_x), Wad::from_f64(self.reserve_y)))
+    }
+
+    /// Preview a trade where AMM buys X (trader sells X for Y).
+    ///
+    /// Returns the projected trade without touching reserves, fees or the
+    /// strategy, or None if the trade would be rejected.
+    /// Amounts above the per-trade cap are clamped to it.
+    pub fn preview_buy_x(&self, amount_x: f64) -> Option<TradePreview> {
+        let amount_x = self.capped_x(amount_x);
+        let (y_out, fee_amount) = self.quote_buy_x(amount_x);
+        if y_out <= 0.0 {
             return None;
         }
 
-        // Update reserves - fees go to separate bucket, not into liquidity
-        let net_y = total_y - fee_amount;
-        self.reserve_x -= amount_x;
-        self.reserve_y += net_y;
-        self.accumulated_fees_y += fee_amount;
-
-        let trade_info = TradeInfo::new(
-            false, // is_buy = false (AMM sells X)
-            Wad::from_f64(amount_x),
-            Wad::from_f64(total_y),
-            timestamp,
-            Wad::from_f64(self.reserve_x),
-            Wad::from_f64(self.reserve_y),
-        );
+        // Fees go to a separate bucket, not into liquidity
+        let (reserve_x, reserve_y) = match self.fee_model {
+            FeeModel::OnInput => (self.reserve_x + (amount_x - fee_amount), self.reserve_y - y_out),
+            FeeModel::OnOutput => (self.reserve_x + amount_x, self.reserve_y - (y_out + fee_amount)),
+        };
+        Some(self.preview(true, amount_x, y_out, fee_amount, reserve_x, reserve_y))
+    }
 
-        // Update fees from strategy
-        self.update_fees(&trade_info);
+    /// Preview a trade where AMM sells X (trader buys X with Y).
+    ///
+    /// Returns the projected trade without touching reserves, fees or the
+    /// strategy, or None if the trade would be rejected.
+    /// Amounts above the per-trade cap are clamped to it.
+    pub fn preview_sell_x(&self, amount_x: f64) -> Option<TradePreview> {
+        let amount_x = self.capped_x(amount_x);
+        let (total_y, fee_amount) = self.quote_sell_x(amount_x);
+        if total_y <= 0.0 {
+            return None;
+        }
 
-        Some(TradeResult {
-            trade_info,
-            fee_amount,
-        })
+        let (reserve_x, reserve_y) = match self.fee_model {
+            FeeModel::OnInput => (self.reserve_x - amount_x, self.reserve_y + (total_y - fee_amount)),
+            FeeModel::OnOutput => (self.reserve_x - (amount_x + fee_amount), self.reserve_y + total_y),
+        };
+        Some(self.preview(false, amount_x, total_y, fee_amount, reserve_x, reserve_y))
     }
 
-    /// Execute trade where trader pays Y to receive X.
-    pub fn execute_buy_x_with_y(&mut self, amount_y: f64, timestamp: u64) -> Option<TradeResult> {
+    /// Preview a trade where trader pays Y to receive X.
+    ///
+    /// Returns the projected trade without touching reserves, fees or the
+    /// strategy, or None if the trade would be rejected.
+    /// Amounts above the per-trade cap are clamped to it.
+    pub fn preview_buy_x_with_y(&self, amount_y: f64) -> Option<TradePreview> {
+        let amount_y = self.capped_y(amount_y);
         let (x_out, fee_amount) = self.quote_x_for_y(amount_y);
         if x_out <= 0.0 {
             return None;
         }
 
-        // Update reserves - fees go to separate bucket, not into liquidity
-        let net_y = amount_y - fee_amount;
-        self.reserve_x -= x_out;
-        self.reserve_y += net_y;
-        self.accumulated_fees_y += fee_amount;
+        let (reserve_x, reserve_y) = match self.fee_model {
+            FeeModel::OnInput => (self.reserve_x - x_out, self.reserve_y + (amount_y - fee_amount)),
+            FeeModel::OnOutput => (self.reserve_x - (x_out + fee_amount), self.reserve_y + amount_y),
+        };
+        Some(self.preview(false, x_out, amount_y, fee_amount, reserve_x, reserve_y))
+    }
+
+    /// Depth chart: `(average_price, marginal_price)` in Y per X for a
+    /// trader on `side`, one pair per size in `sizes` (in X).
+    ///
+    /// Sizes go through the same quotes and per-trade cap as previews, and
+    /// the pool is left untouched. The marginal price is that of the next
+    /// infinitesimal unit after the trade: the post-trade spot divided by
+    /// γ_ask when buying, times γ_bid when selling. Sizes the pool would
+    /// reject give (0, 0).
+    pub fn depth_curve(&self, side: DepthSide, sizes: Vec<f64>) -> Vec<(f64, f64)> {
+        let buy = side == DepthSide::Buy;
+        let gamma = if buy {
+            1.0 - self.current_fees.ask_fee.to_f64()
+        } else {
+            1.0 - self.current_fees.bid_fee.to_f64()
+        };
+        sizes
+            .into_iter()
+            .map(|size| {
+                let preview = if buy { self.preview_sell_x(size) } else { self.preview_buy_x(size) };
+                match preview {
+                    Some(p) => {
+                        let marginal = if buy {
+                            p.spot_price_after / gamma
+                        } else {
+                            p.spot_price_after * gamma
+                        };
+                        (p.amount_y / p.amount_x, marginal)
+                    }
+                    None => (0.0, 0.0),
+                }
+            })
+            .collect()
+    }
+
+    fn preview(
+        &self,
+        is_buy: bool,
+        amount_x: f64,
+        amount_y: f64,
+        fee_amount: f64,
+        reserve_x: f64,
+        reserve_y: f64,
+    ) -> TradePreview {
+        TradePreview {
+            is_buy,
+            amount_x,
+            amount_y,
+            fee_amount,
+            // Fee is charged in the trader's input token under fee-on-input
+            fee_in_x: is_buy == (self.fee_model == FeeModel::OnInput),
+            reserve_x,
+            reserve_y,
+            spot_price_before: self.spot_price(),
+            spot_price_after: if reserve_x == 0.0 { 0.0 } else { reserve_y / reserve_x },
+        }
+    }
+
+    /// Apply a previewed trade and notify the strategy.
+    fn apply(&mut self, preview: TradePreview, timestamp: u64) -> TradeResult {
+        #[cfg(debug_assertions)]
+        let k_before = self.k();
+        let pre_reserves = self.reserves();
+        match &mut self.exact_reserves {
+            Some((x, y)) => {
+                // Same bookkeeping as the preview, in exact Wad amounts
+                let amount_x = Wad::from_f64(preview.amount_x);
+                let amount_y = Wad::from_f64(preview.amount_y);
+                let fee = Wad::from_f64(preview.fee_amount);
+                let (fee_x, fee_y) = if preview.fee_in_x { (fee, Wad::zero()) } else { (Wad::zero(), fee) };
+                if preview.is_buy {
+                    *x = *x + amount_x - fee_x;
+                    *y = *y - amount_y - fee_y;
+                } else {
+                    *x = *x - amount_x - fee_x;
+                    *y = *y + amount_y - fee_y;
+                }
+                self.sync_reserves();
+            }
+            None => {
+                self.reserve_x = preview.reserve_x;
+                self.reserve_y = preview.reserve_y;
+            }
+        }
+        #[cfg(debug_assertions)]
+        self.check_invariant(k_before);
+        if preview.fee_in_x {
+            self.accumulated_fees_x += preview.fee_amount;
+        } else {
+            self.accumulated_fees_y += preview.fee_amount;
+        }
 
         let trade_info = TradeInfo::new(
-            false, // is_buy = false (AMM sells X)
-            Wad::from_f64(x_out),
-            Wad::from_f64(amount_y),
+            preview.is_buy,
+            Wad::from_f64(preview.amount_x),
+            Wad::from_f64(preview.amount_y),
             timestamp,
+            self.trade_counter.fetch_add(1, Ordering::Relaxed),
             Wad::from_f64(self.reserve_x),
             Wad::from_f64(self.reserve_y),
         );
 
         // Update fees from strategy
-        self.update_fees(&trade_info);
+        match self.fee_update_frequency {
+            FeeUpdateFrequency::PerTrade => {
+                self.update_fees(&trade_info, self.arbitrage_flow);
+            }
+            FeeUpdateFrequency::PerStep => {
+                self.pending_fee_update = Some((trade_info, self.arbitrage_flow));
+            }
+        }
 
-        Some(TradeResult {
+        TradeResult {
             trade_info,
-            fee_amount,
-        })
+            fee_amount: preview.fee_amount,
+            price: preview.amount_y / preview.amount_x,
+            pre_reserves,
+            post_reserves: self.reserves(),
+        }
+    }
+
+    /// Panic if a trade moved `k` by more than the invariant tolerance.
+    ///
+    /// Fees are collected outside the reserves, so every trade should
+    /// leave `k` unchanged up to rounding.
+    #[cfg(debug_assertions)]
+    fn check_invariant(&self, k_before: f64) {
+        let k_after = self.k();
+        let discrepancy = (k_after - k_before).abs() / k_before.abs().max(f64::MIN_POSITIVE);
+        assert!(
+            discrepancy <= self.invariant_tolerance,
+            "{}: x*y invariant violated: k {} -> {} (relative discrepancy {:e}, tolerance {:e}) \
+             with reserves ({}, {})",
+            self.name,
+            k_before,
+            k_after,
+            discrepancy,
+            self.invariant_tolerance,
+            self.reserve_x,
+            self.reserve_y,
+        );
+    }
+
+    /// Execute trade where AMM buys X (trader sells X for Y).
+    pub fn execute_buy_x(&mut self, amount_x: f64, timestamp: u64) -> Option<TradeResult> {
+        let Some(preview) = self.preview_buy_x(amount_x) else {
+            let (quote, reserve_y_after) = self.unfloored_quote_buy_x(self.capped_x(amount_x));
+            self.drained |= self.below_floor(quote, reserve_y_after);
+            return None;
+        };
+        Some(self.apply(preview, timestamp))
+    }
+
+    /// Execute trade where AMM sells X (trader buys X with Y).
+    pub fn execute_sell_x(&mut self, amount_x: f64, timestamp: u64) -> Option<TradeResult> {
+        let Some(preview) = self.preview_sell_x(amount_x) else {
+            let (quote, reserve_x_after) = self.unfloored_quote_sell_x(self.capped_x(amount_x));
+            self.drained |= self.below_floor(quote, reserve_x_after);
+            return None;
+        };
+        Some(self.apply(preview, timestamp))
+    }
+
+    /// Execute trade where trader pays Y to receive X.
+    pub fn execute_buy_x_with_y(&mut self, amount_y: f64, timestamp: u64) -> Option<TradeResult> {
+        let Some(preview) = self.preview_buy_x_with_y(amount_y) else {
+            let (quote, reserve_x_after) = self.unfloored_quote_x_for_y(self.capped_y(amount_y));
+            self.drained |= self.below_floor(quote, reserve_x_after);
+            return None;
+        };
+        Some(self.apply(preview, timestamp))
+    }
+
+    /// Ask the strategy for new fees with the last trade since the
+    /// previous update, if any (`FeeUpdateFrequency::PerStep`).
+    pub fn flush_fee_update(&mut self) {
+        if let Some((trade_info, arbitrage_flow)) = self.pending_fee_update.take() {
+            self.update_fees(&trade_info, arbitrage_flow);
+        }
     }
 
     /// Update fees from strategy after a trade.
-    fn update_fees(&mut self, trade_info: &TradeInfo) {
-        if let Ok((bid_fee, ask_fee)) = self.strategy.after_swap(trade_info) {
-            self.current_fees = FeeQuote::new(bid_fee.clamp_fee(), ask_fee.clamp_fee());
+    ///
+    /// On error the current fees are kept and the failure is recorded;
+    /// pinned fees are never updated. Returns the total number of failed updates so far.
+    fn update_fees(&mut self, trade_info: &TradeInfo, arbitrage_flow: bool) -> u64 {
+        if self.fees_pinned {
+            return self.swap_failures;
+        }
+        let fees = match (&mut self.strategy, &mut self.native) {
+            (Some(strategy), _) => {
+                if let Some(price) = self.oracle_price {
+                    strategy.set_oracle(price);
+                }
+                if arbitrage_flow {
+                    strategy.after_arbitrage(trade_info)
+                } else {
+                    strategy.after_swap(trade_info)
+                }
+            }
+            (None, Some(native)) => {
+                let fees = native.after_trade(trade_info);
+                Ok((fees.bid_fee, fees.ask_fee))
+            }
+            (None, None) => return self.swap_failures,
+        };
+        match fees {
+            Ok((bid_fee, ask_fee)) if self.check_fee_bounds(bid_fee, ask_fee) => {}
+            Ok((bid_fee, ask_fee)) => {
+                self.current_fees = FeeQuote::new(self.apply_fee_rules(bid_fee), self.apply_fee_rules(ask_fee));
+            }
+            Err(e) => {
+                self.swap_failures += 1;
+                self.last_swap_error = Some(e.to_string());
+            }
+        }
+        if self.fee_history_capacity > 0 {
+            if self.fee_history.len() == self.fee_history_capacity {
+                self.fee_history.pop_front();
+            }
+            let fees = self.current_fees;
+            self.fee_history.push_back((trade_info.timestamp, fees.bid_fee, fees.ask_fee));
         }
-        // On error, keep current fees
+        self.swap_failures
     }
 
     /// Reset the AMM for a new simulation.
     pub fn reset(&mut self, reserve_x: f64, reserve_y: f64) -> Result<(), crate::evm::strategy::EVMError> {
         self.reserve_x = reserve_x;
         self.reserve_y = reserve_y;
+        if self.exact_reserves.is_some() {
+            self.set_exact_reserves(true);
+        }
         self.accumulated_fees_x = 0.0;
         self.accumulated_fees_y = 0.0;
+        self.swap_failures = 0;
+        self.last_swap_error = None;
+        self.fee_violations = 0;
+        self.drained = false;
+        self.oracle_price = None;
+        self.arbitrage_flow = false;
+        self.fee_history.clear();
+        self.pending_fee_update = None;
+        self.fees_pinned = false;
         self.initialized = false;
-        self.strategy.reset()
+        match &mut self.strategy {
+            Some(strategy) => strategy.reset(),
+            None => Ok(()),
+        }
     }
 }
 
+/// Round `value` down to WAD granularity, never landing above it.
+#[inline]
+fn wad_floor(value: f64) -> f64 {
+    let rounded = (value * WAD as f64).floor() / WAD as f64;
+    if rounded > value {
+        rounded.next_down()
+    } else {
+        rounded
+    }
+}
+
+/// Round `value` up to WAD granularity, never landing below it.
+#[inline]
+fn wad_ceil(value: f64) -> f64 {
+    let rounded = (value * WAD as f64).ceil() / WAD as f64;
+    if rounded < value {
+        rounded.next_up()
+    } else {
+        rounded
+    }
+}
+
+#[inline]
+fn wad_pair_to_f64((a, b): (Wad, Wad)) -> (f64, f64) {
+    (a.to_f64(), b.to_f64())
+}
+
+/// Snap a fee to the nearest tier by log distance (ties go to the lower tier).
+///
+/// A zero fee only matches a zero tier; if there is none it takes the
+/// smallest tier.
+fn snap_to_tier(fee: Wad, tiers: &[Wad]) -> Wad {
+    let log_distance = |tier: Wad| -> f64 {
+        match (fee.is_positive(), tier.is_positive()) {
+            (true, true) => (fee.to_f64().ln() - tier.to_f64().ln()).abs(),
+            (false, false) => 0.0,
+            _ => f64::INFINITY,
+        }
+    };
+
+    let mut best = tiers[0];
+    let mut best_distance = log_distance(best);
+    for &tier in &tiers[1..] {
+        let distance = log_distance(tier);
+        if distance < best_distance {
+            best = tier;
+            best_distance = distance;
+        }
+    }
+    best
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::wad::WAD;
 
     // Note: Full tests require EVM bytecode, which is complex to embed.
     // The Python integration tests will verify correctness.
@@ -335,5 +1511,676 @@ mod tests {
 
         // y_out should be approximately 9.876 (accounting for fee and price impact)
         assert!(y_out > 9.8 && y_out < 10.0);
+
+        // A fixed-fee pool quotes the same without any bytecode
+        let amm = CFMM::fixed_fee("fixed".into(), rx, ry, fee_quote);
+        let (quoted_y, fee_x) = amm.quote_buy_x(amount_x);
+        assert!((quoted_y - y_out).abs() < 1e-12);
+        assert!((fee_x - amount_x * fee).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_no_arb_band_edges() {
+        use crate::evm::fixtures::fixed_fee_strategy;
+        use crate::market::Arbitrageur;
+
+        let new_amm = || {
+            let mut amm = CFMM::new(fixed_fee_strategy(30, 50), 1000.0, 2000.0);
+            amm.initialize().unwrap();
+            amm
+        };
+        let arb = Arbitrageur::new();
+
+        let (lower, upper) = new_amm().no_arb_band();
+        assert!((lower - 2.0 * 0.997).abs() < 1e-12);
+        assert!((upper - 2.0 / 0.995).abs() < 1e-12);
+
+        // Exactly at the edges any arbitrage is (numerically) zero-profit
+        for edge in [lower, upper] {
+            if let Some(result) = arb.execute_arb(&mut new_amm(), edge, 0) {
+                assert!(result.profit < 1e-9);
+            }
+        }
+
+        // Just inside the band there is nothing to do
+        assert!(arb.execute_arb(&mut new_amm(), lower * (1.0 + 1e-9), 0).is_none());
+        assert!(arb.execute_arb(&mut new_amm(), upper * (1.0 - 1e-9), 0).is_none());
+
+        // Just outside the band arbitrage becomes profitable
+        assert!(arb.execute_arb(&mut new_amm(), lower * (1.0 - 1e-3), 0).is_some());
+        assert!(arb.execute_arb(&mut new_amm(), upper * (1.0 + 1e-3), 0).is_some());
+    }
+
+    #[test]
+    fn test_snap_to_tier() {
+        let tiers = [Wad::from_bps(1), Wad::from_bps(5), Wad::from_bps(30), Wad::from_bps(100)];
+        assert_eq!(snap_to_tier(Wad::from_bps(17), &tiers), Wad::from_bps(30));
+        assert_eq!(snap_to_tier(Wad::from_bps(3), &tiers), Wad::from_bps(5));
+        assert_eq!(snap_to_tier(Wad::from_bps(500), &tiers), Wad::from_bps(100));
+        assert_eq!(snap_to_tier(Wad::zero(), &tiers), Wad::from_bps(1));
+    }
+
+    #[test]
+    fn test_fee_tiers_snap_strategy_fees() {
+        use crate::evm::fixtures::fixed_fee_strategy;
+
+        let mut amm = CFMM::new(fixed_fee_strategy(17, 17), 1000.0, 1000.0);
+        amm.set_fee_tiers(vec![Wad::from_bps(30), Wad::from_bps(5)]);
+        amm.initialize().unwrap();
+        assert_eq!(amm.fees().bid_fee, Wad::from_bps(30));
+        assert_eq!(amm.fees().ask_fee, Wad::from_bps(30));
+
+        // Fee updates after a swap are snapped too
+        amm.execute_buy_x(1.0, 0).unwrap();
+        assert_eq!(amm.fees().bid_fee, Wad::from_bps(30));
+    }
+
+    #[test]
+    fn test_negative_fee_floor_pays_rebates_from_fee_bucket() {
+        let pool = |fee_bps| {
+            let fees = FeeQuote::symmetric(Wad::from_bps(fee_bps));
+            let mut amm = CFMM::fixed_fee("pool".into(), 1000.0, 1000.0, fees);
+            amm.set_fee_bounds(Some(Wad::from_bps(-10)), None);
+            amm.initialize().unwrap();
+            amm
+        };
+        let (mut rebate, no_fee) = (pool(-5), pool(0));
+        assert_eq!(rebate.fees().bid_fee, Wad::from_bps(-5));
+
+        // The trader gets slightly more Y than at zero fee, and the X
+        // rebate comes out of the fee bucket
+        let (no_fee_y, _) = no_fee.quote_buy_x(10.0);
+        let result = rebate.execute_buy_x(10.0, 0).unwrap();
+        let y_out = result.trade_info.amount_y.to_f64();
+        assert!(y_out > no_fee_y && y_out < no_fee_y * 1.001, "{y_out} vs {no_fee_y}");
+        let (fees_x, fees_y) = rebate.accumulated_fees();
+        assert!((fees_x + 10.0 * 0.0005).abs() < 1e-12, "{fees_x}");
+        assert_eq!(fees_y, 0.0);
+        let (reserve_x, _) = rebate.reserves();
+        assert!((reserve_x - (1000.0 + 10.0 * 1.0005)).abs() < 1e-9);
+
+        // Without a negative floor the same fees clamp to zero
+        let fees = FeeQuote::symmetric(Wad::from_bps(-5));
+        let mut clamped = CFMM::fixed_fee("pool".into(), 1000.0, 1000.0, fees);
+        clamped.initialize().unwrap();
+        assert_eq!(clamped.fees().bid_fee, Wad::zero());
+    }
+
+    #[test]
+    fn test_fee_bounds_clamp_strategy_fees() {
+        use crate::evm::fixtures::fixed_fee_strategy;
+
+        let mut amm = CFMM::new(fixed_fee_strategy(5, 500), 1000.0, 1000.0);
+        amm.set_fee_bounds(Some(Wad::from_bps(10)), Some(Wad::from_bps(100)));
+        amm.initialize().unwrap();
+        assert_eq!(amm.fees().bid_fee, Wad::from_bps(10));
+        assert_eq!(amm.fees().ask_fee, Wad::from_bps(100));
+
+        amm.execute_buy_x(1.0, 0).unwrap();
+        assert_eq!(amm.fees().bid_fee, Wad::from_bps(10));
+
+        // Only the floor set: the ceiling stays at MAX_FEE
+        let mut amm = CFMM::new(fixed_fee_strategy(5, 500), 1000.0, 1000.0);
+        amm.set_fee_bounds(Some(Wad::from_bps(10)), None);
+        amm.initialize().unwrap();
+        assert_eq!(amm.fees().ask_fee, Wad::from_bps(500));
+    }
+
+    #[test]
+    fn test_out_of_bounds_fees_are_counted_and_rejected_when_strict() {
+        use crate::evm::fixtures::{deploy, fixed_fee_strategy, Asm};
+        use crate::types::trade_info::SELECTOR_AFTER_SWAP;
+        use crate::types::wad::BPS;
+
+        // Lenient: clamped and counted, at initialization and after trades
+        let mut amm = CFMM::new(fixed_fee_strategy(50, 20), 1000.0, 1000.0);
+        amm.set_fee_bounds(None, Some(Wad::from_bps(30)));
+        amm.initialize().unwrap();
+        assert_eq!(amm.fees().bid_fee, Wad::from_bps(30));
+        assert_eq!(amm.fees().ask_fee, Wad::from_bps(20));
+        amm.execute_buy_x(1.0, 0).unwrap();
+        assert_eq!(amm.fee_violations(), 2);
+
+        // Strict: out-of-bounds initial fees fail initialization
+        let mut amm = CFMM::new(fixed_fee_strategy(50, 20), 1000.0, 1000.0);
+        amm.set_fee_bounds(None, Some(Wad::from_bps(30)));
+        amm.set_strict_fees(true);
+        assert!(amm.initialize().is_err());
+        assert_eq!(amm.fee_violations(), 1);
+
+        // and out-of-bounds updates leave the current fees in place
+        let bps = BPS as u128;
+        let runtime = Asm::new()
+            .dispatch(SELECTOR_AFTER_SWAP, "swap")
+            .return_pair(10 * bps, 10 * bps)
+            .label("swap")
+            .return_pair(50 * bps, 10 * bps)
+            .build();
+        let strategy = EVMStrategy::new(deploy(&runtime), "Wide".into()).unwrap();
+        let mut amm = CFMM::new(strategy, 1000.0, 1000.0);
+        amm.set_fee_bounds(None, Some(Wad::from_bps(30)));
+        amm.set_strict_fees(true);
+        amm.initialize().unwrap();
+        assert_eq!(amm.fee_violations(), 0);
+        amm.execute_buy_x(1.0, 0).unwrap();
+        assert_eq!(amm.fee_violations(), 1);
+        assert_eq!(amm.fees().bid_fee, Wad::from_bps(10));
+    }
+
+    fn fixed_fee_amm(fee_bps: u128, fee_model: FeeModel) -> CFMM {
+        use crate::evm::fixtures::fixed_fee_strategy;
+
+        let mut amm = CFMM::new(fixed_fee_strategy(fee_bps, fee_bps), 1000.0, 1000.0);
+        amm.set_fee_model(fee_model);
+        amm.initialize().unwrap();
+        amm
+    }
+
+    #[test]
+    fn test_fee_models_single_trade() {
+        let on_input = fixed_fee_amm(100, FeeModel::OnInput);
+        let on_output = fixed_fee_amm(100, FeeModel::OnOutput);
+        let gamma = 0.99;
+
+        // Trader sells 10 X
+        let (y_in_model, fee_in_model) = on_input.quote_buy_x(10.0);
+        let (y_out_model, fee_out_model) = on_output.quote_buy_x(10.0);
+        assert!((y_in_model - (1000.0 - 1e6 / (1000.0 + 10.0 * gamma))).abs() < 1e-9);
+        assert!((fee_in_model - 0.1).abs() < 1e-12); // in X
+        let gross_y = 1000.0 - 1e6 / 1010.0;
+        assert!((y_out_model - gross_y * gamma).abs() < 1e-9);
+        assert!((fee_out_model - gross_y * 0.01).abs() < 1e-9); // in Y
+
+        // Trader buys 10 X: output fee means more X leaves the pool
+        let (cost_in_model, _) = on_input.quote_sell_x(10.0);
+        let (cost_out_model, fee_x) = on_output.quote_sell_x(10.0);
+        assert!((fee_x - (10.0 / gamma - 10.0)).abs() < 1e-9);
+        assert!((cost_out_model - (1e6 / (1000.0 - 10.0 / gamma) - 1000.0)).abs() < 1e-9);
+        assert!(cost_in_model > 0.0 && cost_out_model > 0.0);
+
+        // Trader pays 10 Y
+        let (x_in_model, _) = on_input.quote_x_for_y(10.0);
+        let (x_out_model, fee_x) = on_output.quote_x_for_y(10.0);
+        let gross_x = 1000.0 - 1e6 / 1010.0;
+        assert!((x_out_model - gross_x * gamma).abs() < 1e-9);
+        assert!((fee_x - gross_x * 0.01).abs() < 1e-9);
+        assert!(x_in_model > 0.0);
+    }
+
+    #[test]
+    fn test_fee_bookkeeping_conserves_value() {
+        for fee_model in [FeeModel::OnInput, FeeModel::OnOutput] {
+            let mut amm = fixed_fee_amm(50, fee_model);
+            let mut trader_x = 0.0;
+            let mut trader_y = 0.0;
+
+            let trade = amm.execute_buy_x(7.0, 0).unwrap();
+            trader_x -= 7.0;
+            trader_y += trade.trade_info.amount_y.to_f64();
+
+            // The first fee lands in X (input) or Y (output)
+            let (fees_x, fees_y) = amm.accumulated_fees();
+            match fee_model {
+                FeeModel::OnInput => assert!(fees_x > 0.0 && fees_y == 0.0),
+                FeeModel::OnOutput => assert!(fees_x == 0.0 && fees_y > 0.0),
+            }
+
+            let trade = amm.execute_sell_x(3.0, 1).unwrap();
+            trader_x += 3.0;
+            trader_y -= trade.trade_info.amount_y.to_f64();
+
+            let trade = amm.execute_buy_x_with_y(25.0, 2).unwrap();
+            trader_x += trade.trade_info.amount_x.to_f64();
+            trader_y -= 25.0;
+
+            // Every token the trader moved is in the reserves or a fee bucket
+            let (rx, ry) = amm.reserves();
+            let (fees_x, fees_y) = amm.accumulated_fees();
+            assert!((rx + fees_x + trader_x - 1000.0).abs() < 1e-9, "{fee_model:?}");
+            assert!((ry + fees_y + trader_y - 1000.0).abs() < 1e-9, "{fee_model:?}");
+
+            // Fees are not reinvested: k never grows
+            assert!(amm.k() <= 1e6 * (1.0 + 1e-12));
+        }
+    }
+
+    #[test]
+    fn test_trades_preserve_invariant() {
+        for wad_math in [false, true] {
+            for fee_model in [FeeModel::OnInput, FeeModel::OnOutput] {
+                let mut amm = fixed_fee_amm(50, fee_model);
+                amm.set_wad_math(wad_math);
+                for t in 0..200u64 {
+                    let size = 1.0 + (t % 17) as f64 * 3.0;
+                    match t % 3 {
+                        0 => amm.execute_buy_x(size, t),
+                        1 => amm.execute_sell_x(size, t),
+                        _ => amm.execute_buy_x_with_y(size * 10.0, t),
+                    };
+                }
+                assert!((amm.k() - 1e6).abs() < 1e6 * amm.invariant_tolerance());
+            }
+        }
+    }
+
+    #[test]
+    fn test_rounding_against_trader_never_loses_pool_value() {
+        // Many small trades on a 1 X / 100 Y pool under Wad math, where each
+        // quote can be checked exactly against the curve it was taken on
+        let fees = FeeQuote::symmetric(Wad::from_bps(30));
+        let mut native_shortfalls = 0;
+        for rounding_mode in [RoundingMode::Native, RoundingMode::AgainstTrader] {
+            let mut amm = CFMM::fixed_fee("pool".into(), 1.0, 100.0, fees);
+            amm.set_wad_math(true);
+            amm.set_rounding_mode(rounding_mode);
+            amm.initialize().unwrap();
+
+            for t in 0..500u64 {
+                let (rx, ry) = amm.reserves_wad();
+                let amount = Wad::from_f64(1e-6 * (1 + t % 37) as f64);
+
+                // Trader sells X: the Y it gets never exceeds the curve's
+                let (y_out, fee) = amm.quote_buy_x_wad(amount);
+                let net_x = amount - fee;
+                assert!(y_out.raw() * (rx + net_x).raw() <= ry.raw() * net_x.raw());
+
+                // Trader pays Y for X: the Y it gets in never exceeds the curve's
+                let (x_out, fee) = amm.quote_x_for_y_wad(amount);
+                let net_y = amount - fee;
+                assert!(x_out.raw() * (ry + net_y).raw() <= rx.raw() * net_y.raw());
+
+                // Trader buys X: the Y reaching the curve covers it when
+                // rounding against the trader; truncation can fall short
+                let (total_y, fee) = amm.quote_sell_x_wad(amount);
+                let covered = (total_y - fee).raw() * (rx - amount).raw() >= ry.raw() * amount.raw();
+                match rounding_mode {
+                    RoundingMode::AgainstTrader => assert!(covered, "step {t}"),
+                    RoundingMode::Native => native_shortfalls += u32::from(!covered),
+                }
+
+                let size = amount.to_f64();
+                match t % 3 {
+                    0 => amm.execute_buy_x(size, t),
+                    1 => amm.execute_sell_x(size, t),
+                    _ => amm.execute_buy_x_with_y(size * 100.0, t),
+                };
+            }
+        }
+        assert!(native_shortfalls > 0);
+
+        // Under f64 math the directed quotes bracket the native ones
+        let mut native = CFMM::fixed_fee("pool".into(), 1.0, 100.0, fees);
+        native.initialize().unwrap();
+        let mut directed = CFMM::fixed_fee("pool".into(), 1.0, 100.0, fees);
+        directed.set_rounding_mode(RoundingMode::AgainstTrader);
+        directed.initialize().unwrap();
+        for amount in [1e-9, 3.7e-6, 0.01, 0.25] {
+            let (y_native, fee_native) = native.quote_buy_x(amount);
+            let (y_directed, fee_directed) = directed.quote_buy_x(amount);
+            assert!(y_directed <= y_native && fee_directed >= fee_native);
+            let (y_native, _) = native.quote_sell_x(amount);
+            let (y_directed, _) = directed.quote_sell_x(amount);
+            assert!(y_directed >= y_native);
+            assert!(y_directed - y_native <= 1e-18 + f64::EPSILON * y_native);
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "invariant violated")]
+    fn test_corrupted_reserves_trip_invariant_check() {
+        let mut amm = fixed_fee_amm(50, FeeModel::OnInput);
+        let k_before = amm.k();
+        // A reserve update that forgot to subtract the output
+        amm.reserve_y *= 1.001;
+        amm.check_invariant(k_before);
+    }
+
+    #[test]
+    fn test_wad_quotes_match_f64_quotes() {
+        for fee_model in [FeeModel::OnInput, FeeModel::OnOutput] {
+            let mut amm = CFMM::new(
+                crate::evm::fixtures::fixed_fee_strategy(30, 75),
+                123.456,
+                98_765.25,
+            );
+            amm.set_fee_model(fee_model);
+            amm.initialize().unwrap();
+
+            for amount in [1e-6, 0.37, 12.5, 100.0] {
+                let checks = [
+                    (amm.quote_buy_x(amount), amm.quote_buy_x_wad(Wad::from_f64(amount))),
+                    (amm.quote_sell_x(amount), amm.quote_sell_x_wad(Wad::from_f64(amount))),
+                    (amm.quote_x_for_y(amount), amm.quote_x_for_y_wad(Wad::from_f64(amount))),
+                ];
+                for ((out, fee), (out_wad, fee_wad)) in checks {
+                    // The f64 path computes y - k / x', so it carries
+                    // cancellation error on the order of ulp(reserve)
+                    let tolerance = 1e-9 * out + 1e-13 * 98_765.25;
+                    assert!(out > 0.0);
+                    assert!((out_wad.to_f64() - out).abs() <= tolerance, "{fee_model:?} {amount}");
+                    assert!((fee_wad.to_f64() - fee).abs() <= tolerance, "{fee_model:?} {amount}");
+                }
+            }
+
+            // Selling more X than the pool holds is rejected on both paths
+            assert_eq!(amm.quote_sell_x_wad(Wad::from_f64(200.0)), (Wad::zero(), Wad::zero()));
+        }
+    }
+
+    #[test]
+    fn test_wad_math_flag_routes_quotes() {
+        let mut amm = fixed_fee_amm(30, FeeModel::OnInput);
+        let float_quote = amm.quote_buy_x(10.0);
+        amm.set_wad_math(true);
+        let wad_quote = amm.quote_buy_x(10.0);
+        let (y_out, fee) = amm.quote_buy_x_wad(Wad::from_f64(10.0));
+
+        assert_eq!(wad_quote, (y_out.to_f64(), fee.to_f64()));
+        assert!((wad_quote.0 - float_quote.0).abs() < 1e-12);
+        assert_eq!(amm.quote_buy_x(10.0), wad_quote);
+    }
+
+    #[test]
+    fn test_reserve_floor_stops_depletion() {
+        for fee_model in [FeeModel::OnInput, FeeModel::OnOutput] {
+            let mut amm = fixed_fee_amm(0, fee_model);
+            amm.set_min_reserve(10.0);
+            assert!(!amm.drained());
+
+            // Keep buying X with ever larger Y until the floor binds
+            let mut rejected = false;
+            for i in 0..40 {
+                if amm.execute_buy_x_with_y(1000.0 * 2f64.powi(i), i as u64).is_none() {
+                    rejected = true;
+                    break;
+                }
+                assert!(amm.reserves().0 >= 10.0, "{fee_model:?}");
+            }
+            assert!(rejected, "{fee_model:?}");
+            assert!(amm.drained(), "{fee_model:?}");
+
+            // Selling the remaining X out of the pool is rejected too
+            let (reserve_x, _) = amm.reserves();
+            assert_eq!(amm.quote_sell_x(reserve_x - 5.0), (0.0, 0.0));
+            assert!(amm.reserves().0 > 0.0 && amm.reserves().1 > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_quotes_past_the_floor_do_not_mark_the_pool_drained() {
+        let mut amm = fixed_fee_amm(30, FeeModel::OnInput);
+        amm.set_min_reserve(10.0);
+        let (reserve_x, _) = amm.reserves();
+        let past_floor = reserve_x - 5.0;
+
+        assert_eq!(amm.quote_sell_x(past_floor), (0.0, 0.0));
+        assert!(amm.preview_sell_x(past_floor).is_none());
+        assert_eq!(amm.depth_curve(DepthSide::Buy, vec![past_floor]), vec![(0.0, 0.0)]);
+        assert!(!amm.drained());
+
+        // Only a rejected trade marks it
+        assert!(amm.execute_sell_x(past_floor, 0).is_none());
+        assert!(amm.drained());
+    }
+
+    type PreviewFn = fn(&CFMM, f64) -> Option<TradePreview>;
+    type ExecuteFn = fn(&mut CFMM, f64, u64) -> Option<TradeResult>;
+
+    #[test]
+    fn test_preview_matches_execute() {
+        for fee_model in [FeeModel::OnInput, FeeModel::OnOutput] {
+            let mut amm = fixed_fee_amm(30, fee_model);
+            let trades: [(PreviewFn, ExecuteFn, f64); 3] = [
+                (CFMM::preview_buy_x, CFMM::execute_buy_x, 12.5),
+                (CFMM::preview_sell_x, CFMM::execute_sell_x, 7.0),
+                (CFMM::preview_buy_x_with_y, CFMM::execute_buy_x_with_y, 40.0),
+            ];
+            for (t, (preview_fn, execute_fn, amount)) in trades.into_iter().enumerate() {
+                let reserves_before = amm.reserves();
+                let fees_before = amm.accumulated_fees();
+                let preview = preview_fn(&amm, amount).unwrap();
+
+                // Previewing leaves the pool untouched
+                assert_eq!(amm.reserves(), reserves_before);
+                assert_eq!(preview.spot_price_before, amm.spot_price());
+
+                let result = execute_fn(&mut amm, amount, t as u64).unwrap();
+                assert_eq!(amm.reserves(), (preview.reserve_x, preview.reserve_y));
+                assert_eq!(amm.spot_price(), preview.spot_price_after);
+                assert_eq!(result.fee_amount, preview.fee_amount);
+                assert_eq!(result.trade_info.is_buy, preview.is_buy);
+                assert_eq!(result.trade_info.amount_x, Wad::from_f64(preview.amount_x));
+                assert_eq!(result.trade_info.amount_y, Wad::from_f64(preview.amount_y));
+
+                let (fees_x, fees_y) = amm.accumulated_fees();
+                let fee_delta = if preview.fee_in_x { fees_x - fees_before.0 } else { fees_y - fees_before.1 };
+                assert!((fee_delta - preview.fee_amount).abs() < 1e-12);
+                assert_eq!(preview.price_impact() > 0.0, !preview.is_buy, "{fee_model:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_depth_curve_is_monotonic_and_brackets_average() {
+        let sizes: Vec<f64> = (1..=20).map(|i| i as f64 * 2.0).collect();
+        for fee_model in [FeeModel::OnInput, FeeModel::OnOutput] {
+            let amm = fixed_fee_amm(30, fee_model);
+            let spot = amm.spot_price();
+            let reserves = amm.reserves();
+
+            let buys = amm.depth_curve(DepthSide::Buy, sizes.clone());
+            let sells = amm.depth_curve(DepthSide::Sell, sizes.clone());
+            assert_eq!(amm.reserves(), reserves);
+            assert_eq!((buys.len(), sells.len()), (sizes.len(), sizes.len()));
+
+            assert!(buys.windows(2).all(|w| w[1].1 > w[0].1), "{fee_model:?}");
+            assert!(sells.windows(2).all(|w| w[1].1 < w[0].1), "{fee_model:?}");
+            for &(average, marginal) in &buys {
+                assert!(spot < average && average < marginal, "{spot} {average} {marginal}");
+            }
+            for &(average, marginal) in &sells {
+                assert!(marginal < average && average < spot, "{spot} {average} {marginal}");
+            }
+
+            // The marginal price matches a finite difference of the quotes
+            let (size, h) = (10.0, 1e-6);
+            let slope = (amm.quote_sell_x(size + h).0 - amm.quote_sell_x(size).0) / h;
+            let (_, marginal) = amm.depth_curve(DepthSide::Buy, vec![size])[0];
+            assert!((slope - marginal).abs() / marginal < 1e-5, "{slope} {marginal}");
+        }
+    }
+
+    #[test]
+    fn test_trade_result_reports_price_and_reserves() {
+        let mut amm = fixed_fee_amm(30, FeeModel::OnInput);
+        let spot = amm.spot_price();
+
+        // Trader buys X: the fill is pricier than spot but below the new spot
+        let result = amm.execute_sell_x(100.0, 0).unwrap();
+        assert_eq!(result.pre_reserves, (1000.0, 1000.0));
+        assert_eq!(result.post_reserves, amm.reserves());
+        let post_spot = result.post_reserves.1 / result.post_reserves.0;
+        assert!(spot < result.price && result.price < post_spot, "{} {}", result.price, post_spot);
+        assert!((result.price - result.trade_info.amount_y.to_f64() / 100.0).abs() < 1e-12);
+
+        // Trader sells X back: between the new spot and the old one
+        let spot = amm.spot_price();
+        let result = amm.execute_buy_x(100.0, 1).unwrap();
+        let post_spot = amm.spot_price();
+        assert!(post_spot < result.price && result.price < spot, "{} {}", result.price, post_spot);
+    }
+
+    #[test]
+    fn test_trade_sequence_increases_across_pools_within_a_step() {
+        use crate::evm::fixtures::{deploy, Asm};
+        use crate::evm::EVMStrategy;
+        use crate::types::trade_info::SELECTOR_AFTER_SWAP;
+        use crate::types::wad::BPS;
+
+        // afterSwap echoes the trailing sequence word as its bid fee
+        let fee = 30 * BPS as u128;
+        let runtime = Asm::new()
+            .dispatch(SELECTOR_AFTER_SWAP, "swap")
+            .return_pair(fee, fee)
+            .label("swap")
+            .arg(6)
+            .push(fee)
+            .return_pair_from_stack()
+            .build();
+        let strategy = EVMStrategy::new(deploy(&runtime), "Sequence".into()).unwrap();
+        let mut echo = CFMM::new(strategy, 1000.0, 1000.0);
+        echo.initialize().unwrap();
+        let mut other = fixed_fee_amm(30, FeeModel::OnInput);
+        let counter = Arc::new(AtomicU64::new(0));
+        echo.set_trade_counter(counter.clone());
+        other.set_trade_counter(counter.clone());
+
+        let trades = [
+            echo.execute_buy_x(1.0, 5).unwrap(),
+            other.execute_sell_x(1.0, 5).unwrap(),
+            echo.execute_sell_x(2.0, 5).unwrap(),
+            echo.execute_buy_x(1.0, 5).unwrap(),
+        ];
+        let sequences: Vec<u64> = trades.iter().map(|trade| trade.trade_info.sequence).collect();
+        assert_eq!(sequences, vec![0, 1, 2, 3]);
+        assert!(trades.iter().all(|trade| trade.trade_info.timestamp == 5));
+        assert_eq!(echo.fees().bid_fee.raw(), 3);
+        assert_eq!(counter.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn test_fee_history_records_every_update() {
+        use crate::evm::fixtures::{deploy, Asm, DIV};
+        use crate::evm::EVMStrategy;
+        use crate::types::trade_info::SELECTOR_AFTER_SWAP;
+        use crate::types::wad::BPS;
+
+        // afterSwap charges reserveY / 1e6 on both sides, so every trade
+        // moves the fee
+        let runtime = Asm::new()
+            .dispatch(SELECTOR_AFTER_SWAP, "swap")
+            .return_pair(30 * BPS as u128, 30 * BPS as u128)
+            .label("swap")
+            .push(1_000_000)
+            .arg(5)
+            .ops(&[DIV])
+            .push(1_000_000)
+            .arg(5)
+            .ops(&[DIV])
+            .return_pair_from_stack()
+            .build();
+        let strategy = EVMStrategy::new(deploy(&runtime), "ReserveFee".into()).unwrap();
+        let mut amm = CFMM::new(strategy, 1000.0, 10_000.0);
+        amm.initialize().unwrap();
+
+        amm.execute_buy_x(1.0, 3).unwrap();
+        assert!(amm.fee_history().is_empty());
+
+        amm.set_fee_history_capacity(4);
+        let trades = [(true, 2.0), (false, 1.0), (true, 5.0)];
+        for &(buy, amount) in &trades {
+            if buy {
+                amm.execute_buy_x(amount, 7).unwrap();
+            } else {
+                amm.execute_sell_x(amount, 7).unwrap();
+            }
+        }
+        let history: Vec<_> = amm.fee_history().iter().copied().collect();
+        assert_eq!(history.len(), 3);
+        assert!(history.iter().all(|&(t, bid, ask)| t == 7 && bid == ask));
+        assert!(history.windows(2).all(|w| w[0].1 != w[1].1));
+        let last = history[2];
+        assert_eq!((last.1, last.2), (amm.fees().bid_fee, amm.fees().ask_fee));
+
+        // Bounded: only the most recent entries are kept
+        amm.execute_buy_x(1.0, 8).unwrap();
+        amm.execute_buy_x(1.0, 9).unwrap();
+        let timestamps: Vec<u64> = amm.fee_history().iter().map(|e| e.0).collect();
+        assert_eq!(timestamps, vec![7, 7, 8, 9]);
+    }
+
+    #[test]
+    fn test_empty_pool_quotes_nothing() {
+        let fees = FeeQuote::symmetric(Wad::from_bps(30));
+        for (x, y) in [(0.0, 0.0), (0.0, 1000.0), (1000.0, 0.0)] {
+            for wad_math in [false, true] {
+                let mut amm = CFMM::fixed_fee("empty".into(), x, y, fees);
+                amm.initialize().unwrap();
+                // No reserve floor to fall back on
+                amm.set_min_reserve(0.0);
+                amm.set_wad_math(wad_math);
+                assert!(!amm.has_liquidity());
+                assert_eq!(amm.quote_buy_x(10.0), (0.0, 0.0));
+                assert_eq!(amm.quote_sell_x(10.0), (0.0, 0.0));
+                assert_eq!(amm.quote_x_for_y(10.0), (0.0, 0.0));
+                assert!(amm.execute_buy_x(10.0, 0).is_none());
+                assert_eq!(amm.reserves(), (x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_exact_reserves_cancel_offsetting_trades() {
+        // A million round trips of varying size on a fee-free pool whose X
+        // reserve straddles a power of two: the AMM buys X, then sells the
+        // same X back. Quotes stay f64, so the Y legs need not cancel; the
+        // ledger sums the Wad amounts each trade reported. Returns the start
+        // reserves, the end reserves and the ledger's end reserves.
+        let round_trips = |exact: bool| {
+            let fees = FeeQuote::symmetric(Wad::from_bps(0));
+            let mut amm = CFMM::fixed_fee("loop".into(), 1023.9, 98_765.432_1, fees);
+            amm.initialize().unwrap();
+            amm.set_exact_reserves(exact);
+            let start = amm.reserves_wad();
+            let (mut ledger_x, mut ledger_y) = start;
+            for i in 0..1_000_000u64 {
+                let amount_x = 0.1 + (i % 97) as f64 * 0.0137;
+                for result in [amm.execute_buy_x(amount_x, i), amm.execute_sell_x(amount_x, i)] {
+                    let info = result.unwrap().trade_info;
+                    if info.is_buy {
+                        ledger_x = ledger_x + info.amount_x;
+                        ledger_y = ledger_y - info.amount_y;
+                    } else {
+                        ledger_x = ledger_x - info.amount_x;
+                        ledger_y = ledger_y + info.amount_y;
+                    }
+                }
+            }
+            (start, amm.reserves_wad(), (ledger_x, ledger_y))
+        };
+
+        let ((x0, _), (x1, y1), (ledger_x, ledger_y)) = round_trips(true);
+        assert_eq!(x1, x0);
+        assert_eq!((x1, y1), (ledger_x, ledger_y));
+
+        // f64 bookkeeping drifts from the start and from the ledger
+        let ((x0, _), (x1, y1), (ledger_x, ledger_y)) = round_trips(false);
+        assert!((x1 - x0).to_f64().abs() > 1e-9, "{}", (x1 - x0).to_f64());
+        assert!((y1 - ledger_y).raw().abs() > 1_000_000, "{:?}", y1 - ledger_y);
+        assert_ne!(x1, ledger_x);
+    }
+
+    #[test]
+    fn test_add_liquidity_moves_reserves_without_fees() {
+        for exact in [false, true] {
+            let fees = FeeQuote::symmetric(Wad::from_bps(30));
+            let mut amm = CFMM::fixed_fee("lp".into(), 100.0, 10_000.0, fees);
+            amm.initialize().unwrap();
+            amm.set_exact_reserves(exact);
+
+            assert!(amm.add_liquidity(-50.0, -5_000.0));
+            assert_eq!(amm.reserves(), (50.0, 5_000.0));
+            assert_eq!(amm.accumulated_fees(), (0.0, 0.0));
+
+            // Withdrawing down to the floor is refused and changes nothing
+            amm.set_min_reserve(10.0);
+            assert!(!amm.add_liquidity(-40.0, 0.0));
+            assert!(!amm.add_liquidity(f64::NAN, 0.0));
+            assert_eq!(amm.reserves(), (50.0, 5_000.0));
+
+            assert!(amm.add_liquidity(150.0, 15_000.0));
+            assert_eq!(amm.reserves(), (200.0, 20_000.0));
+        }
     }
 }
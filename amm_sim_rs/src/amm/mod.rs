@@ -1,5 +1,10 @@
 //! AMM (Automated Market Maker) module.
 
 pub mod cfmm;
+pub mod native;
 
-pub use cfmm::CFMM;
+pub use cfmm::{
+    DepthSide, FeeModel, FeeUpdateFrequency, RoundingMode, TradePreview, CFMM,
+    DEFAULT_INVARIANT_TOLERANCE, DEFAULT_MIN_RESERVE, DEFAULT_QUOTE_TOKEN,
+};
+pub use native::NativeStrategy;
@@ -0,0 +1,143 @@
+//! Built-in fee rules evaluated in Rust, as baselines that need no EVM
+//! bytecode.
+
+use serde::{Deserialize, Serialize};
+
+use crate::amm::cfmm::FeeQuote;
+use crate::types::trade_info::TradeInfo;
+use crate::types::wad::Wad;
+
+/// Parameterized fee rule a `CFMM` can run in place of an `EVMStrategy`.
+///
+/// Fees are fractions (0.003 = 30 bps) and go through the pool's fee rules
+/// (clamping, tiers) like strategy-returned fees.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum NativeStrategy {
+    /// The same fees after every trade.
+    Constant { bid_fee: f64, ask_fee: f64 },
+    /// `base_fee` widened on the side that would deepen the pool's
+    /// inventory imbalance: when the pool holds less X than it started
+    /// with, the ask fee grows by `sensitivity` times the relative
+    /// shortfall; when it holds more, the bid fee grows likewise.
+    InventoryAware { base_fee: f64, sensitivity: f64 },
+    /// `base_fee` plus `multiplier` times the per-trade volatility of the
+    /// spot price, an EMA (weight `alpha` on the newest trade) of squared
+    /// log price moves between trades.
+    VolatilityScaled { base_fee: f64, multiplier: f64, alpha: f64 },
+}
+
+/// A `NativeStrategy` together with what it has observed of the pool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NativeFees {
+    strategy: NativeStrategy,
+    /// X reserves at initialization, the inventory target
+    initial_x: f64,
+    /// Spot price after the previous trade (or at initialization)
+    last_price: f64,
+    /// EMA of squared log spot-price moves
+    variance: f64,
+}
+
+impl NativeFees {
+    pub fn new(strategy: NativeStrategy) -> Self {
+        Self {
+            strategy,
+            initial_x: 0.0,
+            last_price: 0.0,
+            variance: 0.0,
+        }
+    }
+
+    pub fn strategy(&self) -> NativeStrategy {
+        self.strategy
+    }
+
+    /// Forget past trades and return the starting fees for the reserves.
+    pub fn after_initialize(&mut self, reserve_x: f64, reserve_y: f64) -> FeeQuote {
+        self.initial_x = reserve_x;
+        self.last_price = reserve_y / reserve_x;
+        self.variance = 0.0;
+        self.fees(reserve_x)
+    }
+
+    /// Observe a trade and return the fees for the next one.
+    pub fn after_trade(&mut self, trade: &TradeInfo) -> FeeQuote {
+        let reserve_x = trade.reserve_x.to_f64();
+        let price = trade.reserve_y.to_f64() / reserve_x;
+        if let NativeStrategy::VolatilityScaled { alpha, .. } = self.strategy {
+            let log_return = (price / self.last_price).ln();
+            if log_return.is_finite() {
+                self.variance = (1.0 - alpha) * self.variance + alpha * log_return * log_return;
+            }
+        }
+        self.last_price = price;
+        self.fees(reserve_x)
+    }
+
+    fn fees(&self, reserve_x: f64) -> FeeQuote {
+        let (bid_fee, ask_fee) = match self.strategy {
+            NativeStrategy::Constant { bid_fee, ask_fee } => (bid_fee, ask_fee),
+            NativeStrategy::InventoryAware { base_fee, sensitivity } => {
+                let imbalance = (reserve_x - self.initial_x) / self.initial_x;
+                (
+                    base_fee + sensitivity * imbalance.max(0.0),
+                    base_fee + sensitivity * (-imbalance).max(0.0),
+                )
+            }
+            NativeStrategy::VolatilityScaled { base_fee, multiplier, .. } => {
+                let fee = base_fee + multiplier * self.variance.sqrt();
+                (fee, fee)
+            }
+        };
+        FeeQuote::new(Wad::from_f64(bid_fee), Wad::from_f64(ask_fee))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm::CFMM;
+
+    #[test]
+    fn test_inventory_aware_widens_ask_when_short_x() {
+        let strategy = NativeStrategy::InventoryAware {
+            base_fee: 0.003,
+            sensitivity: 0.05,
+        };
+        let mut amm = CFMM::native("inventory".into(), 100.0, 10_000.0, strategy);
+        amm.initialize().unwrap();
+        let base = Wad::from_bps(30);
+        assert_eq!(amm.fees().bid_fee, base);
+        assert_eq!(amm.fees().ask_fee, base);
+
+        // Selling 20% of its X leaves the pool short: ask widens, bid holds
+        amm.execute_sell_x(20.0, 0).unwrap();
+        let fees = amm.fees();
+        assert_eq!(fees.bid_fee, base);
+        assert!((fees.ask_fee.to_f64() - (0.003 + 0.05 * 0.2)).abs() < 1e-12, "{}", fees.ask_fee.to_f64());
+
+        // Buying it all back and more flips the widened side
+        amm.execute_buy_x(40.0, 1).unwrap();
+        let fees = amm.fees();
+        assert!(fees.bid_fee > base);
+        assert_eq!(fees.ask_fee, base);
+    }
+
+    #[test]
+    fn test_volatility_scaled_fee_rises_with_price_moves() {
+        let strategy = NativeStrategy::VolatilityScaled {
+            base_fee: 0.001,
+            multiplier: 1.0,
+            alpha: 0.5,
+        };
+        let mut amm = CFMM::native("vol".into(), 100.0, 10_000.0, strategy);
+        amm.initialize().unwrap();
+        assert_eq!(amm.fees().bid_fee, Wad::from_bps(10));
+
+        amm.execute_sell_x(5.0, 0).unwrap();
+        let after_small = amm.fees().bid_fee;
+        amm.execute_buy_x(20.0, 1).unwrap();
+        assert!(after_small > Wad::from_bps(10));
+        assert!(amm.fees().bid_fee > after_small);
+    }
+}
@@ -0,0 +1,159 @@
+//! Hand-assembled strategy bytecode for unit tests.
+//!
+//! Lets tests exercise `EVMStrategy`, `CFMM`, and the market actors without
+//! compiling Solidity. `Asm` is a minimal assembler with named jump labels;
+//! `deploy` wraps runtime code in creation code that returns it verbatim.
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use crate::evm::EVMStrategy;
+use crate::types::wad::BPS;
+
+pub const STOP: u8 = 0x00;
+pub const ADD: u8 = 0x01;
+pub const MUL: u8 = 0x02;
+pub const SUB: u8 = 0x03;
+pub const DIV: u8 = 0x04;
+pub const MOD: u8 = 0x06;
+pub const LT: u8 = 0x10;
+pub const GT: u8 = 0x11;
+pub const EQ: u8 = 0x14;
+pub const ISZERO: u8 = 0x15;
+pub const SHR: u8 = 0x1c;
+pub const CALLDATALOAD: u8 = 0x35;
+pub const CODECOPY: u8 = 0x39;
+pub const POP: u8 = 0x50;
+pub const MLOAD: u8 = 0x51;
+pub const MSTORE: u8 = 0x52;
+pub const SLOAD: u8 = 0x54;
+pub const SSTORE: u8 = 0x55;
+pub const JUMP: u8 = 0x56;
+pub const JUMPI: u8 = 0x57;
+pub const JUMPDEST: u8 = 0x5b;
+pub const DUP1: u8 = 0x80;
+pub const DUP2: u8 = 0x81;
+pub const SWAP1: u8 = 0x90;
+pub const RETURN: u8 = 0xf3;
+pub const REVERT: u8 = 0xfd;
+
+/// Minimal EVM assembler with forward-referencable labels.
+#[derive(Default)]
+pub struct Asm {
+    code: Vec<u8>,
+    labels: HashMap<&'static str, usize>,
+    fixups: Vec<(usize, &'static str)>,
+}
+
+impl Asm {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Emit raw opcodes.
+    pub fn ops(&mut self, ops: &[u8]) -> &mut Self {
+        self.code.extend_from_slice(ops);
+        self
+    }
+
+    /// Push a value using the shortest PUSHn encoding.
+    pub fn push(&mut self, value: u128) -> &mut Self {
+        let bytes = value.to_be_bytes();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count().min(15);
+        let width = 16 - skip;
+        self.code.push(0x5f + width as u8);
+        self.code.extend_from_slice(&bytes[skip..]);
+        self
+    }
+
+    /// Push the address of a label (resolved in `build`).
+    pub fn push_label(&mut self, label: &'static str) -> &mut Self {
+        self.code.push(0x61); // PUSH2
+        self.fixups.push((self.code.len(), label));
+        self.code.extend_from_slice(&[0, 0]);
+        self
+    }
+
+    /// Define a jump target at the current position.
+    pub fn label(&mut self, label: &'static str) -> &mut Self {
+        self.labels.insert(label, self.code.len());
+        self.code.push(JUMPDEST);
+        self
+    }
+
+    /// Jump to `label` if `calldata[0..4] == selector`.
+    pub fn dispatch(&mut self, selector: [u8; 4], label: &'static str) -> &mut Self {
+        self.push(0)
+            .ops(&[CALLDATALOAD])
+            .push(224)
+            .ops(&[SHR])
+            .push(u32::from_be_bytes(selector) as u128)
+            .ops(&[EQ])
+            .push_label(label)
+            .ops(&[JUMPI])
+    }
+
+    /// Push `calldata[4 + 32 * index]` (the `index`-th ABI word).
+    pub fn arg(&mut self, index: u128) -> &mut Self {
+        self.push(4 + 32 * index).ops(&[CALLDATALOAD])
+    }
+
+    /// Return the two words currently on the stack as `(second, top)`.
+    ///
+    /// With stack `[.., bid, ask]` this returns `(bid, ask)`.
+    pub fn return_pair_from_stack(&mut self) -> &mut Self {
+        self.push(0x20)
+            .ops(&[MSTORE])
+            .push(0)
+            .ops(&[MSTORE])
+            .push(0x40)
+            .push(0)
+            .ops(&[RETURN])
+    }
+
+    /// Return a constant `(bid, ask)` pair.
+    pub fn return_pair(&mut self, bid: u128, ask: u128) -> &mut Self {
+        self.push(bid).push(ask).return_pair_from_stack()
+    }
+
+    /// Revert with empty data.
+    pub fn revert(&mut self) -> &mut Self {
+        self.push(0).push(0).ops(&[REVERT])
+    }
+
+    /// Resolve labels and return the runtime code.
+    pub fn build(&self) -> Vec<u8> {
+        let mut code = self.code.clone();
+        for &(pos, label) in &self.fixups {
+            let target = *self
+                .labels
+                .get(label)
+                .unwrap_or_else(|| panic!("undefined label {label}"));
+            code[pos..pos + 2].copy_from_slice(&(target as u16).to_be_bytes());
+        }
+        code
+    }
+}
+
+/// Wrap runtime code in creation code that deploys it unchanged.
+pub fn deploy(runtime: &[u8]) -> Vec<u8> {
+    assert!(runtime.len() <= u16::MAX as usize);
+    let len = (runtime.len() as u16).to_be_bytes();
+    // PUSH2 len, DUP1, PUSH1 12, PUSH1 0, CODECOPY, PUSH1 0, RETURN
+    let mut code = vec![0x61, len[0], len[1], DUP1, 0x60, 12, 0x60, 0, CODECOPY, 0x60, 0, RETURN];
+    code.extend_from_slice(runtime);
+    code
+}
+
+/// Creation code for a strategy that always returns `(bid_bps, ask_bps)`.
+pub fn fixed_fee_bytecode(bid_bps: u128, ask_bps: u128) -> Vec<u8> {
+    let bps = BPS as u128;
+    deploy(&Asm::new().return_pair(bid_bps * bps, ask_bps * bps).build())
+}
+
+/// Deployed strategy that always returns `(bid_bps, ask_bps)`.
+pub fn fixed_fee_strategy(bid_bps: u128, ask_bps: u128) -> EVMStrategy {
+    EVMStrategy::new(fixed_fee_bytecode(bid_bps, ask_bps), "Fixed".to_string())
+        .expect("fixture bytecode deploys")
+}
@@ -2,4 +2,7 @@
 
 pub mod strategy;
 
-pub use strategy::EVMStrategy;
+#[cfg(test)]
+pub(crate) mod fixtures;
+
+pub use strategy::{DeployedBytecode, EVMStrategy, GasLimits};
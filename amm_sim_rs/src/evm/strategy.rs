@@ -7,9 +7,14 @@ use revm::{
     },
     Evm, InMemoryDB,
 };
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::types::trade_info::{encode_after_initialize, decode_fee_pair, TradeInfo, SELECTOR_GET_NAME};
+use crate::types::result::GasBreakdown;
+use crate::types::trade_info::{
+    encode_after_initialize, encode_set_oracle, decode_name, decode_strategy_output, TradeInfo,
+    SELECTOR_AFTER_ARBITRAGE, TRADE_CALLDATA_LEN, SELECTOR_AFTER_SWAP, SELECTOR_GET_NAME,
+};
 use crate::types::wad::Wad;
 
 /// Errors that can occur during EVM execution.
@@ -28,10 +33,44 @@ pub enum EVMError {
     OutOfGas,
 }
 
-/// Gas limits for strategy execution.
-const GAS_LIMIT_INIT: u64 = 250_000;
-const GAS_LIMIT_TRADE: u64 = 250_000;
-const GAS_LIMIT_NAME: u64 = 50_000;
+/// Default gas limits for strategy execution.
+pub const GAS_LIMIT_INIT: u64 = 250_000;
+pub const GAS_LIMIT_TRADE: u64 = 250_000;
+pub const GAS_LIMIT_NAME: u64 = 50_000;
+
+/// Per-call gas limits for a strategy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasLimits {
+    /// Limit for afterInitialize
+    pub init: u64,
+    /// Limit for afterSwap
+    pub trade: u64,
+    /// Limit for getName
+    pub name: u64,
+}
+
+impl Default for GasLimits {
+    fn default() -> Self {
+        Self {
+            init: GAS_LIMIT_INIT,
+            trade: GAS_LIMIT_TRADE,
+            name: GAS_LIMIT_NAME,
+        }
+    }
+}
+
+/// Evolving state of a strategy, for engine checkpoints.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyState {
+    /// Contract storage as big-endian (slot, value) pairs, sorted by slot
+    storage: Vec<([u8; 32], [u8; 32])>,
+    out_of_gas_count: u64,
+    last_gas_used: u64,
+    gas_breakdown: GasBreakdown,
+    oracle_supported: bool,
+    arbitrage_hook_supported: bool,
+    signal: Option<Wad>,
+}
 
 /// Fixed addresses for simulation.
 const STRATEGY_ADDRESS: Address = Address::new([
@@ -54,21 +93,71 @@ pub struct EVMStrategy {
     bytecode: Vec<u8>,
     /// In-memory database for EVM state
     db: InMemoryDB,
-    /// Pre-allocated calldata buffer for after_swap (196 bytes)
-    trade_calldata: [u8; 196],
+    /// Pre-allocated calldata buffer for trade hooks
+    trade_calldata: [u8; TRADE_CALLDATA_LEN],
+    /// Gas limits per call type
+    gas_limits: GasLimits,
+    /// Number of after_swap calls that ran out of gas
+    out_of_gas_count: u64,
+    /// Gas used by the most recent call
+    last_gas_used: u64,
+    /// Gas used per call type and trade side
+    gas_breakdown: GasBreakdown,
+    /// Whether setOracle has not failed yet (strategies may not implement it)
+    oracle_supported: bool,
+    /// Whether afterArbitrage has not failed yet (strategies may not implement it)
+    arbitrage_hook_supported: bool,
+    /// Third word of the latest successful fee return, if it had one
+    signal: Option<Wad>,
 }
 
 impl EVMStrategy {
     /// Create a new EVM strategy from compiled bytecode.
     pub fn new(bytecode: Vec<u8>, default_name: String) -> Result<Self, EVMError> {
+        Self::with_gas_limits(bytecode, default_name, GasLimits::default())
+    }
+
+    /// Create a new EVM strategy with custom per-call gas limits.
+    pub fn with_gas_limits(
+        bytecode: Vec<u8>,
+        default_name: String,
+        gas_limits: GasLimits,
+    ) -> Result<Self, EVMError> {
+        let db = deploy_db(&bytecode)?;
+        Self::with_db(bytecode, db, default_name, gas_limits)
+    }
+
+    /// Create a strategy from an already-deployed contract, skipping the
+    /// deployment transaction.
+    pub fn from_deployed(
+        deployed: &DeployedBytecode,
+        default_name: String,
+        gas_limits: GasLimits,
+    ) -> Result<Self, EVMError> {
+        Self::with_db(deployed.bytecode.clone(), deployed.db.clone(), default_name, gas_limits)
+    }
+
+    /// Wrap a database holding the deployed contract.
+    fn with_db(
+        bytecode: Vec<u8>,
+        db: InMemoryDB,
+        default_name: String,
+        gas_limits: GasLimits,
+    ) -> Result<Self, EVMError> {
         let mut strategy = Self {
             name: default_name,
-            bytecode: bytecode.clone(),
-            db: InMemoryDB::default(),
-            trade_calldata: [0u8; 196],
+            bytecode,
+            db,
+            trade_calldata: [0u8; TRADE_CALLDATA_LEN],
+            gas_limits,
+            out_of_gas_count: 0,
+            last_gas_used: 0,
+            gas_breakdown: GasBreakdown::default(),
+            oracle_supported: true,
+            arbitrage_hook_supported: true,
+            signal: None,
         };
 
-        strategy.deploy()?;
         strategy.fetch_name()?;
 
         Ok(strategy)
@@ -76,81 +165,17 @@ impl EVMStrategy {
 
     /// Deploy the contract to the EVM.
     fn deploy(&mut self) -> Result<(), EVMError> {
-        // Reset database
-        self.db = InMemoryDB::default();
-
-        // Give caller some balance
-        let caller_info = AccountInfo {
-            balance: U256::from(1_000_000_000_000_000_000_000u128),
-            nonce: 0,
-            code_hash: Default::default(),
-            code: None,
-        };
-        self.db.insert_account_info(CALLER_ADDRESS, caller_info);
-
-        // First, run the deployment transaction
-        let deployed_code = {
-            let mut evm = Evm::builder()
-                .with_db(&mut self.db)
-                .modify_tx_env(|tx| {
-                    tx.caller = CALLER_ADDRESS;
-                    tx.transact_to = TxKind::Create;
-                    tx.data = Bytes::copy_from_slice(&self.bytecode);
-                    tx.value = U256::ZERO;
-                    tx.gas_limit = 10_000_000;
-                })
-                .build();
-
-            let result = evm.transact_commit()
-                .map_err(|e| EVMError::DeploymentFailed(format!("{:?}", e)))?;
-
-            match result {
-                ExecutionResult::Success { output, .. } => {
-                    match output {
-                        Output::Create(code, _) => Ok(code),
-                        Output::Call(_) => {
-                            Err(EVMError::DeploymentFailed("Expected Create output".into()))
-                        }
-                    }
-                }
-                ExecutionResult::Revert { output, .. } => {
-                    Err(EVMError::DeploymentFailed(format!("Reverted: {:?}", output)))
-                }
-                ExecutionResult::Halt { reason, .. } => {
-                    Err(EVMError::DeploymentFailed(format!("Halted: {:?}", reason)))
-                }
-            }
-        }?;
-
-        // Now insert the code at our fixed address
-        let bytecode = Bytecode::new_raw(deployed_code);
-        let account_info = AccountInfo {
-            balance: U256::ZERO,
-            nonce: 1,
-            code_hash: bytecode.hash_slow(),
-            code: Some(bytecode),
-        };
-        self.db.insert_account_info(STRATEGY_ADDRESS, account_info);
-
+        self.db = deploy_db(&self.bytecode)?;
         Ok(())
     }
 
     /// Fetch the strategy name from the contract.
     fn fetch_name(&mut self) -> Result<(), EVMError> {
-        let result = self.call(&SELECTOR_GET_NAME, GAS_LIMIT_NAME)?;
-
-        // Decode string return value
-        // String is encoded as: offset (32 bytes) + length (32 bytes) + data
-        if result.len() >= 64 {
-            let offset = u256_to_usize(&result[0..32]).unwrap_or(32);
-            if offset + 32 <= result.len() {
-                let length = u256_to_usize(&result[offset..offset + 32]).unwrap_or(0);
-                if offset + 32 + length <= result.len() {
-                    if let Ok(name) = String::from_utf8(result[offset + 32..offset + 32 + length].to_vec()) {
-                        self.name = name;
-                    }
-                }
-            }
+        let result = self.call(&SELECTOR_GET_NAME, self.gas_limits.name)?;
+
+        // Malformed or empty names keep the default name
+        if let Some(name) = decode_name(&result) {
+            self.name = name;
         }
 
         Ok(())
@@ -161,15 +186,37 @@ impl EVMStrategy {
         &self.name
     }
 
+    /// Get the per-call gas limits.
+    pub fn gas_limits(&self) -> GasLimits {
+        self.gas_limits
+    }
+
+    /// Number of after_swap calls that ran out of gas.
+    pub fn out_of_gas_count(&self) -> u64 {
+        self.out_of_gas_count
+    }
+
+    /// Gas used so far, by call type and trade side.
+    pub fn gas_breakdown(&self) -> GasBreakdown {
+        self.gas_breakdown
+    }
+
+    /// Signal (third return word, see `StrategyOutput`) of the latest
+    /// successful afterInitialize or trade hook; None if it returned only
+    /// fees.
+    pub fn signal(&self) -> Option<Wad> {
+        self.signal
+    }
+
     /// Initialize the strategy with starting reserves.
     ///
     /// Returns (bid_fee, ask_fee) in WAD.
     pub fn after_initialize(&mut self, initial_x: Wad, initial_y: Wad) -> Result<(Wad, Wad), EVMError> {
         let calldata = encode_after_initialize(initial_x, initial_y);
-        let result = self.call(&calldata, GAS_LIMIT_INIT)?;
-
-        decode_fee_pair(&result)
-            .ok_or_else(|| EVMError::InvalidReturnData("Failed to decode fee pair".into()))
+        let result = self.call(&calldata, self.gas_limits.init);
+        self.gas_breakdown.after_initialize = self.last_gas_used;
+        let result = result?;
+        self.decode_fees(&result)
     }
 
     /// Handle a trade event and return updated fees.
@@ -177,19 +224,130 @@ impl EVMStrategy {
     /// Returns (bid_fee, ask_fee) in WAD.
     #[inline]
     pub fn after_swap(&mut self, trade: &TradeInfo) -> Result<(Wad, Wad), EVMError> {
+        let result = self.trade_hook(SELECTOR_AFTER_SWAP, trade);
+        self.gas_breakdown.record_swap(trade.is_buy, self.last_gas_used);
+        result
+    }
+
+    /// Handle an arbitrage trade and return updated fees.
+    ///
+    /// Calls `afterArbitrage(TradeInfo)`, falling back to `afterSwap` when
+    /// the strategy reverts or returns bad data. The first such failure is
+    /// taken to mean the hook is not implemented, and later arbitrage
+    /// trades go straight to `afterSwap` until `reset`.
+    ///
+    /// Returns (bid_fee, ask_fee) in WAD.
+    #[inline]
+    pub fn after_arbitrage(&mut self, trade: &TradeInfo) -> Result<(Wad, Wad), EVMError> {
+        if self.arbitrage_hook_supported {
+            match self.trade_hook(SELECTOR_AFTER_ARBITRAGE, trade) {
+                Err(EVMError::ExecutionFailed(_)) | Err(EVMError::InvalidReturnData(_)) => {
+                    self.arbitrage_hook_supported = false;
+                }
+                result => {
+                    self.gas_breakdown.record_swap(trade.is_buy, self.last_gas_used);
+                    return result;
+                }
+            }
+        }
+        self.after_swap(trade)
+    }
+
+    /// Call a `(TradeInfo) -> (uint256, uint256)` hook.
+    #[inline]
+    fn trade_hook(&mut self, selector: [u8; 4], trade: &TradeInfo) -> Result<(Wad, Wad), EVMError> {
         // Encode trade info into pre-allocated buffer
-        trade.encode_calldata(&mut self.trade_calldata);
+        trade.encode_calldata_for(selector, &mut self.trade_calldata);
 
         // Copy calldata to avoid borrow conflict
         let calldata = self.trade_calldata;
-        let result = self.call(&calldata, GAS_LIMIT_TRADE)?;
+        let result = match self.call(&calldata, self.gas_limits.trade) {
+            Err(EVMError::OutOfGas) => {
+                self.out_of_gas_count += 1;
+                return Err(EVMError::OutOfGas);
+            }
+            result => result?,
+        };
+        self.decode_fees(&result)
+    }
+
+    /// Decode a hook's fee return, keeping any signal it carried.
+    fn decode_fees(&mut self, result: &[u8]) -> Result<(Wad, Wad), EVMError> {
+        let output = decode_strategy_output(result)
+            .ok_or_else(|| EVMError::InvalidReturnData("Failed to decode fee pair".into()))?;
+        self.signal = output.signal;
+        Ok((output.bid_fee, output.ask_fee))
+    }
+
+    /// Pass the current oracle price to the strategy via `setOracle(uint256)`.
+    ///
+    /// The call is optional: the first failure (e.g. a revert from a strategy
+    /// without the function) disables further oracle calls until `reset`.
+    /// Returns whether the strategy accepted the price.
+    pub fn set_oracle(&mut self, price: Wad) -> bool {
+        if !self.oracle_supported {
+            return false;
+        }
+        let calldata = encode_set_oracle(price);
+        self.oracle_supported = self.call(&calldata, self.gas_limits.trade).is_ok();
+        self.oracle_supported
+    }
 
-        decode_fee_pair(&result)
-            .ok_or_else(|| EVMError::InvalidReturnData("Failed to decode fee pair".into()))
+    /// Snapshot the contract's storage and the call bookkeeping.
+    pub fn save_state(&self) -> StrategyState {
+        let mut storage: Vec<([u8; 32], [u8; 32])> = self
+            .db
+            .accounts
+            .get(&STRATEGY_ADDRESS)
+            .map(|account| {
+                account
+                    .storage
+                    .iter()
+                    .map(|(slot, value)| (slot.to_be_bytes::<32>(), value.to_be_bytes::<32>()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        storage.sort_unstable();
+        StrategyState {
+            storage,
+            out_of_gas_count: self.out_of_gas_count,
+            last_gas_used: self.last_gas_used,
+            gas_breakdown: self.gas_breakdown,
+            oracle_supported: self.oracle_supported,
+            arbitrage_hook_supported: self.arbitrage_hook_supported,
+            signal: self.signal,
+        }
+    }
+
+    /// Restore a snapshot taken by `save_state`, replacing the deployed
+    /// contract's storage.
+    pub fn load_state(&mut self, state: StrategyState) -> Result<(), EVMError> {
+        let account = self
+            .db
+            .accounts
+            .get_mut(&STRATEGY_ADDRESS)
+            .ok_or_else(|| EVMError::ExecutionFailed("strategy is not deployed".into()))?;
+        account.storage = state
+            .storage
+            .into_iter()
+            .map(|(slot, value)| (U256::from_be_bytes(slot), U256::from_be_bytes(value)))
+            .collect();
+        self.out_of_gas_count = state.out_of_gas_count;
+        self.last_gas_used = state.last_gas_used;
+        self.gas_breakdown = state.gas_breakdown;
+        self.oracle_supported = state.oracle_supported;
+        self.arbitrage_hook_supported = state.arbitrage_hook_supported;
+        self.signal = state.signal;
+        Ok(())
     }
 
     /// Reset the strategy for a new simulation.
     pub fn reset(&mut self) -> Result<(), EVMError> {
+        self.out_of_gas_count = 0;
+        self.gas_breakdown = GasBreakdown::default();
+        self.oracle_supported = true;
+        self.arbitrage_hook_supported = true;
+        self.signal = None;
         self.deploy()
     }
 
@@ -206,8 +364,10 @@ impl EVMStrategy {
             })
             .build();
 
+        self.last_gas_used = 0;
         let result = evm.transact_commit()
             .map_err(|e| EVMError::ExecutionFailed(format!("{:?}", e)))?;
+        self.last_gas_used = result.gas_used();
 
         match result {
             ExecutionResult::Success { output, .. } => {
@@ -232,30 +392,207 @@ impl EVMStrategy {
     }
 }
 
-/// Convert 32-byte big-endian slice to usize.
-fn u256_to_usize(data: &[u8]) -> Option<usize> {
-    if data.len() != 32 {
-        return None;
-    }
-    // Check upper bytes are zero
-    if data[0..24].iter().any(|&b| b != 0) {
-        return None;
+/// Contract state right after deployment.
+///
+/// Starting strategies from a snapshot runs the constructor once however
+/// many simulations use the same bytecode.
+#[derive(Clone)]
+pub struct DeployedBytecode {
+    bytecode: Vec<u8>,
+    db: InMemoryDB,
+}
+
+impl DeployedBytecode {
+    /// Deploy `bytecode` once for later use by `EVMStrategy::from_deployed`.
+    pub fn deploy(bytecode: Vec<u8>) -> Result<Self, EVMError> {
+        let db = deploy_db(&bytecode)?;
+        Ok(Self { bytecode, db })
     }
-    let mut bytes = [0u8; 8];
-    bytes.copy_from_slice(&data[24..32]);
-    Some(u64::from_be_bytes(bytes) as usize)
+}
+
+/// Run the deployment transaction for `bytecode` in a fresh database and
+/// install the resulting code at `STRATEGY_ADDRESS`.
+fn deploy_db(bytecode: &[u8]) -> Result<InMemoryDB, EVMError> {
+    let mut db = InMemoryDB::default();
+
+    // Give caller some balance
+    let caller_info = AccountInfo {
+        balance: U256::from(1_000_000_000_000_000_000_000u128),
+        nonce: 0,
+        code_hash: Default::default(),
+        code: None,
+    };
+    db.insert_account_info(CALLER_ADDRESS, caller_info);
+
+    // First, run the deployment transaction
+    let deployed_code = {
+        let mut evm = Evm::builder()
+            .with_db(&mut db)
+            .modify_tx_env(|tx| {
+                tx.caller = CALLER_ADDRESS;
+                tx.transact_to = TxKind::Create;
+                tx.data = Bytes::copy_from_slice(bytecode);
+                tx.value = U256::ZERO;
+                tx.gas_limit = 10_000_000;
+            })
+            .build();
+
+        let result = evm.transact_commit()
+            .map_err(|e| EVMError::DeploymentFailed(format!("{:?}", e)))?;
+
+        match result {
+            ExecutionResult::Success { output, .. } => {
+                match output {
+                    Output::Create(code, _) => Ok(code),
+                    Output::Call(_) => {
+                        Err(EVMError::DeploymentFailed("Expected Create output".into()))
+                    }
+                }
+            }
+            ExecutionResult::Revert { output, .. } => {
+                Err(EVMError::DeploymentFailed(format!("Reverted: {:?}", output)))
+            }
+            ExecutionResult::Halt { reason, .. } => {
+                Err(EVMError::DeploymentFailed(format!("Halted: {:?}", reason)))
+            }
+        }
+    }?;
+
+    // Now insert the code at our fixed address
+    let bytecode = Bytecode::new_raw(deployed_code);
+    let account_info = AccountInfo {
+        balance: U256::ZERO,
+        nonce: 1,
+        code_hash: bytecode.hash_slow(),
+        code: Some(bytecode),
+    };
+    db.insert_account_info(STRATEGY_ADDRESS, account_info);
+
+    Ok(db)
 }
 
 impl Clone for EVMStrategy {
     fn clone(&self) -> Self {
         // Create a fresh strategy from bytecode
-        Self::new(self.bytecode.clone(), self.name.clone())
+        Self::with_gas_limits(self.bytecode.clone(), self.name.clone(), self.gas_limits)
             .expect("Failed to clone EVMStrategy")
     }
 }
 
 #[cfg(test)]
 mod tests {
-    // Note: Full tests require EVM bytecode, which is complex to embed.
-    // The Python integration tests will verify correctness.
+    use super::*;
+    use crate::evm::fixtures::{deploy, Asm, DUP1, JUMPI, POP, SUB, SWAP1};
+    use crate::types::trade_info::{SELECTOR_AFTER_ARBITRAGE, SELECTOR_AFTER_SWAP};
+    use crate::types::wad::BPS;
+
+    /// Strategy whose afterSwap spins for `iterations` loop passes
+    /// (~26 gas each) before returning 30 bps.
+    fn expensive_swap_bytecode(iterations: u128) -> Vec<u8> {
+        let fee = 30 * BPS as u128;
+        let runtime = Asm::new()
+            .dispatch(SELECTOR_AFTER_SWAP, "swap")
+            .return_pair(fee, fee)
+            .label("swap")
+            .push(iterations)
+            .label("loop")
+            .push(1)
+            .ops(&[SWAP1, SUB, DUP1])
+            .push_label("loop")
+            .ops(&[JUMPI, POP])
+            .return_pair(fee, fee)
+            .build();
+        deploy(&runtime)
+    }
+
+    fn trade() -> TradeInfo {
+        TradeInfo::new(
+            true,
+            Wad::from_f64(1.0),
+            Wad::from_f64(1.0),
+            0,
+            0,
+            Wad::from_f64(1001.0),
+            Wad::from_f64(999.0),
+        )
+    }
+
+    #[test]
+    fn test_expensive_strategy_needs_raised_gas_limit() {
+        let bytecode = expensive_swap_bytecode(15_000);
+
+        let mut default = EVMStrategy::new(bytecode.clone(), "Expensive".into()).unwrap();
+        assert!(default.after_initialize(Wad::one(), Wad::one()).is_ok());
+        assert!(matches!(default.after_swap(&trade()), Err(EVMError::OutOfGas)));
+        assert_eq!(default.out_of_gas_count(), 1);
+
+        let limits = GasLimits {
+            trade: 1_000_000,
+            ..GasLimits::default()
+        };
+        let mut raised = EVMStrategy::with_gas_limits(bytecode, "Expensive".into(), limits).unwrap();
+        let (bid, ask) = raised.after_swap(&trade()).unwrap();
+        assert_eq!(bid, Wad::from_bps(30));
+        assert_eq!(ask, Wad::from_bps(30));
+        assert_eq!(raised.out_of_gas_count(), 0);
+    }
+
+    #[test]
+    fn test_gas_breakdown_splits_buys_and_sells() {
+        // Burns ~26 gas per iteration on buys only
+        let fee = 30 * BPS as u128;
+        let runtime = Asm::new()
+            .dispatch(SELECTOR_AFTER_SWAP, "swap")
+            .return_pair(fee, fee)
+            .label("swap")
+            .arg(0)
+            .push_label("buy")
+            .ops(&[JUMPI])
+            .return_pair(fee, fee)
+            .label("buy")
+            .push(1_000)
+            .label("loop")
+            .push(1)
+            .ops(&[SWAP1, SUB, DUP1])
+            .push_label("loop")
+            .ops(&[JUMPI, POP])
+            .return_pair(fee, fee)
+            .build();
+        let mut strategy = EVMStrategy::new(deploy(&runtime), "BuyHeavy".into()).unwrap();
+        strategy.after_initialize(Wad::one(), Wad::one()).unwrap();
+
+        let sell = TradeInfo { is_buy: false, ..trade() };
+        for _ in 0..3 {
+            strategy.after_swap(&trade()).unwrap();
+            strategy.after_swap(&sell).unwrap();
+        }
+        strategy.after_swap(&sell).unwrap();
+
+        let gas = strategy.gas_breakdown();
+        assert!(gas.after_initialize > 21_000);
+        assert_eq!((gas.buy_swaps, gas.sell_swaps), (3, 4));
+        assert!(gas.avg_sell_swap_gas() > 21_000.0);
+        assert!(gas.avg_buy_swap_gas() > gas.avg_sell_swap_gas() + 20_000.0);
+
+        strategy.reset().unwrap();
+        assert_eq!(strategy.gas_breakdown(), GasBreakdown::default());
+    }
+
+    #[test]
+    fn test_after_arbitrage_falls_back_to_after_swap() {
+        let bps = BPS as u128;
+        let runtime = Asm::new()
+            .dispatch(SELECTOR_AFTER_ARBITRAGE, "arb")
+            .return_pair(20 * bps, 20 * bps)
+            .label("arb")
+            .revert()
+            .build();
+        let mut strategy = EVMStrategy::new(deploy(&runtime), "NoArbHook".into()).unwrap();
+        for _ in 0..3 {
+            let (bid, ask) = strategy.after_arbitrage(&trade()).unwrap();
+            assert_eq!((bid, ask), (Wad::from_bps(20), Wad::from_bps(20)));
+        }
+        // The reverted probe is not counted as a swap
+        assert_eq!(strategy.gas_breakdown().buy_swaps, 3);
+    }
 }
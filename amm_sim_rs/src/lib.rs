@@ -10,68 +10,120 @@ pub mod amm;
 pub mod market;
 pub mod simulation;
 
-use pyo3::prelude::*;
+#[cfg(feature = "python")]
+mod python;
 
-use crate::simulation::runner::{run_simulations_parallel, SimulationBatchConfig};
+use crate::amm::CFMM;
+use crate::evm::strategy::EVMStrategy;
+use crate::simulation::engine::SimulationError;
+use crate::simulation::runner::{
+    replay, run_batch_summary, run_simulation, run_simulations_parallel, SimulationBatchConfig,
+};
 use crate::types::config::SimulationConfig;
-use crate::types::result::{BatchSimulationResult, LightweightSimResult};
+use crate::types::result::{BatchSimulationResult, LightweightSimResult, SimulationSummary};
 
-/// Run multiple simulations in parallel using Rust engine.
+/// Run multiple simulations in parallel.
+///
+/// Native entry point for embedding the engine without Python; the
+/// `run_batch` Python function wraps the same runner.
 ///
 /// # Arguments
 /// * `submission_bytecode` - Compiled bytecode for the submission strategy
 /// * `baseline_bytecode` - Compiled bytecode for the baseline strategy
-/// * `configs` - List of simulation configurations (one per simulation)
+/// * `configs` - Simulation configurations (one per simulation)
 /// * `n_workers` - Number of parallel workers (0 = auto-detect)
+pub fn run_batch_rs(
+    submission_bytecode: Vec<u8>,
+    baseline_bytecode: Vec<u8>,
+    configs: Vec<SimulationConfig>,
+    n_workers: usize,
+) -> Result<BatchSimulationResult, SimulationError> {
+    run_simulations_parallel(SimulationBatchConfig::new(
+        submission_bytecode,
+        baseline_bytecode,
+        configs,
+        n_workers,
+    ))
+}
+
+/// Run multiple simulations in parallel, keeping only each one's seed,
+/// PnLs, edges and winner.
 ///
-/// # Returns
-/// BatchSimulationResult containing all simulation results
-#[pyfunction]
-#[pyo3(signature = (submission_bytecode, baseline_bytecode, configs, n_workers = 0))]
-fn run_batch(
+/// Native counterpart of the `run_batch_summary` Python function; per-step
+/// results are never built, which makes very large sweeps cheaper.
+pub fn run_batch_summary_rs(
     submission_bytecode: Vec<u8>,
     baseline_bytecode: Vec<u8>,
     configs: Vec<SimulationConfig>,
     n_workers: usize,
-) -> PyResult<BatchSimulationResult> {
-    let batch_config = SimulationBatchConfig {
+) -> Result<Vec<SimulationSummary>, SimulationError> {
+    run_batch_summary(SimulationBatchConfig::new(
         submission_bytecode,
         baseline_bytecode,
         configs,
-        n_workers: if n_workers == 0 { None } else { Some(n_workers) },
-    };
+        n_workers,
+    ))
+}
 
-    run_simulations_parallel(batch_config)
-        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+/// Run one config across several seeds in parallel.
+///
+/// Each simulation is `base_config` with its seed replaced, in the order
+/// of `seeds`.
+pub fn run_seed_sweep_rs(
+    submission_bytecode: Vec<u8>,
+    baseline_bytecode: Vec<u8>,
+    base_config: &SimulationConfig,
+    seeds: &[u64],
+    n_workers: usize,
+) -> Result<BatchSimulationResult, SimulationError> {
+    let configs = seeds
+        .iter()
+        .map(|&seed| SimulationConfig {
+            seed: Some(seed),
+            ..base_config.clone()
+        })
+        .collect();
+    run_batch_rs(submission_bytecode, baseline_bytecode, configs, n_workers)
 }
 
-/// Run a single simulation and return lightweight result.
-#[pyfunction]
-fn run_single(
+/// Run a single simulation and return its lightweight result.
+///
+/// Native counterpart of the `run_single` Python function.
+pub fn run_single_rs(
     submission_bytecode: Vec<u8>,
     baseline_bytecode: Vec<u8>,
     config: SimulationConfig,
-) -> PyResult<LightweightSimResult> {
-    use crate::simulation::engine::SimulationEngine;
-    use crate::evm::strategy::EVMStrategy;
-
-    let submission = EVMStrategy::new(submission_bytecode, "Submission".to_string())
-        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
-    let baseline = EVMStrategy::new(baseline_bytecode, "Baseline".to_string())
-        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+) -> Result<LightweightSimResult, SimulationError> {
+    run_simulation(submission_bytecode, baseline_bytecode, config)
+}
 
-    let mut engine = SimulationEngine::new(config);
-    engine.run(submission, baseline)
-        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+/// Rerun the simulation behind a result, optionally in verbose mode.
+///
+/// Native counterpart of the `replay` Python function; see
+/// `simulation::runner::replay`.
+pub fn replay_rs(
+    result: &LightweightSimResult,
+    submission_bytecode: Vec<u8>,
+    baseline_bytecode: Vec<u8>,
+    verbose: bool,
+) -> Result<LightweightSimResult, SimulationError> {
+    replay(result, submission_bytecode, baseline_bytecode, verbose)
 }
 
-/// Python module definition
-#[pymodule]
-fn amm_sim_rs(m: &Bound<'_, PyModule>) -> PyResult<()> {
-    m.add_function(wrap_pyfunction!(run_batch, m)?)?;
-    m.add_function(wrap_pyfunction!(run_single, m)?)?;
-    m.add_class::<SimulationConfig>()?;
-    m.add_class::<LightweightSimResult>()?;
-    m.add_class::<BatchSimulationResult>()?;
-    Ok(())
+/// Deploy a strategy and report its name and initial `(bid_fee, ask_fee)`
+/// for the given reserves, without running a simulation.
+///
+/// Fees are fractions (0.003 = 30 bps) after the default pool's fee
+/// clamping, i.e. what a pool would start a run with.
+pub fn inspect_strategy(
+    bytecode: Vec<u8>,
+    initial_x: f64,
+    initial_y: f64,
+) -> Result<(String, f64, f64), SimulationError> {
+    let strategy = EVMStrategy::new(bytecode, "Strategy".to_string())?;
+    let name = strategy.name().to_string();
+    let mut amm = CFMM::new(strategy, initial_x, initial_y);
+    amm.initialize()?;
+    let fees = amm.fees();
+    Ok((name, fees.bid_fee.to_f64(), fees.ask_fee.to_f64()))
 }
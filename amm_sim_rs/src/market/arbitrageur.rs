@@ -1,6 +1,10 @@
 //! Arbitrageur logic for extracting profit from mispriced AMMs.
 
-use crate::amm::CFMM;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::amm::{FeeModel, CFMM};
+use crate::market::price_process::CIRProcess;
 
 /// Result of an arbitrage attempt.
 #[derive(Debug, Clone)]
@@ -17,22 +21,151 @@ pub struct ArbResult {
     pub amount_y: f64,
 }
 
+impl ArbResult {
+    /// Profit from the arbitrage with X valued at `price` rather than the
+    /// price the arbitrageur traded toward.
+    pub fn profit_at(&self, price: f64) -> f64 {
+        if self.side == "sell" {
+            self.amount_x * price - self.amount_y
+        } else {
+            self.amount_y - self.amount_x * price
+        }
+    }
+}
+
+/// Closed-form loss-versus-rebalancing rate of a fee-free constant-product
+/// pool worth `pool_value`, arbitraged continuously while its price follows
+/// a GBM with volatility `sigma`: `sigma^2 / 8 * pool_value` per unit time.
+pub fn theoretical_lvr(sigma: f64, pool_value: f64) -> f64 {
+    sigma * sigma / 8.0 * pool_value
+}
+
 /// Arbitrageur that extracts profit from mispriced AMMs.
 ///
 /// Uses closed-form solutions for constant product AMMs.
-/// For reserves (x, y), k=xy, fee f (fee-on-input), γ = 1 - f, and fair price p (Y per X):
-/// - Buy X from AMM (AMM sells X): Δx_out = x - sqrt(k / (γ·p)) (profit-maximizing)
-/// - Sell X to AMM (AMM buys X): Δx_in = (sqrt(k·γ / p) - x) / γ (profit-maximizing, Δx_in is gross input)
-pub struct Arbitrageur;
+/// For reserves (x, y), k=xy, fee f, γ = 1 - f, and fair price p (Y per X):
+/// - Buy X from AMM (AMM sells X): the pool releases x - sqrt(k / (γ·p)) X
+/// - Sell X to AMM (AMM buys X): the pool absorbs sqrt(k·γ / p) - x X
+///
+/// The optimal curve move is the same under both fee models; only the
+/// trader-side amount differs. Fee-on-input grosses the sold X up by 1/γ,
+/// fee-on-output nets the bought X down by γ.
+///
+/// Each arbitrageur has a fixed gas cost (in Y) that a trade's profit must
+/// exceed, and a probability of being active on any given step. Several
+/// arbitrageurs with different parameters model competing searchers.
+///
+/// An arbitrageur may also pay a funding rate on the capital a trade ties
+/// up (the Y value it puts in), driven by a CIR process stepped once per
+/// simulation step; the funding cost is added to the gas hurdle.
+///
+/// A minimum edge widens the band it leaves alone: it only trades when the
+/// fair price lies more than `min_edge_bps` beyond the pool's fee band
+/// (spot / γ when buying X, spot · γ when selling), and then trades all the
+/// way to the band edge as usual.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Arbitrageur {
+    /// Cost (in Y) of submitting an arbitrage; trades must clear it
+    gas_cost: f64,
+    /// Probability of acting on a given step
+    act_prob: f64,
+    /// Funding rate charged on each trade's capital (None = free capital)
+    funding: Option<CIRProcess>,
+    /// Mispricing beyond the fee band (bps) needed before trading
+    min_edge_bps: f64,
+}
 
 impl Arbitrageur {
-    /// Create a new arbitrageur.
+    /// Create a new arbitrageur with no gas cost that always acts.
     pub fn new() -> Self {
-        Self
+        Self::with_params(0.0, 1.0)
+    }
+
+    /// Create an arbitrageur with the given gas cost and act probability.
+    pub fn with_params(gas_cost: f64, act_prob: f64) -> Self {
+        Self {
+            gas_cost: gas_cost.max(0.0),
+            act_prob: act_prob.clamp(0.0, 1.0),
+            funding: None,
+            min_edge_bps: 0.0,
+        }
+    }
+
+    /// Only trade on mispricings more than `min_edge_bps` beyond the fee
+    /// band (below 0 is treated as 0).
+    pub fn with_min_edge_bps(mut self, min_edge_bps: f64) -> Self {
+        self.min_edge_bps = min_edge_bps.max(0.0);
+        self
+    }
+
+    /// Charge a funding rate from `process` on the capital of each trade.
+    pub fn with_funding(mut self, process: CIRProcess) -> Self {
+        self.funding = Some(process);
+        self
+    }
+
+    /// Current funding rate (0 without a funding process).
+    pub fn funding_rate(&self) -> f64 {
+        self.funding.as_ref().map_or(0.0, CIRProcess::current_rate)
+    }
+
+    /// Advance the funding rate by one step (no-op without a funding
+    /// process).
+    pub fn step_funding(&mut self) {
+        if let Some(funding) = &mut self.funding {
+            funding.step();
+        }
+    }
+
+    /// Minimum profit (in Y) for a trade tying up `capital` Y: gas plus
+    /// funding.
+    #[inline]
+    fn hurdle(&self, capital: f64) -> f64 {
+        match &self.funding {
+            Some(funding) => self.gas_cost + funding.current_rate() * capital,
+            None => self.gas_cost,
+        }
+    }
+
+    /// Gas cost (in Y) per arbitrage.
+    pub fn gas_cost(&self) -> f64 {
+        self.gas_cost
+    }
+
+    /// Probability of acting on a given step.
+    pub fn act_prob(&self) -> f64 {
+        self.act_prob
+    }
+
+    /// Mispricing beyond the fee band (bps) needed before trading.
+    pub fn min_edge_bps(&self) -> f64 {
+        self.min_edge_bps
+    }
+
+    /// Whether `high` exceeds `low` by more than the minimum edge (always
+    /// without one; the optimal trade size then decides alone).
+    #[inline]
+    fn clears_min_edge(&self, low: f64, high: f64) -> bool {
+        self.min_edge_bps == 0.0 || high > low * (1.0 + self.min_edge_bps / 10_000.0)
+    }
+
+    /// Draw whether this arbitrageur is active this step.
+    ///
+    /// Always-on arbitrageurs do not consume randomness.
+    #[inline]
+    pub fn should_act<R: Rng>(&self, rng: &mut R) -> bool {
+        self.act_prob >= 1.0 || rng.gen::<f64>() < self.act_prob
     }
 
     /// Find and execute the optimal arbitrage trade.
+    ///
+    /// The reported profit is gross of gas and funding; the trade is only
+    /// taken if it exceeds the arbitrageur's gas cost plus funding cost.
     pub fn execute_arb(&self, amm: &mut CFMM, fair_price: f64, timestamp: u64) -> Option<ArbResult> {
+        // An empty pool has no price to arbitrage
+        if !amm.has_liquidity() || !fair_price.is_finite() {
+            return None;
+        }
         let (rx, ry) = amm.reserves();
         let spot_price = ry / rx;
 
@@ -50,7 +183,8 @@ impl Arbitrageur {
     /// Compute and execute optimal trade when buying X from AMM.
     ///
     /// Maximize profit = Δx * p - Y_paid
-    /// Closed-form (fee-on-input): Δx_out = x - sqrt(k / (γ·p))
+    /// Closed-form: Δx_out = x - sqrt(k / (γ·p)) leaves the pool, of which
+    /// the trader receives γ·Δx_out under fee-on-output.
     fn compute_buy_arb(&self, amm: &mut CFMM, fair_price: f64, timestamp: u64) -> Option<ArbResult> {
         let (rx, ry) = amm.reserves();
         let k = rx * ry;
@@ -60,17 +194,27 @@ impl Arbitrageur {
         if gamma <= 0.0 || fair_price <= 0.0 {
             return None;
         }
+        // The fair price must exceed the pool's ask by the minimum edge
+        if !self.clears_min_edge(ry / (rx * gamma), fair_price) {
+            return None;
+        }
 
-        // Optimal trade size
+        // Optimal trade size (X leaving the pool)
         let new_x = (k / (gamma * fair_price)).sqrt();
-        let amount_x = rx - new_x;
+        let gross_x = rx - new_x;
 
-        if amount_x <= 0.0 {
+        if gross_x <= 0.0 {
             return None;
         }
 
         // Cap at 99% of reserves
-        let amount_x = amount_x.min(rx * 0.99);
+        let gross_x = gross_x.min(rx * 0.99);
+        let amount_x = match amm.fee_model() {
+            FeeModel::OnInput => gross_x,
+            FeeModel::OnOutput => gross_x * gamma,
+        };
+        // The AMM may cap single trades below the optimum
+        let amount_x = amm.capped_x(amount_x);
 
         // Use fast quote to compute profit
         let (total_y, _) = amm.quote_sell_x(amount_x);
@@ -81,7 +225,7 @@ impl Arbitrageur {
         // Profit = value of X at fair price - Y paid
         let profit = amount_x * fair_price - total_y;
 
-        if profit <= 0.0 {
+        if profit <= self.hurdle(total_y) {
             return None;
         }
 
@@ -100,7 +244,9 @@ impl Arbitrageur {
     /// Compute and execute optimal trade when selling X to AMM.
     ///
     /// Maximize profit = Y_received - Δx * p
-    /// Closed-form (fee-on-input): Δx_in = (sqrt(k·γ / p) - x) / γ
+    /// Closed-form: the pool absorbs sqrt(k·γ / p) - x, so the trader sends
+    /// Δx_in = (sqrt(k·γ / p) - x) / γ under fee-on-input and
+    /// Δx_in = sqrt(k·γ / p) - x under fee-on-output.
     fn compute_sell_arb(&self, amm: &mut CFMM, fair_price: f64, timestamp: u64) -> Option<ArbResult> {
         let (rx, ry) = amm.reserves();
         let k = rx * ry;
@@ -110,16 +256,26 @@ impl Arbitrageur {
         if gamma <= 0.0 || fair_price <= 0.0 {
             return None;
         }
+        // The pool's bid must exceed the fair price by the minimum edge
+        if !self.clears_min_edge(fair_price, ry * gamma / rx) {
+            return None;
+        }
 
         // Optimal trade size (gross input):
-        // x + γ·Δx_in = sqrt(k·γ/p)  =>  Δx_in = (sqrt(k·γ/p) - x) / γ
+        // fee-on-input:  x + γ·Δx_in = sqrt(k·γ/p)  =>  Δx_in = (sqrt(k·γ/p) - x) / γ
+        // fee-on-output: x + Δx_in = sqrt(k·γ/p)
         let x_virtual = (k * gamma / fair_price).sqrt();
         let net_x = x_virtual - rx;
-        let amount_x = net_x / gamma;
+        let amount_x = match amm.fee_model() {
+            FeeModel::OnInput => net_x / gamma,
+            FeeModel::OnOutput => net_x,
+        };
 
         if amount_x <= 0.0 {
             return None;
         }
+        // The AMM may cap single trades below the optimum
+        let amount_x = amm.capped_x(amount_x);
 
         // Use fast quote to compute profit
         let (y_out, _) = amm.quote_buy_x(amount_x);
@@ -130,7 +286,7 @@ impl Arbitrageur {
         // Profit = Y received - cost of X at fair price
         let profit = y_out - amount_x * fair_price;
 
-        if profit <= 0.0 {
+        if profit <= self.hurdle(amount_x * fair_price) {
             return None;
         }
 
@@ -163,6 +319,15 @@ impl Default for Arbitrageur {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::amm::cfmm::FeeQuote;
+    use crate::evm::fixtures::fixed_fee_strategy;
+    use crate::types::wad::Wad;
+
+    fn fixed_fee_amm(fee_bps: u128) -> CFMM {
+        let mut amm = CFMM::new(fixed_fee_strategy(fee_bps, fee_bps), 1000.0, 1000.0);
+        amm.initialize().unwrap();
+        amm
+    }
 
     fn quote_buy_x(reserve_x: f64, reserve_y: f64, fee: f64, amount_x_in: f64) -> f64 {
         if amount_x_in <= 0.0 {
@@ -199,7 +364,7 @@ mod tests {
     #[test]
     fn test_arb_formulas() {
         // Test the closed-form formulas without EVM
-        let rx = 1000.0;
+        let rx: f64 = 1000.0;
         let ry = 1000.0;
         let k = rx * ry;
         let fee = 0.0025; // 25 bps
@@ -220,7 +385,7 @@ mod tests {
 
     #[test]
     fn test_arb_sizes_maximize_profit() {
-        let rx = 1000.0;
+        let rx: f64 = 1000.0;
         let ry = 1000.0;
         let k = rx * ry;
         let fee = 0.05; // 5%
@@ -256,7 +421,7 @@ mod tests {
 
     #[test]
     fn test_arb_moves_price_into_no_arb_band() {
-        let rx = 1000.0;
+        let rx: f64 = 1000.0;
         let ry = 1000.0;
         let fee = 0.05; // 5%
         let gamma = 1.0 - fee;
@@ -282,4 +447,158 @@ mod tests {
         let spot2 = ry2 / rx2;
         assert!(spot2 <= fair_price / gamma + 1e-9);
     }
+
+    #[test]
+    fn test_no_arb_on_empty_pool() {
+        let fees = FeeQuote::symmetric(Wad::from_bps(30));
+        for (x, y) in [(0.0, 0.0), (0.0, 1000.0), (1000.0, 0.0)] {
+            let mut amm = CFMM::fixed_fee("empty".into(), x, y, fees);
+            amm.initialize().unwrap();
+            for fair_price in [0.5, 2.0, f64::NAN] {
+                assert!(Arbitrageur::new().execute_arb(&mut amm, fair_price, 0).is_none());
+            }
+            assert_eq!(amm.reserves(), (x, y));
+        }
+    }
+
+    #[test]
+    fn test_multiple_arbs_bounded_by_single_arb_profit() {
+        for fair_price in [0.8, 1.25] {
+            let mut single_amm = fixed_fee_amm(30);
+            let single_profit = Arbitrageur::new()
+                .execute_arb(&mut single_amm, fair_price, 0)
+                .map(|r| r.profit)
+                .unwrap();
+
+            // Competing searchers with different hurdles, in every order
+            let arbs = [
+                Arbitrageur::with_params(5.0, 1.0),
+                Arbitrageur::with_params(0.5, 1.0),
+                Arbitrageur::new(),
+            ];
+            for order in [[0, 1, 2], [2, 1, 0], [1, 0, 2]] {
+                let mut amm = fixed_fee_amm(30);
+                let total: f64 = order
+                    .iter()
+                    .filter_map(|&i| arbs[i].execute_arb(&mut amm, fair_price, 0))
+                    .map(|r| r.profit)
+                    .sum();
+                assert!(total > 0.0);
+                assert!(total <= single_profit + 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gas_cost_blocks_unprofitable_arb() {
+        let mut amm = fixed_fee_amm(30);
+        let expensive = Arbitrageur::with_params(1e6, 1.0);
+        assert!(expensive.execute_arb(&mut amm, 1.25, 0).is_none());
+        assert_eq!(amm.reserves(), (1000.0, 1000.0));
+    }
+
+    #[test]
+    fn test_min_edge_ignores_small_mispricings() {
+        // 30 bps pool at spot 1: the fee band is [0.997, 1 / 0.997]
+        let picky = Arbitrageur::new().with_min_edge_bps(10.0);
+        let cases = [(1.0035, true), (0.9965, true), (1.006, false), (0.994, false)];
+        for (fair_price, inside) in cases {
+            let mut amm = fixed_fee_amm(30);
+            let mut plain_amm = fixed_fee_amm(30);
+            let plain = Arbitrageur::new().execute_arb(&mut plain_amm, fair_price, 0);
+            assert!(plain.is_some(), "{fair_price}");
+
+            let arb = picky.execute_arb(&mut amm, fair_price, 0);
+            if inside {
+                assert!(arb.is_none(), "{fair_price}");
+                assert_eq!(amm.reserves(), (1000.0, 1000.0));
+            } else {
+                // Past the threshold it takes the same optimal trade
+                assert_eq!(arb.unwrap().amount_x, plain.unwrap().amount_x);
+                assert_eq!(amm.reserves(), plain_amm.reserves());
+            }
+        }
+    }
+
+    #[test]
+    fn test_output_fee_arb_is_optimal() {
+        for fair_price in [0.8, 1.25] {
+            let new_amm = || {
+                let mut amm = fixed_fee_amm(100);
+                amm.set_fee_model(FeeModel::OnOutput);
+                amm
+            };
+
+            let mut amm = new_amm();
+            let result = Arbitrageur::new().execute_arb(&mut amm, fair_price, 0).unwrap();
+
+            // After the trade the price sits on the edge of the no-arb band
+            let (lower, upper) = amm.no_arb_band();
+            let edge = if fair_price > 1.0 { upper } else { lower };
+            assert!((edge - fair_price).abs() < 1e-9);
+
+            // Perturbing the size does not improve profit
+            let probe = new_amm();
+            for scale in [0.99, 1.01] {
+                let amount_x = result.amount_x * scale;
+                let profit = if result.side == "sell" {
+                    amount_x * fair_price - probe.quote_sell_x(amount_x).0
+                } else {
+                    probe.quote_buy_x(amount_x).0 - amount_x * fair_price
+                };
+                assert!(result.profit >= profit - 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_trade_cap_leaves_residual_mispricing() {
+        // Fair price 50% above spot: the optimal arb buys ~17% of the X reserve
+        let fair_price = 1.5;
+        let mut uncapped = fixed_fee_amm(30);
+        let full = Arbitrageur::new().execute_arb(&mut uncapped, fair_price, 0).unwrap();
+        let (lower, upper) = uncapped.no_arb_band();
+        assert!(fair_price >= lower && fair_price <= upper * (1.0 + 1e-9));
+
+        let mut capped = fixed_fee_amm(30);
+        capped.set_max_trade_fraction(Some(0.05));
+        let clamped = Arbitrageur::new().execute_arb(&mut capped, fair_price, 0).unwrap();
+        assert!((clamped.amount_x - 50.0).abs() < 1e-9, "{}", clamped.amount_x);
+        assert!(clamped.amount_x < full.amount_x);
+        assert!(clamped.profit < full.profit);
+        assert_eq!(capped.reserves().0, 950.0);
+
+        // Spot is still well below the band the arb would have reached
+        let (_, upper) = capped.no_arb_band();
+        assert!(upper < fair_price * 0.9, "{upper}");
+    }
+
+    #[test]
+    fn test_rising_funding_rate_deters_arbs() {
+        // Rate climbs deterministically from 0 toward 5% per step
+        let funding = CIRProcess::new(0.0, 0.05, 0.05, 0.0, 1.0, Some(0));
+        let mut funded = Arbitrageur::new().with_funding(funding);
+        let unfunded = Arbitrageur::new();
+
+        let mut funded_amm = fixed_fee_amm(30);
+        let mut unfunded_amm = fixed_fee_amm(30);
+        let (mut funded_arbs, mut unfunded_arbs) = (Vec::new(), 0);
+        for t in 0..200 {
+            let fair_price = if t % 2 == 0 { 1.02 } else { 0.98 };
+            funded.step_funding();
+            if funded.execute_arb(&mut funded_amm, fair_price, t).is_some() {
+                funded_arbs.push(t);
+            }
+            if unfunded.execute_arb(&mut unfunded_amm, fair_price, t).is_some() {
+                unfunded_arbs += 1;
+            }
+        }
+
+        assert_eq!(unfunded_arbs, 200);
+        assert!(!funded_arbs.is_empty());
+        assert!(funded_arbs.len() < unfunded_arbs);
+        // Arbs only happen while funding is still cheap
+        assert!(funded_arbs.iter().all(|&t| t < 100), "{funded_arbs:?}");
+        assert!(funded.funding_rate() > 0.04);
+    }
 }
@@ -3,9 +3,22 @@
 pub mod price_process;
 pub mod arbitrageur;
 pub mod retail;
+pub mod reputation;
 pub mod router;
+pub mod sandwich;
+pub mod oracle;
 
-pub use price_process::GBMPriceProcess;
-pub use arbitrageur::Arbitrageur;
-pub use retail::{RetailTrader, RetailOrder};
-pub use router::OrderRouter;
+pub use price_process::{
+    CIRProcess, CorrelatedGBM, GBMPriceProcess, PriceFeeds, PriceProcess, ReplayPriceProcess,
+};
+pub use arbitrageur::{theoretical_lvr, Arbitrageur};
+pub use retail::{
+    aggregate_orders, RetailBatching, RetailCohort, RetailFlowStyle, RetailMode, RetailOrder,
+    RetailTrader, FLOW_LOOKBACK,
+};
+pub use reputation::FlowReputation;
+pub use router::{
+    OrderRouter, RouterMode, RoutingDiagnostics, DEFAULT_MIN_ROUTED_AMOUNT, RESIDUAL_GAP_TOLERANCE,
+};
+pub use sandwich::{SandwichResult, Sandwicher};
+pub use oracle::EmaOracle;
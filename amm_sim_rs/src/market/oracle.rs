@@ -0,0 +1,100 @@
+//! Exponential moving average price oracle fed to strategies.
+
+use serde::{Deserialize, Serialize};
+
+/// Exponential moving average of a price series.
+///
+/// Each update moves the average a fraction `alpha` of the way toward the
+/// new observation, so a constant price is approached geometrically at
+/// rate `1 - alpha` per step.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct EmaOracle {
+    /// Smoothing factor in (0, 1]; 1 tracks the latest price exactly
+    alpha: f64,
+    /// Current average
+    value: f64,
+}
+
+impl EmaOracle {
+    /// Create an oracle starting at `initial_price`.
+    pub fn new(alpha: f64, initial_price: f64) -> Self {
+        Self {
+            alpha: alpha.clamp(f64::MIN_POSITIVE, 1.0),
+            value: initial_price,
+        }
+    }
+
+    /// Fold in a new price observation and return the updated average.
+    #[inline]
+    pub fn update(&mut self, price: f64) -> f64 {
+        self.value += self.alpha * (price - self.value);
+        self.value
+    }
+
+    /// Get the current average.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Get the smoothing factor.
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm::CFMM;
+    use crate::evm::fixtures::{deploy, Asm, DIV, SLOAD, SSTORE, STOP};
+    use crate::evm::EVMStrategy;
+    use crate::types::trade_info::{SELECTOR_AFTER_SWAP, SELECTOR_SET_ORACLE};
+
+    #[test]
+    fn test_ema_converges_geometrically() {
+        let mut oracle = EmaOracle::new(0.25, 50.0);
+        assert_eq!(oracle.update(100.0), 62.5);
+        for _ in 0..99 {
+            oracle.update(100.0);
+        }
+        assert!((oracle.value() - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_strategy_reads_oracle() {
+        // Stores the oracle price and charges price / 10_000 as its fee, so
+        // the AMM's fees reveal what the strategy last saw
+        let runtime = Asm::new()
+            .dispatch(SELECTOR_SET_ORACLE, "oracle")
+            .dispatch(SELECTOR_AFTER_SWAP, "swap")
+            .return_pair(0, 0)
+            .label("oracle")
+            .arg(0)
+            .push(0)
+            .ops(&[SSTORE, STOP])
+            .label("swap")
+            .push(10_000)
+            .push(0)
+            .ops(&[SLOAD, DIV])
+            .push(10_000)
+            .push(0)
+            .ops(&[SLOAD, DIV])
+            .return_pair_from_stack()
+            .build();
+        let strategy = EVMStrategy::new(deploy(&runtime), "OracleReader".into()).unwrap();
+        let mut amm = CFMM::new(strategy, 1000.0, 1000.0);
+        amm.initialize().unwrap();
+
+        let mut oracle = EmaOracle::new(0.2, 50.0);
+        let mut seen = Vec::new();
+        for t in 0..60 {
+            amm.set_oracle_price(Some(oracle.update(100.0)));
+            amm.execute_buy_x(1.0, t).unwrap();
+            seen.push(amm.fees().bid_fee.to_f64() * 10_000.0);
+        }
+
+        assert!(seen.windows(2).all(|w| w[1] >= w[0]));
+        assert!((seen[0] - 60.0).abs() < 1e-9);
+        assert!((seen[59] - 100.0).abs() < 1e-3);
+    }
+}
@@ -1,8 +1,35 @@
-//! Geometric Brownian Motion price process.
+//! Fair price processes: Geometric Brownian Motion, correlated GBM pairs
+//! and path replay, plus a CIR rate process for funding costs.
+
+use std::collections::HashMap;
 
 use rand::SeedableRng;
 use rand_distr::{Distribution, StandardNormal};
 use rand_pcg::Pcg64;
+use serde::{Deserialize, Serialize};
+
+/// A source of fair prices, advanced once per simulation step.
+pub trait PriceProcess: Send {
+    /// Get current price.
+    fn current_price(&self) -> f64;
+
+    /// Generate the next price.
+    fn step(&mut self) -> f64;
+
+    /// Snapshot what `step()` evolves, for engine checkpoints.
+    fn save_state(&self) -> ProcessState;
+
+    /// Restore a snapshot taken by `save_state` on the same kind of
+    /// process.
+    fn load_state(&mut self, state: ProcessState) -> Result<(), String>;
+}
+
+/// Evolving state of a price process: everything but its parameters.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ProcessState {
+    Gbm { price: f64, rng: Pcg64 },
+    Replay { price: f64, index: usize },
+}
 
 /// Generates fair prices using Geometric Brownian Motion.
 ///
@@ -30,6 +57,9 @@ pub struct GBMPriceProcess {
     vol_term: f64,
     /// Random number generator
     rng: Pcg64,
+    /// Whether each step negates its normal draw (the mirrored path of an
+    /// antithetic pair)
+    antithetic: bool,
 }
 
 impl GBMPriceProcess {
@@ -39,7 +69,14 @@ impl GBMPriceProcess {
             Some(s) => Pcg64::seed_from_u64(s),
             None => Pcg64::from_entropy(),
         };
+        Self::with_rng(initial_price, mu, sigma, dt, rng)
+    }
 
+    /// Create a GBM price process drawing from `rng` as given, e.g. a
+    /// generator restored from a checkpoint or split off another stream;
+    /// `new` with `Some(seed)` is the same as passing
+    /// `Pcg64::seed_from_u64(seed)`.
+    pub fn with_rng(initial_price: f64, mu: f64, sigma: f64, dt: f64, rng: Pcg64) -> Self {
         Self {
             current_price: initial_price,
             mu,
@@ -48,6 +85,36 @@ impl GBMPriceProcess {
             drift_term: (mu - 0.5 * sigma * sigma) * dt,
             vol_term: sigma * dt.sqrt(),
             rng,
+            antithetic: false,
+        }
+    }
+
+    /// Create a GBM price process from already-discretized terms.
+    ///
+    /// Each step multiplies the price by `exp(per_step_drift + per_step_vol * z)`,
+    /// so `per_step_drift` is the log drift including the Itô correction,
+    /// i.e. what `new` computes as `(mu - 0.5 * sigma^2) * dt`, and
+    /// `per_step_vol` is `sigma * sqrt(dt)`.
+    pub fn with_per_step_params(
+        initial_price: f64,
+        per_step_drift: f64,
+        per_step_vol: f64,
+        seed: Option<u64>,
+    ) -> Self {
+        let rng = match seed {
+            Some(s) => Pcg64::seed_from_u64(s),
+            None => Pcg64::from_entropy(),
+        };
+
+        Self {
+            current_price: initial_price,
+            mu: per_step_drift + 0.5 * per_step_vol * per_step_vol,
+            sigma: per_step_vol,
+            dt: 1.0,
+            drift_term: per_step_drift,
+            vol_term: per_step_vol,
+            rng,
+            antithetic: false,
         }
     }
 
@@ -57,10 +124,23 @@ impl GBMPriceProcess {
         self.current_price
     }
 
+    /// Negate every normal draw, so the process follows the mirror image
+    /// of the path the same seed gives without it: each step's log-return
+    /// deviation from the drift flips sign.
+    pub fn set_antithetic(&mut self, antithetic: bool) {
+        self.antithetic = antithetic;
+    }
+
+    /// Whether the normal draws are negated.
+    pub fn is_antithetic(&self) -> bool {
+        self.antithetic
+    }
+
     /// Generate the next price.
     #[inline]
     pub fn step(&mut self) -> f64 {
         let z: f64 = StandardNormal.sample(&mut self.rng);
+        let z = if self.antithetic { -z } else { z };
         let exponent = self.drift_term + self.vol_term * z;
         self.current_price *= exponent.exp();
         self.current_price
@@ -75,6 +155,380 @@ impl GBMPriceProcess {
     }
 }
 
+impl PriceProcess for GBMPriceProcess {
+    #[inline]
+    fn current_price(&self) -> f64 {
+        GBMPriceProcess::current_price(self)
+    }
+
+    #[inline]
+    fn step(&mut self) -> f64 {
+        GBMPriceProcess::step(self)
+    }
+
+    fn save_state(&self) -> ProcessState {
+        ProcessState::Gbm {
+            price: self.current_price,
+            rng: self.rng.clone(),
+        }
+    }
+
+    fn load_state(&mut self, state: ProcessState) -> Result<(), String> {
+        let ProcessState::Gbm { price, rng } = state else {
+            return Err("expected GBM price process state".into());
+        };
+        self.current_price = price;
+        self.rng = rng;
+        Ok(())
+    }
+}
+
+/// Replays an explicit fair price path, e.g. historical prices.
+///
+/// Each `step()` returns the next entry of the path. Once the path is
+/// exhausted the last price is held.
+pub struct ReplayPriceProcess {
+    /// Current price
+    current_price: f64,
+    /// Prices returned by successive steps
+    path: Vec<f64>,
+    /// Index of the next price to return
+    index: usize,
+}
+
+impl ReplayPriceProcess {
+    /// Create a replay process starting at `initial_price`.
+    pub fn new(initial_price: f64, path: Vec<f64>) -> Self {
+        Self {
+            current_price: initial_price,
+            path,
+            index: 0,
+        }
+    }
+
+    /// Number of prices in the path.
+    pub fn len(&self) -> usize {
+        self.path.len()
+    }
+
+    /// Whether the path is empty.
+    pub fn is_empty(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    /// Number of prices not yet returned.
+    pub fn remaining(&self) -> usize {
+        self.path.len() - self.index
+    }
+}
+
+impl PriceProcess for ReplayPriceProcess {
+    #[inline]
+    fn current_price(&self) -> f64 {
+        self.current_price
+    }
+
+    #[inline]
+    fn step(&mut self) -> f64 {
+        if let Some(&price) = self.path.get(self.index) {
+            self.current_price = price;
+            self.index += 1;
+        }
+        self.current_price
+    }
+
+    fn save_state(&self) -> ProcessState {
+        ProcessState::Replay {
+            price: self.current_price,
+            index: self.index,
+        }
+    }
+
+    fn load_state(&mut self, state: ProcessState) -> Result<(), String> {
+        let ProcessState::Replay { price, index } = state else {
+            return Err("expected replay price process state".into());
+        };
+        if index > self.path.len() {
+            return Err(format!("replay index {} is past the {}-price path", index, self.path.len()));
+        }
+        self.current_price = price;
+        self.index = index;
+        Ok(())
+    }
+}
+
+/// Two GBM prices whose shocks have correlation `rho`.
+///
+/// Both legs share one RNG: each step draws independent normals z1, z2 and
+/// drives the second leg with rho·z1 + sqrt(1 - rho²)·z2.
+pub struct CorrelatedGBM {
+    /// Current prices
+    prices: [f64; 2],
+    /// Pre-computed drift terms: (mu - 0.5 * sigma_i^2) * dt
+    drift_terms: [f64; 2],
+    /// Pre-computed volatility terms: sigma_i * sqrt(dt)
+    vol_terms: [f64; 2],
+    /// Shock correlation in [-1, 1]
+    rho: f64,
+    /// Weight of the independent shock: sqrt(1 - rho^2)
+    rho_perp: f64,
+    /// Random number generator
+    rng: Pcg64,
+    /// Whether each step negates its normal draws
+    antithetic: bool,
+}
+
+impl CorrelatedGBM {
+    /// Create a correlated pair; `rho` is clamped to [-1, 1].
+    pub fn new(
+        initial_prices: [f64; 2],
+        mu: f64,
+        sigmas: [f64; 2],
+        rho: f64,
+        dt: f64,
+        seed: Option<u64>,
+    ) -> Self {
+        let rng = match seed {
+            Some(s) => Pcg64::seed_from_u64(s),
+            None => Pcg64::from_entropy(),
+        };
+        let rho = rho.clamp(-1.0, 1.0);
+
+        Self {
+            prices: initial_prices,
+            drift_terms: sigmas.map(|sigma| (mu - 0.5 * sigma * sigma) * dt),
+            vol_terms: sigmas.map(|sigma| sigma * dt.sqrt()),
+            rho,
+            rho_perp: (1.0 - rho * rho).sqrt(),
+            rng,
+            antithetic: false,
+        }
+    }
+
+    /// Negate both legs' normal draws, as `GBMPriceProcess::set_antithetic`.
+    pub fn set_antithetic(&mut self, antithetic: bool) {
+        self.antithetic = antithetic;
+    }
+
+    /// Shock correlation.
+    pub fn rho(&self) -> f64 {
+        self.rho
+    }
+
+    /// Get current prices.
+    #[inline]
+    pub fn current_prices(&self) -> (f64, f64) {
+        (self.prices[0], self.prices[1])
+    }
+
+    /// Generate the next pair of prices.
+    #[inline]
+    pub fn step(&mut self) -> (f64, f64) {
+        let z1: f64 = StandardNormal.sample(&mut self.rng);
+        let z2: f64 = StandardNormal.sample(&mut self.rng);
+        let (z1, z2) = if self.antithetic { (-z1, -z2) } else { (z1, z2) };
+        let shocks = [z1, self.rho * z1 + self.rho_perp * z2];
+        for (i, z) in shocks.into_iter().enumerate() {
+            let exponent = self.drift_terms[i] + self.vol_terms[i] * z;
+            self.prices[i] *= exponent.exp();
+        }
+        self.current_prices()
+    }
+}
+
+/// Cox-Ingersoll-Ross mean-reverting rate, e.g. a cost of capital.
+///
+/// The CIR model: dr = kappa * (theta - r) * dt + sigma * sqrt(r) * dW
+/// where:
+/// - r is the rate
+/// - kappa is the speed of mean reversion
+/// - theta is the long-run mean rate
+/// - sigma is the volatility of the rate
+///
+/// Discretized with full truncation: the drift and diffusion see max(r, 0)
+/// and the stepped rate is floored at 0, so the series never goes negative
+/// even when 2·kappa·theta < sigma² (the Feller condition fails).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CIRProcess {
+    /// Current rate
+    rate: f64,
+    /// Speed of mean reversion
+    kappa: f64,
+    /// Long-run mean rate
+    theta: f64,
+    /// Rate volatility
+    sigma: f64,
+    /// Time step
+    dt: f64,
+    /// Random number generator
+    rng: Pcg64,
+}
+
+impl CIRProcess {
+    /// Create a new CIR process; a negative `initial_rate` starts at 0.
+    pub fn new(
+        initial_rate: f64,
+        kappa: f64,
+        theta: f64,
+        sigma: f64,
+        dt: f64,
+        seed: Option<u64>,
+    ) -> Self {
+        let rng = match seed {
+            Some(s) => Pcg64::seed_from_u64(s),
+            None => Pcg64::from_entropy(),
+        };
+
+        Self {
+            rate: initial_rate.max(0.0),
+            kappa,
+            theta,
+            sigma,
+            dt,
+            rng,
+        }
+    }
+
+    /// Get current rate.
+    #[inline]
+    pub fn current_rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Generate the next rate.
+    #[inline]
+    pub fn step(&mut self) -> f64 {
+        let z: f64 = StandardNormal.sample(&mut self.rng);
+        let drift = self.kappa * (self.theta - self.rate) * self.dt;
+        let diffusion = self.sigma * (self.rate * self.dt).sqrt() * z;
+        self.rate = (self.rate + drift + diffusion).max(0.0);
+        self.rate
+    }
+}
+
+/// A set of named fair prices, all advanced once per simulation step.
+///
+/// Each AMM values its trades against one of these by name, so pools of
+/// different pairs can follow independent or correlated prices.
+#[derive(Default)]
+pub struct PriceFeeds {
+    sources: Vec<FeedSource>,
+    prices: HashMap<String, f64>,
+}
+
+enum FeedSource {
+    Single(String, Box<dyn PriceProcess>),
+    Correlated([String; 2], CorrelatedGBM),
+}
+
+/// Evolving state of a set of feeds, for engine checkpoints.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedsState {
+    prices: HashMap<String, f64>,
+    sources: Vec<SourceState>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum SourceState {
+    Single(ProcessState),
+    Correlated { prices: [f64; 2], rng: Pcg64 },
+}
+
+impl PriceFeeds {
+    /// Create an empty set of feeds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a feed driven by a single price process.
+    pub fn add(&mut self, name: impl Into<String>, process: Box<dyn PriceProcess>) {
+        let name = name.into();
+        self.prices.insert(name.clone(), process.current_price());
+        self.sources.push(FeedSource::Single(name, process));
+    }
+
+    /// Add two feeds driven by a correlated pair.
+    pub fn add_correlated(&mut self, names: [String; 2], process: CorrelatedGBM) {
+        let (price_a, price_b) = process.current_prices();
+        self.prices.insert(names[0].clone(), price_a);
+        self.prices.insert(names[1].clone(), price_b);
+        self.sources.push(FeedSource::Correlated(names, process));
+    }
+
+    /// Whether a feed with this name exists.
+    pub fn contains(&self, name: &str) -> bool {
+        self.prices.contains_key(name)
+    }
+
+    /// Current price of a feed.
+    #[inline]
+    pub fn price(&self, name: &str) -> Option<f64> {
+        self.prices.get(name).copied()
+    }
+
+    /// Advance every feed by one step.
+    pub fn step(&mut self) {
+        for source in &mut self.sources {
+            match source {
+                FeedSource::Single(name, process) => {
+                    let price = process.step();
+                    *self.prices.get_mut(name.as_str()).unwrap() = price;
+                }
+                FeedSource::Correlated(names, process) => {
+                    let (price_a, price_b) = process.step();
+                    *self.prices.get_mut(names[0].as_str()).unwrap() = price_a;
+                    *self.prices.get_mut(names[1].as_str()).unwrap() = price_b;
+                }
+            }
+        }
+    }
+
+    /// Snapshot every feed's price and process state.
+    pub fn save_state(&self) -> FeedsState {
+        let sources = self
+            .sources
+            .iter()
+            .map(|source| match source {
+                FeedSource::Single(_, process) => SourceState::Single(process.save_state()),
+                FeedSource::Correlated(_, process) => SourceState::Correlated {
+                    prices: process.prices,
+                    rng: process.rng.clone(),
+                },
+            })
+            .collect();
+        FeedsState {
+            prices: self.prices.clone(),
+            sources,
+        }
+    }
+
+    /// Restore a snapshot taken by `save_state` on feeds set up the same
+    /// way.
+    pub fn load_state(&mut self, state: FeedsState) -> Result<(), String> {
+        if state.sources.len() != self.sources.len() {
+            return Err(format!(
+                "expected {} price sources, got {}",
+                self.sources.len(),
+                state.sources.len()
+            ));
+        }
+        for (source, saved) in self.sources.iter_mut().zip(state.sources) {
+            match (source, saved) {
+                (FeedSource::Single(_, process), SourceState::Single(saved)) => {
+                    process.load_state(saved)?;
+                }
+                (FeedSource::Correlated(_, process), SourceState::Correlated { prices, rng }) => {
+                    process.prices = prices;
+                    process.rng = rng;
+                }
+                _ => return Err("price source kinds do not match".into()),
+            }
+        }
+        self.prices = state.prices;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -90,6 +544,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_gbm_from_cloned_rng_state() {
+        // A generator partway through its stream, as a worker would resume it
+        let mut rng = Pcg64::seed_from_u64(42);
+        let _: f64 = StandardNormal.sample(&mut rng);
+        let mut process1 = GBMPriceProcess::with_rng(100.0, 0.0, 0.1, 1.0, rng.clone());
+        let mut process2 = GBMPriceProcess::with_rng(100.0, 0.0, 0.1, 1.0, rng);
+
+        // The seeded stream after one step makes the same moves from there
+        let mut seeded = GBMPriceProcess::new(100.0, 0.0, 0.1, 1.0, Some(42));
+        let scale = 100.0 / seeded.step();
+        for _ in 0..100 {
+            let price = process1.step();
+            assert_eq!(price, process2.step());
+            let resumed = seeded.step() * scale;
+            assert!((price - resumed).abs() < 1e-9 * price, "{price} vs {resumed}");
+        }
+    }
+
     #[test]
     fn test_gbm_positive_prices() {
         let mut process = GBMPriceProcess::new(100.0, -0.5, 0.3, 1.0, Some(42));
@@ -100,4 +573,122 @@ mod tests {
             assert!(price > 0.0);
         }
     }
+
+    #[test]
+    fn test_per_step_params_match_annualized() {
+        let (mu, sigma, dt) = (0.05, 0.8, 1.0 / 365.0);
+        let mut annualized = GBMPriceProcess::new(100.0, mu, sigma, dt, Some(7));
+        let mut per_step = GBMPriceProcess::with_per_step_params(
+            100.0,
+            (mu - 0.5 * sigma * sigma) * dt,
+            sigma * dt.sqrt(),
+            Some(7),
+        );
+
+        for _ in 0..500 {
+            assert_eq!(annualized.step(), per_step.step());
+        }
+    }
+
+    #[test]
+    fn test_antithetic_path_negates_log_returns() {
+        let mut plain = GBMPriceProcess::with_per_step_params(100.0, 0.0, 0.01, Some(42));
+        let mut mirrored = GBMPriceProcess::with_per_step_params(100.0, 0.0, 0.01, Some(42));
+        mirrored.set_antithetic(true);
+
+        let (mut last, mut last_mirrored) = (100.0, 100.0);
+        for _ in 0..500 {
+            let (price, price_mirrored) = (plain.step(), mirrored.step());
+            let log_return = (price / last).ln();
+            let mirrored_return = (price_mirrored / last_mirrored).ln();
+            assert!(log_return != 0.0);
+            assert!((log_return + mirrored_return).abs() < 1e-12, "{log_return} {mirrored_return}");
+            (last, last_mirrored) = (price, price_mirrored);
+        }
+    }
+
+    #[test]
+    fn test_replay_follows_path() {
+        let path = vec![101.0, 99.5, 102.25];
+        let mut process = ReplayPriceProcess::new(100.0, path.clone());
+
+        assert_eq!(process.current_price(), 100.0);
+        for &expected in &path {
+            assert_eq!(process.step(), expected);
+        }
+        assert_eq!(process.remaining(), 0);
+
+        // Exhausted path holds the last price
+        assert_eq!(process.step(), 102.25);
+    }
+
+    #[test]
+    fn test_perfectly_correlated_pair_moves_identically() {
+        let mut process = CorrelatedGBM::new([100.0, 100.0], 0.0, [0.01, 0.01], 1.0, 1.0, Some(42));
+        for _ in 0..500 {
+            let (a, b) = process.step();
+            assert_eq!(a, b);
+        }
+
+        // Same seed reproduces the series; anti-correlated legs diverge
+        let mut again = CorrelatedGBM::new([100.0, 100.0], 0.0, [0.01, 0.01], 1.0, 1.0, Some(42));
+        let mut anti = CorrelatedGBM::new([100.0, 100.0], 0.0, [0.01, 0.01], -1.0, 1.0, Some(42));
+        let drift = -0.5 * 0.01 * 0.01;
+        let (mut prev_c, mut prev_d) = anti.current_prices();
+        for _ in 0..10 {
+            let (a, _) = again.step();
+            let (c, d) = anti.step();
+            assert_eq!(a, c);
+            // Opposite shocks: log returns sum to twice the drift
+            let sum = (c / prev_c).ln() + (d / prev_d).ln();
+            assert!((sum - 2.0 * drift).abs() < 1e-12);
+            (prev_c, prev_d) = (c, d);
+        }
+    }
+
+    #[test]
+    fn test_price_feeds_step_all_sources() {
+        let mut feeds = PriceFeeds::new();
+        feeds.add("fair", Box::new(ReplayPriceProcess::new(100.0, vec![101.0, 102.0])));
+        feeds.add_correlated(
+            ["a".to_string(), "b".to_string()],
+            CorrelatedGBM::new([10.0, 20.0], 0.0, [0.01, 0.02], 0.5, 1.0, Some(1)),
+        );
+
+        assert_eq!(feeds.price("fair"), Some(100.0));
+        assert_eq!(feeds.price("b"), Some(20.0));
+        assert!(!feeds.contains("c"));
+
+        feeds.step();
+        assert_eq!(feeds.price("fair"), Some(101.0));
+        assert_ne!(feeds.price("a"), Some(10.0));
+        assert_ne!(feeds.price("b"), Some(20.0));
+    }
+
+    #[test]
+    fn test_cir_rate_stays_nonnegative() {
+        // 2·kappa·theta < sigma²: the exact process touches zero, so the
+        // discretization must floor it
+        let mut process = CIRProcess::new(0.02, 0.5, 0.02, 0.5, 1.0 / 365.0, Some(42));
+        let mut hit_zero = false;
+        let mut total = 0.0;
+        let n = 100_000;
+        for _ in 0..n {
+            let rate = process.step();
+            assert!(rate >= 0.0);
+            hit_zero |= rate == 0.0;
+            total += rate;
+        }
+        assert!(hit_zero);
+        assert!(total / n as f64 > 0.0);
+
+        // Without noise it decays monotonically toward theta
+        let mut process = CIRProcess::new(0.10, 2.0, 0.03, 0.0, 0.01, Some(1));
+        let mut previous = process.current_rate();
+        for _ in 0..1000 {
+            let rate = process.step();
+            assert!(rate <= previous && rate >= 0.03);
+            previous = rate;
+        }
+    }
 }
@@ -0,0 +1,136 @@
+//! Retail memory of execution quality per venue.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Fraction of a venue's reputation carried over from one step to the next.
+pub const REPUTATION_DECAY: f64 = 0.9;
+
+/// Retail traders' memory of the slippage each AMM gave them.
+///
+/// Each venue's reputation is an exponential moving average of its
+/// average retail slippage per step (in bps), decaying toward zero on
+/// steps it gets no fills. Venues whose reputation is worse than the best
+/// one lose a share `1 - exp(-elasticity * excess_bps)` of the flow the
+/// router would otherwise send them, which goes to the other venue.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowReputation {
+    /// Strength of the feedback, per bps of excess slippage
+    elasticity: f64,
+    /// Smoothed slippage (bps) by AMM name
+    slippage_bps: HashMap<String, f64>,
+    /// Slippage sum and fill count by AMM name for the current step
+    pending: HashMap<String, (f64, u64)>,
+}
+
+impl FlowReputation {
+    /// Create a memory with no history; `elasticity` below 0 is treated as 0.
+    pub fn new(elasticity: f64) -> Self {
+        Self {
+            elasticity: elasticity.max(0.0),
+            slippage_bps: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Strength of the feedback, per bps of excess slippage.
+    pub fn elasticity(&self) -> f64 {
+        self.elasticity
+    }
+
+    /// Smoothed slippage (bps) of a venue (0 without history).
+    pub fn slippage_bps(&self, amm_name: &str) -> f64 {
+        self.slippage_bps.get(amm_name).copied().unwrap_or(0.0)
+    }
+
+    /// Record one retail fill's slippage for the current step.
+    pub fn observe(&mut self, amm_name: &str, slippage_bps: f64) {
+        let (total, fills) = self.pending.entry(amm_name.to_string()).or_insert((0.0, 0));
+        *total += slippage_bps;
+        *fills += 1;
+    }
+
+    /// Fold the current step's fills into each venue's reputation.
+    pub fn end_step(&mut self) {
+        for (name, reputation) in self.slippage_bps.iter_mut() {
+            if !self.pending.contains_key(name) {
+                *reputation *= REPUTATION_DECAY;
+            }
+        }
+        for (name, (total, fills)) in self.pending.drain() {
+            let average = total / fills as f64;
+            let reputation = self.slippage_bps.entry(name).or_insert(0.0);
+            *reputation = REPUTATION_DECAY * *reputation + (1.0 - REPUTATION_DECAY) * average;
+        }
+    }
+
+    /// Share of its routed flow a venue keeps, in (0, 1]: 1 for the
+    /// best-reputed venue among `amm_names`.
+    pub fn flow_weight(&self, amm_name: &str, amm_names: &[&str]) -> f64 {
+        let best = amm_names
+            .iter()
+            .map(|name| self.slippage_bps(name))
+            .fold(f64::INFINITY, f64::min);
+        let excess = (self.slippage_bps(amm_name) - best).max(0.0);
+        (-self.elasticity * excess).exp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm::cfmm::FeeQuote;
+    use crate::amm::CFMM;
+    use crate::market::retail::RetailOrder;
+    use crate::market::router::OrderRouter;
+    use crate::types::wad::Wad;
+
+    /// Route a buy and a sell through fresh 30 and 60 bps pools, feeding
+    /// the fills back into `reputation`; returns the 60 bps pool's share
+    /// of the Y volume.
+    fn route_step(reputation: &mut FlowReputation) -> f64 {
+        let pool = |name: &str, fee_bps| {
+            let fees = FeeQuote::symmetric(Wad::from_bps(fee_bps));
+            let mut amm = CFMM::fixed_fee(name.into(), 100.0, 10_000.0, fees);
+            amm.initialize().unwrap();
+            amm
+        };
+        let mut amms = [pool("fair", 30), pool("greedy", 60)];
+        let orders = [
+            RetailOrder { side: "buy", size: 200.0 },
+            RetailOrder { side: "sell", size: 200.0 },
+        ];
+
+        let (trades, _) =
+            OrderRouter::new().route_orders_with(&orders, &mut amms, 100.0, 0, None, Some(reputation));
+        let mut volume = HashMap::new();
+        for trade in &trades {
+            reputation.observe(&trade.amm_name, trade.slippage_bps(100.0));
+            *volume.entry(trade.amm_name.clone()).or_insert(0.0) += trade.amount_y;
+        }
+        reputation.end_step();
+        volume["greedy"] / (volume["fair"] + volume["greedy"])
+    }
+
+    #[test]
+    fn test_overcharging_pool_loses_flow_over_time() {
+        let mut memoryless = FlowReputation::new(0.0);
+        let mut elastic = FlowReputation::new(0.1);
+        let shares: Vec<(f64, f64)> =
+            (0..30).map(|_| (route_step(&mut memoryless), route_step(&mut elastic))).collect();
+
+        // Without elasticity the optimal split is the same every step
+        let (first, _) = shares[0];
+        assert!(first > 0.1, "{first}");
+        assert!(shares.iter().all(|&(share, _)| (share - first).abs() < 1e-12));
+
+        // With it the greedy pool's share falls as its reputation builds
+        let (_, elastic_first) = shares[0];
+        let (_, elastic_last) = shares[shares.len() - 1];
+        assert!((elastic_first - first).abs() < 1e-12);
+        assert!(shares.windows(2).all(|pair| pair[1].1 <= pair[0].1 + 1e-12));
+        assert!(elastic_last < 0.75 * elastic_first, "{elastic_first} -> {elastic_last}");
+        assert!(elastic.slippage_bps("greedy") > elastic.slippage_bps("fair"));
+    }
+}
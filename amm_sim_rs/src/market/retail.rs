@@ -1,8 +1,11 @@
 //! Retail trader simulation with Poisson arrivals.
 
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
 use rand::SeedableRng;
 use rand_distr::{Distribution, LogNormal, Poisson};
 use rand_pcg::Pcg64;
+use serde::{Deserialize, Serialize};
 
 /// A retail order to be routed to AMMs.
 #[derive(Debug, Clone)]
@@ -13,45 +16,190 @@ pub struct RetailOrder {
     pub size: f64,
 }
 
+/// How the retail orders arriving within one step are routed.
+///
+/// - `Sequential`: route each order in arrival order (the default), so
+///   earlier orders get better prices.
+/// - `Aggregate`: merge all same-side orders into one block per side and
+///   route each block once (buys first, then sells).
+/// - `Shuffle`: route each order separately in a seeded random order.
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RetailBatching {
+    #[default]
+    Sequential,
+    Aggregate,
+    Shuffle,
+}
+
+/// How retail arrivals and order sizes are drawn.
+///
+/// - `Stochastic`: Poisson arrivals with lognormal sizes (the default).
+/// - `Deterministic`: every step gets its expected flow, `arrival_rate *
+///   mean_size` (scaled by the profiles and the chance of a non-quiet
+///   step), as one buy and one sell order split by the buy probability.
+///   Nothing is drawn, so the orders do not depend on the seed.
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RetailMode {
+    #[default]
+    Stochastic,
+    Deterministic,
+}
+
+/// Number of most recent fair-price returns that `Momentum` and
+/// `Contrarian` flow react to.
+pub const FLOW_LOOKBACK: usize = 5;
+
+/// How the side of retail orders responds to recent fair-price moves.
+///
+/// - `Random`: sides ignore the price path (the default).
+/// - `Momentum`: the buy probability rises after up-moves and falls after
+///   down-moves, by the flow sensitivity times the log return over the
+///   last `FLOW_LOOKBACK` steps.
+/// - `Contrarian`: the reverse, buying dips and selling rallies.
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RetailFlowStyle {
+    #[default]
+    Random,
+    Momentum,
+    Contrarian,
+}
+
+/// Parameters of an additional retail population, e.g. rare large whales
+/// alongside the main stream of small noise traders.
+#[cfg_attr(feature = "python", pyclass(get_all, set_all))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RetailCohort {
+    /// Expected number of orders per step (Poisson lambda)
+    pub arrival_rate: f64,
+
+    /// Mean order size in Y (lognormal mean)
+    pub mean_size: f64,
+
+    /// Lognormal sigma for order sizes (log-space)
+    pub size_sigma: f64,
+
+    /// Probability of a buy order
+    pub buy_prob: f64,
+}
+
+impl RetailCohort {
+    /// Create a cohort with the given arrival rate and size distribution.
+    pub fn new(arrival_rate: f64, mean_size: f64, size_sigma: f64, buy_prob: f64) -> Self {
+        Self {
+            arrival_rate,
+            mean_size,
+            size_sigma,
+            buy_prob,
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl RetailCohort {
+    #[new]
+    #[pyo3(signature = (arrival_rate, mean_size, size_sigma = 1.2, buy_prob = 0.5))]
+    fn py_new(arrival_rate: f64, mean_size: f64, size_sigma: f64, buy_prob: f64) -> Self {
+        Self::new(arrival_rate, mean_size, size_sigma, buy_prob)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "RetailCohort(arrival_rate={}, mean_size={})",
+            self.arrival_rate, self.mean_size
+        )
+    }
+}
+
+/// Merge same-side orders into at most one buy and one sell block.
+pub fn aggregate_orders(orders: &[RetailOrder]) -> Vec<RetailOrder> {
+    let mut blocks: Vec<RetailOrder> = Vec::with_capacity(2);
+    for side in ["buy", "sell"] {
+        let size: f64 = orders.iter().filter(|o| o.side == side).map(|o| o.size).sum();
+        if size > 0.0 {
+            blocks.push(RetailOrder { side, size });
+        }
+    }
+    blocks
+}
+
 /// Generates retail trading flow with Poisson arrivals.
 ///
 /// Retail traders arrive according to a Poisson process and
 /// submit orders of random size. They are uninformed and
 /// trade randomly (buy or sell with equal probability by default).
+///
+/// Optional per-step profiles scale the arrival rate and order size to
+/// model intraday seasonality; profiles wrap around when shorter than
+/// the simulation.
 pub struct RetailTrader {
     /// Expected number of trades per time step (lambda)
-    #[allow(dead_code)]
     arrival_rate: f64,
     /// Mean trade size (in Y terms)
-    #[allow(dead_code)]
     mean_size: f64,
     /// Lognormal sigma (log-space)
     #[allow(dead_code)]
     size_sigma: f64,
     /// Probability of a buy order
     buy_prob: f64,
+    /// Probability that a step is quiet (no arrivals at all)
+    quiet_prob: f64,
     /// Random number generator
     rng: Pcg64,
     /// Poisson distribution for arrivals
     poisson: Poisson<f64>,
     /// Lognormal distribution for sizes
     lognormal: LogNormal<f64>,
+    /// Per-step multiplier on the arrival rate
+    arrival_profile: Option<Vec<f64>>,
+    /// Per-step multiplier on order sizes
+    size_profile: Option<Vec<f64>>,
+    /// How strongly pool inventory imbalance tilts the buy probability
+    inventory_sensitivity: f64,
+    /// Whether orders are drawn or set to the expected flow
+    mode: RetailMode,
+    /// How order sides respond to recent price moves
+    flow_style: RetailFlowStyle,
+    /// Buy probability tilt per unit of recent log return
+    flow_sensitivity: f64,
+    /// Fair-price log return over the last `FLOW_LOOKBACK` steps
+    recent_return: f64,
 }
 
 impl RetailTrader {
     /// Create a new retail trader.
+    ///
+    /// With probability `quiet_prob` a step has no arrivals regardless of
+    /// the arrival rate (zero-inflated Poisson); 0 disables quiet steps
+    /// and draws nothing extra from the RNG.
     pub fn new(
         arrival_rate: f64,
         mean_size: f64,
         size_sigma: f64,
         buy_prob: f64,
+        quiet_prob: f64,
         seed: Option<u64>,
     ) -> Self {
         let rng = match seed {
             Some(s) => Pcg64::seed_from_u64(s),
             None => Pcg64::from_entropy(),
         };
+        Self::with_rng(arrival_rate, mean_size, size_sigma, buy_prob, quiet_prob, rng)
+    }
 
+    /// Create a retail trader drawing from `rng` as given rather than a
+    /// fresh seed (see `GBMPriceProcess::with_rng`).
+    pub fn with_rng(
+        arrival_rate: f64,
+        mean_size: f64,
+        size_sigma: f64,
+        buy_prob: f64,
+        quiet_prob: f64,
+        rng: Pcg64,
+    ) -> Self {
         // Create distributions, handling edge cases
         let poisson = Poisson::new(arrival_rate.max(0.01)).unwrap_or_else(|_| Poisson::new(1.0).unwrap());
         let mean = mean_size.max(0.01);
@@ -60,34 +208,139 @@ impl RetailTrader {
         let lognormal = LogNormal::new(mu, sigma).unwrap_or_else(|_| LogNormal::new(0.0, 1.0).unwrap());
 
         Self {
-            arrival_rate,
-            mean_size,
+            arrival_rate: arrival_rate.max(0.01),
+            mean_size: mean,
             size_sigma: sigma,
             buy_prob,
+            quiet_prob: quiet_prob.clamp(0.0, 1.0),
             rng,
             poisson,
             lognormal,
+            arrival_profile: None,
+            size_profile: None,
+            inventory_sensitivity: 0.0,
+            mode: RetailMode::Stochastic,
+            flow_style: RetailFlowStyle::Random,
+            flow_sensitivity: 0.0,
+            recent_return: 0.0,
+        }
+    }
+
+    /// Scale the arrival rate by `profile[step % len]` each step.
+    pub fn set_arrival_profile(&mut self, profile: Vec<f64>) {
+        self.arrival_profile = if profile.is_empty() { None } else { Some(profile) };
+    }
+
+    /// Scale order sizes by `profile[step % len]` each step.
+    pub fn set_size_profile(&mut self, profile: Vec<f64>) {
+        self.size_profile = if profile.is_empty() { None } else { Some(profile) };
+    }
+
+    /// Tilt the buy probability by `sensitivity * imbalance` each step.
+    ///
+    /// See `generate_orders_with_imbalance` for the imbalance convention.
+    pub fn set_inventory_sensitivity(&mut self, sensitivity: f64) {
+        self.inventory_sensitivity = sensitivity;
+    }
+
+    /// Draw orders at random or use the expected flow (see `RetailMode`).
+    pub fn set_mode(&mut self, mode: RetailMode) {
+        self.mode = mode;
+    }
+
+    /// Tilt the buy probability by `sensitivity` times the recent return
+    /// (see `set_recent_return`), in the direction `style` gives.
+    pub fn set_flow_style(&mut self, style: RetailFlowStyle, sensitivity: f64) {
+        self.flow_style = style;
+        self.flow_sensitivity = sensitivity;
+    }
+
+    /// Set the fair-price log return over the last `FLOW_LOOKBACK` steps,
+    /// which momentum and contrarian flow react to from the next orders on.
+    pub fn set_recent_return(&mut self, log_return: f64) {
+        self.recent_return = log_return;
+    }
+
+    /// Buy probability after the inventory and flow-style tilts, clamped
+    /// to [0, 1].
+    fn buy_prob(&self, imbalance: f64) -> f64 {
+        let direction = match self.flow_style {
+            RetailFlowStyle::Random => 0.0,
+            RetailFlowStyle::Momentum => 1.0,
+            RetailFlowStyle::Contrarian => -1.0,
+        };
+        let flow_tilt = direction * self.flow_sensitivity * self.recent_return;
+        (self.buy_prob + self.inventory_sensitivity * imbalance + flow_tilt).clamp(0.0, 1.0)
+    }
+
+    /// Snapshot the RNG, the only state order generation evolves.
+    pub fn save_state(&self) -> Pcg64 {
+        self.rng.clone()
+    }
+
+    /// Restore an RNG snapshot taken by `save_state`.
+    pub fn load_state(&mut self, rng: Pcg64) {
+        self.rng = rng;
+    }
+
+    /// Look up a profile multiplier for a step (1.0 when unset).
+    #[inline]
+    fn multiplier(profile: &Option<Vec<f64>>, step: u32) -> f64 {
+        match profile {
+            Some(p) => p[step as usize % p.len()],
+            None => 1.0,
         }
     }
 
     /// Generate retail orders for one time step.
     #[inline]
-    pub fn generate_orders(&mut self) -> Vec<RetailOrder> {
+    pub fn generate_orders(&mut self, step: u32) -> Vec<RetailOrder> {
+        self.generate_orders_with_imbalance(step, 0.0)
+    }
+
+    /// Generate retail orders for one time step given the pools' inventory
+    /// imbalance.
+    ///
+    /// `imbalance` is `(x * p - y) / (x * p + y)` over the pooled reserves:
+    /// positive when the pools are heavy in X. With a positive sensitivity
+    /// the buy probability rises so flow buys X back out of the pools;
+    /// together with the flow-style tilt it is clamped to [0, 1].
+    #[inline]
+    pub fn generate_orders_with_imbalance(&mut self, step: u32, imbalance: f64) -> Vec<RetailOrder> {
+        if self.mode == RetailMode::Deterministic {
+            return self.expected_orders(step, imbalance);
+        }
+        if self.quiet_prob > 0.0 && rand::Rng::gen::<f64>(&mut self.rng) < self.quiet_prob {
+            return Vec::new();
+        }
+
+        let buy_prob = self.buy_prob(imbalance);
+
         // Number of arrivals follows Poisson distribution
-        let n_arrivals = self.poisson.sample(&mut self.rng) as usize;
+        let rate_multiplier = Self::multiplier(&self.arrival_profile, step);
+        let n_arrivals = if rate_multiplier == 1.0 {
+            self.poisson.sample(&mut self.rng) as usize
+        } else if rate_multiplier > 0.0 {
+            Poisson::new(self.arrival_rate * rate_multiplier)
+                .map(|poisson| poisson.sample(&mut self.rng) as usize)
+                .unwrap_or(0)
+        } else {
+            0
+        };
 
         if n_arrivals == 0 {
             return Vec::new();
         }
 
         let mut orders = Vec::with_capacity(n_arrivals);
+        let size_multiplier = Self::multiplier(&self.size_profile, step);
 
         for _ in 0..n_arrivals {
             // Lognormally distributed sizes
-            let size = self.lognormal.sample(&mut self.rng);
+            let size = self.lognormal.sample(&mut self.rng) * size_multiplier;
 
             // Random side
-            let side = if rand::Rng::gen::<f64>(&mut self.rng) < self.buy_prob {
+            let side = if rand::Rng::gen::<f64>(&mut self.rng) < buy_prob {
                 "buy"
             } else {
                 "sell"
@@ -99,6 +352,20 @@ impl RetailTrader {
         orders
     }
 
+    /// The mean flow of a step as one buy and one sell order (empty
+    /// sides omitted).
+    fn expected_orders(&self, step: u32, imbalance: f64) -> Vec<RetailOrder> {
+        let buy_prob = self.buy_prob(imbalance);
+        let rate = self.arrival_rate * Self::multiplier(&self.arrival_profile, step).max(0.0);
+        let size = self.mean_size * Self::multiplier(&self.size_profile, step);
+        let flow = (1.0 - self.quiet_prob) * rate * size;
+        [("buy", buy_prob * flow), ("sell", (1.0 - buy_prob) * flow)]
+            .into_iter()
+            .filter(|&(_, size)| size > 0.0)
+            .map(|(side, size)| RetailOrder { side, size })
+            .collect()
+    }
+
     /// Reset the random state.
     pub fn reset(&mut self, seed: Option<u64>) {
         if let Some(s) = seed {
@@ -113,30 +380,200 @@ mod tests {
 
     #[test]
     fn test_retail_trader_deterministic() {
-        let mut trader1 = RetailTrader::new(5.0, 2.0, 0.5, 0.5, Some(42));
-        let mut trader2 = RetailTrader::new(5.0, 2.0, 0.5, 0.5, Some(42));
+        let mut trader1 = RetailTrader::new(5.0, 2.0, 0.5, 0.5, 0.0, Some(42));
+        let mut trader2 = RetailTrader::new(5.0, 2.0, 0.5, 0.5, 0.0, Some(42));
+        let rng = Pcg64::seed_from_u64(42);
+        let mut trader3 = RetailTrader::with_rng(5.0, 2.0, 0.5, 0.5, 0.0, rng);
 
-        // Same seed should produce same orders
+        // Same seed (or seeded generator) should produce same orders
         for _ in 0..10 {
-            let orders1 = trader1.generate_orders();
-            let orders2 = trader2.generate_orders();
+            let orders1 = trader1.generate_orders(0);
+            let orders2 = trader2.generate_orders(0);
+            let orders3 = trader3.generate_orders(0);
             assert_eq!(orders1.len(), orders2.len());
-            for (o1, o2) in orders1.iter().zip(orders2.iter()) {
+            assert_eq!(orders1.len(), orders3.len());
+            for ((o1, o2), o3) in orders1.iter().zip(orders2.iter()).zip(&orders3) {
                 assert_eq!(o1.side, o2.side);
                 assert_eq!(o1.size, o2.size);
+                assert_eq!((o1.side, o1.size), (o3.side, o3.size));
             }
         }
     }
 
     #[test]
     fn test_retail_trader_positive_sizes() {
-        let mut trader = RetailTrader::new(5.0, 2.0, 0.5, 0.5, Some(42));
+        let mut trader = RetailTrader::new(5.0, 2.0, 0.5, 0.5, 0.0, Some(42));
 
         for _ in 0..100 {
-            let orders = trader.generate_orders();
+            let orders = trader.generate_orders(0);
             for order in orders {
                 assert!(order.size > 0.0);
             }
         }
     }
+
+    #[test]
+    fn test_quiet_prob_zero_inflates_arrivals() {
+        let mut always_quiet = RetailTrader::new(50.0, 2.0, 0.5, 0.5, 1.0, Some(42));
+        for step in 0..500 {
+            assert!(always_quiet.generate_orders(step).is_empty());
+        }
+
+        // No quiet steps: no extra draws, so the RNG stream is unchanged
+        let mut plain = RetailTrader::new(5.0, 2.0, 0.5, 0.5, 0.0, Some(42));
+        let mut rng = Pcg64::seed_from_u64(42);
+        let (poisson, lognormal) = (plain.poisson, plain.lognormal);
+        for step in 0..20 {
+            let orders = plain.generate_orders(step);
+            assert_eq!(orders.len(), poisson.sample(&mut rng) as usize);
+            for order in orders {
+                assert_eq!(order.size, lognormal.sample(&mut rng));
+                rand::Rng::gen::<f64>(&mut rng);
+            }
+        }
+
+        // Half the steps go quiet
+        let mut bursty = RetailTrader::new(50.0, 2.0, 0.5, 0.5, 0.5, Some(42));
+        let quiet = (0..1000).filter(|&step| bursty.generate_orders(step).is_empty()).count();
+        assert!((400..600).contains(&quiet), "{quiet}");
+    }
+
+    #[test]
+    fn test_arrival_profile_seasonality() {
+        let mut trader = RetailTrader::new(5.0, 2.0, 0.5, 0.5, 0.0, Some(42));
+        trader.set_arrival_profile(vec![0.0, 10.0]);
+
+        let mut odd_orders = 0;
+        for step in 0..200 {
+            let orders = trader.generate_orders(step);
+            if step % 2 == 0 {
+                assert!(orders.is_empty());
+            } else {
+                odd_orders += orders.len();
+            }
+        }
+        // lambda = 50 on odd steps
+        assert!(odd_orders > 100 * 40);
+    }
+
+    #[test]
+    fn test_size_profile_scales_orders() {
+        let mut plain = RetailTrader::new(5.0, 2.0, 0.5, 0.5, 0.0, Some(42));
+        let mut scaled = RetailTrader::new(5.0, 2.0, 0.5, 0.5, 0.0, Some(42));
+        scaled.set_size_profile(vec![3.0]);
+
+        for step in 0..20 {
+            for (a, b) in plain.generate_orders(step).iter().zip(scaled.generate_orders(step).iter()) {
+                assert!((b.size - 3.0 * a.size).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_inventory_imbalance_tilts_flow() {
+        let count_buys = |imbalance: f64| {
+            let mut trader = RetailTrader::new(5.0, 2.0, 0.5, 0.5, 0.0, Some(42));
+            trader.set_inventory_sensitivity(1.0);
+            (0..200)
+                .flat_map(|step| trader.generate_orders_with_imbalance(step, imbalance))
+                .fold((0, 0), |(buys, total), order| (buys + (order.side == "buy") as usize, total + 1))
+        };
+
+        // buy_prob 0.9 with pools heavy in X, 0.1 with pools heavy in Y
+        let (buys, total) = count_buys(0.4);
+        assert!(buys as f64 > 0.85 * total as f64);
+        let (buys, total) = count_buys(-0.4);
+        assert!((buys as f64) < 0.15 * total as f64);
+
+        // Clamped to [0, 1]: every order is a buy
+        let (buys, total) = count_buys(1.0);
+        assert_eq!(buys, total);
+    }
+
+    #[test]
+    fn test_flow_style_follows_or_fades_the_trend() {
+        // A steady 0.2% rise per step
+        let path: Vec<f64> = (0..200).map(|i| 100.0 * 1.002f64.powi(i)).collect();
+        let count_buys = |style| {
+            let mut trader = RetailTrader::new(5.0, 2.0, 0.5, 0.5, 0.0, Some(42));
+            trader.set_flow_style(style, 50.0);
+            (FLOW_LOOKBACK..path.len())
+                .flat_map(|t| {
+                    trader.set_recent_return((path[t] / path[t - FLOW_LOOKBACK]).ln());
+                    trader.generate_orders(t as u32)
+                })
+                .fold((0, 0), |(buys, total), order| (buys + (order.side == "buy") as usize, total + 1))
+        };
+
+        // A 1% move tilts the buy probability by 0.5
+        let (buys, total) = count_buys(RetailFlowStyle::Momentum);
+        assert!(buys as f64 > 0.9 * total as f64, "{buys}/{total}");
+        let (buys, total) = count_buys(RetailFlowStyle::Contrarian);
+        assert!((buys as f64) < 0.1 * total as f64, "{buys}/{total}");
+        let (buys, total) = count_buys(RetailFlowStyle::Random);
+        assert!((buys as f64 - 0.5 * total as f64).abs() < 0.1 * total as f64, "{buys}/{total}");
+    }
+
+    #[test]
+    fn test_aggregated_buys_match_single_block() {
+        use crate::evm::fixtures::fixed_fee_strategy;
+        use crate::amm::CFMM;
+        use crate::market::OrderRouter;
+
+        let amms = || {
+            let mut amms = vec![
+                CFMM::new(fixed_fee_strategy(30, 30), 100.0, 10_000.0),
+                CFMM::new(fixed_fee_strategy(50, 50), 80.0, 8_000.0),
+            ];
+            amms[1].name = "second".into();
+            for amm in &mut amms {
+                amm.initialize().unwrap();
+            }
+            amms
+        };
+        let total_x = |orders: &[RetailOrder]| {
+            OrderRouter::new()
+                .route_orders(orders, &mut amms(), 100.0, 0)
+                .iter()
+                .map(|trade| trade.amount_x)
+                .sum::<f64>()
+        };
+
+        let split = [RetailOrder { side: "buy", size: 25.0 }, RetailOrder { side: "buy", size: 25.0 }];
+        let batched = aggregate_orders(&split);
+        assert_eq!(batched.len(), 1);
+        assert_eq!((batched[0].side, batched[0].size), ("buy", 50.0));
+
+        let single = total_x(&[RetailOrder { side: "buy", size: 50.0 }]);
+        assert_eq!(total_x(&batched), single);
+
+        let mixed = [
+            RetailOrder { side: "sell", size: 5.0 },
+            RetailOrder { side: "buy", size: 2.0 },
+            RetailOrder { side: "sell", size: 1.0 },
+        ];
+        let blocks: Vec<_> = aggregate_orders(&mixed).iter().map(|o| (o.side, o.size)).collect();
+        assert_eq!(blocks, vec![("buy", 2.0), ("sell", 6.0)]);
+    }
+
+    #[test]
+    fn test_deterministic_mode_emits_the_mean_flow() {
+        let orders = |seed| {
+            let mut trader = RetailTrader::new(5.0, 2.0, 0.5, 0.7, 0.0, Some(seed));
+            trader.set_mode(RetailMode::Deterministic);
+            (0..50).map(|step| trader.generate_orders(step)).collect::<Vec<_>>()
+        };
+
+        let (a, b) = (orders(1), orders(2));
+        for (step_a, step_b) in a.iter().zip(&b) {
+            let sides: Vec<_> = step_a.iter().map(|o| (o.side, o.size)).collect();
+            assert_eq!(sides, step_b.iter().map(|o| (o.side, o.size)).collect::<Vec<_>>());
+
+            // lambda * mean_size of Y per step, split 70/30 by side
+            let total: f64 = step_a.iter().map(|o| o.size).sum();
+            assert!((total - 10.0).abs() < 1e-12, "{total}");
+            assert_eq!(sides[0].0, "buy");
+            assert!((sides[0].1 - 7.0).abs() < 1e-12);
+        }
+    }
 }
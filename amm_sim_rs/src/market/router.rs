@@ -1,14 +1,20 @@
 //! Order router with optimal splitting across multiple AMMs.
 
-use crate::amm::CFMM;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::amm::{FeeModel, CFMM};
+use crate::market::reputation::FlowReputation;
 use crate::market::retail::RetailOrder;
+use crate::market::sandwich::{SandwichResult, Sandwicher};
 
 /// Result of routing a trade to an AMM.
 #[derive(Debug, Clone)]
 pub struct RoutedTrade {
     /// AMM name
     pub amm_name: String,
-    /// Amount of Y spent (buy) or received (sell)
+    /// Amount of Y spent (buy) or received (sell), in the numeraire
     pub amount_y: f64,
     /// Amount of X traded
     pub amount_x: f64,
@@ -16,6 +22,73 @@ pub struct RoutedTrade {
     pub amm_buys_x: bool,
 }
 
+impl RoutedTrade {
+    /// Cost to the trader versus trading at `fair_price`, in bps: fees
+    /// plus price impact (negative when the pool was mispriced in the
+    /// trader's favor).
+    pub fn slippage_bps(&self, fair_price: f64) -> f64 {
+        if self.amount_x <= 0.0 || fair_price <= 0.0 {
+            return 0.0;
+        }
+        let execution_price = self.amount_y / self.amount_x;
+        let slippage = if self.amm_buys_x {
+            (fair_price - execution_price) / fair_price
+        } else {
+            (execution_price - fair_price) / fair_price
+        };
+        slippage * 10_000.0
+    }
+}
+
+/// How the router divides an order between AMMs.
+///
+/// - `OptimalSplit`: split so marginal prices end up equal across AMMs
+///   (the default).
+/// - `BestExecution`: send the whole order to the AMM with the best
+///   effective price for its full size (winner-take-all); flow reputation
+///   has nothing to reweight in this mode.
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RouterMode {
+    #[default]
+    OptimalSplit,
+    BestExecution,
+}
+
+/// Smallest leg of a split order the router executes by default; smaller
+/// legs are dropped as dust.
+pub const DEFAULT_MIN_ROUTED_AMOUNT: f64 = 0.0001;
+
+/// Residual gap (see `RoutingDiagnostics`) above which a split counts as
+/// not having converged.
+pub const RESIDUAL_GAP_TOLERANCE: f64 = 1e-6;
+
+/// How far orders routed across more than two AMMs ended from the optimal
+/// split.
+///
+/// An optimal split leaves every AMM that filled part of an order at the
+/// same marginal price, with no other AMM offering a better one. The
+/// residual gap is how much worse, as a fraction, the worst filled AMM's
+/// marginal price is than the best on offer after the order. The
+/// many-AMM path only splits across the first two AMMs, so a better-priced
+/// third venue shows up here instead of silently leaving prices dispersed.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RoutingDiagnostics {
+    /// Orders whose residual gap exceeded `RESIDUAL_GAP_TOLERANCE`
+    pub nonconvergence_count: u64,
+    /// Largest residual gap of any order
+    pub max_residual_gap: f64,
+}
+
+impl RoutingDiagnostics {
+    fn observe(&mut self, residual_gap: f64) {
+        if residual_gap > RESIDUAL_GAP_TOLERANCE {
+            self.nonconvergence_count += 1;
+        }
+        self.max_residual_gap = self.max_residual_gap.max(residual_gap);
+    }
+}
+
 /// Routes retail orders optimally across AMMs.
 ///
 /// Implements optimal order splitting so that the marginal price is equal
@@ -24,22 +97,72 @@ pub struct RoutedTrade {
 ///
 /// For constant product AMMs (xy=k), the optimal split can be computed
 /// analytically rather than using numerical methods.
-pub struct OrderRouter;
+///
+/// AMMs may quote X in different tokens (see `CFMM::set_quote_token`).
+/// Order sizes, fair prices and routed Y amounts are in the common
+/// numeraire; each AMM's Y side is converted at its quote rate, so its
+/// Y reserves count as `y * rate` when comparing prices.
+///
+/// Under `RouterMode::BestExecution` orders are not split at all.
+///
+/// Legs below the minimum routed amount (`set_min_amount`) are not
+/// executed. The minimum is absolute, in the leg's input token (numeraire
+/// Y when buying X, X when selling), or relative to the receiving AMM's
+/// reserve of that token, which suits pools of any unit scale.
+pub struct OrderRouter {
+    mode: RouterMode,
+    min_amount: f64,
+    min_amount_relative: bool,
+}
 
 impl OrderRouter {
     /// Create a new order router.
     pub fn new() -> Self {
-        Self
+        Self::with_mode(RouterMode::OptimalSplit)
+    }
+
+    /// Create a router dividing orders per `mode`.
+    pub fn with_mode(mode: RouterMode) -> Self {
+        Self {
+            mode,
+            min_amount: DEFAULT_MIN_ROUTED_AMOUNT,
+            min_amount_relative: false,
+        }
+    }
+
+    /// How orders are divided between AMMs.
+    pub fn mode(&self) -> RouterMode {
+        self.mode
+    }
+
+    /// Set the smallest leg executed: `amount` of the input token, or with
+    /// `relative` that fraction of the receiving AMM's reserve of it.
+    pub fn set_min_amount(&mut self, amount: f64, relative: bool) {
+        self.min_amount = amount;
+        self.min_amount_relative = relative;
+    }
+
+    /// Whether a leg spending `amount` on `amm` (Y when `trader_buys_x`,
+    /// else X) is too small to execute.
+    fn is_dust(&self, amm: &CFMM, trader_buys_x: bool, amount: f64) -> bool {
+        let min_amount = if self.min_amount_relative {
+            let (x, y) = numeraire_reserves(amm);
+            self.min_amount * if trader_buys_x { y } else { x }
+        } else {
+            self.min_amount
+        };
+        amount <= min_amount
     }
 
     /// Compute optimal Y split for buying X across two AMMs.
     ///
-    /// Uses Uniswap v2 fee-on-input model with γ = 1 - f:
+    /// With γ = 1 - f and g_i the share of input reaching the curve
+    /// (γ_i under fee-on-input, 1 under fee-on-output):
     /// - A_i = sqrt(x_i * γ_i * y_i), r = A_1/A_2
-    /// - Δy_1* = (r * (y_2 + γ_2 * Y) - y_1) / (γ_1 + r * γ_2)
+    /// - Δy_1* = (r * (y_2 + g_2 * Y) - y_1) / (g_1 + r * g_2)
     fn split_buy_two_amms(&self, amm1: &CFMM, amm2: &CFMM, total_y: f64) -> (f64, f64) {
-        let (x1, y1) = amm1.reserves();
-        let (x2, y2) = amm2.reserves();
+        let (x1, y1) = numeraire_reserves(amm1);
+        let (x2, y2) = numeraire_reserves(amm2);
         let f1 = amm1.fees().ask_fee.to_f64();
         let f2 = amm2.fees().ask_fee.to_f64();
 
@@ -57,9 +180,11 @@ impl OrderRouter {
         // r = A_1 / A_2
         let r = a1 / a2;
 
-        // Δy_1* = (r * (y_2 + γ_2 * Y) - y_1) / (γ_1 + r * γ_2)
-        let numerator = r * (y2 + gamma2 * total_y) - y1;
-        let denominator = gamma1 + r * gamma2;
+        // Δy_1* = (r * (y_2 + g_2 * Y) - y_1) / (g_1 + r * g_2)
+        let g1 = curve_share(amm1, gamma1);
+        let g2 = curve_share(amm2, gamma2);
+        let numerator = r * (y2 + g2 * total_y) - y1;
+        let denominator = g1 + r * g2;
 
         let y1_amount = if denominator == 0.0 {
             total_y / 2.0
@@ -76,12 +201,13 @@ impl OrderRouter {
 
     /// Compute optimal X split for selling X across two AMMs.
     ///
-    /// Uses Uniswap v2 fee-on-input model with γ = 1 - f:
+    /// With γ = 1 - f and g_i the share of input reaching the curve
+    /// (γ_i under fee-on-input, 1 under fee-on-output):
     /// - B_i = sqrt(y_i * γ_i * x_i), r = B_1/B_2
-    /// - Δx_1* = (r * (x_2 + γ_2 * X) - x_1) / (γ_1 + r * γ_2)
+    /// - Δx_1* = (r * (x_2 + g_2 * X) - x_1) / (g_1 + r * g_2)
     fn split_sell_two_amms(&self, amm1: &CFMM, amm2: &CFMM, total_x: f64) -> (f64, f64) {
-        let (x1, y1) = amm1.reserves();
-        let (x2, y2) = amm2.reserves();
+        let (x1, y1) = numeraire_reserves(amm1);
+        let (x2, y2) = numeraire_reserves(amm2);
         let f1 = amm1.fees().bid_fee.to_f64();
         let f2 = amm2.fees().bid_fee.to_f64();
 
@@ -99,9 +225,11 @@ impl OrderRouter {
         // r = B_1 / B_2
         let r = b1 / b2;
 
-        // Δx_1* = (r * (x_2 + γ_2 * X) - x_1) / (γ_1 + r * γ_2)
-        let numerator = r * (x2 + gamma2 * total_x) - x1;
-        let denominator = gamma1 + r * gamma2;
+        // Δx_1* = (r * (x_2 + g_2 * X) - x_1) / (g_1 + r * g_2)
+        let g1 = curve_share(amm1, gamma1);
+        let g2 = curve_share(amm2, gamma2);
+        let numerator = r * (x2 + g2 * total_x) - x1;
+        let denominator = g1 + r * g2;
 
         let x1_amount = if denominator == 0.0 {
             total_x / 2.0
@@ -124,22 +252,40 @@ impl OrderRouter {
         fair_price: f64,
         timestamp: u64,
     ) -> Vec<RoutedTrade> {
+        let mut fills = Fills::default();
+        self.route_order_into(order, amms, fair_price, timestamp, &mut fills);
+        fills.trades
+    }
+
+    fn route_order_into(
+        &self,
+        order: &RetailOrder,
+        amms: &mut [CFMM],
+        fair_price: f64,
+        timestamp: u64,
+        fills: &mut Fills,
+    ) {
         if amms.is_empty() {
-            return Vec::new();
+            return;
         }
 
         if amms.len() == 1 {
-            return self.route_to_single_amm(order, &mut amms[0], fair_price, timestamp);
+            return self.route_to_single_amm(order, &mut amms[0], fair_price, timestamp, fills);
+        }
+
+        if self.mode == RouterMode::BestExecution {
+            let best = best_execution_amm(order, amms, fair_price);
+            return self.route_to_single_amm(order, &mut amms[best], fair_price, timestamp, fills);
         }
 
         // For 2 AMMs, use optimal splitting
         if amms.len() == 2 {
-            return self.route_to_two_amms(order, amms, fair_price, timestamp);
+            return self.route_to_two_amms(order, amms, fair_price, timestamp, fills);
         }
 
         // For >2 AMMs, use iterative pairwise splitting
         // (Simplified - true optimal would require solving simultaneously)
-        self.route_to_many_amms(order, amms, fair_price, timestamp)
+        self.route_to_many_amms(order, amms, fair_price, timestamp, fills)
     }
 
     fn route_to_single_amm(
@@ -148,33 +294,20 @@ impl OrderRouter {
         amm: &mut CFMM,
         fair_price: f64,
         timestamp: u64,
-    ) -> Vec<RoutedTrade> {
-        let mut trades = Vec::new();
-
+        fills: &mut Fills,
+    ) {
         if order.side == "buy" {
             // Trader wants to buy X, spending Y
-            if let Some(result) = amm.execute_buy_x_with_y(order.size, timestamp) {
-                trades.push(RoutedTrade {
-                    amm_name: amm.name.clone(),
-                    amount_y: order.size,
-                    amount_x: result.trade_info.amount_x.to_f64(),
-                    amm_buys_x: false,
-                });
+            if !self.is_dust(amm, true, order.size) {
+                fills.fill(amm, true, order.size, fair_price, timestamp);
             }
         } else {
             // Trader wants to sell X, receiving Y
             let total_x = order.size / fair_price;
-            if let Some(result) = amm.execute_buy_x(total_x, timestamp) {
-                trades.push(RoutedTrade {
-                    amm_name: amm.name.clone(),
-                    amount_y: result.trade_info.amount_y.to_f64(),
-                    amount_x: total_x,
-                    amm_buys_x: true,
-                });
+            if !self.is_dust(amm, false, total_x) {
+                fills.fill(amm, false, total_x, fair_price, timestamp);
             }
         }
-
-        trades
     }
 
     fn route_to_two_amms(
@@ -183,68 +316,34 @@ impl OrderRouter {
         amms: &mut [CFMM],
         fair_price: f64,
         timestamp: u64,
-    ) -> Vec<RoutedTrade> {
-        let mut trades = Vec::new();
-        const MIN_AMOUNT: f64 = 0.0001;
-
+        fills: &mut Fills,
+    ) {
         // Split amms mutably
         let (amm1, rest) = amms.split_first_mut().unwrap();
         let amm2 = &mut rest[0];
 
         if order.side == "buy" {
             // Trader wants to buy X, spending Y
-            let (y1, y2) = self.split_buy_two_amms(amm1, amm2, order.size);
-
-            if y1 > MIN_AMOUNT {
-                if let Some(result) = amm1.execute_buy_x_with_y(y1, timestamp) {
-                    trades.push(RoutedTrade {
-                        amm_name: amm1.name.clone(),
-                        amount_y: y1,
-                        amount_x: result.trade_info.amount_x.to_f64(),
-                        amm_buys_x: false,
-                    });
-                }
-            }
+            let (y1, y2) = fills.reweight(amm1, amm2, self.split_buy_two_amms(amm1, amm2, order.size));
 
-            if y2 > MIN_AMOUNT {
-                if let Some(result) = amm2.execute_buy_x_with_y(y2, timestamp) {
-                    trades.push(RoutedTrade {
-                        amm_name: amm2.name.clone(),
-                        amount_y: y2,
-                        amount_x: result.trade_info.amount_x.to_f64(),
-                        amm_buys_x: false,
-                    });
-                }
+            if !self.is_dust(amm1, true, y1) {
+                fills.fill(amm1, true, y1, fair_price, timestamp);
+            }
+            if !self.is_dust(amm2, true, y2) {
+                fills.fill(amm2, true, y2, fair_price, timestamp);
             }
         } else {
             // Trader wants to sell X, receiving Y
             let total_x = order.size / fair_price;
-            let (x1, x2) = self.split_sell_two_amms(amm1, amm2, total_x);
-
-            if x1 > MIN_AMOUNT {
-                if let Some(result) = amm1.execute_buy_x(x1, timestamp) {
-                    trades.push(RoutedTrade {
-                        amm_name: amm1.name.clone(),
-                        amount_y: result.trade_info.amount_y.to_f64(),
-                        amount_x: x1,
-                        amm_buys_x: true,
-                    });
-                }
-            }
+            let (x1, x2) = fills.reweight(amm1, amm2, self.split_sell_two_amms(amm1, amm2, total_x));
 
-            if x2 > MIN_AMOUNT {
-                if let Some(result) = amm2.execute_buy_x(x2, timestamp) {
-                    trades.push(RoutedTrade {
-                        amm_name: amm2.name.clone(),
-                        amount_y: result.trade_info.amount_y.to_f64(),
-                        amount_x: x2,
-                        amm_buys_x: true,
-                    });
-                }
+            if !self.is_dust(amm1, false, x1) {
+                fills.fill(amm1, false, x1, fair_price, timestamp);
+            }
+            if !self.is_dust(amm2, false, x2) {
+                fills.fill(amm2, false, x2, fair_price, timestamp);
             }
         }
-
-        trades
     }
 
     fn route_to_many_amms(
@@ -253,14 +352,18 @@ impl OrderRouter {
         amms: &mut [CFMM],
         fair_price: f64,
         timestamp: u64,
-    ) -> Vec<RoutedTrade> {
+        fills: &mut Fills,
+    ) {
         // Simplified: just use first two AMMs
         // Full implementation would need recursive splitting
+        let first_fill = fills.trades.len();
         if amms.len() >= 2 {
-            self.route_to_two_amms(order, &mut amms[0..2], fair_price, timestamp)
+            self.route_to_two_amms(order, &mut amms[0..2], fair_price, timestamp, fills)
         } else {
-            self.route_to_single_amm(order, &mut amms[0], fair_price, timestamp)
+            self.route_to_single_amm(order, &mut amms[0], fair_price, timestamp, fills)
         }
+        let gap = residual_gap(amms, &fills.trades[first_fill..], order.side == "buy");
+        fills.diagnostics.observe(gap);
     }
 
     /// Route multiple orders.
@@ -271,31 +374,230 @@ impl OrderRouter {
         fair_price: f64,
         timestamp: u64,
     ) -> Vec<RoutedTrade> {
-        let mut all_trades = Vec::new();
+        let mut fills = Fills::default();
+        for order in orders {
+            self.route_order_into(order, amms, fair_price, timestamp, &mut fills);
+        }
+        fills.trades
+    }
 
+    /// Route multiple orders, with `sandwicher` wrapping every fill in a
+    /// front-run and back-run on the same AMM.
+    pub fn route_orders_sandwiched(
+        &self,
+        orders: &[RetailOrder],
+        amms: &mut [CFMM],
+        fair_price: f64,
+        timestamp: u64,
+        sandwicher: &Sandwicher,
+    ) -> (Vec<RoutedTrade>, Vec<SandwichResult>) {
+        self.route_orders_with(orders, amms, fair_price, timestamp, Some(sandwicher), None)
+    }
+
+    /// Route multiple orders with optional adversaries and retail memory.
+    ///
+    /// `sandwicher` wraps every fill in a front-run and back-run on the
+    /// same AMM; `reputation` shifts flow between two AMMs away from the
+    /// one that gave retail worse fills recently (see `FlowReputation`).
+    pub fn route_orders_with(
+        &self,
+        orders: &[RetailOrder],
+        amms: &mut [CFMM],
+        fair_price: f64,
+        timestamp: u64,
+        sandwicher: Option<&Sandwicher>,
+        reputation: Option<&FlowReputation>,
+    ) -> (Vec<RoutedTrade>, Vec<SandwichResult>) {
+        let mut fills = Fills {
+            sandwicher,
+            reputation,
+            ..Fills::default()
+        };
+        for order in orders {
+            self.route_order_into(order, amms, fair_price, timestamp, &mut fills);
+        }
+        (fills.trades, fills.sandwiches)
+    }
+
+    /// Route multiple orders, also reporting how far splits across more
+    /// than two AMMs ended from optimal.
+    pub fn route_orders_diagnosed(
+        &self,
+        orders: &[RetailOrder],
+        amms: &mut [CFMM],
+        fair_price: f64,
+        timestamp: u64,
+    ) -> (Vec<RoutedTrade>, RoutingDiagnostics) {
+        let mut fills = Fills::default();
         for order in orders {
-            let trades = self.route_order(order, amms, fair_price, timestamp);
-            all_trades.extend(trades);
+            self.route_order_into(order, amms, fair_price, timestamp, &mut fills);
         }
+        (fills.trades, fills.diagnostics)
+    }
+}
+
+/// Trades executed while routing, plus any sandwiches around them.
+#[derive(Default)]
+struct Fills<'a> {
+    trades: Vec<RoutedTrade>,
+    sandwicher: Option<&'a Sandwicher>,
+    sandwiches: Vec<SandwichResult>,
+    reputation: Option<&'a FlowReputation>,
+    diagnostics: RoutingDiagnostics,
+}
+
+impl Fills<'_> {
+    /// Move the flow retail withholds from a badly-reputed AMM to the other.
+    fn reweight(&self, amm1: &CFMM, amm2: &CFMM, (a1, a2): (f64, f64)) -> (f64, f64) {
+        let Some(reputation) = self.reputation else {
+            return (a1, a2);
+        };
+        let names = [amm1.name.as_str(), amm2.name.as_str()];
+        let moved1 = a1 * (1.0 - reputation.flow_weight(&amm1.name, &names));
+        let moved2 = a2 * (1.0 - reputation.flow_weight(&amm2.name, &names));
+        (a1 - moved1 + moved2, a2 - moved2 + moved1)
+    }
+
+    /// Execute one leg of an order on `amm`: the trader spends `amount` of
+    /// Y when buying X, or of X when selling it.
+    fn fill(
+        &mut self,
+        amm: &mut CFMM,
+        trader_buys_x: bool,
+        amount: f64,
+        fair_price: f64,
+        timestamp: u64,
+    ) {
+        let trade = match self.sandwicher {
+            Some(sandwicher) => {
+                let victim = |amm: &mut CFMM| execute_leg(amm, trader_buys_x, amount, timestamp);
+                let (trade, sandwich) =
+                    sandwicher.sandwich(amm, trader_buys_x, amount, fair_price, timestamp, victim);
+                self.sandwiches.extend(sandwich);
+                trade
+            }
+            None => execute_leg(amm, trader_buys_x, amount, timestamp),
+        };
+        self.trades.extend(trade);
+    }
+}
 
-        all_trades
+/// Execute a retail trade on one AMM: the trader spends `amount` of Y
+/// (in the numeraire) when buying X, or of X when selling it, up to the
+/// AMM's per-trade cap.
+pub(crate) fn execute_leg(
+    amm: &mut CFMM,
+    trader_buys_x: bool,
+    amount: f64,
+    timestamp: u64,
+) -> Option<RoutedTrade> {
+    let rate = amm.quote_rate();
+    if trader_buys_x {
+        let amount_y = amm.capped_y(amount / rate);
+        let result = amm.execute_buy_x_with_y(amount_y, timestamp)?;
+        Some(RoutedTrade {
+            amm_name: amm.name.clone(),
+            amount_y: amount_y * rate,
+            amount_x: result.trade_info.amount_x.to_f64(),
+            amm_buys_x: false,
+        })
+    } else {
+        let amount_x = amm.capped_x(amount);
+        let result = amm.execute_buy_x(amount_x, timestamp)?;
+        Some(RoutedTrade {
+            amm_name: amm.name.clone(),
+            amount_y: result.trade_info.amount_y.to_f64() * rate,
+            amount_x,
+            amm_buys_x: true,
+        })
     }
 }
 
+/// Reserves of an AMM with Y converted to the numeraire.
+fn numeraire_reserves(amm: &CFMM) -> (f64, f64) {
+    let (x, y) = amm.reserves();
+    (x, y * amm.quote_rate())
+}
+
 impl Default for OrderRouter {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Fraction by which the worst marginal price among the AMMs that filled
+/// `trades` trails the best marginal price any AMM offers for the next unit
+/// of X (0 when nothing filled).
+fn residual_gap(amms: &[CFMM], trades: &[RoutedTrade], trader_buys_x: bool) -> f64 {
+    // Y paid per X when buying, received per X when selling
+    let marginal_price = |amm: &CFMM| {
+        let (x, y) = numeraire_reserves(amm);
+        let fees = amm.fees();
+        if trader_buys_x {
+            y / x / (1.0 - fees.ask_fee.to_f64())
+        } else {
+            y / x * (1.0 - fees.bid_fee.to_f64())
+        }
+    };
+    let filled = amms
+        .iter()
+        .filter(|amm| trades.iter().any(|trade| trade.amm_name == amm.name))
+        .map(marginal_price);
+    let offered = amms.iter().map(marginal_price);
+    let gap = if trader_buys_x {
+        filled.fold(f64::NEG_INFINITY, f64::max) / offered.fold(f64::INFINITY, f64::min) - 1.0
+    } else {
+        offered.fold(f64::NEG_INFINITY, f64::max) / filled.fold(f64::INFINITY, f64::min) - 1.0
+    };
+    if gap.is_finite() { gap.max(0.0) } else { 0.0 }
+}
+
+/// Index of the AMM giving the most output per unit of input for the
+/// whole of `order` (the first on ties or when none can fill it).
+fn best_execution_amm(order: &RetailOrder, amms: &[CFMM], fair_price: f64) -> usize {
+    let effective_price = |amm: &CFMM| {
+        let rate = amm.quote_rate();
+        let (amount_in, amount_out) = if order.side == "buy" {
+            let amount_y = amm.capped_y(order.size / rate);
+            (amount_y * rate, amm.quote_x_for_y(amount_y).0)
+        } else {
+            let amount_x = amm.capped_x(order.size / fair_price);
+            (amount_x, amm.quote_buy_x(amount_x).0 * rate)
+        };
+        if amount_in > 0.0 { amount_out / amount_in } else { 0.0 }
+    };
+
+    let mut best = 0;
+    let mut best_price = effective_price(&amms[0]);
+    for (i, amm) in amms.iter().enumerate().skip(1) {
+        let price = effective_price(amm);
+        if price > best_price {
+            best = i;
+            best_price = price;
+        }
+    }
+    best
+}
+
+/// Fraction of a trade's input that reaches the curve.
+#[inline]
+fn curve_share(amm: &CFMM, gamma: f64) -> f64 {
+    match amm.fee_model() {
+        FeeModel::OnInput => gamma,
+        FeeModel::OnOutput => 1.0,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::amm::cfmm::FeeQuote;
+    use crate::types::wad::Wad;
 
     #[test]
     fn test_split_formulas() {
         // Test the split formulas without EVM
-        let x1 = 1000.0;
+        let x1: f64 = 1000.0;
         let y1 = 1000.0;
         let x2 = 1000.0;
         let y2 = 1000.0;
@@ -315,4 +617,151 @@ mod tests {
         // Should be approximately equal split
         assert!((y1_amount - 50.0).abs() < 1.0);
     }
+
+    #[test]
+    fn test_best_execution_sends_whole_order_to_cheaper_pool() {
+        let pools = || {
+            let pool = |name: &str, fee_bps| {
+                let fees = FeeQuote::symmetric(Wad::from_bps(fee_bps));
+                let mut amm = CFMM::fixed_fee(name.into(), 100.0, 10_000.0, fees);
+                amm.initialize().unwrap();
+                amm
+            };
+            [pool("expensive", 60), pool("cheap", 30)]
+        };
+        let orders = [
+            RetailOrder { side: "buy", size: 500.0 },
+            RetailOrder { side: "sell", size: 500.0 },
+        ];
+
+        // Splitting gives the expensive pool part of each order
+        let mut amms = pools();
+        let trades = OrderRouter::new().route_orders(&orders, &mut amms, 100.0, 0);
+        assert!(trades.iter().any(|trade| trade.amm_name == "expensive"));
+
+        // Best execution routes each order whole to the cheaper pool
+        let mut amms = pools();
+        let router = OrderRouter::with_mode(RouterMode::BestExecution);
+        let trades = router.route_orders(&orders, &mut amms, 100.0, 0);
+        assert_eq!(trades.len(), 2);
+        assert!(trades.iter().all(|trade| trade.amm_name == "cheap"));
+        assert_eq!(trades[0].amount_y, 500.0);
+        assert!((trades[1].amount_x - 5.0).abs() < 1e-12);
+        assert_eq!(amms[0].reserves(), (100.0, 10_000.0));
+    }
+
+    #[test]
+    fn test_relative_min_amount_routes_small_unit_pools() {
+        // 1e9 X against 1 Y: a 1.5e-4 Y buy is 0.015% of the pool, split in
+        // legs below the absolute default
+        let pools = || {
+            let pool = |name: &str, fee_bps| {
+                let fees = FeeQuote::symmetric(Wad::from_bps(fee_bps));
+                let mut amm = CFMM::fixed_fee(name.into(), 1e9, 1.0, fees);
+                amm.initialize().unwrap();
+                amm
+            };
+            [pool("first", 30), pool("second", 30)]
+        };
+        let orders = [RetailOrder { side: "buy", size: 1.5e-4 }];
+        let fair_price = 1e-9;
+
+        let mut amms = pools();
+        let trades = OrderRouter::new().route_orders(&orders, &mut amms, fair_price, 0);
+        assert!(trades.is_empty());
+        assert_eq!(amms[0].reserves(), (1e9, 1.0));
+
+        let mut router = OrderRouter::new();
+        router.set_min_amount(1e-6, true);
+        let mut amms = pools();
+        let trades = router.route_orders(&orders, &mut amms, fair_price, 0);
+        assert_eq!(trades.len(), 2);
+        let spent: f64 = trades.iter().map(|trade| trade.amount_y).sum();
+        assert!((spent - 1.5e-4).abs() < 1e-15, "{spent}");
+        assert!(trades.iter().all(|trade| trade.amount_x > 1e4));
+
+        // Relative to 1e9 X, a 100 X leg is dust
+        let sell = [RetailOrder { side: "sell", size: 100.0 * fair_price }];
+        assert!(router.route_orders(&sell, &mut amms, fair_price, 0).is_empty());
+    }
+
+    #[test]
+    fn test_diagnostics_flag_splits_that_miss_better_pools() {
+        let pool = |name: &str, x: f64, fee_bps| {
+            let fees = FeeQuote::symmetric(Wad::from_bps(fee_bps));
+            let mut amm = CFMM::fixed_fee(name.into(), x, x * 100.0, fees);
+            amm.initialize().unwrap();
+            amm
+        };
+        let orders = [
+            RetailOrder { side: "buy", size: 500.0 },
+            RetailOrder { side: "sell", size: 500.0 },
+        ];
+
+        let router = OrderRouter::new();
+
+        // A pricier third pool rightly gets nothing: the split converged
+        let mut amms = [pool("a", 100.0, 30), pool("b", 100.0, 30), pool("c", 100.0, 1_000)];
+        let (trades, diagnostics) = router.route_orders_diagnosed(&orders, &mut amms, 100.0, 0);
+        assert!(!trades.is_empty());
+        assert_eq!(diagnostics.nonconvergence_count, 0);
+        let gap = diagnostics.max_residual_gap;
+        assert!(gap < RESIDUAL_GAP_TOLERANCE, "{gap}");
+
+        // Thin, expensive pools first and deep, cheap ones after them:
+        // every order ends with better prices left untouched
+        let mut amms = [
+            pool("thin", 10.0, 100),
+            pool("thinner", 5.0, 200),
+            pool("deep", 10_000.0, 5),
+            pool("deeper", 50_000.0, 1),
+        ];
+        let (trades, diagnostics) = router.route_orders_diagnosed(&orders, &mut amms, 100.0, 0);
+        assert!(trades.iter().all(|trade| trade.amm_name.starts_with("thin")));
+        assert_eq!(diagnostics.nonconvergence_count, 2);
+        assert!(diagnostics.max_residual_gap > 0.05, "{}", diagnostics.max_residual_gap);
+    }
+
+    #[test]
+    fn test_cross_pair_split_matches_same_unit_split() {
+        // The second pool quotes X in cents, 100 to the numeraire's unit
+        let pools = |cents: bool| {
+            let fees = |bps| FeeQuote::symmetric(Wad::from_bps(bps));
+            let mut first = CFMM::fixed_fee("first".into(), 100.0, 10_000.0, fees(30));
+            let mut second = if cents {
+                let mut amm = CFMM::fixed_fee("second".into(), 80.0, 800_000.0, fees(50));
+                amm.set_quote_token("cents", 0.01);
+                amm
+            } else {
+                CFMM::fixed_fee("second".into(), 80.0, 8_000.0, fees(50))
+            };
+            first.initialize().unwrap();
+            second.initialize().unwrap();
+            [first, second]
+        };
+        let orders = [
+            RetailOrder { side: "buy", size: 300.0 },
+            RetailOrder { side: "sell", size: 200.0 },
+        ];
+
+        for mode in [RouterMode::OptimalSplit, RouterMode::BestExecution] {
+            let router = OrderRouter::with_mode(mode);
+            let (mut same, mut cross) = (pools(false), pools(true));
+            let same_trades = router.route_orders(&orders, &mut same, 100.0, 0);
+            let cross_trades = router.route_orders(&orders, &mut cross, 100.0, 0);
+
+            assert_eq!(same_trades.len(), cross_trades.len());
+            if mode == RouterMode::OptimalSplit {
+                assert!(same_trades.iter().any(|trade| trade.amm_name == "second"));
+            }
+            for (a, b) in same_trades.iter().zip(&cross_trades) {
+                assert_eq!(a.amm_name, b.amm_name);
+                assert!((a.amount_x - b.amount_x).abs() < 1e-9 * a.amount_x, "{mode:?}");
+                assert!((a.amount_y - b.amount_y).abs() < 1e-9 * a.amount_y, "{mode:?}");
+            }
+            let (_, same_y) = same[1].reserves();
+            let (_, cross_y) = cross[1].reserves();
+            assert!((cross_y / 100.0 - same_y).abs() < 1e-9 * same_y);
+        }
+    }
 }
@@ -0,0 +1,132 @@
+//! Adversarial searcher that sandwiches routed retail orders.
+
+use crate::amm::CFMM;
+use crate::market::router::RoutedTrade;
+
+/// Profit one sandwich extracted from an AMM's retail flow.
+#[derive(Debug, Clone)]
+pub struct SandwichResult {
+    /// AMM the sandwich ran on
+    pub amm_name: String,
+    /// Profit in Y; negative when fees outweigh the victim's price impact
+    pub profit: f64,
+}
+
+/// Searcher that front-runs each retail fill in the same direction and
+/// back-runs it with the opposite trade on the same AMM.
+///
+/// The front-run is `ratio` times the victim's input: Y when the victim
+/// buys X, X when it sells. The back-run unwinds exactly the inventory the
+/// front-run acquired, so profit is measured purely in Y.
+#[derive(Debug, Clone)]
+pub struct Sandwicher {
+    ratio: f64,
+}
+
+impl Sandwicher {
+    /// Create a sandwicher whose front-run is `ratio` times the victim's
+    /// input.
+    pub fn new(ratio: f64) -> Self {
+        Self { ratio }
+    }
+
+    /// Front-run size relative to the victim's input.
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    /// Wrap the victim trade `victim` in a front-run and back-run on `amm`.
+    ///
+    /// `amount` is the victim's input (Y when `trader_buys_x`, X otherwise).
+    /// If the front-run is rejected the victim trades alone and no sandwich
+    /// is reported. Inventory the back-run cannot unwind (rejected, or
+    /// beyond the AMM's per-trade cap) is marked at `fair_price`.
+    pub fn sandwich(
+        &self,
+        amm: &mut CFMM,
+        trader_buys_x: bool,
+        amount: f64,
+        fair_price: f64,
+        timestamp: u64,
+        victim: impl FnOnce(&mut CFMM) -> Option<RoutedTrade>,
+    ) -> (Option<RoutedTrade>, Option<SandwichResult>) {
+        let front_amount = self.ratio * amount;
+
+        let (trade, profit) = if trader_buys_x {
+            // Buy X ahead of the victim, sell it back after
+            let y_spent = amm.capped_y(front_amount);
+            let Some(front) = amm.execute_buy_x_with_y(y_spent, timestamp) else {
+                return (victim(amm), None);
+            };
+            let x_bought = front.trade_info.amount_x.to_f64();
+            let trade = victim(amm);
+            let x_back = amm.capped_x(x_bought);
+            let y_back = match amm.execute_buy_x(x_back, timestamp) {
+                Some(back) => back.trade_info.amount_y.to_f64(),
+                None => x_back * fair_price,
+            };
+            let leftover = (x_bought - x_back) * fair_price;
+            (trade, y_back + leftover - y_spent)
+        } else {
+            // Sell X ahead of the victim, buy it back after
+            let x_sold = amm.capped_x(front_amount);
+            let Some(front) = amm.execute_buy_x(x_sold, timestamp) else {
+                return (victim(amm), None);
+            };
+            let y_received = front.trade_info.amount_y.to_f64();
+            let trade = victim(amm);
+            let x_back = amm.capped_x(x_sold);
+            let y_back = match amm.execute_sell_x(x_back, timestamp) {
+                Some(back) => back.trade_info.amount_y.to_f64(),
+                None => x_back * fair_price,
+            };
+            let shortfall = (x_sold - x_back) * fair_price;
+            (trade, y_received - y_back - shortfall)
+        };
+
+        let result = SandwichResult {
+            amm_name: amm.name.clone(),
+            profit,
+        };
+        (trade, Some(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm::cfmm::FeeQuote;
+    use crate::market::router::execute_leg;
+    use crate::types::wad::Wad;
+
+    fn sandwich_profit(fee_bps: i128, trader_buys_x: bool, amount: f64) -> f64 {
+        let mut amm = CFMM::fixed_fee(
+            "pool".into(),
+            100.0,
+            10000.0,
+            FeeQuote::symmetric(Wad::from_bps(fee_bps)),
+        );
+        amm.initialize().unwrap();
+
+        let sandwicher = Sandwicher::new(1.0);
+        let victim = |amm: &mut CFMM| execute_leg(amm, trader_buys_x, amount, 0);
+        let (trade, sandwich) = sandwicher.sandwich(&mut amm, trader_buys_x, amount, 100.0, 0, victim);
+        assert!(trade.is_some());
+        sandwich.unwrap().profit
+    }
+
+    #[test]
+    fn test_higher_fees_reduce_sandwich_profit() {
+        // Buy of 500 Y against 10000 Y of depth
+        let low = sandwich_profit(5, true, 500.0);
+        let high = sandwich_profit(50, true, 500.0);
+        assert!(low > 0.0, "{low}");
+        assert!(low > high, "{low} vs {high}");
+
+        // Sell of 5 X against 100 X of depth
+        let low = sandwich_profit(5, false, 5.0);
+        let high = sandwich_profit(50, false, 5.0);
+        assert!(low > 0.0, "{low}");
+        assert!(low > high, "{low} vs {high}");
+    }
+}
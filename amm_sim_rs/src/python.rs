@@ -0,0 +1,490 @@
+//! Python bindings (enabled by the `python` feature).
+
+// pyo3 0.22's generated wrappers convert every `PyResult` error into `PyErr`
+#![allow(clippy::useless_conversion)]
+
+use std::sync::{Arc, Mutex};
+
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::amm::{FeeModel, FeeUpdateFrequency, RoundingMode};
+use crate::evm::strategy::EVMStrategy;
+use crate::market::{RetailBatching, RetailCohort, RetailFlowStyle, RetailMode, RouterMode};
+use crate::{
+    inspect_strategy as inspect_strategy_rs, replay_rs, run_batch_summary_rs, run_seed_sweep_rs,
+    run_single_rs,
+};
+use crate::simulation::runner::{
+    run_simulation_with_hook, run_simulations_parallel, ResultSink, SimulationBatchConfig,
+};
+use crate::simulation::engine::{self, SimulationEngine};
+use crate::types::config::{BaselineMode, SimulationConfig};
+use crate::types::observation::{StateObservation, StepObservation};
+use crate::types::result::{
+    BatchSimulationResult, ComparisonStats, FeeStats, GasBreakdown, LightweightSimResult,
+    PairedBatchResult, PnlAttribution, ScoreScheme, SimulationSummary, TieBreak,
+};
+
+// pyo3 0.22's `create_exception!` checks a `gil-refs` feature this crate doesn't declare
+#[allow(unexpected_cfgs)]
+mod exceptions {
+    use pyo3::create_exception;
+    use pyo3::exceptions::PyRuntimeError;
+
+    create_exception!(amm_sim_rs, SimulationError, PyRuntimeError, "Base class of all simulator errors.");
+    create_exception!(amm_sim_rs, ConfigError, SimulationError, "The simulation config is invalid.");
+    create_exception!(amm_sim_rs, EVMDeployError, SimulationError, "A strategy's bytecode failed to deploy.");
+    create_exception!(amm_sim_rs, EVMExecError, SimulationError, "A strategy call failed during the run.");
+    create_exception!(amm_sim_rs, StateError, SimulationError, "The engine is not in a state that allows the call.");
+}
+
+use exceptions::{ConfigError, EVMDeployError, EVMExecError, SimulationError, StateError};
+
+impl From<engine::SimulationError> for PyErr {
+    fn from(e: engine::SimulationError) -> Self {
+        let message = e.to_string();
+        match e {
+            engine::SimulationError::InvalidConfig(_) => ConfigError::new_err(message),
+            engine::SimulationError::EVMDeployError(_) => EVMDeployError::new_err(message),
+            engine::SimulationError::EVMError(_) => EVMExecError::new_err(message),
+            engine::SimulationError::InvalidState(_) => StateError::new_err(message),
+        }
+    }
+}
+
+/// Run multiple simulations in parallel using Rust engine.
+///
+/// # Arguments
+/// * `submission_bytecode` - Compiled bytecode for the submission strategy
+/// * `baseline_bytecode` - Compiled bytecode for the baseline strategy
+/// * `configs` - List of simulation configurations (one per simulation)
+/// * `n_workers` - Number of parallel workers (0 = auto-detect)
+/// * `deterministic` - Require seeded configs and a fixed worker count
+/// * `retain_steps` - Keep per-step series in the returned results
+/// * `on_result` - Called as `on_result(index, result)` with each finished
+///   simulation (steps included) as it completes; its first exception is
+///   raised once the batch is done
+/// * `capture_errors` - Return a failing simulation as a result with
+///   `failed` set, its `error` message and the `failed_strategy` it counts
+///   as a loss for, instead of raising
+/// * `shared_pool` - Reuse a process-wide worker pool across calls with
+///   the same `n_workers` instead of starting new threads each time
+/// * `antithetic` - Follow each config's result with that of its mirrored
+///   price path (negated normal draws)
+/// * `timeout_ms` - Stop any simulation still running after this many
+///   milliseconds and return it as a `failed`, `timed_out` result
+///
+/// # Returns
+/// BatchSimulationResult containing all simulation results
+#[pyfunction]
+#[pyo3(signature = (
+    submission_bytecode,
+    baseline_bytecode,
+    configs,
+    n_workers = 0,
+    deterministic = false,
+    retain_steps = true,
+    on_result = None,
+    capture_errors = false,
+    shared_pool = false,
+    antithetic = false,
+    timeout_ms = None
+))]
+#[allow(clippy::too_many_arguments)]
+fn run_batch(
+    py: Python<'_>,
+    submission_bytecode: Vec<u8>,
+    baseline_bytecode: Vec<u8>,
+    configs: Vec<SimulationConfig>,
+    n_workers: usize,
+    deterministic: bool,
+    retain_steps: bool,
+    on_result: Option<PyObject>,
+    capture_errors: bool,
+    shared_pool: bool,
+    antithetic: bool,
+    timeout_ms: Option<u64>,
+) -> PyResult<BatchSimulationResult> {
+    let callback_error: Arc<Mutex<Option<PyErr>>> = Arc::default();
+    let sink = on_result.map(|callback| {
+        let callback_error = Arc::clone(&callback_error);
+        Arc::new(move |index: usize, result: &LightweightSimResult| {
+            Python::with_gil(|py| {
+                if let Err(e) = callback.call1(py, (index, result.clone())) {
+                    callback_error.lock().unwrap().get_or_insert(e);
+                }
+            })
+        }) as ResultSink
+    });
+    let batch_config = SimulationBatchConfig {
+        deterministic,
+        retain_steps,
+        sink,
+        capture_errors,
+        shared_pool,
+        antithetic,
+        timeout_ms,
+        ..SimulationBatchConfig::new(submission_bytecode, baseline_bytecode, configs, n_workers)
+    };
+
+    // Workers need the GIL to call back into Python
+    let batch = py.allow_threads(|| run_simulations_parallel(batch_config))?;
+    if let Some(e) = callback_error.lock().unwrap().take() {
+        return Err(e);
+    }
+    Ok(batch)
+}
+
+/// Run `configs` in parallel, returning one `SimulationSummary` (seed,
+/// PnLs, edges, winner index) per config instead of full results.
+///
+/// No per-step data is recorded, so this is the cheap way to score very
+/// large batches.
+#[pyfunction]
+#[pyo3(signature = (submission_bytecode, baseline_bytecode, configs, n_workers = 0))]
+fn run_batch_summary(
+    py: Python<'_>,
+    submission_bytecode: Vec<u8>,
+    baseline_bytecode: Vec<u8>,
+    configs: Vec<SimulationConfig>,
+    n_workers: usize,
+) -> PyResult<Vec<SimulationSummary>> {
+    py.allow_threads(|| {
+        run_batch_summary_rs(submission_bytecode, baseline_bytecode, configs, n_workers)
+    })
+    .map_err(PyErr::from)
+}
+
+/// Run `base_config` once per seed in `seeds`, in parallel.
+///
+/// Equivalent to `run_batch` on copies of `base_config` with each seed
+/// set, without building the copies in Python.
+#[pyfunction]
+#[pyo3(signature = (submission_bytecode, baseline_bytecode, base_config, seeds, n_workers = 0))]
+fn run_seed_sweep(
+    py: Python<'_>,
+    submission_bytecode: Vec<u8>,
+    baseline_bytecode: Vec<u8>,
+    base_config: SimulationConfig,
+    seeds: Vec<u64>,
+    n_workers: usize,
+) -> PyResult<BatchSimulationResult> {
+    py.allow_threads(|| {
+        run_seed_sweep_rs(submission_bytecode, baseline_bytecode, &base_config, &seeds, n_workers)
+    })
+    .map_err(PyErr::from)
+}
+
+/// Run a deterministic batch twice and raise if any simulation differs.
+///
+/// Returns the first run's results.
+#[pyfunction]
+#[pyo3(signature = (submission_bytecode, baseline_bytecode, configs, n_workers = 0))]
+fn verify_determinism(
+    submission_bytecode: Vec<u8>,
+    baseline_bytecode: Vec<u8>,
+    configs: Vec<SimulationConfig>,
+    n_workers: usize,
+) -> PyResult<BatchSimulationResult> {
+    let batch_config = SimulationBatchConfig {
+        deterministic: true,
+        ..SimulationBatchConfig::new(submission_bytecode, baseline_bytecode, configs, n_workers)
+    };
+
+    crate::simulation::runner::verify_determinism(batch_config)
+        .map_err(PyErr::from)
+}
+
+/// Run two submissions against the same baseline on identical configs.
+///
+/// Returns both batches plus their paired per-config differences.
+#[pyfunction]
+#[pyo3(signature = (submission_a_bytecode, submission_b_bytecode, baseline_bytecode, configs, n_workers = 0))]
+fn run_paired(
+    submission_a_bytecode: Vec<u8>,
+    submission_b_bytecode: Vec<u8>,
+    baseline_bytecode: Vec<u8>,
+    configs: Vec<SimulationConfig>,
+    n_workers: usize,
+) -> PyResult<PairedBatchResult> {
+    crate::simulation::runner::run_paired(
+        submission_a_bytecode,
+        submission_b_bytecode,
+        baseline_bytecode,
+        configs,
+        n_workers,
+    )
+    .map_err(PyErr::from)
+}
+
+/// Run a batch where each match is its own `(submission, baseline, config)`.
+///
+/// Repeated bytecodes are deployed once and shared across matches.
+#[pyfunction]
+#[pyo3(signature = (matches, n_workers = 0))]
+fn run_matches(
+    matches: Vec<(Vec<u8>, Vec<u8>, SimulationConfig)>,
+    n_workers: usize,
+) -> PyResult<BatchSimulationResult> {
+    crate::simulation::runner::run_matches(matches, n_workers)
+        .map_err(PyErr::from)
+}
+
+/// Paired t-test of batch A's submission edges against batch B's.
+///
+/// Both batches must come from the same configs (matching seeds, in order).
+#[pyfunction]
+fn compare_batches(
+    a: &BatchSimulationResult,
+    b: &BatchSimulationResult,
+) -> PyResult<ComparisonStats> {
+    crate::simulation::compare::compare_batches(a, b)
+        .map_err(PyErr::from)
+}
+
+/// Run a single simulation and return lightweight result.
+///
+/// `on_step`, if given, is called after every step with its
+/// `StepObservation` (step index, fair price, per-strategy spot prices,
+/// fees, reserves and PnL). Returning `False` ends the run early; the
+/// result then covers the steps run so far. An exception raised by the
+/// callback also ends the run and is re-raised.
+#[pyfunction]
+#[pyo3(signature = (submission_bytecode, baseline_bytecode, config, on_step = None))]
+fn run_single(
+    py: Python<'_>,
+    submission_bytecode: Vec<u8>,
+    baseline_bytecode: Vec<u8>,
+    config: SimulationConfig,
+    on_step: Option<PyObject>,
+) -> PyResult<LightweightSimResult> {
+    let Some(on_step) = on_step else {
+        return run_single_rs(submission_bytecode, baseline_bytecode, config).map_err(PyErr::from);
+    };
+
+    let mut callback_error = None;
+    let result = run_simulation_with_hook(submission_bytecode, baseline_bytecode, config, |observation| {
+        match on_step.call1(py, (observation.clone(),)) {
+            Ok(keep_going) => !matches!(keep_going.extract::<bool>(py), Ok(false)),
+            Err(e) => {
+                callback_error = Some(e);
+                false
+            }
+        }
+    });
+    if let Some(e) = callback_error {
+        return Err(e);
+    }
+    result.map_err(PyErr::from)
+}
+
+/// Rerun the simulation behind `result` (from its embedded config and
+/// seed) with the strategies it ran with; `verbose` adds per-step detail
+/// without changing the outcome.
+#[pyfunction]
+#[pyo3(signature = (result, submission_bytecode, baseline_bytecode, verbose = false))]
+fn replay(
+    result: &LightweightSimResult,
+    submission_bytecode: Vec<u8>,
+    baseline_bytecode: Vec<u8>,
+    verbose: bool,
+) -> PyResult<LightweightSimResult> {
+    replay_rs(result, submission_bytecode, baseline_bytecode, verbose).map_err(PyErr::from)
+}
+
+/// Deploy a strategy and return `(name, bid_fee, ask_fee)` as it would
+/// start a run on the given reserves, without simulating.
+#[pyfunction]
+#[pyo3(signature = (bytecode, initial_x = 100.0, initial_y = 10000.0))]
+fn inspect_strategy(bytecode: Vec<u8>, initial_x: f64, initial_y: f64) -> PyResult<(String, f64, f64)> {
+    inspect_strategy_rs(bytecode, initial_x, initial_y).map_err(PyErr::from)
+}
+
+/// Step-by-step simulation for interactive (e.g. gym-style) use.
+///
+/// `reset()` deploys fresh strategies and returns the initial state;
+/// each `step()` advances exactly one timestep. `result()` ends the run
+/// and returns the same summary `run_single` would.
+#[pyclass(name = "SimulationEngine", unsendable)]
+struct PySimulationEngine {
+    engine: SimulationEngine,
+    config: SimulationConfig,
+    submission_bytecode: Vec<u8>,
+    baseline_bytecode: Vec<u8>,
+}
+
+#[pymethods]
+impl PySimulationEngine {
+    #[new]
+    fn new(submission_bytecode: Vec<u8>, baseline_bytecode: Vec<u8>, config: SimulationConfig) -> Self {
+        Self {
+            engine: SimulationEngine::new(config.clone()),
+            config,
+            submission_bytecode,
+            baseline_bytecode,
+        }
+    }
+
+    fn reset(&mut self) -> PyResult<StateObservation> {
+        let submission = EVMStrategy::with_gas_limits(self.submission_bytecode.clone(), "Submission".to_string(), self.config.gas_limits())
+            .map_err(engine::SimulationError::from)?;
+        let baseline = EVMStrategy::with_gas_limits(self.baseline_bytecode.clone(), "Baseline".to_string(), self.config.gas_limits())
+            .map_err(engine::SimulationError::from)?;
+
+        self.engine
+            .reset(submission, baseline)
+            .map_err(PyErr::from)
+    }
+
+    fn step(&mut self) -> PyResult<StepObservation> {
+        self.engine
+            .step()
+            .map_err(PyErr::from)
+    }
+
+    #[getter]
+    fn done(&self) -> bool {
+        self.engine.is_done()
+    }
+
+    fn result(&mut self) -> PyResult<LightweightSimResult> {
+        self.engine
+            .finish()
+            .map_err(PyErr::from)
+    }
+
+    /// Checkpoint the run in progress as bytes.
+    fn save_state<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let bytes = self.engine.save_state()?;
+        Ok(PyBytes::new_bound(py, &bytes))
+    }
+
+    /// Resume from `save_state` bytes; call `reset()` first.
+    fn load_state(&mut self, state: &[u8]) -> PyResult<()> {
+        self.engine
+            .load_state(state)
+            .map_err(PyErr::from)
+    }
+}
+
+/// Python module definition
+#[pymodule]
+fn amm_sim_rs(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(run_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(run_batch_summary, m)?)?;
+    m.add_function(wrap_pyfunction!(run_single, m)?)?;
+    m.add_function(wrap_pyfunction!(run_seed_sweep, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_determinism, m)?)?;
+    m.add_function(wrap_pyfunction!(run_paired, m)?)?;
+    m.add_function(wrap_pyfunction!(run_matches, m)?)?;
+    m.add_function(wrap_pyfunction!(compare_batches, m)?)?;
+    m.add_function(wrap_pyfunction!(inspect_strategy, m)?)?;
+    m.add_function(wrap_pyfunction!(replay, m)?)?;
+    m.add_class::<SimulationConfig>()?;
+    m.add_class::<FeeModel>()?;
+    m.add_class::<FeeUpdateFrequency>()?;
+    m.add_class::<RoundingMode>()?;
+    m.add_class::<BaselineMode>()?;
+    m.add_class::<RetailBatching>()?;
+    m.add_class::<RetailMode>()?;
+    m.add_class::<RetailFlowStyle>()?;
+    m.add_class::<RouterMode>()?;
+    m.add_class::<RetailCohort>()?;
+    m.add_class::<LightweightSimResult>()?;
+    m.add_class::<BatchSimulationResult>()?;
+    m.add_class::<PairedBatchResult>()?;
+    m.add_class::<TieBreak>()?;
+    m.add_class::<ScoreScheme>()?;
+    m.add_class::<GasBreakdown>()?;
+    m.add_class::<FeeStats>()?;
+    m.add_class::<PnlAttribution>()?;
+    m.add_class::<ComparisonStats>()?;
+    m.add_class::<SimulationSummary>()?;
+    m.add_class::<PySimulationEngine>()?;
+    m.add_class::<StateObservation>()?;
+    m.add_class::<StepObservation>()?;
+    m.add("SimulationError", m.py().get_type_bound::<SimulationError>())?;
+    m.add("ConfigError", m.py().get_type_bound::<ConfigError>())?;
+    m.add("EVMDeployError", m.py().get_type_bound::<EVMDeployError>())?;
+    m.add("EVMExecError", m.py().get_type_bound::<EVMExecError>())?;
+    m.add("StateError", m.py().get_type_bound::<StateError>())?;
+    Ok(())
+}
+
+#[cfg(all(test, not(feature = "extension-module")))]
+mod tests {
+    use super::*;
+    use pyo3::types::{PyDict, PyTuple};
+
+    #[test]
+    fn test_simulation_errors_map_to_exception_classes() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let cases: [(engine::SimulationError, &Bound<'_, pyo3::types::PyType>); 4] = [
+                (
+                    engine::SimulationError::InvalidConfig("bad".into()),
+                    &py.get_type_bound::<ConfigError>(),
+                ),
+                (
+                    engine::SimulationError::EVMDeployError("bad".into()),
+                    &py.get_type_bound::<EVMDeployError>(),
+                ),
+                (
+                    engine::SimulationError::EVMError("bad".into()),
+                    &py.get_type_bound::<EVMExecError>(),
+                ),
+                (
+                    engine::SimulationError::InvalidState("bad".into()),
+                    &py.get_type_bound::<StateError>(),
+                ),
+            ];
+            for (error, expected) in cases {
+                let message = error.to_string();
+                let err = PyErr::from(error);
+                assert!(err.get_type_bound(py).is(expected), "{message}");
+                assert!(err.is_instance_of::<SimulationError>(py), "{message}");
+                assert_eq!(err.value_bound(py).to_string(), message);
+            }
+        });
+    }
+
+    #[test]
+    fn test_config_constructor_fills_defaults_and_rejects_unknown_fields() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let class = py.get_type_bound::<SimulationConfig>();
+            let kwargs = PyDict::new_bound(py);
+            kwargs.set_item("seed", 7).unwrap();
+            kwargs.set_item("warmup_steps", 20).unwrap();
+            kwargs.set_item("fee_model", FeeModel::OnOutput.into_py(py)).unwrap();
+            let config: SimulationConfig =
+                class.call((500, 50.0), Some(&kwargs)).unwrap().extract().unwrap();
+            assert_eq!(
+                config,
+                SimulationConfig {
+                    n_steps: 500,
+                    initial_price: 50.0,
+                    seed: Some(7),
+                    warmup_steps: 20,
+                    fee_model: FeeModel::OnOutput,
+                    ..SimulationConfig::default()
+                }
+            );
+
+            let call = |args: &Bound<'_, PyTuple>, key: &str, value: PyObject| {
+                let kwargs = PyDict::new_bound(py);
+                kwargs.set_item(key, value).unwrap();
+                class.call(args, Some(&kwargs)).unwrap_err()
+            };
+            let none = PyTuple::empty_bound(py);
+            for err in [
+                call(&none, "n_stepz", 10.into_py(py)),
+                call(&none, "n_steps", "ten".into_py(py)),
+                call(&PyTuple::new_bound(py, [10]), "n_steps", 10.into_py(py)),
+            ] {
+                assert!(err.is_instance_of::<pyo3::exceptions::PyTypeError>(py), "{err}");
+            }
+        });
+    }
+}
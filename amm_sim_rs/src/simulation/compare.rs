@@ -0,0 +1,221 @@
+//! Paired significance test between two batches run on the same configs.
+
+use crate::simulation::engine::SimulationError;
+use crate::types::result::{
+    mean_and_std_error, BatchSimulationResult, ComparisonStats, LightweightSimResult,
+};
+
+/// Paired t-test of A's submission edge against B's, per simulation.
+///
+/// The batches must hold the same number of results with matching seeds,
+/// in the same order (e.g. two `run_batch` calls on the same configs), and
+/// at least two pairs. The submission is each batch's first strategy.
+pub fn compare_batches(
+    a: &BatchSimulationResult,
+    b: &BatchSimulationResult,
+) -> Result<ComparisonStats, SimulationError> {
+    if a.results.len() != b.results.len() {
+        return Err(SimulationError::InvalidConfig(format!(
+            "batches differ in size ({} vs {})",
+            a.results.len(),
+            b.results.len()
+        )));
+    }
+    if a.results.len() < 2 {
+        return Err(SimulationError::InvalidConfig(
+            "need at least two paired simulations".into(),
+        ));
+    }
+    let (Some(name_a), Some(name_b)) = (a.strategies.first(), b.strategies.first()) else {
+        return Err(SimulationError::InvalidConfig("batch has no strategies".into()));
+    };
+
+    let mut diffs = Vec::with_capacity(a.results.len());
+    for (i, (result_a, result_b)) in a.results.iter().zip(&b.results).enumerate() {
+        if result_a.seed != result_b.seed {
+            return Err(SimulationError::InvalidConfig(format!(
+                "simulation {} has seed {} in A but {} in B",
+                i, result_a.seed, result_b.seed
+            )));
+        }
+        let edge = |result: &LightweightSimResult, name: &String| {
+            result.edges.get(name).copied().unwrap_or(0.0)
+        };
+        diffs.push(edge(result_a, name_a) - edge(result_b, name_b));
+    }
+
+    let (mean_diff, std_error) = mean_and_std_error(&diffs);
+    let t_stat = if std_error > 0.0 {
+        mean_diff / std_error
+    } else if mean_diff == 0.0 {
+        0.0
+    } else {
+        mean_diff.signum() * f64::INFINITY
+    };
+    let p_value = student_t_two_sided_p(t_stat, (diffs.len() - 1) as f64);
+
+    Ok(ComparisonStats {
+        n: diffs.len(),
+        mean_diff,
+        std_error,
+        t_stat,
+        p_value,
+    })
+}
+
+/// Two-sided p-value of `t` under Student's t with `df` degrees of freedom:
+/// P(|T| >= |t|) = I_{df / (df + t²)}(df / 2, 1 / 2).
+fn student_t_two_sided_p(t: f64, df: f64) -> f64 {
+    if t.is_infinite() {
+        return 0.0;
+    }
+    regularized_incomplete_beta(df / (df + t * t), df / 2.0, 0.5)
+}
+
+/// Regularized incomplete beta function I_x(a, b), by Lentz's continued
+/// fraction (Numerical Recipes `betai`).
+fn regularized_incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+    // The continued fraction converges fast for x < (a + 1) / (a + b + 2);
+    // otherwise use the symmetry I_x(a, b) = 1 - I_{1-x}(b, a)
+    if x < (a + 1.0) / (a + b + 2.0) {
+        ln_front.exp() * beta_continued_fraction(x, a, b) / a
+    } else {
+        1.0 - ln_front.exp() * beta_continued_fraction(1.0 - x, b, a) / b
+    }
+}
+
+fn beta_continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const MAX_ITERATIONS: usize = 300;
+    const EPSILON: f64 = 1e-14;
+    const TINY: f64 = 1e-300;
+
+    let (qab, qap, qam) = (a + b, a + 1.0, a - 1.0);
+    let mut c = 1.0;
+    let mut d = 1.0 - qab * x / qap;
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..=MAX_ITERATIONS {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        // Even step
+        let aa = m * (b - m) * x / ((qam + m2) * (a + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        h *= d * c;
+        // Odd step
+        let aa = -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    h
+}
+
+/// Natural log of the gamma function (Lanczos approximation, g = 7).
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // Reflection: Γ(x)Γ(1-x) = π / sin(πx)
+        return (std::f64::consts::PI / (std::f64::consts::PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let mut sum = COEFFICIENTS[0];
+    for (i, &coefficient) in COEFFICIENTS.iter().enumerate().skip(1) {
+        sum += coefficient / (x + i as f64);
+    }
+    let t = x + 7.5;
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn batch(edges: &[f64]) -> BatchSimulationResult {
+        let results = edges
+            .iter()
+            .enumerate()
+            .map(|(i, &edge)| LightweightSimResult {
+                seed: i as u64,
+                edges: HashMap::from([("submission".into(), edge), ("normalizer".into(), 0.0)]),
+                ..Default::default()
+            })
+            .collect();
+        BatchSimulationResult {
+            results,
+            strategies: vec!["submission".into(), "normalizer".into()],
+        }
+    }
+
+    #[test]
+    fn test_dominated_batch_has_small_p_value() {
+        let edges_a: Vec<f64> = (0..30).map(|i| 10.0 + (i % 7) as f64).collect();
+        // B beats A on every seed by 1.5 to 2.5
+        let edges_b: Vec<f64> = edges_a
+            .iter()
+            .enumerate()
+            .map(|(i, edge)| edge + 1.5 + (i % 3) as f64 * 0.5)
+            .collect();
+
+        let stats = compare_batches(&batch(&edges_a), &batch(&edges_b)).unwrap();
+        assert_eq!(stats.n, 30);
+        assert!((stats.mean_diff + 2.0).abs() < 0.05, "{stats:?}");
+        assert!(stats.t_stat < -10.0, "{stats:?}");
+        assert!(stats.p_value < 1e-6, "{stats:?}");
+
+        // Misaligned batches are rejected
+        assert!(compare_batches(&batch(&edges_a), &batch(&edges_b[1..])).is_err());
+        let mut shifted = batch(&edges_b);
+        shifted.results[3].seed = 99;
+        assert!(compare_batches(&batch(&edges_a), &shifted).is_err());
+    }
+
+    #[test]
+    fn test_student_t_p_values() {
+        // Reference values from scipy.stats.t.sf(t, df) * 2
+        assert!((student_t_two_sided_p(0.0, 5.0) - 1.0).abs() < 1e-12);
+        assert!((student_t_two_sided_p(2.0, 10.0) - 0.073_388_034).abs() < 1e-6);
+        assert!((student_t_two_sided_p(-2.0, 10.0) - 0.073_388_034).abs() < 1e-6);
+        assert!((student_t_two_sided_p(2.570_581_836, 5.0) - 0.05).abs() < 1e-6);
+        assert!((student_t_two_sided_p(1.959_963_985, 1e6) - 0.05).abs() < 1e-4);
+    }
+}
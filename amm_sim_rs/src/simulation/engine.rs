@@ -1,45 +1,231 @@
 //! Main simulation engine.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_distr::{Distribution, Poisson};
+use rand_pcg::Pcg64;
+use serde::{Deserialize, Serialize};
+
+use crate::amm::cfmm::{FeeQuote, PoolState};
 use crate::amm::CFMM;
+use crate::evm::strategy::EVMError;
 use crate::evm::EVMStrategy;
-use crate::market::{Arbitrageur, GBMPriceProcess, OrderRouter, RetailTrader};
-use crate::types::config::SimulationConfig;
-use crate::types::result::{LightweightSimResult, LightweightStepResult};
+use crate::market::{
+    aggregate_orders, theoretical_lvr, Arbitrageur, CorrelatedGBM, EmaOracle, FlowReputation,
+    GBMPriceProcess, OrderRouter, PriceFeeds, ReplayPriceProcess, RetailBatching,
+    RetailOrder, RetailTrader, Sandwicher, FLOW_LOOKBACK,
+};
+use crate::market::price_process::FeedsState;
+use crate::types::config::{BaselineMode, SimulationConfig};
+use crate::types::wad::{Wad, MAX_FEE};
+use crate::types::observation::{StateObservation, StepObservation};
+use crate::types::result::{
+    FeeStats, GasBreakdown, LightweightSimResult, LightweightStepResult, PnlAttribution,
+};
+
+/// Name of the primary fair price feed (drives retail order sizing).
+pub const FAIR_PRICE_FEED: &str = "fair";
+/// Name of the optional second asset's price feed.
+pub const ASSET_B_PRICE_FEED: &str = "fair_b";
+
+/// Widest range (as a fee fraction, here 1 bps) a fee may span and still
+/// count as settled for `adaptation_steps`.
+pub const ADAPTATION_BAND: f64 = 0.0001;
+/// Steps the fees must stay within `ADAPTATION_BAND` to count as settled.
+pub const ADAPTATION_WINDOW: u32 = 100;
+
+/// Version of the `save_state` encoding; bumped whenever the layout of
+/// `Checkpoint` changes.
+pub const CHECKPOINT_VERSION: u32 = 15;
+
+/// Name the submission pool runs under, whatever its contract is called.
+pub const SUBMISSION_NAME: &str = "submission";
+
+/// Name the baseline pool runs under, whatever its contract is called.
+pub const BASELINE_NAME: &str = "normalizer";
 
 /// Error type for simulation.
 #[derive(Debug)]
 pub enum SimulationError {
+    /// A strategy call failed or misbehaved during the run
     EVMError(String),
+    /// A strategy's bytecode could not be deployed
+    EVMDeployError(String),
     InvalidConfig(String),
+    InvalidState(String),
 }
 
 impl std::fmt::Display for SimulationError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             SimulationError::EVMError(s) => write!(f, "EVM error: {}", s),
+            SimulationError::EVMDeployError(s) => write!(f, "EVM deployment failed: {}", s),
             SimulationError::InvalidConfig(s) => write!(f, "Invalid config: {}", s),
+            SimulationError::InvalidState(s) => write!(f, "Invalid state: {}", s),
         }
     }
 }
 
 impl std::error::Error for SimulationError {}
 
+impl From<EVMError> for SimulationError {
+    fn from(e: EVMError) -> Self {
+        match e {
+            EVMError::DeploymentFailed(s) => SimulationError::EVMDeployError(s),
+            e => SimulationError::EVMError(e.to_string()),
+        }
+    }
+}
+
 /// Main simulation engine for AMM competition.
 ///
 /// Runs a simulation with the following loop per step:
-/// 1. Generate new fair price via GBM
-/// 2. Arbitrageur extracts profit from each AMM
+/// 1. Generate new fair price via GBM (or replay the configured path)
+/// 2. Arbitrageurs (in a random order) extract profit from each AMM
 /// 3. Retail orders arrive and are routed to best AMM
+///
+/// `run()` drives the whole loop; `reset()`, `step()` and `finish()` expose
+/// it one timestep at a time for interactive use.
 pub struct SimulationEngine {
     config: SimulationConfig,
+    state: Option<RunState>,
+    failed_strategy: Option<String>,
+}
+
+/// Everything that evolves over a single simulation run.
+struct RunState {
+    seed: u64,
+    price_feeds: PriceFeeds,
+    /// Price feed each AMM is valued against (parallel to `amms`)
+    reference_names: Vec<String>,
+    retail_trader: RetailTrader,
+    /// Second retail cohort, if configured; its orders follow the main
+    /// cohort's each step
+    retail_trader_b: Option<RetailTrader>,
+    /// Orders retail flow within a step under `RetailBatching::Shuffle`
+    retail_shuffle_rng: Pcg64,
+    arbitrageurs: Vec<Arbitrageur>,
+    arb_order: Vec<usize>,
+    arb_rng: Pcg64,
+    router: OrderRouter,
+    sandwicher: Option<Sandwicher>,
+    flow_reputation: Option<FlowReputation>,
+    amms: Vec<CFMM>,
+    /// Trades executed so far, shared by the pools to number their trades
+    trade_counter: Arc<AtomicU64>,
+    names: Vec<String>,
+    /// Display name by strategy name (see `resolve_display_names`)
+    display_names: HashMap<String, String>,
+    initial_fair_price: f64,
+    initial_reserves: HashMap<String, (f64, f64)>,
+    /// Value of each AMM at the scoring baseline, against which PnL is
+    /// measured (re-snapshotted after warmup)
+    initial_values: HashMap<String, f64>,
+    /// Fee buckets at the scoring baseline (those earned during warmup);
+    /// every fee metric counts only what was collected since
+    warmup_fees: HashMap<String, (f64, f64)>,
+    edges: HashMap<String, f64>,
+    arb_volume_y: HashMap<String, f64>,
+    /// Net X the arbitrageurs bought from each AMM (negative when they
+    /// sold it more than they bought)
+    arb_net_x: HashMap<String, f64>,
+    retail_volume_y: HashMap<String, f64>,
+    /// Sum of retail trade slippage (bps) and retail trade count, for
+    /// averaging
+    retail_slippage_bps: HashMap<String, f64>,
+    retail_trades: HashMap<String, u64>,
+    sandwich_profit: HashMap<String, f64>,
+    /// Arbitrageur profit at the reference price, and the closed-form LVR
+    /// benchmark, by strategy name
+    realized_lvr: HashMap<String, f64>,
+    theoretical_lvr: HashMap<String, f64>,
+    /// Y volume traded on each AMM in the current step (parallel to `amms`)
+    step_volume: Vec<f64>,
+    /// Per-step volume by strategy name (verbose only)
+    volume_series: HashMap<String, Vec<f64>>,
+    /// EMA of each AMM's reference price (parallel to `amms`; empty when
+    /// the oracle is disabled)
+    oracles: Vec<EmaOracle>,
+    /// Reference prices (parallel to `amms`) of the last `arb_price_lag + 1`
+    /// steps, oldest first; arbitrageurs trade toward the front
+    arb_price_history: VecDeque<Vec<f64>>,
+    cumulative_bid_fees: HashMap<String, f64>,
+    cumulative_ask_fees: HashMap<String, f64>,
+    /// Sum of `|bid - ask|` over steps by strategy name
+    cumulative_fee_asymmetry: HashMap<String, f64>,
+    /// Running (bid, ask) fee statistics by strategy name
+    fee_moments: HashMap<String, (RunningStats, RunningStats)>,
+    /// Progress of each strategy's fees toward settling
+    fee_settling: HashMap<String, FeeSettling>,
+    cumulative_sq_deviation: HashMap<String, f64>,
+    /// Sum of two-sided spreads (bps) quoted for `spread_probe_size`, and
+    /// the number of steps both sides were quotable, by strategy name
+    spread_bps: HashMap<String, (f64, u32)>,
+    steps: Vec<LightweightStepResult>,
+    /// Fair price at reset and after each step
+    fair_price_path: Vec<f64>,
+    /// Next step to run
+    t: u32,
+}
+
+/// Everything in a `RunState` that evolves, as saved by `save_state`.
+///
+/// What the config and strategies determine (names, actors' parameters,
+/// bytecode) is rebuilt by `reset` before a checkpoint is loaded.
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    seed: u64,
+    t: u32,
+    price_feeds: FeedsState,
+    retail_rng: Pcg64,
+    retail_rng_b: Option<Pcg64>,
+    retail_shuffle_rng: Pcg64,
+    arbitrageurs: Vec<Arbitrageur>,
+    arb_order: Vec<usize>,
+    arb_rng: Pcg64,
+    flow_reputation: Option<FlowReputation>,
+    pools: Vec<PoolState>,
+    trade_sequence: u64,
+    initial_fair_price: f64,
+    initial_reserves: HashMap<String, (f64, f64)>,
+    initial_values: HashMap<String, f64>,
+    warmup_fees: HashMap<String, (f64, f64)>,
+    edges: HashMap<String, f64>,
+    arb_volume_y: HashMap<String, f64>,
+    arb_net_x: HashMap<String, f64>,
+    retail_volume_y: HashMap<String, f64>,
+    retail_slippage_bps: HashMap<String, f64>,
+    retail_trades: HashMap<String, u64>,
+    sandwich_profit: HashMap<String, f64>,
+    realized_lvr: HashMap<String, f64>,
+    theoretical_lvr: HashMap<String, f64>,
+    step_volume: Vec<f64>,
+    volume_series: HashMap<String, Vec<f64>>,
+    oracles: Vec<EmaOracle>,
+    arb_price_history: VecDeque<Vec<f64>>,
+    cumulative_bid_fees: HashMap<String, f64>,
+    cumulative_ask_fees: HashMap<String, f64>,
+    cumulative_fee_asymmetry: HashMap<String, f64>,
+    fee_moments: HashMap<String, (RunningStats, RunningStats)>,
+    fee_settling: HashMap<String, FeeSettling>,
+    cumulative_sq_deviation: HashMap<String, f64>,
+    spread_bps: HashMap<String, (f64, u32)>,
+    steps: Vec<LightweightStepResult>,
+    fair_price_path: Vec<f64>,
 }
 
 impl SimulationEngine {
     /// Create a new simulation engine.
     pub fn new(config: SimulationConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            state: None,
+            failed_strategy: None,
+        }
     }
 
     /// Run a complete simulation.
@@ -48,16 +234,116 @@ impl SimulationEngine {
         submission: EVMStrategy,
         baseline: EVMStrategy,
     ) -> Result<LightweightSimResult, SimulationError> {
+        self.reset(submission, baseline)?;
+        self.run_to_end()
+    }
+
+    /// Run a complete simulation on the given pools (see `reset_pools`).
+    pub fn run_pools(
+        &mut self,
+        submission: CFMM,
+        baseline: CFMM,
+    ) -> Result<LightweightSimResult, SimulationError> {
+        self.reset_pools(submission, baseline)?;
+        self.run_to_end()
+    }
+
+    /// Run a complete simulation, calling `on_step` after every step.
+    ///
+    /// The run stops early, and is summarized over the steps run so far,
+    /// as soon as `on_step` returns false.
+    pub fn run_with_hook(
+        &mut self,
+        submission: EVMStrategy,
+        baseline: EVMStrategy,
+        mut on_step: impl FnMut(&StepObservation) -> bool,
+    ) -> Result<LightweightSimResult, SimulationError> {
+        self.reset(submission, baseline)?;
+        while !self.is_done() {
+            let observation = self.step()?;
+            if !on_step(&observation) {
+                break;
+            }
+        }
+        self.finish()
+    }
+
+    /// Step the current run to completion and summarize it.
+    fn run_to_end(&mut self) -> Result<LightweightSimResult, SimulationError> {
+        while !self.is_done() {
+            self.advance(false)?;
+        }
+        self.finish()
+    }
+
+    /// Start a new run with fresh market actors and initialized AMMs.
+    ///
+    /// Discards any run in progress.
+    pub fn reset(
+        &mut self,
+        submission: EVMStrategy,
+        baseline: EVMStrategy,
+    ) -> Result<StateObservation, SimulationError> {
+        let initial_y = self.config.effective_initial_y();
+        let amm_submission = CFMM::new(submission, self.config.initial_x, initial_y);
+        let amm_baseline = CFMM::new(baseline, self.config.initial_x, initial_y);
+        self.reset_pools(amm_submission, amm_baseline)
+    }
+
+    /// Start a new run on the given pools, which are renamed, configured
+    /// and initialized per the config.
+    ///
+    /// Lets pools without an EVM strategy (see `CFMM::fixed_fee`) run
+    /// through the same engine.
+    pub fn reset_pools(
+        &mut self,
+        mut amm_submission: CFMM,
+        mut amm_baseline: CFMM,
+    ) -> Result<StateObservation, SimulationError> {
+        self.state = None;
+        self.failed_strategy = None;
+        validate_config(&self.config)?;
         let seed = self.config.seed.unwrap_or(0);
 
-        // Initialize price process
-        let mut price_process = GBMPriceProcess::new(
-            self.config.initial_price,
-            self.config.gbm_mu,
-            self.config.gbm_sigma,
-            self.config.gbm_dt,
-            Some(seed),
-        );
+        // Initialize price feeds
+        let mut price_feeds = PriceFeeds::new();
+        match (&self.config.price_path, self.config.asset_b_sigma) {
+            (Some(path), _) => price_feeds.add(
+                FAIR_PRICE_FEED,
+                Box::new(ReplayPriceProcess::new(self.config.initial_price, path.clone())),
+            ),
+            (None, Some(sigma_b)) => {
+                let mut process = CorrelatedGBM::new(
+                    [self.config.initial_price; 2],
+                    self.config.gbm_mu,
+                    [self.config.gbm_sigma, sigma_b],
+                    self.config.asset_correlation,
+                    self.config.gbm_dt,
+                    Some(seed),
+                );
+                process.set_antithetic(self.config.antithetic);
+                price_feeds.add_correlated(
+                    [FAIR_PRICE_FEED.to_string(), ASSET_B_PRICE_FEED.to_string()],
+                    process,
+                );
+            }
+            (None, None) => {
+                let mut process = GBMPriceProcess::new(
+                    self.config.initial_price,
+                    self.config.gbm_mu,
+                    self.config.gbm_sigma,
+                    self.config.gbm_dt,
+                    Some(seed),
+                );
+                process.set_antithetic(self.config.antithetic);
+                price_feeds.add(FAIR_PRICE_FEED, Box::new(process));
+            }
+        }
+        let reference_names = if self.config.reference_prices.is_empty() {
+            vec![FAIR_PRICE_FEED.to_string(); 2]
+        } else {
+            self.config.reference_prices.clone()
+        };
 
         // Initialize retail trader with different seed
         let mut retail_trader = RetailTrader::new(
@@ -65,39 +351,106 @@ impl SimulationEngine {
             self.config.retail_mean_size,
             self.config.retail_size_sigma,
             self.config.retail_buy_prob,
-            Some(seed + 1),
+            self.config.retail_quiet_prob,
+            Some(seed.wrapping_add(1)),
         );
+        if let Some(profile) = &self.config.retail_arrival_profile {
+            retail_trader.set_arrival_profile(profile.clone());
+        }
+        if let Some(profile) = &self.config.retail_size_profile {
+            retail_trader.set_size_profile(profile.clone());
+        }
+        retail_trader.set_inventory_sensitivity(self.config.retail_inventory_sensitivity);
+        retail_trader.set_mode(self.config.retail_mode);
+        let (flow_style, flow_sensitivity) =
+            (self.config.retail_flow_style, self.config.retail_flow_sensitivity);
+        retail_trader.set_flow_style(flow_style, flow_sensitivity);
+        let retail_trader_b = self.config.retail_cohort_b.map(|cohort| {
+            let mut trader = RetailTrader::new(
+                cohort.arrival_rate,
+                cohort.mean_size,
+                cohort.size_sigma,
+                cohort.buy_prob,
+                self.config.retail_quiet_prob,
+                Some(seed.wrapping_add(4)),
+            );
+            if let Some(profile) = &self.config.retail_arrival_profile {
+                trader.set_arrival_profile(profile.clone());
+            }
+            if let Some(profile) = &self.config.retail_size_profile {
+                trader.set_size_profile(profile.clone());
+            }
+            trader.set_inventory_sensitivity(self.config.retail_inventory_sensitivity);
+            trader.set_mode(self.config.retail_mode);
+            trader.set_flow_style(flow_style, flow_sensitivity);
+            trader
+        });
 
-        let arbitrageur = Arbitrageur::new();
-        let router = OrderRouter::new();
+        let arbitrageurs = build_arbitrageurs(&self.config);
+        let arb_order: Vec<usize> = (0..arbitrageurs.len()).collect();
+        let arb_rng = Pcg64::seed_from_u64(seed.wrapping_add(2));
+        let mut router = OrderRouter::with_mode(self.config.router_mode);
+        router.set_min_amount(self.config.min_routed_amount, self.config.min_routed_relative);
+        let retail_shuffle_rng = Pcg64::seed_from_u64(seed.wrapping_add(3));
 
         // Create AMMs with fixed positional names to avoid HashMap collision
-        // when both contracts return the same getName()
-        let submission_name = "submission".to_string();
-        let baseline_name = "normalizer".to_string();
-
-        let mut amm_submission = CFMM::new(
-            submission,
-            self.config.initial_x,
-            self.config.initial_y,
-        );
-        amm_submission.name = submission_name.clone();
+        // when both contracts return the same getName(); the contract names
+        // are kept for display
+        let contract_names = [amm_submission.name.clone(), amm_baseline.name.clone()];
+        let submission_name = SUBMISSION_NAME.to_string();
+        let baseline_name = BASELINE_NAME.to_string();
 
-        let mut amm_baseline = CFMM::new(
-            baseline,
-            self.config.initial_x,
-            self.config.initial_y,
-        );
+        amm_submission.name = submission_name.clone();
         amm_baseline.name = baseline_name.clone();
 
+        amm_submission.set_fee_model(self.config.fee_model);
+        amm_baseline.set_fee_model(self.config.fee_model);
+        amm_submission.set_wad_math(self.config.wad_math);
+        amm_baseline.set_wad_math(self.config.wad_math);
+        amm_submission.set_exact_reserves(self.config.exact_reserves);
+        amm_baseline.set_exact_reserves(self.config.exact_reserves);
+        amm_submission.set_rounding_mode(self.config.rounding_mode);
+        amm_baseline.set_rounding_mode(self.config.rounding_mode);
+        amm_submission.set_min_reserve(self.config.min_reserve);
+        amm_baseline.set_min_reserve(self.config.min_reserve);
+        amm_submission.set_max_trade_fraction(self.config.max_trade_fraction);
+        amm_baseline.set_max_trade_fraction(self.config.max_trade_fraction);
+        amm_submission.set_fee_update_frequency(self.config.fee_update_frequency);
+        amm_baseline.set_fee_update_frequency(self.config.fee_update_frequency);
+
+        let fee_min = self.config.fee_min.map(Wad::from_f64);
+        let fee_max = self.config.fee_max.map(Wad::from_f64);
+        amm_submission.set_fee_bounds(fee_min, fee_max);
+        amm_baseline.set_fee_bounds(fee_min, fee_max);
+        amm_submission.set_strict_fees(self.config.strict_fees);
+        amm_baseline.set_strict_fees(self.config.strict_fees);
+        let trade_counter = Arc::new(AtomicU64::new(0));
+        amm_submission.set_trade_counter(trade_counter.clone());
+        amm_baseline.set_trade_counter(trade_counter.clone());
+
+        if let Some(tiers) = &self.config.fee_tiers {
+            let tiers: Vec<Wad> = tiers.iter().map(|&fee| Wad::from_f64(fee)).collect();
+            amm_submission.set_fee_tiers(tiers.clone());
+            amm_baseline.set_fee_tiers(tiers);
+        }
+
         // Initialize AMMs
-        amm_submission.initialize()
-            .map_err(|e| SimulationError::EVMError(e.to_string()))?;
-        amm_baseline.initialize()
-            .map_err(|e| SimulationError::EVMError(e.to_string()))?;
+        for amm in [&mut amm_submission, &mut amm_baseline] {
+            if let Err(e) = amm.initialize() {
+                self.failed_strategy = Some(amm.name.clone());
+                return Err(e.into());
+            }
+        }
+        match self.config.baseline_mode {
+            BaselineMode::Strategy => {}
+            BaselineMode::Constant => {
+                amm_baseline.pin_fees(FeeQuote::symmetric(Wad::from_f64(self.config.baseline_fee)));
+            }
+            BaselineMode::Mirror => amm_baseline.pin_fees(amm_submission.fees()),
+        }
 
         // Record initial state
-        let initial_fair_price = price_process.current_price();
+        let initial_fair_price = price_feeds.price(FAIR_PRICE_FEED).unwrap();
         let mut initial_reserves = HashMap::new();
         initial_reserves.insert(
             submission_name.clone(),
@@ -108,130 +461,1154 @@ impl SimulationEngine {
             (amm_baseline.reserves().0, amm_baseline.reserves().1),
         );
 
-        // Track edge per strategy
-        let mut edges: HashMap<String, f64> = HashMap::new();
-        edges.insert(submission_name.clone(), 0.0);
-        edges.insert(baseline_name.clone(), 0.0);
-
-        // Run simulation steps
-        let mut steps = Vec::with_capacity(self.config.n_steps as usize);
+        let initial_values = initial_reserves
+            .iter()
+            .map(|(name, (init_x, init_y))| (name.clone(), init_x * initial_fair_price + init_y))
+            .collect();
 
         // Store AMMs in a Vec for easier mutable access
-        let mut amms = vec![amm_submission, amm_baseline];
-        let names = vec![submission_name.clone(), baseline_name.clone()];
-
-        // Track cumulative volumes
-        let mut arb_volume_y: HashMap<String, f64> = HashMap::new();
-        let mut retail_volume_y: HashMap<String, f64> = HashMap::new();
-        // Track cumulative fees for averaging
-        let mut cumulative_bid_fees: HashMap<String, f64> = HashMap::new();
-        let mut cumulative_ask_fees: HashMap<String, f64> = HashMap::new();
-        for name in &names {
-            arb_volume_y.insert(name.clone(), 0.0);
-            retail_volume_y.insert(name.clone(), 0.0);
-            cumulative_bid_fees.insert(name.clone(), 0.0);
-            cumulative_ask_fees.insert(name.clone(), 0.0);
-        }
-
-        for t in 0..self.config.n_steps {
-            // 1. Generate new fair price
-            let fair_price = price_process.step();
-
-            // 2. Arbitrageur extracts profit from each AMM
-            for amm in amms.iter_mut() {
-                if let Some(arb_result) = arbitrageur.execute_arb(amm, fair_price, t as u64) {
-                    *arb_volume_y.get_mut(&arb_result.amm_name).unwrap() += arb_result.amount_y;
-                    let entry = edges.entry(arb_result.amm_name).or_insert(0.0);
+        let amms = vec![amm_submission, amm_baseline];
+        let names = vec![submission_name, baseline_name];
+        let display_names = resolve_display_names(&names, &contract_names);
+
+        // Track edge, cumulative volumes, fees for averaging and squared
+        // relative spot-vs-fair deviations per strategy
+        let zeros: HashMap<String, f64> = names.iter().map(|name| (name.clone(), 0.0)).collect();
+        let retail_trades = names.iter().map(|name| (name.clone(), 0)).collect();
+        let fee_moments = names.iter().map(|name| (name.clone(), Default::default())).collect();
+        let fee_settling = names.iter().map(|name| (name.clone(), Default::default())).collect();
+        let warmup_fees = names.iter().map(|name| (name.clone(), (0.0, 0.0))).collect();
+        let spread_bps = names.iter().map(|name| (name.clone(), (0.0, 0))).collect();
+        let n_amms = amms.len();
+        let volume_series: HashMap<String, Vec<f64>> = if self.config.verbose {
+            names
+                .iter()
+                .map(|name| (name.clone(), Vec::with_capacity(self.config.n_steps as usize)))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        let oracles: Vec<EmaOracle> = match self.config.oracle_ema_alpha {
+            Some(alpha) => reference_names
+                .iter()
+                .map(|name| EmaOracle::new(alpha, price_feeds.price(name).unwrap()))
+                .collect(),
+            None => Vec::new(),
+        };
+        let initial_ref_prices = reference_names
+            .iter()
+            .map(|name| price_feeds.price(name).unwrap())
+            .collect();
+        let mut fair_price_path = Vec::with_capacity(self.config.n_steps as usize + 1);
+        fair_price_path.push(initial_fair_price);
+
+        let state = RunState {
+            seed,
+            price_feeds,
+            reference_names,
+            retail_trader,
+            retail_trader_b,
+            retail_shuffle_rng,
+            arbitrageurs,
+            arb_order,
+            arb_rng,
+            router,
+            sandwicher: self.config.sandwich_ratio.map(Sandwicher::new),
+            flow_reputation: (self.config.flow_elasticity > 0.0)
+                .then(|| FlowReputation::new(self.config.flow_elasticity)),
+            amms,
+            trade_counter,
+            names,
+            display_names,
+            initial_fair_price,
+            initial_reserves,
+            initial_values,
+            warmup_fees,
+            edges: zeros.clone(),
+            arb_volume_y: zeros.clone(),
+            arb_net_x: zeros.clone(),
+            retail_volume_y: zeros.clone(),
+            retail_slippage_bps: zeros.clone(),
+            retail_trades,
+            sandwich_profit: zeros.clone(),
+            realized_lvr: zeros.clone(),
+            theoretical_lvr: zeros.clone(),
+            step_volume: vec![0.0; n_amms],
+            volume_series,
+            oracles,
+            arb_price_history: VecDeque::from([initial_ref_prices]),
+            cumulative_bid_fees: zeros.clone(),
+            cumulative_ask_fees: zeros.clone(),
+            cumulative_fee_asymmetry: zeros.clone(),
+            fee_moments,
+            fee_settling,
+            cumulative_sq_deviation: zeros,
+            spread_bps,
+            steps: Vec::with_capacity(self.config.n_steps as usize),
+            fair_price_path,
+            t: 0,
+        };
+        let observation = StateObservation {
+            timestamp: 0,
+            fair_price: initial_fair_price,
+            reserves: state.reserves(),
+            spot_prices: state.spot_prices(),
+            fees: state.fees(),
+        };
+        self.state = Some(state);
+        Ok(observation)
+    }
+
+    /// Name of the pool whose strategy ended the last run with an error,
+    /// if the error came from a strategy call (see `strict_strategy`).
+    pub fn failed_strategy(&self) -> Option<&str> {
+        self.failed_strategy.as_deref()
+    }
+
+    /// Whether there is no run in progress or all configured steps have run.
+    pub fn is_done(&self) -> bool {
+        self.state
+            .as_ref()
+            .is_none_or(|state| state.t >= self.config.n_steps)
+    }
+
+    /// Advance the run by exactly one timestep.
+    pub fn step(&mut self) -> Result<StepObservation, SimulationError> {
+        Ok(self.advance(true)?.expect("observed steps return an observation"))
+    }
+
+    /// Advance the run by one timestep, building its observation only when
+    /// `observe` is set and its step result only when it is observed or
+    /// recorded (`record_steps`).
+    fn advance(&mut self, observe: bool) -> Result<Option<StepObservation>, SimulationError> {
+        let n_steps = self.config.n_steps;
+        let strict_strategy = self.config.strict_strategy;
+        let strict_fees = self.config.strict_fees;
+        let state = self.state.as_mut().ok_or_else(|| {
+            SimulationError::InvalidState("step() called before reset()".into())
+        })?;
+        if state.t >= n_steps {
+            return Err(SimulationError::InvalidState(format!(
+                "all {} steps have already run",
+                n_steps
+            )));
+        }
+        let t = state.t;
+
+        // 1. Generate new fair prices
+        state.price_feeds.step();
+        let fair_price = state.price_feeds.price(FAIR_PRICE_FEED).unwrap();
+        state.fair_price_path.push(fair_price);
+        let ref_prices: Vec<f64> = state
+            .reference_names
+            .iter()
+            .map(|name| state.price_feeds.price(name).unwrap())
+            .collect();
+        for ((oracle, amm), &ref_price) in state.oracles.iter_mut().zip(&mut state.amms).zip(&ref_prices) {
+            amm.set_oracle_price(Some(oracle.update(ref_price)));
+        }
+
+        // Arbitrageurs see the reference prices from `arb_price_lag` steps
+        // ago (the oldest available early in the run), scaled by
+        // `arb_price_bias`
+        state.arb_price_history.push_back(ref_prices.clone());
+        if state.arb_price_history.len() > self.config.arb_price_lag as usize + 1 {
+            state.arb_price_history.pop_front();
+        }
+
+        // Scheduled liquidity changes; the capital moved counts toward the
+        // pool's starting value so PnL reflects only what it earned
+        let events = self.config.liquidity_events.iter().filter(|event| event.0 == t);
+        for (_, name, delta_x, delta_y) in events {
+            let amm_idx = state.names.iter().position(|n| n == name).ok_or_else(|| {
+                SimulationError::InvalidConfig(format!(
+                    "liquidity event at step {} targets unknown pool {:?}",
+                    t, name
+                ))
+            })?;
+            if !state.amms[amm_idx].add_liquidity(*delta_x, *delta_y) {
+                return Err(SimulationError::InvalidConfig(format!(
+                    "liquidity event at step {} would leave {} without reserves",
+                    t, name
+                )));
+            }
+            let (initial_x, initial_y) = state.initial_reserves.get_mut(name).unwrap();
+            *initial_x += delta_x;
+            *initial_y += delta_y;
+            *state.initial_values.get_mut(name).unwrap() += delta_x * ref_prices[amm_idx] + delta_y;
+        }
+
+        // Closed-form LVR each pool would lose over this step at its value now
+        for ((amm, ref_name), &ref_price) in state.amms.iter().zip(&state.reference_names).zip(&ref_prices) {
+            let sigma = match self.config.asset_b_sigma {
+                Some(sigma_b) if ref_name == ASSET_B_PRICE_FEED => sigma_b,
+                _ => self.config.gbm_sigma,
+            };
+            let (x, y) = amm.reserves();
+            *state.theoretical_lvr.get_mut(&amm.name).unwrap() +=
+                theoretical_lvr(sigma, x * ref_price + y) * self.config.gbm_dt;
+        }
+
+        // 2. Arbitrageurs extract profit from each AMM; the first to act
+        // captures the bulk of the mispricing, so shuffle who goes first
+        state.arb_order.shuffle(&mut state.arb_rng);
+        state.arbitrageurs.iter_mut().for_each(Arbitrageur::step_funding);
+        state.step_volume.fill(0.0);
+        let arb_prices = &state.arb_price_history[0];
+        for (((amm, &ref_price), &arb_price), step_volume) in state
+            .amms
+            .iter_mut()
+            .zip(&ref_prices)
+            .zip(arb_prices)
+            .zip(state.step_volume.iter_mut())
+        {
+            let arb_price = arb_price * self.config.arb_price_bias;
+            let passes = match self.config.arb_arrival_rate {
+                None => 1,
+                Some(rate) if rate > 0.0 => Poisson::new(rate)
+                    .map_or(0, |arrivals| arrivals.sample(&mut state.arb_rng) as u64),
+                Some(_) => 0,
+            };
+            amm.set_arbitrage_flow(true);
+            let arb_order = &state.arb_order;
+            for &idx in (0..passes).flat_map(|_| arb_order) {
+                let arbitrageur = &state.arbitrageurs[idx];
+                if !arbitrageur.should_act(&mut state.arb_rng) {
+                    continue;
+                }
+                if let Some(arb_result) = arbitrageur.execute_arb(amm, arb_price, t as u64) {
+                    *state.arb_volume_y.get_mut(&arb_result.amm_name).unwrap() += arb_result.amount_y;
+                    // The AMM's sells are the arbitrageur's buys
+                    let net_x = if arb_result.side == "sell" {
+                        arb_result.amount_x
+                    } else {
+                        -arb_result.amount_x
+                    };
+                    *state.arb_net_x.get_mut(&arb_result.amm_name).unwrap() += net_x;
+                    *step_volume += arb_result.amount_y;
+                    let profit = arb_result.profit_at(ref_price);
+                    *state.realized_lvr.get_mut(&arb_result.amm_name).unwrap() += profit;
+                    let entry = state.edges.entry(arb_result.amm_name).or_insert(0.0);
                     // AMM edge is the negative of arbitrageur profit at true price
-                    *entry += -arb_result.profit;
+                    *entry += -profit;
                 }
             }
+            amm.set_arbitrage_flow(false);
+        }
 
-            // 3. Retail orders arrive and get routed
-            let orders = retail_trader.generate_orders();
-            let routed_trades = router.route_orders(&orders, &mut amms, fair_price, t as u64);
-            for trade in routed_trades {
-                *retail_volume_y.get_mut(&trade.amm_name).unwrap() += trade.amount_y;
-                let trade_edge = if trade.amm_buys_x {
-                    trade.amount_x * fair_price - trade.amount_y
-                } else {
-                    trade.amount_y - trade.amount_x * fair_price
-                };
-                let entry = edges.entry(trade.amm_name).or_insert(0.0);
-                *entry += trade_edge;
+        // 3. Retail orders arrive and get routed
+        let mut orders = state.retail_orders(t, fair_price, self.config.retail_inventory_sensitivity);
+        match self.config.retail_batching {
+            RetailBatching::Sequential => {}
+            RetailBatching::Aggregate => orders = aggregate_orders(&orders),
+            RetailBatching::Shuffle => orders.shuffle(&mut state.retail_shuffle_rng),
+        }
+        let (routed_trades, sandwiches) = state.router.route_orders_with(
+            &orders,
+            &mut state.amms,
+            fair_price,
+            t as u64,
+            state.sandwicher.as_ref(),
+            state.flow_reputation.as_ref(),
+        );
+        // The sandwicher's round trip nets out in X, so its profit is
+        // exactly the edge the AMM gave up on the two legs
+        for sandwich in sandwiches {
+            *state.sandwich_profit.get_mut(&sandwich.amm_name).unwrap() += sandwich.profit;
+            *state.edges.get_mut(&sandwich.amm_name).unwrap() -= sandwich.profit;
+        }
+        for trade in routed_trades {
+            *state.retail_volume_y.get_mut(&trade.amm_name).unwrap() += trade.amount_y;
+            let amm_idx = state.names.iter().position(|name| *name == trade.amm_name).unwrap();
+            let ref_price = ref_prices[amm_idx];
+            state.step_volume[amm_idx] += trade.amount_y;
+            let slippage_bps = trade.slippage_bps(ref_price);
+            *state.retail_slippage_bps.get_mut(&trade.amm_name).unwrap() += slippage_bps;
+            if let Some(reputation) = &mut state.flow_reputation {
+                reputation.observe(&trade.amm_name, slippage_bps);
             }
+            *state.retail_trades.get_mut(&trade.amm_name).unwrap() += 1;
+            let trade_edge = if trade.amm_buys_x {
+                trade.amount_x * ref_price - trade.amount_y
+            } else {
+                trade.amount_y - trade.amount_x * ref_price
+            };
+            let entry = state.edges.entry(trade.amm_name).or_insert(0.0);
+            *entry += trade_edge;
+        }
+        if let Some(reputation) = &mut state.flow_reputation {
+            reputation.end_step();
+        }
+        for amm in &mut state.amms {
+            amm.flush_fee_update();
+        }
+        if self.config.baseline_mode == BaselineMode::Mirror {
+            let fees = state.amms[0].fees();
+            state.amms[1].pin_fees(fees);
+        }
 
-            // 4. Capture step result and accumulate fees
-            let step = capture_step(
+        if strict_strategy {
+            if let Some(amm) = state.amms.iter().find(|amm| amm.swap_failures() > 0) {
+                self.failed_strategy = Some(amm.name.clone());
+                return Err(SimulationError::EVMError(format!(
+                    "{} afterSwap failed at step {}: {}",
+                    amm.name,
+                    t,
+                    amm.last_swap_error().unwrap_or("unknown error")
+                )));
+            }
+        }
+        if strict_fees {
+            if let Some(amm) = state.amms.iter().find(|amm| amm.fee_violations() > 0) {
+                self.failed_strategy = Some(amm.name.clone());
+                return Err(SimulationError::EVMError(format!(
+                    "{} returned a fee outside its bounds at step {}",
+                    amm.name, t
+                )));
+            }
+        }
+
+        // 4. Capture step result and accumulate fees
+        let record_steps = self.config.record_steps;
+        let step = (observe || record_steps).then(|| {
+            let mut step = capture_step(
                 t,
                 fair_price,
-                &amms,
-                &names,
-                &initial_reserves,
-                initial_fair_price,
+                &ref_prices,
+                &state.amms,
+                &state.names,
+                &state.initial_values,
+                &state.warmup_fees,
             );
-            // Accumulate fees for averaging
-            for name in &names {
-                if let Some((bid_fee, ask_fee)) = step.fees.get(name) {
-                    *cumulative_bid_fees.get_mut(name).unwrap() += bid_fee;
-                    *cumulative_ask_fees.get_mut(name).unwrap() += ask_fee;
-                }
+            if self.config.verbose {
+                step.reserves = state.reserves();
+            }
+            step
+        });
+        // Accumulate fees for averaging
+        for ((amm, name), &ref_price) in state.amms.iter().zip(&state.names).zip(&ref_prices) {
+            let fees = amm.fees();
+            let (bid_fee, ask_fee) = (fees.bid_fee.to_f64(), fees.ask_fee.to_f64());
+            *state.cumulative_bid_fees.get_mut(name).unwrap() += bid_fee;
+            *state.cumulative_ask_fees.get_mut(name).unwrap() += ask_fee;
+            *state.cumulative_fee_asymmetry.get_mut(name).unwrap() += (bid_fee - ask_fee).abs();
+            let (bid_stats, ask_stats) = state.fee_moments.get_mut(name).unwrap();
+            bid_stats.push(bid_fee);
+            ask_stats.push(ask_fee);
+            state.fee_settling.get_mut(name).unwrap().push(t, bid_fee, ask_fee);
+
+            let spot = amm.spot_price();
+            let deviation = (spot - ref_price) / ref_price;
+            *state.cumulative_sq_deviation.get_mut(name).unwrap() += deviation * deviation;
+
+            // Quote both sides of the probe size without trading
+            let probe = self.config.spread_probe_size;
+            if let (Some(ask), Some(bid)) = (amm.preview_sell_x(probe), amm.preview_buy_x(probe)) {
+                let ask_price = ask.amount_y / ask.amount_x;
+                let bid_price = bid.amount_y / bid.amount_x;
+                let (total, quoted) = state.spread_bps.get_mut(name).unwrap();
+                *total += (ask_price - bid_price) / spot * 10_000.0;
+                *quoted += 1;
             }
-            steps.push(step);
+        }
+        for (name, series) in state.volume_series.iter_mut() {
+            let amm_idx = state.names.iter().position(|n| n == name).unwrap();
+            series.push(state.step_volume[amm_idx]);
+        }
+        state.t += 1;
+        if state.t == self.config.warmup_steps {
+            state.rebase_scoring(fair_price);
         }
 
+        let Some(step) = step else {
+            return Ok(None);
+        };
+        let observation = observe.then(|| StepObservation {
+            timestamp: t,
+            fair_price,
+            reserves: state.reserves(),
+            spot_prices: step.spot_prices.clone(),
+            fees: step.fees.clone(),
+            pnls: step.pnls.clone(),
+            signals: state.signals(),
+            done: state.t >= n_steps,
+        });
+        if record_steps {
+            state.steps.push(step);
+        }
+        Ok(observation)
+    }
+
+    /// Encode the run in progress as a versioned binary checkpoint.
+    ///
+    /// The checkpoint holds everything the run has evolved: reserves,
+    /// fees, strategy storage, RNG states, prices, accumulators and the
+    /// step index. It does not hold the config or strategy bytecode; see
+    /// `load_state`.
+    pub fn save_state(&self) -> Result<Vec<u8>, SimulationError> {
+        let state = self.state.as_ref().ok_or_else(|| {
+            SimulationError::InvalidState("save_state() called before reset()".into())
+        })?;
+        let checkpoint = Checkpoint {
+            seed: state.seed,
+            t: state.t,
+            price_feeds: state.price_feeds.save_state(),
+            retail_rng: state.retail_trader.save_state(),
+            retail_rng_b: state.retail_trader_b.as_ref().map(RetailTrader::save_state),
+            retail_shuffle_rng: state.retail_shuffle_rng.clone(),
+            arbitrageurs: state.arbitrageurs.clone(),
+            arb_order: state.arb_order.clone(),
+            arb_rng: state.arb_rng.clone(),
+            flow_reputation: state.flow_reputation.clone(),
+            pools: state.amms.iter().map(CFMM::save_state).collect(),
+            trade_sequence: state.trade_counter.load(Ordering::Relaxed),
+            initial_fair_price: state.initial_fair_price,
+            initial_reserves: state.initial_reserves.clone(),
+            initial_values: state.initial_values.clone(),
+            warmup_fees: state.warmup_fees.clone(),
+            edges: state.edges.clone(),
+            arb_volume_y: state.arb_volume_y.clone(),
+            arb_net_x: state.arb_net_x.clone(),
+            retail_volume_y: state.retail_volume_y.clone(),
+            retail_slippage_bps: state.retail_slippage_bps.clone(),
+            retail_trades: state.retail_trades.clone(),
+            sandwich_profit: state.sandwich_profit.clone(),
+            realized_lvr: state.realized_lvr.clone(),
+            theoretical_lvr: state.theoretical_lvr.clone(),
+            step_volume: state.step_volume.clone(),
+            volume_series: state.volume_series.clone(),
+            oracles: state.oracles.clone(),
+            arb_price_history: state.arb_price_history.clone(),
+            cumulative_bid_fees: state.cumulative_bid_fees.clone(),
+            cumulative_ask_fees: state.cumulative_ask_fees.clone(),
+            cumulative_fee_asymmetry: state.cumulative_fee_asymmetry.clone(),
+            fee_moments: state.fee_moments.clone(),
+            fee_settling: state.fee_settling.clone(),
+            cumulative_sq_deviation: state.cumulative_sq_deviation.clone(),
+            spread_bps: state.spread_bps.clone(),
+            steps: state.steps.clone(),
+            fair_price_path: state.fair_price_path.clone(),
+        };
+
+        let mut bytes = CHECKPOINT_VERSION.to_le_bytes().to_vec();
+        bincode::serialize_into(&mut bytes, &checkpoint)
+            .map_err(|e| SimulationError::InvalidState(format!("checkpoint encoding failed: {}", e)))?;
+        Ok(bytes)
+    }
+
+    /// Resume a run from a checkpoint written by `save_state`.
+    ///
+    /// Call `reset` (or `reset_pools`) first with the same config and
+    /// strategies as the saved run; the checkpoint then overwrites
+    /// everything that run evolved. On error the run in progress is
+    /// discarded.
+    pub fn load_state(&mut self, bytes: &[u8]) -> Result<(), SimulationError> {
+        let mut state = self.state.take().ok_or_else(|| {
+            SimulationError::InvalidState("load_state() called before reset()".into())
+        })?;
+        let invalid = |message: String| SimulationError::InvalidState(format!("bad checkpoint: {}", message));
+
+        let Some((version, body)) = bytes.split_first_chunk::<4>() else {
+            return Err(invalid("truncated header".into()));
+        };
+        let version = u32::from_le_bytes(*version);
+        if version != CHECKPOINT_VERSION {
+            return Err(invalid(format!(
+                "version {} (expected {})",
+                version, CHECKPOINT_VERSION
+            )));
+        }
+        let checkpoint: Checkpoint = bincode::deserialize(body).map_err(|e| invalid(e.to_string()))?;
+
+        if checkpoint.t > self.config.n_steps {
+            return Err(invalid(format!(
+                "step {} is past n_steps ({})",
+                checkpoint.t, self.config.n_steps
+            )));
+        }
+        let shape_matches = checkpoint.pools.len() == state.amms.len()
+            && checkpoint.arbitrageurs.len() == state.arbitrageurs.len()
+            && checkpoint.oracles.len() == state.oracles.len()
+            && checkpoint.retail_rng_b.is_some() == state.retail_trader_b.is_some()
+            && checkpoint.flow_reputation.is_some() == state.flow_reputation.is_some()
+            && checkpoint.arb_price_history.len() <= self.config.arb_price_lag as usize + 1
+            && checkpoint.arb_price_history.iter().all(|prices| prices.len() == state.amms.len());
+        if !shape_matches {
+            return Err(invalid("saved run was configured differently".into()));
+        }
+
+        state.price_feeds.load_state(checkpoint.price_feeds).map_err(invalid)?;
+        for (amm, pool) in state.amms.iter_mut().zip(checkpoint.pools) {
+            amm.load_state(pool).map_err(invalid)?;
+        }
+        state.retail_trader.load_state(checkpoint.retail_rng);
+        if let (Some(trader), Some(rng)) = (&mut state.retail_trader_b, checkpoint.retail_rng_b) {
+            trader.load_state(rng);
+        }
+        state.trade_counter.store(checkpoint.trade_sequence, Ordering::Relaxed);
+        state.seed = checkpoint.seed;
+        state.t = checkpoint.t;
+        state.retail_shuffle_rng = checkpoint.retail_shuffle_rng;
+        state.arbitrageurs = checkpoint.arbitrageurs;
+        state.arb_order = checkpoint.arb_order;
+        state.arb_rng = checkpoint.arb_rng;
+        state.flow_reputation = checkpoint.flow_reputation;
+        state.initial_fair_price = checkpoint.initial_fair_price;
+        state.initial_reserves = checkpoint.initial_reserves;
+        state.initial_values = checkpoint.initial_values;
+        state.warmup_fees = checkpoint.warmup_fees;
+        state.edges = checkpoint.edges;
+        state.arb_volume_y = checkpoint.arb_volume_y;
+        state.arb_net_x = checkpoint.arb_net_x;
+        state.retail_volume_y = checkpoint.retail_volume_y;
+        state.retail_slippage_bps = checkpoint.retail_slippage_bps;
+        state.retail_trades = checkpoint.retail_trades;
+        state.sandwich_profit = checkpoint.sandwich_profit;
+        state.realized_lvr = checkpoint.realized_lvr;
+        state.theoretical_lvr = checkpoint.theoretical_lvr;
+        state.step_volume = checkpoint.step_volume;
+        state.volume_series = checkpoint.volume_series;
+        state.oracles = checkpoint.oracles;
+        state.arb_price_history = checkpoint.arb_price_history;
+        state.cumulative_bid_fees = checkpoint.cumulative_bid_fees;
+        state.cumulative_ask_fees = checkpoint.cumulative_ask_fees;
+        state.cumulative_fee_asymmetry = checkpoint.cumulative_fee_asymmetry;
+        state.fee_moments = checkpoint.fee_moments;
+        state.fee_settling = checkpoint.fee_settling;
+        state.cumulative_sq_deviation = checkpoint.cumulative_sq_deviation;
+        state.spread_bps = checkpoint.spread_bps;
+        state.steps = checkpoint.steps;
+        state.fair_price_path = checkpoint.fair_price_path;
+        self.state = Some(state);
+        Ok(())
+    }
+
+    /// End the current run and summarize it.
+    ///
+    /// Averages cover the steps actually run, so a run can be finished early.
+    pub fn finish(&mut self) -> Result<LightweightSimResult, SimulationError> {
+        let state = self.state.take().ok_or_else(|| {
+            SimulationError::InvalidState("finish() called before reset()".into())
+        })?;
+
         // Calculate final PnL (reserves + accumulated fees)
-        let final_fair_price = price_process.current_price();
         let mut pnl = HashMap::new();
 
-        // Calculate average fees
-        let n_steps = self.config.n_steps as f64;
+        // Calculate average fees over the scored steps (all of them if the
+        // run ended inside the warmup, which is then not rebased)
+        let warmup_steps = self.config.warmup_steps;
+        let n_steps = if state.t >= warmup_steps { state.t - warmup_steps } else { state.t } as f64;
+        // No steps scored yet (finish() straight after reset() or the warmup)
+        // reports zero rather than NaN, as tracking_error does
+        let per_step = |total: f64| if n_steps > 0.0 { total / n_steps } else { 0.0 };
         let mut average_fees: HashMap<String, (f64, f64)> = HashMap::new();
-        for name in &names {
-            let avg_bid = cumulative_bid_fees.get(name).unwrap() / n_steps;
-            let avg_ask = cumulative_ask_fees.get(name).unwrap() / n_steps;
+        let mut fee_asymmetry: HashMap<String, f64> = HashMap::new();
+        for name in &state.names {
+            let avg_bid = per_step(state.cumulative_bid_fees[name]);
+            let avg_ask = per_step(state.cumulative_ask_fees[name]);
             average_fees.insert(name.clone(), (avg_bid, avg_ask));
+            let asymmetry = per_step(state.cumulative_fee_asymmetry[name]);
+            fee_asymmetry.insert(name.clone(), asymmetry);
+        }
+
+        let fee_stats: HashMap<String, FeeStats> = state
+            .fee_moments
+            .iter()
+            .map(|(name, (bid, ask))| {
+                let stats = FeeStats {
+                    bid_min: bid.min,
+                    bid_max: bid.max,
+                    bid_mean: bid.mean,
+                    bid_stddev: bid.stddev(),
+                    ask_min: ask.min,
+                    ask_max: ask.max,
+                    ask_mean: ask.mean,
+                    ask_stddev: ask.stddev(),
+                };
+                (name.clone(), stats)
+            })
+            .collect();
+
+        let adaptation_steps: HashMap<String, u32> = state
+            .fee_settling
+            .iter()
+            .map(|(name, settling)| (name.clone(), settling.settled.unwrap_or(state.t)))
+            .collect();
+
+        let mut tracking_error: HashMap<String, f64> = HashMap::new();
+        for name in &state.names {
+            let mean_sq = per_step(state.cumulative_sq_deviation[name]);
+            tracking_error.insert(name.clone(), mean_sq.sqrt());
         }
 
-        for (amm, name) in amms.iter().zip(names.iter()) {
-            let (init_x, init_y) = initial_reserves.get(name).unwrap();
-            let init_value = init_x * initial_fair_price + init_y;
+        let avg_spread_bps: HashMap<String, f64> = state
+            .spread_bps
+            .iter()
+            .map(|(name, &(total, quoted))| {
+                let average = if quoted > 0 { total / quoted as f64 } else { 0.0 };
+                (name.clone(), average)
+            })
+            .collect();
+
+        let retail_slippage_bps: HashMap<String, f64> = state
+            .retail_slippage_bps
+            .iter()
+            .map(|(name, &total)| {
+                let trades = state.retail_trades[name];
+                let average = if trades > 0 { total / trades as f64 } else { 0.0 };
+                (name.clone(), average)
+            })
+            .collect();
+
+        let mut swap_failures: HashMap<String, u64> = HashMap::new();
+        let mut fee_violations: HashMap<String, u64> = HashMap::new();
+        let mut gas: HashMap<String, GasBreakdown> = HashMap::new();
+        let mut drained: HashMap<String, bool> = HashMap::new();
+        let mut impermanent_loss: HashMap<String, f64> = HashMap::new();
+        let mut accumulated_fees: HashMap<String, (f64, f64)> = HashMap::new();
+        let mut fee_il_ratio: HashMap<String, f64> = HashMap::new();
+        let mut pnl_attribution: HashMap<String, PnlAttribution> = HashMap::new();
+        for ((amm, name), ref_name) in state.amms.iter().zip(&state.names).zip(&state.reference_names) {
+            swap_failures.insert(name.clone(), amm.swap_failures());
+            fee_violations.insert(name.clone(), amm.fee_violations());
+            gas.insert(name.clone(), amm.strategy_gas());
+            drained.insert(name.clone(), amm.drained());
+            let final_fair_price = state.price_feeds.price(ref_name).unwrap();
+
+            let (init_x, init_y) = state.initial_reserves.get(name).unwrap();
+            let init_value = state.initial_values[name];
             let (final_x, final_y) = amm.reserves();
-            let (fees_x, fees_y) = amm.accumulated_fees();
+            let (fees_x, fees_y) = scored_fees(amm, &state.warmup_fees[name]);
+            accumulated_fees.insert(name.clone(), (fees_x, fees_y));
             let reserves_value = final_x * final_fair_price + final_y;
             let fees_value = fees_x * final_fair_price + fees_y;
             let final_value = reserves_value + fees_value;
             pnl.insert(name.clone(), final_value - init_value);
+
+            // IL: holding the initial tokens vs the pool, both at the final price
+            let hodl_value = init_x * final_fair_price + init_y;
+            let il = hodl_value - reserves_value;
+            impermanent_loss.insert(name.clone(), il);
+            let ratio = if il != 0.0 {
+                fees_value / il.abs()
+            } else if fees_value > 0.0 {
+                f64::INFINITY
+            } else {
+                0.0
+            };
+            fee_il_ratio.insert(name.clone(), ratio);
+
+            let fee_revenue = fees_value;
+            let arb_loss = -state.realized_lvr[name];
+            let attribution = PnlAttribution {
+                fee_revenue,
+                impermanent_loss: -il,
+                arb_loss,
+                residual: pnl[name] - (fee_revenue - il + arb_loss),
+            };
+            pnl_attribution.insert(name.clone(), attribution);
         }
 
         Ok(LightweightSimResult {
-            seed,
-            strategies: vec![submission_name, baseline_name],
+            seed: state.seed,
+            config: SimulationConfig {
+                seed: Some(state.seed),
+                ..self.config.clone()
+            },
+            strategies: state.names,
+            display_names: state.display_names,
             pnl,
-            edges,
-            initial_fair_price,
-            initial_reserves,
-            steps,
-            arb_volume_y,
-            retail_volume_y,
+            edges: state.edges,
+            initial_fair_price: state.initial_fair_price,
+            initial_reserves: state.initial_reserves,
+            steps: state.steps,
+            fair_price_path: state.fair_price_path,
+            gbm_dt: self.config.gbm_dt,
+            arb_volume_y: state.arb_volume_y,
+            arb_net_x: state.arb_net_x,
+            retail_volume_y: state.retail_volume_y,
+            retail_slippage_bps,
+            sandwich_profit: state.sandwich_profit,
+            volume_series: state.volume_series,
             average_fees,
+            fee_asymmetry,
+            fee_stats,
+            adaptation_steps,
+            realized_lvr: state.realized_lvr,
+            theoretical_lvr: state.theoretical_lvr,
+            tracking_error,
+            avg_spread_bps,
+            swap_failures,
+            fee_violations,
+            gas,
+            impermanent_loss,
+            accumulated_fees,
+            fee_il_ratio,
+            pnl_attribution,
+            drained,
+            elapsed_micros: 0,
+            failed: false,
+            error: None,
+            failed_strategy: None,
+            timed_out: false,
         })
     }
 }
 
+impl RunState {
+    fn reserves(&self) -> HashMap<String, (f64, f64)> {
+        self.names.iter().cloned().zip(self.amms.iter().map(CFMM::reserves)).collect()
+    }
+
+    fn signals(&self) -> HashMap<String, f64> {
+        self.names
+            .iter()
+            .zip(&self.amms)
+            .filter_map(|(name, amm)| Some((name.clone(), amm.strategy_signal()?)))
+            .collect()
+    }
+
+    fn spot_prices(&self) -> HashMap<String, f64> {
+        self.names.iter().cloned().zip(self.amms.iter().map(CFMM::spot_price)).collect()
+    }
+
+    fn fees(&self) -> HashMap<String, (f64, f64)> {
+        self.names
+            .iter()
+            .cloned()
+            .zip(self.amms.iter().map(|amm| {
+                let fee_quote = amm.fees();
+                (fee_quote.bid_fee.to_f64(), fee_quote.ask_fee.to_f64())
+            }))
+            .collect()
+    }
+
+    /// Draw this step's retail orders: the main cohort's, then the second
+    /// cohort's.
+    fn retail_orders(
+        &mut self,
+        t: u32,
+        fair_price: f64,
+        inventory_sensitivity: f64,
+    ) -> Vec<RetailOrder> {
+        let imbalance = if inventory_sensitivity != 0.0 {
+            Some(inventory_imbalance(&self.amms, fair_price))
+        } else {
+            None
+        };
+        // The fair price path includes this step's price
+        let path = &self.fair_price_path;
+        let last = path.len() - 1;
+        let recent_return = (path[last] / path[last.saturating_sub(FLOW_LOOKBACK)]).ln();
+        let mut orders = Vec::new();
+        for trader in std::iter::once(&mut self.retail_trader).chain(self.retail_trader_b.as_mut()) {
+            trader.set_recent_return(recent_return);
+            orders.extend(match imbalance {
+                Some(imbalance) => trader.generate_orders_with_imbalance(t, imbalance),
+                None => trader.generate_orders(t),
+            });
+        }
+        orders
+    }
+
+    /// Restart scoring from the current state: edges, volumes and the
+    /// per-step averages are zeroed and PnL and the fee metrics are
+    /// measured from here on, so fees already earned do not count.
+    ///
+    /// Per-step series, strategy failure and gas counters and fee
+    /// adaptation still cover the whole run.
+    fn rebase_scoring(&mut self, fair_price: f64) {
+        self.initial_fair_price = fair_price;
+        for sums in [
+            &mut self.edges,
+            &mut self.sandwich_profit,
+            &mut self.realized_lvr,
+            &mut self.theoretical_lvr,
+            &mut self.arb_volume_y,
+            &mut self.arb_net_x,
+            &mut self.retail_volume_y,
+            &mut self.retail_slippage_bps,
+            &mut self.cumulative_bid_fees,
+            &mut self.cumulative_ask_fees,
+            &mut self.cumulative_fee_asymmetry,
+            &mut self.cumulative_sq_deviation,
+        ] {
+            sums.values_mut().for_each(|sum| *sum = 0.0);
+        }
+        self.retail_trades.values_mut().for_each(|trades| *trades = 0);
+        self.spread_bps.values_mut().for_each(|spread| *spread = (0.0, 0));
+        self.fee_moments.values_mut().for_each(|moments| *moments = Default::default());
+        for (amm, name) in self.amms.iter().zip(&self.names) {
+            let (x, y) = amm.reserves();
+            self.initial_reserves.insert(name.clone(), (x, y));
+            self.initial_values.insert(name.clone(), x * fair_price + y);
+            self.warmup_fees.insert(name.clone(), amm.accumulated_fees());
+        }
+    }
+}
+
+/// Streaming min, max, mean and variance (Welford's algorithm).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct RunningStats {
+    count: u64,
+    mean: f64,
+    /// Sum of squared deviations from the running mean
+    m2: f64,
+    /// Extremes seen so far (0 while empty)
+    min: f64,
+    max: f64,
+}
+
+impl RunningStats {
+    fn push(&mut self, value: f64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// Population standard deviation (0 if empty).
+    fn stddev(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        (self.m2 / self.count as f64).sqrt()
+    }
+}
+
+/// Tracks when a pool's fees settle: the first step from which its bid
+/// and ask each span at most `ADAPTATION_BAND` for `ADAPTATION_WINDOW`
+/// steps.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct FeeSettling {
+    /// Steps pushed so far
+    count: u32,
+    /// Step the current in-band stretch began at
+    start: u32,
+    /// (min, max) of the bid and ask fees since `start`
+    bid_range: (f64, f64),
+    ask_range: (f64, f64),
+    /// Step the fees settled at, once they have
+    settled: Option<u32>,
+}
+
+impl FeeSettling {
+    fn push(&mut self, t: u32, bid_fee: f64, ask_fee: f64) {
+        if self.settled.is_some() {
+            return;
+        }
+        let widen = |(min, max): (f64, f64), fee: f64| (min.min(fee), max.max(fee));
+        let bid_range = widen(self.bid_range, bid_fee);
+        let ask_range = widen(self.ask_range, ask_fee);
+        let in_band = |(min, max): (f64, f64)| max - min <= ADAPTATION_BAND;
+        if self.count == 0 || !in_band(bid_range) || !in_band(ask_range) {
+            // Start a new stretch at this step
+            self.start = t;
+            self.bid_range = (bid_fee, bid_fee);
+            self.ask_range = (ask_fee, ask_fee);
+        } else {
+            self.bid_range = bid_range;
+            self.ask_range = ask_range;
+        }
+        self.count += 1;
+        if t + 1 - self.start >= ADAPTATION_WINDOW {
+            self.settled = Some(self.start);
+        }
+    }
+}
+
+/// Reject configs the engine cannot run.
+fn validate_config(config: &SimulationConfig) -> Result<(), SimulationError> {
+    let reserves = [config.initial_x, config.effective_initial_y()];
+    if reserves.iter().any(|r| !r.is_finite() || *r <= config.min_reserve) {
+        return Err(SimulationError::InvalidConfig(format!(
+            "initial reserves ({}, {}) must be finite and exceed min_reserve ({})",
+            reserves[0], reserves[1], config.min_reserve
+        )));
+    }
+    let n_arbs = config.n_arbitrageurs as usize;
+    if config.arb_gas_costs.len() > n_arbs
+        || config.arb_act_probs.len() > n_arbs
+        || config.arb_min_edge_bps.len() > n_arbs
+    {
+        return Err(SimulationError::InvalidConfig(format!(
+            "per-arbitrageur parameters exceed n_arbitrageurs ({})",
+            n_arbs
+        )));
+    }
+    if config.arb_act_probs.iter().any(|p| !(0.0..=1.0).contains(p)) {
+        return Err(SimulationError::InvalidConfig(
+            "arb_act_probs must be in [0, 1]".into(),
+        ));
+    }
+    if config.arb_min_edge_bps.iter().any(|bps| !(bps.is_finite() && *bps >= 0.0)) {
+        return Err(SimulationError::InvalidConfig(
+            "arb_min_edge_bps must be finite and non-negative".into(),
+        ));
+    }
+    if let Some(path) = &config.price_path {
+        if (config.n_steps as usize) > path.len() {
+            return Err(SimulationError::InvalidConfig(format!(
+                "n_steps ({}) exceeds price_path length ({})",
+                config.n_steps,
+                path.len()
+            )));
+        }
+        if path.iter().any(|p| !p.is_finite() || *p <= 0.0) {
+            return Err(SimulationError::InvalidConfig(
+                "price_path must contain positive finite prices".into(),
+            ));
+        }
+        if config.antithetic {
+            return Err(SimulationError::InvalidConfig(
+                "antithetic cannot be combined with price_path".into(),
+            ));
+        }
+    }
+    for profile in [&config.retail_arrival_profile, &config.retail_size_profile]
+        .into_iter()
+        .flatten()
+    {
+        if profile.is_empty() || profile.iter().any(|m| !m.is_finite() || *m < 0.0) {
+            return Err(SimulationError::InvalidConfig(
+                "retail profiles must be non-empty with finite, non-negative multipliers".into(),
+            ));
+        }
+    }
+    if let Some(sigma_b) = config.asset_b_sigma {
+        if config.price_path.is_some() {
+            return Err(SimulationError::InvalidConfig(
+                "asset_b_sigma cannot be combined with price_path".into(),
+            ));
+        }
+        if !sigma_b.is_finite() || sigma_b < 0.0 {
+            return Err(SimulationError::InvalidConfig(
+                "asset_b_sigma must be finite and non-negative".into(),
+            ));
+        }
+    }
+    if !(-1.0..=1.0).contains(&config.asset_correlation) {
+        return Err(SimulationError::InvalidConfig(
+            "asset_correlation must be in [-1, 1]".into(),
+        ));
+    }
+    if !config.reference_prices.is_empty() {
+        if config.reference_prices.len() != 2 {
+            return Err(SimulationError::InvalidConfig(format!(
+                "reference_prices needs one feed per AMM (2), got {}",
+                config.reference_prices.len()
+            )));
+        }
+        for name in &config.reference_prices {
+            let known = name == FAIR_PRICE_FEED
+                || (name == ASSET_B_PRICE_FEED && config.asset_b_sigma.is_some());
+            if !known {
+                return Err(SimulationError::InvalidConfig(format!(
+                    "unknown price feed '{}'",
+                    name
+                )));
+            }
+        }
+    }
+    if let Some(alpha) = config.oracle_ema_alpha {
+        if !(alpha > 0.0 && alpha <= 1.0) {
+            return Err(SimulationError::InvalidConfig(
+                "oracle_ema_alpha must be in (0, 1]".into(),
+            ));
+        }
+    }
+    if !(0.0..=1.0).contains(&config.retail_quiet_prob) {
+        return Err(SimulationError::InvalidConfig(
+            "retail_quiet_prob must be in [0, 1]".into(),
+        ));
+    }
+    if let Some(cohort) = config.retail_cohort_b {
+        let valid = cohort.arrival_rate.is_finite()
+            && cohort.arrival_rate >= 0.0
+            && cohort.mean_size.is_finite()
+            && cohort.mean_size > 0.0
+            && (0.0..=1.0).contains(&cohort.buy_prob);
+        if !valid {
+            return Err(SimulationError::InvalidConfig(
+                "retail_cohort_b needs a non-negative arrival rate, positive mean size \
+                 and buy_prob in [0, 1]"
+                    .into(),
+            ));
+        }
+    }
+    if let Some(ratio) = config.sandwich_ratio {
+        if !(ratio.is_finite() && ratio > 0.0) {
+            return Err(SimulationError::InvalidConfig(
+                "sandwich_ratio must be finite and positive".into(),
+            ));
+        }
+    }
+    if !(config.flow_elasticity.is_finite() && config.flow_elasticity >= 0.0) {
+        return Err(SimulationError::InvalidConfig(
+            "flow_elasticity must be finite and non-negative".into(),
+        ));
+    }
+    if config.arb_arrival_rate.is_some_and(|rate| !(rate.is_finite() && rate >= 0.0)) {
+        return Err(SimulationError::InvalidConfig(
+            "arb_arrival_rate must be finite and non-negative".into(),
+        ));
+    }
+    if !(config.arb_price_bias.is_finite() && config.arb_price_bias > 0.0) {
+        return Err(SimulationError::InvalidConfig(
+            "arb_price_bias must be finite and positive".into(),
+        ));
+    }
+    if !(config.baseline_fee.is_finite() && config.baseline_fee >= 0.0) {
+        return Err(SimulationError::InvalidConfig(
+            "baseline_fee must be finite and non-negative".into(),
+        ));
+    }
+    if !(config.spread_probe_size.is_finite() && config.spread_probe_size > 0.0) {
+        return Err(SimulationError::InvalidConfig(
+            "spread_probe_size must be finite and > 0".into(),
+        ));
+    }
+    if !(config.min_routed_amount.is_finite() && config.min_routed_amount >= 0.0) {
+        return Err(SimulationError::InvalidConfig(
+            "min_routed_amount must be finite and >= 0".into(),
+        ));
+    }
+    for (step, name, delta_x, delta_y) in &config.liquidity_events {
+        if name != SUBMISSION_NAME && name != BASELINE_NAME {
+            return Err(SimulationError::InvalidConfig(format!(
+                "liquidity event for unknown pool {:?} (expected {:?} or {:?})",
+                name, SUBMISSION_NAME, BASELINE_NAME
+            )));
+        }
+        if *step >= config.n_steps || !(delta_x.is_finite() && delta_y.is_finite()) {
+            return Err(SimulationError::InvalidConfig(format!(
+                "liquidity event at step {} must fall within the run and have finite deltas",
+                step
+            )));
+        }
+    }
+    if !config.retail_flow_sensitivity.is_finite() {
+        return Err(SimulationError::InvalidConfig(
+            "retail_flow_sensitivity must be finite".into(),
+        ));
+    }
+    if !config.retail_inventory_sensitivity.is_finite() {
+        return Err(SimulationError::InvalidConfig(
+            "retail_inventory_sensitivity must be finite".into(),
+        ));
+    }
+    if let Some(fraction) = config.max_trade_fraction {
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            return Err(SimulationError::InvalidConfig(
+                "max_trade_fraction must be in (0, 1]".into(),
+            ));
+        }
+    }
+    if !config.min_reserve.is_finite() || config.min_reserve < 0.0 {
+        return Err(SimulationError::InvalidConfig(
+            "min_reserve must be finite and non-negative".into(),
+        ));
+    }
+    if config.warmup_steps > 0 && config.warmup_steps >= config.n_steps {
+        return Err(SimulationError::InvalidConfig(format!(
+            "warmup_steps ({}) must be less than n_steps ({})",
+            config.warmup_steps, config.n_steps
+        )));
+    }
+    if let Some(tiers) = &config.fee_tiers {
+        let max_fee = Wad::new(MAX_FEE).to_f64();
+        if tiers.iter().any(|fee| !(0.0..=max_fee).contains(fee)) {
+            return Err(SimulationError::InvalidConfig(format!(
+                "fee_tiers must be in [0, {}]",
+                max_fee
+            )));
+        }
+    }
+    let max_fee = Wad::new(MAX_FEE).to_f64();
+    for (name, bound) in [("fee_min", config.fee_min), ("fee_max", config.fee_max)] {
+        if bound.is_some_and(|fee| !(-max_fee..=max_fee).contains(&fee)) {
+            return Err(SimulationError::InvalidConfig(format!(
+                "{} must be in [-{}, {}]",
+                name, max_fee, max_fee
+            )));
+        }
+    }
+    if let (Some(min), Some(max)) = (config.fee_min, config.fee_max) {
+        if min > max {
+            return Err(SimulationError::InvalidConfig(format!(
+                "fee_min ({}) must not exceed fee_max ({})",
+                min, max
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Value-weighted inventory imbalance of the pooled reserves,
+/// `(x * p - y) / (x * p + y)`: positive when the pools are heavy in X.
+fn inventory_imbalance(amms: &[CFMM], fair_price: f64) -> f64 {
+    let (value_x, value_y) = amms.iter().fold((0.0, 0.0), |(vx, vy), amm| {
+        let (x, y) = amm.reserves();
+        (vx + x * fair_price, vy + y)
+    });
+    let total = value_x + value_y;
+    if total > 0.0 {
+        (value_x - value_y) / total
+    } else {
+        0.0
+    }
+}
+
+/// Build the arbitrageur set described by the config.
+fn build_arbitrageurs(config: &SimulationConfig) -> Vec<Arbitrageur> {
+    (0..config.n_arbitrageurs as usize)
+        .map(|i| {
+            Arbitrageur::with_params(
+                config.arb_gas_costs.get(i).copied().unwrap_or(0.0),
+                config.arb_act_probs.get(i).copied().unwrap_or(1.0),
+            )
+            .with_min_edge_bps(config.arb_min_edge_bps.get(i).copied().unwrap_or(0.0))
+        })
+        .collect()
+}
+
+/// Map each positional strategy name to its contract's name, or to itself
+/// when that contract name is shared with another strategy, so display
+/// names are always distinct.
+fn resolve_display_names(names: &[String], contract_names: &[String]) -> HashMap<String, String> {
+    names
+        .iter()
+        .zip(contract_names)
+        .map(|(name, contract_name)| {
+            let shared = contract_names.iter().filter(|other| *other == contract_name).count() > 1;
+            let display = if shared { name } else { contract_name };
+            (name.clone(), display.clone())
+        })
+        .collect()
+}
+
 fn capture_step(
     timestamp: u32,
     fair_price: f64,
+    ref_prices: &[f64],
     amms: &[CFMM],
     names: &[String],
-    initial_reserves: &HashMap<String, (f64, f64)>,
-    initial_fair_price: f64,
+    initial_values: &HashMap<String, f64>,
+    warmup_fees: &HashMap<String, (f64, f64)>,
 ) -> LightweightStepResult {
     let mut spot_prices = HashMap::new();
     let mut pnls = HashMap::new();
     let mut fees = HashMap::new();
 
-    for (amm, name) in amms.iter().zip(names.iter()) {
+    for ((amm, name), &ref_price) in amms.iter().zip(names.iter()).zip(ref_prices) {
         spot_prices.insert(name.clone(), amm.spot_price());
 
         let fee_quote = amm.fees();
@@ -241,12 +1618,11 @@ fn capture_step(
         );
 
         // Calculate running PnL (reserves + accumulated fees)
-        let (init_x, init_y) = initial_reserves.get(name).unwrap();
-        let init_value = init_x * initial_fair_price + init_y;
+        let init_value = initial_values[name];
         let (curr_x, curr_y) = amm.reserves();
-        let (fees_x, fees_y) = amm.accumulated_fees();
-        let reserves_value = curr_x * fair_price + curr_y;
-        let fees_value = fees_x * fair_price + fees_y;
+        let (fees_x, fees_y) = scored_fees(amm, &warmup_fees[name]);
+        let reserves_value = curr_x * ref_price + curr_y;
+        let fees_value = fees_x * ref_price + fees_y;
         let curr_value = reserves_value + fees_value;
         pnls.insert(name.clone(), curr_value - init_value);
     }
@@ -257,10 +1633,1341 @@ fn capture_step(
         spot_prices,
         pnls,
         fees,
+        reserves: HashMap::new(),
     }
 }
 
+/// Fees an AMM collected since the scoring baseline, given its fee
+/// buckets at that point.
+fn scored_fees(amm: &CFMM, &(warmup_x, warmup_y): &(f64, f64)) -> (f64, f64) {
+    let (fees_x, fees_y) = amm.accumulated_fees();
+    (fees_x - warmup_x, fees_y - warmup_y)
+}
+
 #[cfg(test)]
 mod tests {
-    // Full tests require EVM bytecode - see integration tests
+    use super::*;
+    use crate::amm::cfmm::FeeUpdateFrequency;
+    use crate::amm::NativeStrategy;
+    use crate::market::RetailCohort;
+    use crate::evm::fixtures::{
+        deploy, fixed_fee_bytecode, fixed_fee_strategy, Asm, ADD, DUP1, EQ, GT, ISZERO, JUMPI, MOD,
+        MSTORE, MUL, RETURN, SLOAD, SSTORE, SWAP1,
+    };
+    use crate::types::trade_info::{SELECTOR_AFTER_ARBITRAGE, SELECTOR_AFTER_SWAP};
+    use crate::types::wad::BPS;
+
+    fn replay_config(path: Vec<f64>, n_steps: u32) -> SimulationConfig {
+        SimulationConfig {
+            n_steps,
+            initial_price: 100.0,
+            initial_x: 100.0,
+            initial_y: 10_000.0,
+            retail_mean_size: 0.01,
+            price_path: Some(path),
+            seed: Some(7),
+            ..SimulationConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_replay_path_drives_fair_and_spot_prices() {
+        let path: Vec<f64> = (1..=50).map(|i| 100.0 + (i as f64 * 0.7).sin() * 5.0).collect();
+        let mut engine = SimulationEngine::new(replay_config(path.clone(), 50));
+        let result = engine
+            .run(fixed_fee_strategy(0, 0), fixed_fee_strategy(0, 0))
+            .unwrap();
+
+        assert_eq!(result.steps.len(), path.len());
+        for (step, &price) in result.steps.iter().zip(&path) {
+            assert_eq!(step.fair_price, price);
+            // Zero-fee pools are arbitraged onto the replayed price
+            for spot in step.spot_prices.values() {
+                assert!((spot - price).abs() / price < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_arbitrageur_accumulates_inventory_along_the_trend() {
+        let rising: Vec<f64> = (1..=100).map(|i| 100.0 * 1.005f64.powi(i)).collect();
+        let falling: Vec<f64> = (1..=100).map(|i| 100.0 * 0.995f64.powi(i)).collect();
+        let run = |path| {
+            SimulationEngine::new(replay_config(path, 100))
+                .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(50, 50))
+                .unwrap()
+        };
+
+        // Arbitrageurs buy X while its price climbs and sell it as it falls
+        let up = run(rising);
+        let down = run(falling);
+        for name in ["submission", "normalizer"] {
+            assert!(up.arb_net_x[name] > 0.0, "{name}: {}", up.arb_net_x[name]);
+            assert!(down.arb_net_x[name] < 0.0, "{name}: {}", down.arb_net_x[name]);
+            let (initial_x, _) = up.initial_reserves[name];
+            assert!(up.arb_net_x[name] < initial_x);
+        }
+    }
+
+    #[test]
+    fn test_fee_floor_lifts_low_strategy_fees() {
+        let config = SimulationConfig {
+            n_steps: 100,
+            fee_min: Some(0.001),
+            fee_max: Some(0.01),
+            seed: Some(9),
+            ..SimulationConfig::default()
+        };
+        let result = SimulationEngine::new(config.clone())
+            .run(fixed_fee_strategy(5, 5), fixed_fee_strategy(30, 30))
+            .unwrap();
+        for step in &result.steps {
+            assert_eq!(step.fees["submission"], (0.001, 0.001), "step {}", step.timestamp);
+            assert_eq!(step.fees["normalizer"], (0.003, 0.003), "step {}", step.timestamp);
+        }
+
+        // A negative floor lets a pool pay rebates, debited from its fees
+        let rebates = SimulationConfig {
+            fee_min: Some(-0.001),
+            ..config.clone()
+        };
+        let pool = |name: &str, bps| {
+            CFMM::fixed_fee(name.into(), 100.0, 10_000.0, FeeQuote::symmetric(Wad::from_bps(bps)))
+        };
+        let result = SimulationEngine::new(rebates)
+            .run_pools(pool("submission", -5), pool("normalizer", 30))
+            .unwrap();
+        let (fees_x, fees_y) = result.accumulated_fees["submission"];
+        assert!(fees_x < 0.0 && fees_y < 0.0, "{fees_x}, {fees_y}");
+
+        for (fee_min, fee_max) in [(Some(0.02), Some(0.01)), (Some(-0.2), None), (None, Some(0.5))] {
+            let invalid = SimulationConfig { fee_min, fee_max, ..config.clone() };
+            let err = SimulationEngine::new(invalid)
+                .run(fixed_fee_strategy(5, 5), fixed_fee_strategy(30, 30))
+                .unwrap_err();
+            assert!(matches!(err, SimulationError::InvalidConfig(_)), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_replay_path_shorter_than_n_steps_errors() {
+        let mut engine = SimulationEngine::new(replay_config(vec![100.0; 10], 11));
+        let err = engine
+            .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(30, 30))
+            .unwrap_err();
+        assert!(matches!(err, SimulationError::InvalidConfig(_)));
+    }
+
+    #[test]
+    fn test_display_names_fall_back_to_positional_on_collision() {
+        let config = || SimulationConfig {
+            n_steps: 10,
+            seed: Some(1),
+            ..SimulationConfig::default()
+        };
+        let named = |name: &str| EVMStrategy::new(fixed_fee_bytecode(30, 30), name.into()).unwrap();
+
+        // Both fixtures keep the same default name
+        let same = SimulationEngine::new(config())
+            .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(50, 50))
+            .unwrap();
+        assert_eq!(same.strategies, vec!["submission", "normalizer"]);
+        assert_eq!(same.pnl.len(), 2);
+        for name in ["submission", "normalizer"] {
+            assert_eq!(same.display_names[name], name);
+        }
+
+        let distinct = SimulationEngine::new(config()).run(named("Alpha"), named("Beta")).unwrap();
+        assert_eq!(distinct.strategies, same.strategies);
+        assert_eq!(distinct.display_names["submission"], "Alpha");
+        assert_eq!(distinct.display_names["normalizer"], "Beta");
+    }
+
+    #[test]
+    fn test_tight_pool_has_lower_tracking_error() {
+        let config = SimulationConfig {
+            n_steps: 500,
+            gbm_sigma: 0.01,
+            seed: Some(3),
+            ..SimulationConfig::default()
+        };
+        let mut engine = SimulationEngine::new(config);
+        let result = engine
+            .run(fixed_fee_strategy(0, 0), fixed_fee_strategy(500, 500))
+            .unwrap();
+
+        let tight = result.tracking_error["submission"];
+        let wide = result.tracking_error["normalizer"];
+        assert!(tight >= 0.0);
+        assert!(tight < wide, "tight={} wide={}", tight, wide);
+    }
+
+    #[test]
+    fn test_higher_fee_pool_quotes_wider_spread() {
+        let config = SimulationConfig {
+            n_steps: 200,
+            spread_probe_size: 0.5,
+            seed: Some(5),
+            ..SimulationConfig::default()
+        };
+        let result = SimulationEngine::new(config)
+            .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(100, 100))
+            .unwrap();
+
+        let narrow = result.avg_spread_bps["submission"];
+        let wide = result.avg_spread_bps["normalizer"];
+        // Both sides pay the fee on top of the probe's price impact
+        assert!(narrow > 60.0, "{narrow}");
+        assert!(wide > narrow + 100.0, "narrow={narrow} wide={wide}");
+
+        let config = SimulationConfig { spread_probe_size: 0.0, ..SimulationConfig::default() };
+        let err = SimulationEngine::new(config)
+            .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(30, 30))
+            .unwrap_err();
+        assert!(matches!(err, SimulationError::InvalidConfig(_)));
+    }
+
+    #[test]
+    fn test_reference_price_feeds() {
+        let config = |reference_prices: Vec<&str>, rho: f64| SimulationConfig {
+            n_steps: 300,
+            asset_b_sigma: Some(0.002),
+            asset_correlation: rho,
+            gbm_sigma: 0.002,
+            reference_prices: reference_prices.into_iter().map(String::from).collect(),
+            seed: Some(5),
+            ..SimulationConfig::default()
+        };
+        let run = |config: SimulationConfig| {
+            SimulationEngine::new(config)
+                .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(30, 30))
+                .unwrap()
+        };
+
+        // Perfectly correlated feeds are interchangeable
+        let same = run(config(vec!["fair", "fair"], 1.0));
+        let split = run(config(vec!["fair", "fair_b"], 1.0));
+        assert_eq!(same.pnl, split.pnl);
+        assert_eq!(same.edges, split.edges);
+
+        // An independent second asset changes what the normalizer is valued at
+        let independent = run(config(vec!["fair", "fair_b"], 0.0));
+        assert_ne!(independent.pnl["normalizer"], same.pnl["normalizer"]);
+
+        let mut engine = SimulationEngine::new(SimulationConfig {
+            asset_b_sigma: None,
+            ..config(vec!["fair", "fair_b"], 0.0)
+        });
+        assert!(matches!(
+            engine.run(fixed_fee_strategy(30, 30), fixed_fee_strategy(30, 30)),
+            Err(SimulationError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_finish_before_first_step_reports_zero_averages() {
+        let path: Vec<f64> = (1..=100).map(|i| 100.0 + (i as f64 * 0.7).sin() * 5.0).collect();
+        let mut engine = SimulationEngine::new(replay_config(path, 100));
+        engine.reset(fixed_fee_strategy(30, 30), fixed_fee_strategy(50, 50)).unwrap();
+        let result = engine.finish().unwrap();
+
+        for name in [SUBMISSION_NAME, BASELINE_NAME] {
+            assert_eq!(result.average_fees[name], (0.0, 0.0), "{name}");
+            assert_eq!(result.fee_asymmetry[name], 0.0, "{name}");
+            assert_eq!(result.tracking_error[name], 0.0, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_verbose_steps_record_reserves() {
+        let path: Vec<f64> = (1..=100).map(|i| 100.0 + (i as f64 * 0.7).sin() * 5.0).collect();
+        let config = SimulationConfig {
+            verbose: true,
+            ..replay_config(path, 100)
+        };
+        let mut engine = SimulationEngine::new(config.clone());
+        let initial = engine
+            .reset(fixed_fee_strategy(30, 30), fixed_fee_strategy(50, 50))
+            .unwrap();
+        let first = engine.step().unwrap();
+        let mut last = first.clone();
+        while !engine.is_done() {
+            last = engine.step().unwrap();
+        }
+        let result = engine.finish().unwrap();
+
+        // Recorded after the step's trades, not before
+        assert_eq!(result.steps[0].reserves, first.reserves);
+        assert_ne!(result.steps[0].reserves, initial.reserves);
+        assert_eq!(result.steps[99].reserves, last.reserves);
+        for (name, &(x, y)) in &last.reserves {
+            assert!((result.steps[99].spot_prices[name] - y / x).abs() < 1e-12);
+        }
+
+        let quiet = SimulationEngine::new(SimulationConfig { verbose: false, ..config })
+            .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(50, 50))
+            .unwrap();
+        assert!(quiet.steps.iter().all(|step| step.reserves.is_empty()));
+    }
+
+    #[test]
+    fn test_impermanent_loss_vanishes_on_round_trip() {
+        let run = |path: Vec<f64>| {
+            SimulationEngine::new(replay_config(path, 4))
+                .run(fixed_fee_strategy(0, 0), fixed_fee_strategy(0, 0))
+                .unwrap()
+        };
+
+        // Zero-fee pools are arbitraged back onto the curve point they
+        // started from, so there is nothing lost to rebalancing
+        let round_trip = run(vec![110.0, 90.0, 105.0, 100.0]);
+        for name in ["submission", "normalizer"] {
+            assert!(round_trip.impermanent_loss[name].abs() < 1e-6, "{name}");
+        }
+
+        // Ending away from the initial price: IL of x*y=k is
+        // hodl * (1 - 2 sqrt(r) / (1 + r)) for price ratio r
+        let moved = run(vec![110.0, 90.0, 105.0, 121.0]);
+        let hodl = 100.0 * 121.0 + 10_000.0;
+        let expected = hodl * (1.0 - 2.0 * 1.21f64.sqrt() / 2.21);
+        for name in ["submission", "normalizer"] {
+            let il = moved.impermanent_loss[name];
+            assert!((il - expected).abs() < 1e-3 * expected, "{name}: {il} vs {expected}");
+        }
+    }
+
+    #[test]
+    fn test_fixed_fee_pool_matches_evm_pool() {
+        let config = SimulationConfig {
+            n_steps: 300,
+            verbose: true,
+            seed: Some(4),
+            ..SimulationConfig::default()
+        };
+        let evm = SimulationEngine::new(config.clone())
+            .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(50, 50))
+            .unwrap();
+        let fixed = CFMM::fixed_fee(
+            "fixed".into(),
+            config.initial_x,
+            config.initial_y,
+            FeeQuote::symmetric(Wad::from_bps(30)),
+        );
+        let mixed = SimulationEngine::new(config.clone())
+            .run_pools(fixed, CFMM::new(fixed_fee_strategy(50, 50), config.initial_x, config.initial_y))
+            .unwrap();
+
+        // Same fees, same trades: the reserves move identically
+        assert_eq!(mixed.steps.len(), evm.steps.len());
+        for (a, b) in mixed.steps.iter().zip(&evm.steps) {
+            assert_eq!(a.reserves, b.reserves);
+        }
+        assert_eq!(mixed.pnl, evm.pnl);
+        assert_eq!(mixed.gas["submission"], GasBreakdown::default());
+    }
+
+    #[test]
+    fn test_thin_pools_cost_retail_more() {
+        // A flat price leaves arbitrageurs idle, so both runs see the same
+        // retail orders and only pool depth differs
+        let config = SimulationConfig {
+            n_steps: 200,
+            price_path: Some(vec![100.0; 200]),
+            seed: Some(8),
+            ..SimulationConfig::default()
+        };
+        let run = |depth: f64| {
+            let pool = |name: &str| {
+                CFMM::fixed_fee(name.into(), depth, depth * 100.0, FeeQuote::symmetric(Wad::from_bps(30)))
+            };
+            SimulationEngine::new(config.clone())
+                .run_pools(pool("a"), pool("b"))
+                .unwrap()
+        };
+
+        let thin = run(50.0);
+        let deep = run(5_000.0);
+        for name in ["submission", "normalizer"] {
+            let (thin, deep) = (thin.retail_slippage_bps[name], deep.retail_slippage_bps[name]);
+            // Deep pools cost roughly the 30 bps fee; thin ones add impact
+            assert!(deep > 29.0 && deep < 32.0, "{name}: {deep}");
+            assert!(thin > deep + 5.0, "{name}: {thin} vs {deep}");
+        }
+    }
+
+    #[test]
+    fn test_mid_run_liquidity_removal_halves_depth() {
+        let withdrawal = vec![(100, "submission".to_string(), -50.0, -5_000.0)];
+        let pool = |name: &str| {
+            CFMM::fixed_fee(name.into(), 100.0, 10_000.0, FeeQuote::symmetric(Wad::from_bps(30)))
+        };
+
+        // No retail flow at a flat price: the withdrawal is the only thing
+        // moving the reserves, and it is not a loss
+        let quiet = SimulationConfig {
+            retail_arrival_rate: 0.0,
+            liquidity_events: withdrawal.clone(),
+            ..replay_config(vec![100.0; 200], 200)
+        };
+        let mut engine = SimulationEngine::new(quiet);
+        engine.reset_pools(pool("a"), pool("b")).unwrap();
+        while !engine.is_done() {
+            let obs = engine.step().unwrap();
+            let expected = if obs.timestamp < 100 { (100.0, 10_000.0) } else { (50.0, 5_000.0) };
+            assert_eq!(obs.reserves["submission"], expected, "step {}", obs.timestamp);
+            assert_eq!(obs.reserves["normalizer"], (100.0, 10_000.0));
+            assert!(obs.pnls["submission"].abs() < 1e-9, "{}", obs.pnls["submission"]);
+        }
+        let result = engine.finish().unwrap();
+        assert_eq!(result.initial_reserves["submission"], (50.0, 5_000.0));
+
+        // With retail flow the same orders cost more on the thinner pool
+        let run = |liquidity_events| {
+            let config = SimulationConfig {
+                n_steps: 200,
+                price_path: Some(vec![100.0; 200]),
+                seed: Some(8),
+                liquidity_events,
+                ..SimulationConfig::default()
+            };
+            SimulationEngine::new(config).run_pools(pool("a"), pool("b")).unwrap()
+        };
+        let kept = run(Vec::new()).retail_slippage_bps["submission"];
+        let removed = run(withdrawal).retail_slippage_bps["submission"];
+        assert!(removed > kept, "{removed} vs {kept}");
+    }
+
+    #[test]
+    fn test_per_step_fee_updates_call_strategy_once_per_step() {
+        // Busy steps: several retail trades per step on each pool
+        let config = SimulationConfig {
+            n_steps: 100,
+            retail_arrival_rate: 6.0,
+            seed: Some(12),
+            ..SimulationConfig::default()
+        };
+        let run = |fee_update_frequency| {
+            SimulationEngine::new(SimulationConfig { fee_update_frequency, ..config.clone() })
+                .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(50, 50))
+                .unwrap()
+        };
+        let calls = |result: &LightweightSimResult, name: &str| {
+            let gas = result.gas[name];
+            gas.buy_swaps + gas.sell_swaps
+        };
+
+        let per_trade = run(FeeUpdateFrequency::PerTrade);
+        let per_step = run(FeeUpdateFrequency::PerStep);
+        for name in ["submission", "normalizer"] {
+            assert!(calls(&per_trade, name) > 2 * config.n_steps as u64, "{name}");
+            assert!(calls(&per_step, name) <= config.n_steps as u64, "{name}");
+            assert!(calls(&per_step, name) > 0, "{name}");
+        }
+        // Fixed fees never change, so batching the updates changes nothing else
+        assert_eq!(per_trade.pnl, per_step.pnl);
+    }
+
+    #[test]
+    fn test_accumulated_fees_account_for_fee_revenue() {
+        // A rally arbitraged in small steps, then a shallower pullback
+        let path: Vec<f64> = (1..=20)
+            .map(|i| 100.0 + i as f64)
+            .chain((1..=10).map(|i| 120.0 - i as f64 * 0.5))
+            .collect();
+        let config = SimulationConfig {
+            retail_buy_prob: 0.8,
+            ..replay_config(path, 30)
+        };
+        let result = SimulationEngine::new(config.clone())
+            .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(30, 30))
+            .unwrap();
+
+        let final_price = 115.0;
+        for name in ["submission", "normalizer"] {
+            let (fees_x, fees_y) = result.accumulated_fees[name];
+            assert!(fees_x > 0.0 && fees_y > 0.0, "{name}");
+            // More flow bought X out of the pool than sold it in
+            assert!(fees_y > fees_x * final_price, "{name}");
+
+            // PnL = reserves value + fee value - initial value
+            let init_value = config.initial_x * config.initial_price + config.initial_y;
+            let hodl_value = config.initial_x * final_price + config.initial_y;
+            let reserves_value = hodl_value - result.impermanent_loss[name];
+            let fee_value = result.pnl[name] + init_value - reserves_value;
+            assert!((fees_x * final_price + fees_y - fee_value).abs() < 1e-9, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_fee_il_ratio_compares_fee_income_to_il() {
+        // Oscillating around 100 and ending away from it
+        let path: Vec<f64> = (1..=200).map(|i| 100.0 + (i as f64 * 0.7).sin() * 5.0).collect();
+        let result = SimulationEngine::new(replay_config(path.clone(), 200))
+            .run(fixed_fee_strategy(100, 100), fixed_fee_strategy(0, 0))
+            .unwrap();
+
+        let ratio = result.fee_il_ratio["submission"];
+        assert!(ratio > 1.0, "{ratio}");
+        let (fees_x, fees_y) = result.accumulated_fees["submission"];
+        let fee_value = fees_x * path[199] + fees_y;
+        let il = result.impermanent_loss["submission"];
+        assert!((ratio - fee_value / il.abs()).abs() < 1e-12);
+
+        assert!(result.impermanent_loss["normalizer"] > 0.0);
+        assert!(result.fee_il_ratio["normalizer"].abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_warmup_excludes_early_activity_from_scoring() {
+        let path: Vec<f64> = (1..=60).map(|i| 100.0 + (i as f64 * 0.3).sin() * 8.0).collect();
+        let run = |n_steps: u32, warmup_steps: u32| {
+            SimulationEngine::new(SimulationConfig {
+                warmup_steps,
+                ..replay_config(path.clone(), n_steps)
+            })
+            .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(50, 50))
+            .unwrap()
+        };
+
+        let full = run(60, 0);
+        let warmup_only = run(20, 0);
+        let scored = run(60, 20);
+
+        assert_eq!(scored.initial_fair_price, path[19]);
+        for name in ["submission", "normalizer"] {
+            // Trading is unchanged; only the baseline moves
+            assert_eq!(scored.steps[19].pnls[name], warmup_only.pnl[name]);
+            assert_eq!(scored.steps[59].spot_prices[name], full.steps[59].spot_prices[name]);
+
+            assert!(warmup_only.edges[name].abs() > 1e-6, "{name}");
+            let edge = full.edges[name] - warmup_only.edges[name];
+            assert!((scored.edges[name] - edge).abs() < 1e-9, "{name}");
+            // Warmup fees are left out entirely, not carried at their
+            // value when warmup ended
+            let (warmup_x, warmup_y) = warmup_only.accumulated_fees[name];
+            assert!(warmup_x > 0.0 && warmup_y > 0.0, "{name}");
+            let warmup_drift = warmup_x * (path[59] - path[19]);
+            let pnl = full.pnl[name] - warmup_only.pnl[name] - warmup_drift;
+            assert!((scored.pnl[name] - pnl).abs() < 1e-9, "{name}");
+
+            // Fee metrics count only fees collected after warmup
+            let (full_x, full_y) = full.accumulated_fees[name];
+            let (fees_x, fees_y) = scored.accumulated_fees[name];
+            assert!((fees_x - (full_x - warmup_x)).abs() < 1e-12, "{name}");
+            assert!((fees_y - (full_y - warmup_y)).abs() < 1e-9, "{name}");
+            let fee_value = fees_x * path[59] + fees_y;
+            let il = scored.impermanent_loss[name];
+            assert!((scored.fee_il_ratio[name] - fee_value / il.abs()).abs() < 1e-9, "{name}");
+
+            // Volumes and per-step averages restart after warmup too
+            let volumes =
+                |r: &LightweightSimResult| (r.arb_volume_y[name], r.retail_volume_y[name]);
+            let (full_arb, full_retail) = volumes(&full);
+            let (warmup_arb, warmup_retail) = volumes(&warmup_only);
+            let (arb, retail) = volumes(&scored);
+            assert!(warmup_arb > 0.0, "{name}");
+            assert!((arb - (full_arb - warmup_arb)).abs() < 1e-9, "{name}");
+            assert!((retail - (full_retail - warmup_retail)).abs() < 1e-9, "{name}");
+            let (full_bid, _) = full.average_fees[name];
+            let (warmup_bid, _) = warmup_only.average_fees[name];
+            let bid = (full_bid * 60.0 - warmup_bid * 20.0) / 40.0;
+            assert!((scored.average_fees[name].0 - bid).abs() < 1e-12, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_volume_series_sums_to_totals() {
+        let config = SimulationConfig {
+            n_steps: 300,
+            seed: Some(9),
+            ..SimulationConfig::default()
+        };
+        let run = |config: SimulationConfig| {
+            SimulationEngine::new(config)
+                .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(50, 50))
+                .unwrap()
+        };
+
+        assert!(run(config.clone()).volume_series.is_empty());
+
+        let result = run(SimulationConfig { verbose: true, ..config });
+        for name in ["submission", "normalizer"] {
+            let series = &result.volume_series[name];
+            assert_eq!(series.len(), 300);
+            assert!(series.iter().all(|&v| v >= 0.0));
+            let total = result.arb_volume_y[name] + result.retail_volume_y[name];
+            let sum: f64 = series.iter().sum();
+            assert!((sum - total).abs() <= 1e-9 * total, "{name}: {sum} vs {total}");
+        }
+    }
+
+    #[test]
+    fn test_wad_math_is_reproducible_and_close_to_f64() {
+        let config = SimulationConfig {
+            n_steps: 300,
+            seed: Some(21),
+            ..SimulationConfig::default()
+        };
+        let run = |config: SimulationConfig| {
+            SimulationEngine::new(config)
+                .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(50, 50))
+                .unwrap()
+        };
+
+        let float = run(config.clone());
+        let wad_config = SimulationConfig { wad_math: true, ..config };
+        let first = run(wad_config.clone());
+        let second = run(wad_config);
+
+        for name in ["submission", "normalizer"] {
+            assert_eq!(first.pnl[name].to_bits(), second.pnl[name].to_bits());
+            assert_eq!(first.edges[name].to_bits(), second.edges[name].to_bits());
+            let scale = float.retail_volume_y[name];
+            assert!((first.pnl[name] - float.pnl[name]).abs() < 1e-6 * scale);
+            assert!((first.edges[name] - float.edges[name]).abs() < 1e-6 * scale);
+        }
+    }
+
+    #[test]
+    fn test_stepping_matches_run() {
+        let config = SimulationConfig {
+            n_steps: 200,
+            n_arbitrageurs: 2,
+            arb_act_probs: vec![0.5, 0.8],
+            seed: Some(11),
+            ..SimulationConfig::default()
+        };
+
+        let mut engine = SimulationEngine::new(config.clone());
+        let expected = engine
+            .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(50, 50))
+            .unwrap();
+
+        let mut engine = SimulationEngine::new(config);
+        let initial = engine
+            .reset(fixed_fee_strategy(30, 30), fixed_fee_strategy(50, 50))
+            .unwrap();
+        assert_eq!(initial.timestamp, 0);
+        assert_eq!(initial.reserves["submission"], (100.0, 10_000.0));
+        assert!((initial.fees["normalizer"].0 - 0.005).abs() < 1e-12);
+
+        let mut last = None;
+        for t in 0..200 {
+            assert!(!engine.is_done());
+            let obs = engine.step().unwrap();
+            assert_eq!(obs.timestamp, t);
+            last = Some(obs);
+        }
+        let last = last.unwrap();
+        assert!(last.done && engine.is_done());
+        assert!(matches!(engine.step(), Err(SimulationError::InvalidState(_))));
+
+        let result = engine.finish().unwrap();
+        for name in ["submission", "normalizer"] {
+            assert_eq!(result.pnl[name], expected.pnl[name]);
+            assert_eq!(result.edges[name], expected.edges[name]);
+            assert_eq!(last.pnls[name], expected.pnl[name]);
+        }
+    }
+
+    /// Strategy charging 30 bps whose afterSwap reverts at `timestamp`.
+    fn reverts_at_bytecode(timestamp: u128) -> Vec<u8> {
+        let fee = 30 * BPS as u128;
+        let runtime = Asm::new()
+            .dispatch(SELECTOR_AFTER_SWAP, "swap")
+            .return_pair(fee, fee)
+            .label("swap")
+            .arg(3)
+            .push(timestamp)
+            .ops(&[EQ])
+            .push_label("fail")
+            .ops(&[JUMPI])
+            .return_pair(fee, fee)
+            .label("fail")
+            .revert()
+            .build();
+        deploy(&runtime)
+    }
+
+    fn busy_config(strict_strategy: bool) -> SimulationConfig {
+        SimulationConfig {
+            n_steps: 20,
+            retail_arrival_rate: 20.0,
+            strict_strategy,
+            seed: Some(11),
+            ..SimulationConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_lenient_mode_counts_swap_failures() {
+        let submission = EVMStrategy::new(reverts_at_bytecode(5), "Reverter".into()).unwrap();
+        let mut engine = SimulationEngine::new(busy_config(false));
+        let result = engine.run(submission, fixed_fee_strategy(30, 30)).unwrap();
+
+        assert_eq!(result.steps.len(), 20);
+        assert!(result.swap_failures["submission"] >= 1);
+        assert_eq!(result.swap_failures["normalizer"], 0);
+    }
+
+    #[test]
+    fn test_strict_mode_aborts_on_swap_failure() {
+        let submission = EVMStrategy::new(reverts_at_bytecode(5), "Reverter".into()).unwrap();
+        let mut engine = SimulationEngine::new(busy_config(true));
+        let err = engine.run(submission, fixed_fee_strategy(30, 30)).unwrap_err();
+
+        match err {
+            SimulationError::EVMError(msg) => assert!(msg.contains("step 5"), "{}", msg),
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn test_fee_violations_are_counted_or_abort_when_strict() {
+        let config = |strict_fees| SimulationConfig {
+            fee_max: Some(0.004),
+            strict_fees,
+            ..busy_config(false)
+        };
+
+        // 50 bps against a 40 bps ceiling: clamped and counted
+        let result = SimulationEngine::new(config(false))
+            .run(fixed_fee_strategy(50, 30), fixed_fee_strategy(30, 30))
+            .unwrap();
+        assert_eq!(result.steps.len(), 20);
+        assert!(result.steps.iter().all(|step| step.fees["submission"].0 == 0.004));
+        assert!(result.fee_violations["submission"] > 1);
+        assert_eq!(result.fee_violations["normalizer"], 0);
+
+        let err = SimulationEngine::new(config(true))
+            .run(fixed_fee_strategy(50, 30), fixed_fee_strategy(30, 30))
+            .unwrap_err();
+        assert!(matches!(&err, SimulationError::EVMError(msg) if msg.contains("outside")), "{err}");
+
+        // Within bounds at initialization, out of them after trades
+        let bps = BPS as u128;
+        let runtime = Asm::new()
+            .dispatch(SELECTOR_AFTER_SWAP, "swap")
+            .return_pair(30 * bps, 30 * bps)
+            .label("swap")
+            .return_pair(50 * bps, 30 * bps)
+            .build();
+        let submission = EVMStrategy::new(deploy(&runtime), "Wide".into()).unwrap();
+        let err = SimulationEngine::new(config(true))
+            .run(submission, fixed_fee_strategy(30, 30))
+            .unwrap_err();
+        match err {
+            SimulationError::EVMError(msg) => assert!(msg.contains("step 0"), "{msg}"),
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[test]
+    fn test_after_arbitrage_sets_post_arb_fees() {
+        // 50 bps from afterArbitrage, 10 bps from afterSwap, 30 bps otherwise
+        let bps = BPS as u128;
+        let runtime = Asm::new()
+            .dispatch(SELECTOR_AFTER_ARBITRAGE, "arb")
+            .dispatch(SELECTOR_AFTER_SWAP, "swap")
+            .return_pair(30 * bps, 30 * bps)
+            .label("arb")
+            .return_pair(50 * bps, 50 * bps)
+            .label("swap")
+            .return_pair(10 * bps, 10 * bps)
+            .build();
+        let arb_aware = || EVMStrategy::new(deploy(&runtime), "ArbAware".into()).unwrap();
+
+        // Retail orders too small to route, so only the arbitrageur trades
+        let config = SimulationConfig {
+            retail_mean_size: 1e-6,
+            retail_size_sigma: 0.01,
+            ..replay_config(vec![110.0, 90.0], 2)
+        };
+        let mut engine = SimulationEngine::new(config);
+        engine.reset(arb_aware(), fixed_fee_strategy(30, 30)).unwrap();
+        for _ in 0..2 {
+            let obs = engine.step().unwrap();
+            assert_eq!(obs.fees["submission"], (0.005, 0.005));
+        }
+        let result = engine.finish().unwrap();
+        assert!(result.arb_volume_y["submission"] > 0.0);
+        assert_eq!(result.retail_volume_y["submission"], 0.0);
+
+        // Regular flow still goes through afterSwap
+        let mut amm = CFMM::new(arb_aware(), 100.0, 10_000.0);
+        amm.initialize().unwrap();
+        amm.execute_buy_x(1.0, 0).unwrap();
+        assert_eq!(amm.fees().bid_fee, Wad::from_bps(10));
+    }
+
+    #[test]
+    fn test_step_observation_reports_strategy_signals() {
+        // Trade hooks return 30 bps plus the post-trade X reserve as a signal
+        let fee = 30 * BPS as u128;
+        let runtime = Asm::new()
+            .dispatch(SELECTOR_AFTER_ARBITRAGE, "swap")
+            .dispatch(SELECTOR_AFTER_SWAP, "swap")
+            .return_pair(fee, fee)
+            .label("swap")
+            .push(fee)
+            .push(0)
+            .ops(&[MSTORE])
+            .push(fee)
+            .push(0x20)
+            .ops(&[MSTORE])
+            .arg(4)
+            .push(0x40)
+            .ops(&[MSTORE])
+            .push(0x60)
+            .push(0)
+            .ops(&[RETURN])
+            .build();
+        let signalling = EVMStrategy::new(deploy(&runtime), "Signal".into()).unwrap();
+
+        let config = SimulationConfig { n_steps: 20, seed: Some(4), ..SimulationConfig::default() };
+        let mut engine = SimulationEngine::new(config);
+        engine.reset(signalling, fixed_fee_strategy(30, 30)).unwrap();
+        let mut signalled = 0;
+        for _ in 0..20 {
+            let obs = engine.step().unwrap();
+            assert!(!obs.signals.contains_key("normalizer"));
+            assert_eq!(obs.fees["submission"], (0.003, 0.003));
+            // No signal until the pool first trades
+            let Some(&signal) = obs.signals.get("submission") else {
+                assert_eq!(obs.reserves["submission"], (100.0, 10_000.0));
+                continue;
+            };
+            let (reserve_x, _) = obs.reserves["submission"];
+            assert!((signal - reserve_x).abs() < 1e-9, "{signal} vs {reserve_x}");
+            signalled += 1;
+        }
+        assert!(signalled > 10, "{signalled}");
+    }
+
+    #[test]
+    fn test_fee_stats_track_oscillating_fees() {
+        // afterSwap charges 10 bps on even timestamps and 50 bps on odd ones
+        let bps = BPS as u128;
+        let runtime = Asm::new()
+            .dispatch(SELECTOR_AFTER_SWAP, "swap")
+            .return_pair(30 * bps, 30 * bps)
+            .label("swap")
+            .arg(3)
+            .push(2)
+            .ops(&[SWAP1, MOD])
+            .push(40 * bps)
+            .ops(&[MUL])
+            .push(10 * bps)
+            .ops(&[ADD, DUP1])
+            .return_pair_from_stack()
+            .build();
+        let oscillating = EVMStrategy::new(deploy(&runtime), "Oscillating".into()).unwrap();
+
+        let config = SimulationConfig {
+            n_steps: 200,
+            retail_arrival_rate: 5.0,
+            seed: Some(3),
+            ..SimulationConfig::default()
+        };
+        let result = SimulationEngine::new(config)
+            .run(oscillating, fixed_fee_strategy(30, 30))
+            .unwrap();
+
+        let stats = result.fee_stats["submission"];
+        assert!((stats.bid_min - 0.001).abs() < 1e-12, "{stats:?}");
+        assert!((stats.bid_max - 0.005).abs() < 1e-12, "{stats:?}");
+        assert!((stats.ask_min - 0.001).abs() < 1e-12, "{stats:?}");
+        assert!((stats.ask_max - 0.005).abs() < 1e-12, "{stats:?}");
+        assert!(stats.bid_mean > stats.bid_min && stats.bid_mean < stats.bid_max);
+        assert!((stats.bid_mean - result.average_fees["submission"].0).abs() < 1e-12);
+        assert!(stats.bid_stddev > 0.0 && stats.ask_stddev > 0.0);
+
+        let fixed = result.fee_stats["normalizer"];
+        assert_eq!((fixed.bid_min, fixed.bid_max), (0.003, 0.003));
+        assert_eq!(fixed.bid_stddev, 0.0);
+    }
+
+    #[test]
+    fn test_second_retail_cohort_adds_large_orders() {
+        // Frequent small noise traders plus rare whales
+        let config = SimulationConfig {
+            n_steps: 500,
+            retail_arrival_rate: 5.0,
+            retail_mean_size: 2.0,
+            retail_size_sigma: 0.2,
+            retail_cohort_b: Some(RetailCohort::new(0.1, 500.0, 0.2, 0.5)),
+            seed: Some(4),
+            ..SimulationConfig::default()
+        };
+        let mut engine = SimulationEngine::new(config.clone());
+        engine.reset(fixed_fee_strategy(30, 30), fixed_fee_strategy(30, 30)).unwrap();
+        let state = engine.state.as_mut().unwrap();
+        let sizes: Vec<f64> = (0..config.n_steps)
+            .flat_map(|t| state.retail_orders(t, config.initial_price, 0.0))
+            .map(|order| order.size)
+            .collect();
+
+        let small = sizes.iter().filter(|&&size| size < 10.0).count();
+        let large = sizes.iter().filter(|&&size| size > 100.0).count();
+        assert_eq!(small + large, sizes.len());
+        assert!(small > 2000, "{small}");
+        assert!(large > 20 && large < 100, "{large}");
+
+        // The main cohort's stream is unchanged by adding the second one
+        let mut single = SimulationEngine::new(SimulationConfig {
+            retail_cohort_b: None,
+            ..config.clone()
+        });
+        single.reset(fixed_fee_strategy(30, 30), fixed_fee_strategy(30, 30)).unwrap();
+        let single_state = single.state.as_mut().unwrap();
+        let small_sizes: Vec<f64> = (0..config.n_steps)
+            .flat_map(|t| single_state.retail_orders(t, config.initial_price, 0.0))
+            .map(|order| order.size)
+            .collect();
+        let combined_small: Vec<f64> = sizes.into_iter().filter(|&size| size < 10.0).collect();
+        assert_eq!(small_sizes, combined_small);
+    }
+
+    #[test]
+    fn test_sync_initial_spot_removes_opening_arb() {
+        // Fair price 10% above the pools' spot, held flat, no retail
+        let config = SimulationConfig {
+            initial_price: 110.0,
+            retail_arrival_profile: Some(vec![0.0]),
+            ..replay_config(vec![110.0], 1)
+        };
+        let run = |sync_initial_spot| {
+            SimulationEngine::new(SimulationConfig { sync_initial_spot, ..config.clone() })
+                .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(30, 30))
+                .unwrap()
+        };
+
+        let synced = run(true);
+        assert_eq!(synced.initial_reserves["submission"], (100.0, 11_000.0));
+        assert_eq!(synced.arb_volume_y["submission"], 0.0);
+        assert!(synced.edges["submission"].abs() < 1e-9);
+
+        // Without the flag the opening gap is arbitraged on the first step
+        let unsynced = run(false);
+        assert_eq!(unsynced.initial_reserves["submission"], (100.0, 10_000.0));
+        assert!(unsynced.arb_volume_y["submission"] > 0.0);
+        assert!(unsynced.edges["submission"] < -1.0, "{}", unsynced.edges["submission"]);
+    }
+
+    #[test]
+    fn test_checkpoint_resume_matches_straight_run() {
+        // afterSwap counts trades in storage slot 0 and charges
+        // 10 + 10 * (count % 5) bps, so fees depend on contract state
+        let bps = BPS as u128;
+        let runtime = Asm::new()
+            .dispatch(SELECTOR_AFTER_SWAP, "swap")
+            .return_pair(30 * bps, 30 * bps)
+            .label("swap")
+            .push(0)
+            .ops(&[SLOAD])
+            .push(1)
+            .ops(&[ADD, DUP1])
+            .push(0)
+            .ops(&[SSTORE])
+            .push(5)
+            .ops(&[SWAP1, MOD])
+            .push(10 * bps)
+            .ops(&[MUL])
+            .push(10 * bps)
+            .ops(&[ADD, DUP1])
+            .return_pair_from_stack()
+            .build();
+        let counting = || EVMStrategy::new(deploy(&runtime), "Counting".into()).unwrap();
+
+        let config = SimulationConfig {
+            n_steps: 200,
+            n_arbitrageurs: 2,
+            arb_act_probs: vec![0.5, 0.8],
+            retail_batching: RetailBatching::Shuffle,
+            retail_cohort_b: Some(RetailCohort::new(0.2, 200.0, 0.5, 0.5)),
+            oracle_ema_alpha: Some(0.2),
+            flow_elasticity: 0.05,
+            seed: Some(5),
+            ..SimulationConfig::default()
+        };
+        let straight = SimulationEngine::new(config.clone())
+            .run(counting(), fixed_fee_strategy(30, 30))
+            .unwrap();
+
+        let mut first = SimulationEngine::new(config.clone());
+        first.reset(counting(), fixed_fee_strategy(30, 30)).unwrap();
+        for _ in 0..100 {
+            first.step().unwrap();
+        }
+        let checkpoint = first.save_state().unwrap();
+        drop(first);
+
+        let mut resumed = SimulationEngine::new(config);
+        resumed.reset(counting(), fixed_fee_strategy(30, 30)).unwrap();
+        resumed.load_state(&checkpoint).unwrap();
+        for t in 100..200 {
+            assert_eq!(resumed.step().unwrap().timestamp, t);
+        }
+        assert!(resumed.is_done());
+        assert_eq!(resumed.finish().unwrap(), straight);
+
+        // Checkpoints of another version are rejected
+        let mut engine = SimulationEngine::new(SimulationConfig::default());
+        engine.reset(counting(), fixed_fee_strategy(30, 30)).unwrap();
+        let mut stale = checkpoint.clone();
+        stale[0] ^= 0xff;
+        assert!(matches!(engine.load_state(&stale), Err(SimulationError::InvalidState(_))));
+    }
+
+    #[test]
+    fn test_arb_arrival_rate_sets_how_often_pools_are_corrected() {
+        let path: Vec<f64> = (1..=50).map(|i| 100.0 + (i as f64 * 0.7).sin() * 5.0).collect();
+        let run = |arb_arrival_rate| {
+            let config = SimulationConfig {
+                retail_arrival_rate: 0.0,
+                arb_arrival_rate,
+                ..replay_config(path.clone(), 50)
+            };
+            SimulationEngine::new(config)
+                .run(fixed_fee_strategy(0, 0), fixed_fee_strategy(0, 0))
+                .unwrap()
+        };
+
+        let idle = run(Some(0.0));
+        assert_eq!(idle.arb_volume_y["submission"], 0.0);
+        assert!(idle.steps.iter().all(|step| step.spot_prices["submission"] == 100.0));
+
+        // Zero-fee pools are corrected onto the fair price every step
+        let busy = run(Some(20.0));
+        for (step, fair_price) in busy.steps.iter().zip(&path) {
+            for spot in step.spot_prices.values() {
+                assert!((spot - fair_price).abs() < 1e-9, "{spot} vs {fair_price}");
+            }
+        }
+        assert_eq!(busy.pnl, run(None).pnl);
+
+        let invalid = SimulationConfig {
+            arb_arrival_rate: Some(-1.0),
+            ..SimulationConfig::default()
+        };
+        assert!(validate_config(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_arb_price_lag_leaves_residual_mispricing() {
+        let tracking_error = |arb_price_lag| {
+            let config = SimulationConfig {
+                n_steps: 500,
+                retail_arrival_rate: 0.0,
+                arb_price_lag,
+                seed: Some(5),
+                ..SimulationConfig::default()
+            };
+            let result = SimulationEngine::new(config)
+                .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(30, 30))
+                .unwrap();
+            result.tracking_error["submission"]
+        };
+
+        let instant = tracking_error(0);
+        let lagged = tracking_error(20);
+        assert!(lagged > 1.5 * instant, "{instant} vs {lagged}");
+    }
+
+    #[test]
+    fn test_biased_arb_reference_mis_corrects_pool() {
+        let run = |arb_price_bias| {
+            let config = SimulationConfig {
+                n_steps: 500,
+                retail_arrival_rate: 0.0,
+                arb_price_bias,
+                seed: Some(5),
+                ..SimulationConfig::default()
+            };
+            SimulationEngine::new(config)
+                .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(30, 30))
+                .unwrap()
+        };
+
+        // Arbitrageurs pushing toward a price 5% high keep the pool there
+        let unbiased = run(1.0);
+        let biased = run(1.05);
+        let tracking_error = biased.tracking_error["submission"];
+        assert!(unbiased.tracking_error["submission"] < 0.01);
+        assert!(tracking_error > 0.04 && tracking_error < 0.06, "{tracking_error}");
+        let last = biased.steps.last().unwrap();
+        assert!(last.spot_prices["submission"] > 1.04 * last.fair_price);
+
+        // Their trades are scored at the true price, so the pool gains edge
+        assert!(biased.edges["submission"] > unbiased.edges["submission"]);
+
+        let config = SimulationConfig {
+            arb_price_bias: 0.0,
+            ..SimulationConfig::default()
+        };
+        let err = SimulationEngine::new(config)
+            .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(30, 30))
+            .unwrap_err();
+        assert!(matches!(err, SimulationError::InvalidConfig(_)));
+    }
+
+    #[test]
+    fn test_pnl_attribution_reconciles_to_pnl() {
+        let config = SimulationConfig {
+            n_steps: 500,
+            warmup_steps: 50,
+            seed: Some(21),
+            ..SimulationConfig::default()
+        };
+        let result = SimulationEngine::new(config)
+            .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(80, 80))
+            .unwrap();
+
+        let final_price = *result.fair_price_path.last().unwrap();
+        for name in ["submission", "normalizer"] {
+            let attribution = result.pnl_attribution[name];
+            let pnl = result.pnl[name];
+            let gap = (attribution.total() - pnl).abs();
+            assert!(gap < 1e-9 * pnl.abs().max(1.0), "{name}: {attribution:?} vs {pnl}");
+            assert_eq!(attribution.impermanent_loss, -result.impermanent_loss[name], "{name}");
+            assert_eq!(attribution.arb_loss, -result.realized_lvr[name], "{name}");
+            assert!(attribution.arb_loss < 0.0, "{name}");
+
+            // What is left is the scored tokens' market move, plus the
+            // arbitrage take that the loss against holding already counts
+            let (init_x, _) = result.initial_reserves[name];
+            let start_price = result.fair_price_path[50];
+            let market_move = init_x * (final_price - start_price);
+            let left = attribution.residual - (market_move - attribution.arb_loss);
+            assert!(left.abs() < 1e-6 * pnl.abs().max(1.0), "{name}: {left}");
+
+            // Fee revenue counts only the fees collected after the warmup
+            let (fees_x, fees_y) = result.accumulated_fees[name];
+            assert!(attribution.fee_revenue > 0.0, "{name}");
+            assert_eq!(attribution.fee_revenue, fees_x * final_price + fees_y, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_zero_fee_realized_lvr_matches_theory() {
+        let config = SimulationConfig {
+            n_steps: 20_000,
+            gbm_sigma: 0.2,
+            gbm_dt: 0.01,
+            retail_arrival_rate: 0.0,
+            sync_initial_spot: true,
+            seed: Some(13),
+            ..SimulationConfig::default()
+        };
+        let pool = |name: &str| {
+            let fees = FeeQuote::symmetric(Wad::from_bps(0));
+            CFMM::fixed_fee(name.into(), config.initial_x, config.effective_initial_y(), fees)
+        };
+        let result = SimulationEngine::new(config.clone())
+            .run_pools(pool("submission"), pool("normalizer"))
+            .unwrap();
+
+        let realized = result.realized_lvr["submission"];
+        let theoretical = result.theoretical_lvr["submission"];
+        assert!(theoretical > 0.0);
+        assert!((realized / theoretical - 1.0).abs() < 0.05, "{realized} vs {theoretical}");
+
+        let value = 2.0 * config.initial_x * config.initial_price;
+        assert_eq!(config.theoretical_lvr(value), 0.2 * 0.2 / 8.0 * value);
+    }
+
+    #[test]
+    fn test_realized_volatility_matches_gbm_sigma() {
+        let config = SimulationConfig {
+            n_steps: 20_000,
+            gbm_sigma: 0.3,
+            gbm_dt: 1.0 / 365.0,
+            seed: Some(17),
+            ..SimulationConfig::default()
+        };
+        let pool = |name: &str| {
+            let fees = FeeQuote::symmetric(Wad::from_bps(30));
+            CFMM::fixed_fee(name.into(), config.initial_x, config.initial_y, fees)
+        };
+        let result = SimulationEngine::new(config.clone())
+            .run_pools(pool("submission"), pool("normalizer"))
+            .unwrap();
+
+        assert_eq!(result.fair_price_path.len(), 20_001);
+        assert_eq!(result.fair_price_path[0], config.initial_price);
+        let stepped: Vec<f64> = result.steps.iter().map(|step| step.fair_price).collect();
+        assert_eq!(result.fair_price_path[1..], stepped[..]);
+        let realized = result.realized_volatility();
+        assert!((realized / 0.3 - 1.0).abs() < 0.03, "{realized}");
+    }
+
+    #[test]
+    fn test_mirror_baseline_tracks_submission_fees() {
+        // A submission whose fees move with its inventory
+        let strategy = NativeStrategy::InventoryAware {
+            base_fee: 0.003,
+            sensitivity: 0.05,
+        };
+        let run = |baseline_mode| {
+            let config = SimulationConfig {
+                n_steps: 50,
+                baseline_mode,
+                baseline_fee: 0.002,
+                seed: Some(19),
+                ..SimulationConfig::default()
+            };
+            let submission = CFMM::native("submission".into(), config.initial_x, config.initial_y, strategy);
+            let baseline = CFMM::new(fixed_fee_strategy(80, 80), config.initial_x, config.initial_y);
+            SimulationEngine::new(config).run_pools(submission, baseline).unwrap()
+        };
+
+        let mirrored = run(BaselineMode::Mirror);
+        let submission_fees: Vec<_> = mirrored.steps.iter().map(|step| step.fees["submission"]).collect();
+        assert!(submission_fees.windows(2).any(|pair| pair[0] != pair[1]));
+        for step in &mirrored.steps {
+            assert_eq!(step.fees["normalizer"], step.fees["submission"], "step {}", step.timestamp);
+        }
+
+        let constant = run(BaselineMode::Constant);
+        assert!(constant.steps.iter().all(|step| step.fees["normalizer"] == (0.002, 0.002)));
+
+        let own = run(BaselineMode::Strategy);
+        assert!(own.steps.iter().all(|step| step.fees["normalizer"] == (0.008, 0.008)));
+    }
+
+    /// Panic if any summary or per-step number of `result` is NaN or infinite.
+    fn assert_all_finite(result: &LightweightSimResult) {
+        let maps = [
+            ("pnl", &result.pnl),
+            ("edges", &result.edges),
+            ("arb_volume_y", &result.arb_volume_y),
+            ("arb_net_x", &result.arb_net_x),
+            ("retail_volume_y", &result.retail_volume_y),
+            ("retail_slippage_bps", &result.retail_slippage_bps),
+            ("tracking_error", &result.tracking_error),
+            ("avg_spread_bps", &result.avg_spread_bps),
+            ("impermanent_loss", &result.impermanent_loss),
+            ("realized_lvr", &result.realized_lvr),
+            ("theoretical_lvr", &result.theoretical_lvr),
+        ];
+        for (field, map) in maps {
+            for (name, value) in map {
+                assert!(value.is_finite(), "{field}[{name}] = {value}");
+            }
+        }
+        for step in &result.steps {
+            let values = step.spot_prices.values().chain(step.pnls.values());
+            let fees = step.fees.values().flat_map(|&(bid, ask)| [bid, ask]);
+            for value in values.copied().chain(fees) {
+                assert!(value.is_finite(), "step {}: {value}", step.timestamp);
+            }
+        }
+    }
+
+    #[test]
+    fn test_degenerate_reserves_never_produce_nan() {
+        // Empty or dust pools are rejected by the config
+        for (initial_x, initial_y) in [(0.0, 10_000.0), (100.0, 0.0), (1e-12, 1e-12), (f64::NAN, 1.0)] {
+            let config = SimulationConfig {
+                initial_x,
+                initial_y,
+                ..SimulationConfig::default()
+            };
+            let err = SimulationEngine::new(config)
+                .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(30, 30))
+                .unwrap_err();
+            assert!(matches!(err, SimulationError::InvalidConfig(_)), "{err}");
+        }
+
+        // Pools built by hand skip that check; they just never trade
+        let config = SimulationConfig {
+            n_steps: 50,
+            retail_arrival_rate: 5.0,
+            seed: Some(23),
+            ..SimulationConfig::default()
+        };
+        let fees = FeeQuote::symmetric(Wad::from_bps(30));
+        for (x, y) in [(0.0, 0.0), (0.0, 10_000.0), (100.0, 0.0)] {
+            let degenerate = CFMM::fixed_fee("submission".into(), x, y, fees);
+            let healthy = CFMM::fixed_fee("normalizer".into(), 100.0, 10_000.0, fees);
+            let result = SimulationEngine::new(config.clone())
+                .run_pools(degenerate, healthy)
+                .unwrap();
+            assert_all_finite(&result);
+            assert_eq!(result.arb_volume_y["submission"], 0.0);
+            assert_eq!(result.retail_volume_y["submission"], 0.0);
+        }
+    }
+
+    #[test]
+    fn test_adaptation_steps_separate_instant_and_ramping_fees() {
+        // afterSwap counts trades in storage slot 0 and charges 2 bps per
+        // trade so far, up to a steady 120 bps from the 60th trade on
+        let bps = BPS as u128;
+        let runtime = Asm::new()
+            .dispatch(SELECTOR_AFTER_SWAP, "swap")
+            .return_pair(2 * bps, 2 * bps)
+            .label("swap")
+            .push(0)
+            .ops(&[SLOAD])
+            .push(1)
+            .ops(&[ADD, DUP1])
+            .push(0)
+            .ops(&[SSTORE, DUP1])
+            .push(60)
+            .ops(&[GT, ISZERO])
+            .push_label("steady")
+            .ops(&[JUMPI])
+            .push(2 * bps)
+            .ops(&[MUL, DUP1])
+            .return_pair_from_stack()
+            .label("steady")
+            .return_pair(120 * bps, 120 * bps)
+            .build();
+        let ramping = EVMStrategy::new(deploy(&runtime), "Ramping".into()).unwrap();
+
+        let config = SimulationConfig {
+            n_steps: 500,
+            seed: Some(9),
+            ..SimulationConfig::default()
+        };
+        let result = SimulationEngine::new(config)
+            .run(ramping, fixed_fee_strategy(30, 30))
+            .unwrap();
+
+        // The fixed fee is settled from the first step
+        assert_eq!(result.adaptation_steps["normalizer"], 0);
+
+        // The ramp settles on the step its fee first reaches 120 bps
+        let settled = result.adaptation_steps["submission"] as usize;
+        assert!(settled > 10 && settled < 400, "{settled}");
+        assert_eq!(result.steps[settled].fees["submission"], (0.012, 0.012));
+        assert_ne!(result.steps[settled - 1].fees["submission"], (0.012, 0.012));
+
+        // A run too short to fill the window never settles
+        let short = SimulationConfig {
+            n_steps: ADAPTATION_WINDOW - 1,
+            seed: Some(9),
+            ..SimulationConfig::default()
+        };
+        let result = SimulationEngine::new(short)
+            .run(fixed_fee_strategy(30, 30), fixed_fee_strategy(30, 30))
+            .unwrap();
+        assert_eq!(result.adaptation_steps["normalizer"], ADAPTATION_WINDOW - 1);
+    }
+
+    #[test]
+    fn test_fee_asymmetry_separates_symmetric_and_skewed_fees() {
+        let config = SimulationConfig {
+            n_steps: 100,
+            seed: Some(12),
+            ..SimulationConfig::default()
+        };
+        let result = SimulationEngine::new(config)
+            .run(fixed_fee_strategy(20, 60), fixed_fee_strategy(30, 30))
+            .unwrap();
+
+        assert_eq!(result.fee_asymmetry["normalizer"], 0.0);
+        assert!((result.fee_asymmetry["submission"] - 0.004).abs() < 1e-12);
+    }
 }
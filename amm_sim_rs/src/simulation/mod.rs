@@ -2,6 +2,10 @@
 
 pub mod engine;
 pub mod runner;
+pub mod compare;
+pub mod static_fee;
 
 pub use engine::SimulationEngine;
-pub use runner::{run_simulations_parallel, SimulationBatchConfig};
+pub use compare::compare_batches;
+pub use runner::{run_simulations_parallel, verify_determinism, SimulationBatchConfig};
+pub use static_fee::optimal_static_fee;
@@ -1,13 +1,34 @@
 //! Parallel simulation runner using rayon.
 
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
 use rayon::prelude::*;
+use rayon::ThreadPool;
+use revm::primitives::{keccak256, B256};
 
-use crate::evm::EVMStrategy;
-use crate::simulation::engine::{SimulationEngine, SimulationError};
+use crate::evm::{DeployedBytecode, EVMStrategy};
+use crate::simulation::engine::{
+    SimulationEngine, SimulationError, BASELINE_NAME, SUBMISSION_NAME,
+};
 use crate::types::config::SimulationConfig;
-use crate::types::result::{BatchSimulationResult, LightweightSimResult};
+use crate::types::observation::StepObservation;
+use crate::types::result::{
+    BatchSimulationResult, LightweightSimResult, PairedBatchResult, SimulationSummary,
+};
+
+/// Worker count used by deterministic batches when none is given.
+pub const DETERMINISTIC_N_WORKERS: usize = 8;
+
+/// Callback receiving each finished simulation of a batch, with its index
+/// into the batch's configs. Called from worker threads, in completion
+/// order, before any per-step series are dropped.
+pub type ResultSink = Arc<dyn Fn(usize, &LightweightSimResult) + Send + Sync>;
 
 /// Configuration for a batch of simulations.
+#[derive(Clone)]
 pub struct SimulationBatchConfig {
     /// Bytecode for the submission strategy
     pub submission_bytecode: Vec<u8>,
@@ -17,80 +38,977 @@ pub struct SimulationBatchConfig {
     pub configs: Vec<SimulationConfig>,
     /// Number of parallel workers (None = auto-detect)
     pub n_workers: Option<usize>,
+    /// Require an explicit seed per config and use a fixed worker count
+    /// (`DETERMINISTIC_N_WORKERS`) instead of auto-detecting one
+    pub deterministic: bool,
+    /// Keep each result's per-step series (`steps`, `volume_series`) in
+    /// the returned batch; without them only the summaries remain
+    pub retain_steps: bool,
+    /// Streams every finished result as it completes (None = no streaming)
+    pub sink: Option<ResultSink>,
+    /// Return a failed simulation as a result flagged `failed` (with its
+    /// error message) instead of failing the whole batch
+    pub capture_errors: bool,
+    /// Run on a process-wide pool kept between batches instead of a fresh
+    /// one per batch (see `thread_pool`)
+    pub shared_pool: bool,
+    /// Follow every config with its mirror image (its `antithetic` flag
+    /// flipped), so results come in adjacent pairs whose price shocks are
+    /// negations of each other; sink indices count the pairs' members
+    pub antithetic: bool,
+    /// Wall-clock limit per simulation, in milliseconds (None = no limit).
+    /// A simulation still running past it is stopped between steps and
+    /// returned as a failed, `timed_out` result, whatever `capture_errors`
+    /// says; a step already underway is not interrupted
+    pub timeout_ms: Option<u64>,
+}
+
+impl SimulationBatchConfig {
+    /// Create a non-deterministic batch config (`n_workers = 0` auto-detects).
+    pub fn new(
+        submission_bytecode: Vec<u8>,
+        baseline_bytecode: Vec<u8>,
+        configs: Vec<SimulationConfig>,
+        n_workers: usize,
+    ) -> Self {
+        Self {
+            submission_bytecode,
+            baseline_bytecode,
+            configs,
+            n_workers: if n_workers == 0 { None } else { Some(n_workers) },
+            deterministic: false,
+            retain_steps: true,
+            sink: None,
+            capture_errors: false,
+            shared_pool: false,
+            antithetic: false,
+            timeout_ms: None,
+        }
+    }
+}
+
+/// Pool reused by `shared_pool` batches, with its worker count.
+static SHARED_POOL: OnceLock<(usize, Arc<ThreadPool>)> = OnceLock::new();
+
+/// Get a thread pool with `n_workers` threads.
+///
+/// With `shared` set, the first request builds the process-wide shared
+/// pool and later requests for the same worker count reuse its threads;
+/// other counts get a fresh pool, as do all non-shared requests.
+fn thread_pool(n_workers: usize, shared: bool) -> Result<Arc<ThreadPool>, SimulationError> {
+    let build = || {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(n_workers)
+            .build()
+            .map(Arc::new)
+            .map_err(|e| {
+                SimulationError::InvalidConfig(format!("Failed to create thread pool: {}", e))
+            })
+    };
+    if !shared {
+        return build();
+    }
+    if SHARED_POOL.get().is_none() {
+        // A concurrent first request may win the race; its pool is kept
+        let _ = SHARED_POOL.set((n_workers, build()?));
+    }
+    match SHARED_POOL.get() {
+        Some((count, pool)) if *count == n_workers => Ok(Arc::clone(pool)),
+        _ => build(),
+    }
 }
 
 /// Run multiple simulations in parallel.
 pub fn run_simulations_parallel(
     batch_config: SimulationBatchConfig,
 ) -> Result<BatchSimulationResult, SimulationError> {
+    if batch_config.deterministic {
+        if let Some(i) = batch_config.configs.iter().position(|c| c.seed.is_none()) {
+            return Err(SimulationError::InvalidConfig(format!(
+                "deterministic mode requires a seed for every config (config {} has none)",
+                i
+            )));
+        }
+    }
+
+    let configs = if batch_config.antithetic {
+        batch_config
+            .configs
+            .into_iter()
+            .flat_map(|config| {
+                let mirror = SimulationConfig {
+                    antithetic: !config.antithetic,
+                    ..config.clone()
+                };
+                [config, mirror]
+            })
+            .collect()
+    } else {
+        batch_config.configs
+    };
+
     // Configure thread pool
     let n_workers = batch_config.n_workers.unwrap_or_else(|| {
-        rayon::current_num_threads().min(8)
+        if batch_config.deterministic {
+            DETERMINISTIC_N_WORKERS
+        } else {
+            rayon::current_num_threads().min(8)
+        }
     });
 
     // Build custom thread pool if needed
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(n_workers)
-        .build()
-        .map_err(|e| SimulationError::InvalidConfig(format!("Failed to create thread pool: {}", e)))?;
+    let pool = thread_pool(n_workers, batch_config.shared_pool)?;
 
     // Clone bytecodes for each worker (they need their own EVM instances)
     let submission_bytecode = batch_config.submission_bytecode;
     let baseline_bytecode = batch_config.baseline_bytecode;
+    let retain_steps = batch_config.retain_steps;
+    let sink = batch_config.sink;
+    let capture_errors = batch_config.capture_errors;
+    let timeout = batch_config.timeout_ms.map(Duration::from_millis);
 
     // Run simulations in parallel
     let results: Result<Vec<LightweightSimResult>, SimulationError> = pool.install(|| {
-        batch_config.configs
+        configs
             .into_par_iter()
-            .map(|config| {
-                // Create fresh EVM strategies for this worker
-                let submission = EVMStrategy::new(
-                    submission_bytecode.clone(),
-                    "Submission".to_string(),
-                ).map_err(|e| SimulationError::EVMError(e.to_string()))?;
+            .enumerate()
+            .map(|(index, config)| {
+                let seed = config.seed.unwrap_or(0);
+                // Errors come with the name of the strategy to blame, if any
+                let run = |config: SimulationConfig| {
+                    // Create fresh EVM strategies for this worker
+                    let submission = EVMStrategy::with_gas_limits(
+                        submission_bytecode.clone(),
+                        "Submission".to_string(),
+                        config.gas_limits(),
+                    )
+                    .map_err(|e| (e.into(), Some(SUBMISSION_NAME.to_string())))?;
 
-                let baseline = EVMStrategy::new(
-                    baseline_bytecode.clone(),
-                    "Baseline".to_string(),
-                ).map_err(|e| SimulationError::EVMError(e.to_string()))?;
+                    let baseline = EVMStrategy::with_gas_limits(
+                        baseline_bytecode.clone(),
+                        "Baseline".to_string(),
+                        config.gas_limits(),
+                    )
+                    .map_err(|e| (e.into(), Some(BASELINE_NAME.to_string())))?;
 
-                let mut engine = SimulationEngine::new(config);
-                engine.run(submission, baseline)
+                    let mut engine = SimulationEngine::new(config);
+                    match timeout {
+                        Some(timeout) => {
+                            run_with_timeout(&mut engine, submission, baseline, timeout)
+                        }
+                        None => run_timed(&mut engine, submission, baseline),
+                    }
+                    .map_err(|e| (e, engine.failed_strategy().map(str::to_string)))
+                };
+                let mut result = match run(config.clone()) {
+                    Ok(result) => result,
+                    Err((e, failed_strategy)) if capture_errors => LightweightSimResult {
+                        seed,
+                        config: SimulationConfig {
+                            seed: Some(seed),
+                            ..config
+                        },
+                        strategies: vec![SUBMISSION_NAME.to_string(), BASELINE_NAME.to_string()],
+                        failed: true,
+                        error: Some(e.to_string()),
+                        failed_strategy,
+                        ..Default::default()
+                    },
+                    Err((e, _)) => return Err(e),
+                };
+                if let Some(sink) = &sink {
+                    sink(index, &result);
+                }
+                if !retain_steps {
+                    result.steps = Vec::new();
+                    result.volume_series = HashMap::new();
+                }
+                Ok(result)
             })
             .collect()
     });
 
     let results = results?;
 
-    // Extract strategy names from first result
-    let strategies = if let Some(first) = results.first() {
-        first.strategies.clone()
+    // Extract strategy names from the first result that has them (timed-out
+    // results do not)
+    let strategies = results
+        .iter()
+        .find(|result| !result.strategies.is_empty())
+        .map(|first| first.strategies.clone())
+        .unwrap_or_default();
+
+    Ok(BatchSimulationResult { results, strategies })
+}
+
+/// Run a batch twice and check every simulation reproduces exactly.
+///
+/// Each result must depend only on its config and seed; anything else
+/// (shared caches, thread scheduling) shows up as a mismatch. Wall-clock
+/// timings are ignored. Returns the first run's results.
+pub fn verify_determinism(
+    batch_config: SimulationBatchConfig,
+) -> Result<BatchSimulationResult, SimulationError> {
+    let first = run_simulations_parallel(batch_config.clone())?;
+    let second = run_simulations_parallel(batch_config)?;
+
+    for (i, (a, b)) in first.results.iter().zip(&second.results).enumerate() {
+        let untimed = |r: &LightweightSimResult| LightweightSimResult {
+            elapsed_micros: 0,
+            ..r.clone()
+        };
+        if untimed(a) != untimed(b) {
+            return Err(SimulationError::InvalidState(format!(
+                "simulation {} (seed {}) is not deterministic",
+                i, a.seed
+            )));
+        }
+    }
+    Ok(first)
+}
+
+/// Run two submissions against the same baseline on identical configs.
+///
+/// Both batches share every config, seed included, so each pair of
+/// simulations sees the same price path, retail flow, and arbitrageur
+/// draws; differences between A and B come from the strategies alone.
+pub fn run_paired(
+    submission_a_bytecode: Vec<u8>,
+    submission_b_bytecode: Vec<u8>,
+    baseline_bytecode: Vec<u8>,
+    configs: Vec<SimulationConfig>,
+    n_workers: usize,
+) -> Result<PairedBatchResult, SimulationError> {
+    let a = run_simulations_parallel(SimulationBatchConfig::new(
+        submission_a_bytecode,
+        baseline_bytecode.clone(),
+        configs.clone(),
+        n_workers,
+    ))?;
+    let b = run_simulations_parallel(SimulationBatchConfig::new(
+        submission_b_bytecode,
+        baseline_bytecode,
+        configs,
+        n_workers,
+    ))?;
+    Ok(PairedBatchResult::new(a, b))
+}
+
+/// Run a heterogeneous batch where each match brings its own
+/// `(submission, baseline, config)`.
+///
+/// Each distinct bytecode (keyed by its keccak256 hash) is deployed once
+/// up front; every match then starts its strategies from those snapshots.
+/// Results are in match order.
+pub fn run_matches(
+    matches: Vec<(Vec<u8>, Vec<u8>, SimulationConfig)>,
+    n_workers: usize,
+) -> Result<BatchSimulationResult, SimulationError> {
+    let mut deployed: HashMap<B256, DeployedBytecode> = HashMap::new();
+    let mut keyed_matches = Vec::with_capacity(matches.len());
+    for (submission_bytecode, baseline_bytecode, config) in matches {
+        let mut key = |bytecode: Vec<u8>| -> Result<B256, SimulationError> {
+            let hash = keccak256(&bytecode);
+            if let Entry::Vacant(entry) = deployed.entry(hash) {
+                entry.insert(
+                    DeployedBytecode::deploy(bytecode)?,
+                );
+            }
+            Ok(hash)
+        };
+        keyed_matches.push((key(submission_bytecode)?, key(baseline_bytecode)?, config));
+    }
+
+    let n_workers = if n_workers == 0 {
+        rayon::current_num_threads().min(8)
     } else {
-        Vec::new()
+        n_workers
     };
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(n_workers)
+        .build()
+        .map_err(|e| SimulationError::InvalidConfig(format!("Failed to create thread pool: {}", e)))?;
+
+    let results: Result<Vec<LightweightSimResult>, SimulationError> = pool.install(|| {
+        keyed_matches
+            .into_par_iter()
+            .map(|(submission_hash, baseline_hash, config)| {
+                let submission = EVMStrategy::from_deployed(
+                    &deployed[&submission_hash],
+                    "Submission".to_string(),
+                    config.gas_limits(),
+                )?;
+
+                let baseline = EVMStrategy::from_deployed(
+                    &deployed[&baseline_hash],
+                    "Baseline".to_string(),
+                    config.gas_limits(),
+                )?;
+
+                let mut engine = SimulationEngine::new(config);
+                run_timed(&mut engine, submission, baseline)
+            })
+            .collect()
+    });
+
+    let results = results?;
+    let strategies = results
+        .first()
+        .map(|first| first.strategies.clone())
+        .unwrap_or_default();
 
     Ok(BatchSimulationResult { results, strategies })
 }
 
+/// Run a batch keeping only each simulation's headline scores.
+///
+/// Configs run with `record_steps` off, so no per-step results are built
+/// at all; the summaries come back in config order.
+pub fn run_batch_summary(
+    mut batch: SimulationBatchConfig,
+) -> Result<Vec<SimulationSummary>, SimulationError> {
+    for config in &mut batch.configs {
+        config.record_steps = false;
+    }
+    batch.retain_steps = false;
+    let results = run_simulations_parallel(batch)?;
+    Ok(results.results.iter().map(SimulationSummary::from).collect())
+}
+
 /// Run a single simulation (non-parallel).
 pub fn run_simulation(
     submission_bytecode: Vec<u8>,
     baseline_bytecode: Vec<u8>,
     config: SimulationConfig,
 ) -> Result<LightweightSimResult, SimulationError> {
-    let submission = EVMStrategy::new(submission_bytecode, "Submission".to_string())
-        .map_err(|e| SimulationError::EVMError(e.to_string()))?;
+    let submission = EVMStrategy::with_gas_limits(submission_bytecode, "Submission".to_string(), config.gas_limits())?;
 
-    let baseline = EVMStrategy::new(baseline_bytecode, "Baseline".to_string())
-        .map_err(|e| SimulationError::EVMError(e.to_string()))?;
+    let baseline = EVMStrategy::with_gas_limits(baseline_bytecode, "Baseline".to_string(), config.gas_limits())?;
 
     let mut engine = SimulationEngine::new(config);
-    engine.run(submission, baseline)
+    run_timed(&mut engine, submission, baseline)
+}
+
+/// Rerun the simulation behind `result` from its embedded config and seed.
+///
+/// Given the strategies it ran with, the replay reproduces the result
+/// exactly; `verbose` additionally turns on the config's per-step detail
+/// (reserves, volume series) for inspecting a run found in a big batch.
+pub fn replay(
+    result: &LightweightSimResult,
+    submission_bytecode: Vec<u8>,
+    baseline_bytecode: Vec<u8>,
+    verbose: bool,
+) -> Result<LightweightSimResult, SimulationError> {
+    let config = SimulationConfig {
+        seed: Some(result.seed),
+        verbose: verbose || result.config.verbose,
+        ..result.config.clone()
+    };
+    run_simulation(submission_bytecode, baseline_bytecode, config)
+}
+
+/// Run a single simulation, calling `on_step` after every step; returning
+/// false from it ends the run early (see `SimulationEngine::run_with_hook`).
+pub fn run_simulation_with_hook(
+    submission_bytecode: Vec<u8>,
+    baseline_bytecode: Vec<u8>,
+    config: SimulationConfig,
+    on_step: impl FnMut(&StepObservation) -> bool,
+) -> Result<LightweightSimResult, SimulationError> {
+    let submission = EVMStrategy::with_gas_limits(submission_bytecode, "Submission".to_string(), config.gas_limits())?;
+    let baseline = EVMStrategy::with_gas_limits(baseline_bytecode, "Baseline".to_string(), config.gas_limits())?;
+
+    let start = Instant::now();
+    let mut result = SimulationEngine::new(config).run_with_hook(submission, baseline, on_step)?;
+    result.elapsed_micros = start.elapsed().as_micros() as u64;
+    Ok(result)
+}
+
+/// Run the engine and record its wall-clock time on the result.
+pub(crate) fn run_timed(
+    engine: &mut SimulationEngine,
+    submission: EVMStrategy,
+    baseline: EVMStrategy,
+) -> Result<LightweightSimResult, SimulationError> {
+    let start = Instant::now();
+    let mut result = engine.run(submission, baseline)?;
+    result.elapsed_micros = start.elapsed().as_micros() as u64;
+    Ok(result)
+}
+
+/// Run the engine like `run_timed`, stopping once more than `timeout` has
+/// passed at the end of a step.
+///
+/// A stopped run comes back as a failed, `timed_out` result carrying only
+/// its seed, config, error message and elapsed time.
+fn run_with_timeout(
+    engine: &mut SimulationEngine,
+    submission: EVMStrategy,
+    baseline: EVMStrategy,
+    timeout: Duration,
+) -> Result<LightweightSimResult, SimulationError> {
+    let start = Instant::now();
+    let (mut steps_run, mut timed_out) = (0, false);
+    let mut result = engine.run_with_hook(submission, baseline, |_| {
+        steps_run += 1;
+        timed_out = start.elapsed() > timeout;
+        !timed_out
+    })?;
+    let elapsed_micros = start.elapsed().as_micros() as u64;
+    if timed_out && steps_run < result.config.n_steps {
+        result = LightweightSimResult {
+            seed: result.seed,
+            config: result.config,
+            failed: true,
+            error: Some(format!("timed out after {} ms", timeout.as_millis())),
+            timed_out: true,
+            ..Default::default()
+        };
+    }
+    result.elapsed_micros = elapsed_micros;
+    Ok(result)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::evm::fixtures::{deploy, fixed_fee_bytecode, Asm, ADD, DUP1, GT, JUMPI, POP};
+    use crate::types::config::HyperparameterVariance;
+    use crate::types::result::ScoreScheme;
+    use crate::types::trade_info::SELECTOR_AFTER_INITIALIZE;
+    use crate::types::wad::{BPS, WAD};
+
+    #[test]
+    fn test_batch_records_elapsed_time() {
+        let configs = (0..3)
+            .map(|seed| SimulationConfig {
+                n_steps: 200,
+                seed: Some(seed),
+                ..SimulationConfig::default()
+            })
+            .collect();
+        let batch = run_simulations_parallel(SimulationBatchConfig {
+            submission_bytecode: fixed_fee_bytecode(30, 30),
+            baseline_bytecode: fixed_fee_bytecode(50, 50),
+            configs,
+            n_workers: Some(2),
+            deterministic: false,
+            retain_steps: true,
+            sink: None,
+            capture_errors: false,
+            shared_pool: false,
+            antithetic: false,
+            timeout_ms: None,
+        })
+        .unwrap();
+
+        assert_eq!(batch.results.len(), 3);
+        for result in &batch.results {
+            assert!(result.elapsed_micros > 0);
+        }
+    }
+
+    #[test]
+    fn test_batch_can_stream_results_without_retaining_steps() {
+        let configs: Vec<SimulationConfig> = (0..3)
+            .map(|seed| SimulationConfig {
+                n_steps: 100,
+                verbose: true,
+                seed: Some(seed),
+                ..SimulationConfig::default()
+            })
+            .collect();
+        let batch_config =
+            SimulationBatchConfig::new(fixed_fee_bytecode(30, 30), fixed_fee_bytecode(50, 50), configs, 2);
+        let full = run_simulations_parallel(batch_config.clone()).unwrap();
+
+        let streamed = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink_streamed = Arc::clone(&streamed);
+        let lean = run_simulations_parallel(SimulationBatchConfig {
+            retain_steps: false,
+            sink: Some(Arc::new(move |index, result: &LightweightSimResult| {
+                sink_streamed.lock().unwrap().push((index, result.steps.len()));
+            })),
+            ..batch_config
+        })
+        .unwrap();
+
+        for (full, lean) in full.results.iter().zip(&lean.results) {
+            assert_eq!(full.steps.len(), 100);
+            assert!(lean.steps.is_empty());
+            assert!(lean.volume_series.is_empty());
+            assert_eq!(full.pnl, lean.pnl);
+            assert_eq!(full.edges, lean.edges);
+        }
+        let mut streamed = streamed.lock().unwrap().clone();
+        streamed.sort();
+        assert_eq!(streamed, vec![(0, 100), (1, 100), (2, 100)]);
+    }
+
+    #[test]
+    fn test_deterministic_batch_reproduces() {
+        let configs: Vec<SimulationConfig> = (0..4)
+            .map(|seed| SimulationConfig {
+                n_steps: 150,
+                n_arbitrageurs: 2,
+                arb_act_probs: vec![0.7, 0.7],
+                seed: Some(seed),
+                ..SimulationConfig::default()
+            })
+            .collect();
+        let batch_config = SimulationBatchConfig {
+            submission_bytecode: fixed_fee_bytecode(30, 30),
+            baseline_bytecode: fixed_fee_bytecode(50, 50),
+            configs: configs.clone(),
+            n_workers: None,
+            deterministic: true,
+            retain_steps: true,
+            sink: None,
+            capture_errors: false,
+            shared_pool: false,
+            antithetic: false,
+            timeout_ms: None,
+        };
+
+        let batch = verify_determinism(batch_config.clone()).unwrap();
+        assert_eq!(batch.results.len(), 4);
+
+        // Worker count does not change any result
+        let serial = run_simulations_parallel(SimulationBatchConfig {
+            n_workers: Some(1),
+            ..batch_config.clone()
+        })
+        .unwrap();
+        for (a, b) in batch.results.iter().zip(&serial.results) {
+            assert_eq!(a.pnl, b.pnl);
+            assert_eq!(a.edges, b.edges);
+        }
+
+        // Unseeded configs are rejected
+        let mut unseeded = batch_config;
+        unseeded.configs[2].seed = None;
+        assert!(matches!(
+            run_simulations_parallel(unseeded),
+            Err(SimulationError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_paired_runs_share_random_streams() {
+        // Without retail flow the pools only meet arbitrageurs, so the
+        // baseline is untouched by which submission it runs beside
+        let configs: Vec<SimulationConfig> = (0..4)
+            .map(|seed| SimulationConfig {
+                n_steps: 200,
+                retail_arrival_profile: Some(vec![0.0]),
+                seed: Some(seed),
+                ..SimulationConfig::default()
+            })
+            .collect();
+        let paired = run_paired(
+            fixed_fee_bytecode(30, 30),
+            fixed_fee_bytecode(80, 80),
+            fixed_fee_bytecode(50, 50),
+            configs,
+            2,
+        )
+        .unwrap();
+
+        assert_eq!(paired.edge_diffs.len(), 4);
+        for (i, (a, b)) in paired.a.results.iter().zip(&paired.b.results).enumerate() {
+            assert_eq!(a.seed, b.seed);
+            assert_eq!(a.pnl["normalizer"], b.pnl["normalizer"]);
+            assert_ne!(a.pnl["submission"], b.pnl["submission"]);
+            let diff = a.pnl["submission"] - b.pnl["submission"];
+            assert_eq!(paired.pnl_diffs[i], diff);
+            let fair_prices = |r: &LightweightSimResult| -> Vec<f64> {
+                r.steps.iter().map(|step| step.fair_price).collect()
+            };
+            assert_eq!(fair_prices(a), fair_prices(b));
+        }
+
+        let (mean, std_error) = paired.mean_pnl_diff();
+        let expected = paired.pnl_diffs.iter().sum::<f64>() / 4.0;
+        assert!((mean - expected).abs() < 1e-9);
+        assert!(std_error > 0.0);
+    }
+
+    #[test]
+    fn test_matches_use_their_own_strategies() {
+        let config = |seed| SimulationConfig {
+            n_steps: 150,
+            seed: Some(seed),
+            ..SimulationConfig::default()
+        };
+        let pairs = [(30, 50), (80, 10), (30, 50)];
+        let matches = pairs
+            .iter()
+            .enumerate()
+            .map(|(i, &(sub_bps, base_bps))| {
+                (fixed_fee_bytecode(sub_bps, sub_bps), fixed_fee_bytecode(base_bps, base_bps), config(i as u64))
+            })
+            .collect();
+        let batch = run_matches(matches, 2).unwrap();
+
+        assert_eq!(batch.results.len(), 3);
+        for (i, (result, &(sub_bps, base_bps))) in batch.results.iter().zip(&pairs).enumerate() {
+            for (name, bps) in [("submission", sub_bps), ("normalizer", base_bps)] {
+                let (bid, ask) = result.average_fees[name];
+                assert!((bid - bps as f64 / 10_000.0).abs() < 1e-12, "match {i} {name}");
+                assert!((ask - bps as f64 / 10_000.0).abs() < 1e-12, "match {i} {name}");
+            }
+
+            // Starting from a deployment snapshot matches a fresh deployment
+            let single = run_simulation(
+                fixed_fee_bytecode(sub_bps, sub_bps),
+                fixed_fee_bytecode(base_bps, base_bps),
+                config(i as u64),
+            )
+            .unwrap();
+            assert_eq!(result.pnl, single.pnl);
+        }
+
+        assert!(run_matches(vec![(vec![0xfe], fixed_fee_bytecode(30, 30), config(0))], 1).is_err());
+    }
+
+    #[test]
+    fn test_errors_distinguish_config_and_deployment() {
+        let invalid = SimulationConfig {
+            n_steps: 10,
+            arb_act_probs: vec![2.0],
+            ..SimulationConfig::default()
+        };
+        let err = run_simulation(fixed_fee_bytecode(30, 30), fixed_fee_bytecode(30, 30), invalid)
+            .unwrap_err();
+        assert!(matches!(err, SimulationError::InvalidConfig(_)), "{err}");
+
+        // Creation code that reverts instead of returning runtime code
+        let config = SimulationConfig {
+            n_steps: 10,
+            ..SimulationConfig::default()
+        };
+        let reverting = Asm::new().revert().build();
+        let err = run_simulation(reverting, fixed_fee_bytecode(30, 30), config.clone())
+            .unwrap_err();
+        assert!(matches!(err, SimulationError::EVMDeployError(_)), "{err}");
+
+        // Deploys and initializes, then reverts on the first afterSwap
+        let runtime = Asm::new()
+            .dispatch(SELECTOR_AFTER_INITIALIZE, "init")
+            .revert()
+            .label("init")
+            .return_pair(30 * BPS as u128, 30 * BPS as u128)
+            .build();
+        let strict = SimulationConfig {
+            strict_strategy: true,
+            ..config
+        };
+        let err = run_simulation(deploy(&runtime), fixed_fee_bytecode(30, 30), strict).unwrap_err();
+        assert!(matches!(err, SimulationError::EVMError(_)), "{err}");
+    }
+
+    #[test]
+    fn test_step_hook_can_stop_the_run_early() {
+        let config = SimulationConfig {
+            n_steps: 200,
+            seed: Some(4),
+            ..SimulationConfig::default()
+        };
+        let mut seen = Vec::new();
+        let result = run_simulation_with_hook(
+            fixed_fee_bytecode(30, 30),
+            fixed_fee_bytecode(50, 50),
+            config.clone(),
+            |observation| {
+                seen.push((observation.timestamp, observation.fees["normalizer"]));
+                observation.timestamp + 1 < 50
+            },
+        )
+        .unwrap();
+
+        assert_eq!(result.steps.len(), 50);
+        assert_eq!(seen.len(), 50);
+        assert!(seen.iter().enumerate().all(|(i, &(t, fees))| t == i as u32 && fees == (0.005, 0.005)));
+
+        // A hook that never stops matches a plain run
+        let (a, b) = (fixed_fee_bytecode(30, 30), fixed_fee_bytecode(50, 50));
+        let full = run_simulation_with_hook(a.clone(), b.clone(), config.clone(), |_| true).unwrap();
+        let plain = run_simulation(a, b, config).unwrap();
+        assert_eq!(full.steps.len(), 200);
+        assert_eq!(full.edges, plain.edges);
+    }
+
+    #[test]
+    fn test_captured_errors_flag_the_failed_simulation() {
+        // afterInitialize reverts for pools starting with more than 150 X
+        let fee = 30 * BPS as u128;
+        let runtime = Asm::new()
+            .dispatch(SELECTOR_AFTER_INITIALIZE, "init")
+            .return_pair(fee, fee)
+            .label("init")
+            .push(150 * WAD as u128)
+            .arg(0)
+            .ops(&[GT])
+            .push_label("fail")
+            .ops(&[JUMPI])
+            .return_pair(fee, fee)
+            .label("fail")
+            .revert()
+            .build();
+        let configs = [100.0, 200.0, 100.0]
+            .into_iter()
+            .enumerate()
+            .map(|(seed, initial_x)| SimulationConfig {
+                n_steps: 50,
+                initial_x,
+                seed: Some(seed as u64),
+                ..SimulationConfig::default()
+            })
+            .collect();
+        let batch_config =
+            SimulationBatchConfig::new(deploy(&runtime), fixed_fee_bytecode(30, 30), configs, 2);
+
+        assert!(matches!(
+            run_simulations_parallel(batch_config.clone()),
+            Err(SimulationError::EVMError(_))
+        ));
+
+        let batch = run_simulations_parallel(SimulationBatchConfig {
+            capture_errors: true,
+            ..batch_config
+        })
+        .unwrap();
+        assert_eq!(batch.results.len(), 3);
+        assert_eq!(batch.strategies, vec!["submission", "normalizer"]);
+        let failed = &batch.results[1];
+        assert!(failed.failed);
+        assert_eq!(failed.seed, 1);
+        assert!(failed.error.as_deref().is_some_and(|e| !e.is_empty()));
+        assert!(failed.pnl.is_empty());
+        assert_eq!(failed.failed_strategy.as_deref(), Some("submission"));
+        for result in [&batch.results[0], &batch.results[2]] {
+            assert!(!result.failed && result.error.is_none());
+            assert_eq!(result.steps.len(), 50);
+        }
+    }
+
+    #[test]
+    fn test_failing_submission_loses_instead_of_drawing() {
+        // Initializes fine, then reverts on every afterSwap
+        let fee = 30 * BPS as u128;
+        let runtime = Asm::new()
+            .dispatch(SELECTOR_AFTER_INITIALIZE, "init")
+            .revert()
+            .label("init")
+            .return_pair(fee, fee)
+            .build();
+        let configs: Vec<SimulationConfig> = (0..4)
+            .map(|seed| SimulationConfig {
+                n_steps: 50,
+                seed: Some(seed),
+                strict_strategy: true,
+                ..SimulationConfig::default()
+            })
+            .collect();
+        let undeployable = Asm::new().revert().build();
+
+        for submission in [deploy(&runtime), undeployable] {
+            let baseline = fixed_fee_bytecode(30, 30);
+            let batch_config = SimulationBatchConfig {
+                capture_errors: true,
+                ..SimulationBatchConfig::new(submission, baseline, configs.clone(), 2)
+            };
+            let batch = run_simulations_parallel(batch_config.clone()).unwrap();
+            assert_eq!(batch.strategies, vec!["submission", "normalizer"]);
+            for result in &batch.results {
+                assert!(result.failed);
+                assert_eq!(result.failed_strategy.as_deref(), Some("submission"));
+                assert_eq!(result.winner().as_deref(), Some("normalizer"));
+            }
+            assert_eq!(batch.win_counts(), (0, 4, 0));
+            assert_eq!(batch.score(ScoreScheme::Binary)["submission"], 0.0);
+            assert_eq!(batch.win_rate_ci(0.5).0, 0.0);
+
+            let summaries = run_batch_summary(batch_config).unwrap();
+            assert!(summaries.iter().all(|summary| summary.failed && summary.winner == Some(1)));
+        }
+    }
+
+    #[test]
+    fn test_shared_pool_is_reused_across_batches() {
+        let configs: Vec<SimulationConfig> = (0..3)
+            .map(|seed| SimulationConfig {
+                n_steps: 100,
+                seed: Some(seed),
+                ..SimulationConfig::default()
+            })
+            .collect();
+        let batch_config = |n_workers| SimulationBatchConfig {
+            shared_pool: true,
+            ..SimulationBatchConfig::new(
+                fixed_fee_bytecode(30, 30),
+                fixed_fee_bytecode(50, 50),
+                configs.clone(),
+                n_workers,
+            )
+        };
+
+        let first = run_simulations_parallel(batch_config(2)).unwrap();
+        let second = run_simulations_parallel(batch_config(2)).unwrap();
+        let pool = thread_pool(2, true).unwrap();
+        assert!(Arc::ptr_eq(&pool, &thread_pool(2, true).unwrap()));
+        assert!(!Arc::ptr_eq(&pool, &thread_pool(2, false).unwrap()));
+
+        // Another worker count gets its own pool, even from inside the
+        // shared one
+        let other = pool.install(|| run_simulations_parallel(batch_config(3))).unwrap();
+        for ((a, b), c) in first.results.iter().zip(&second.results).zip(&other.results) {
+            assert_eq!(a.pnl, b.pnl);
+            assert_eq!(a.pnl, c.pnl);
+            assert_eq!(a.steps, b.steps);
+        }
+    }
+
+    #[test]
+    fn test_timeout_stops_a_slow_simulation() {
+        // Burns ~150k gas on every call
+        let fee = 30 * BPS as u128;
+        let runtime = Asm::new()
+            .push(0)
+            .label("loop")
+            .push(1)
+            .ops(&[ADD, DUP1])
+            .push(5000)
+            .ops(&[GT])
+            .push_label("loop")
+            .ops(&[JUMPI, POP])
+            .return_pair(fee, fee)
+            .build();
+        let configs: Vec<SimulationConfig> = (0..2)
+            .map(|seed| SimulationConfig {
+                n_steps: 10_000,
+                // getName burns gas too
+                gas_limit_name: Some(200_000),
+                seed: Some(seed),
+                ..SimulationConfig::default()
+            })
+            .collect();
+
+        let slow = SimulationBatchConfig {
+            timeout_ms: Some(20),
+            ..SimulationBatchConfig::new(
+                deploy(&runtime),
+                fixed_fee_bytecode(30, 30),
+                configs.clone(),
+                2,
+            )
+        };
+        let batch = run_simulations_parallel(slow).unwrap();
+        assert_eq!(batch.results.len(), 2);
+        for (seed, result) in batch.results.iter().enumerate() {
+            assert!(result.timed_out && result.failed);
+            assert_eq!(result.seed, seed as u64);
+            assert_eq!(result.config.n_steps, 10_000);
+            assert!(result.error.as_deref().is_some_and(|e| e.contains("timed out")));
+            assert!(result.elapsed_micros >= 20_000);
+            assert!(result.pnl.is_empty() && result.steps.is_empty());
+        }
+
+        // A generous limit leaves a normal batch untouched
+        let fast = SimulationBatchConfig {
+            timeout_ms: Some(60_000),
+            ..SimulationBatchConfig::new(
+                fixed_fee_bytecode(30, 30),
+                fixed_fee_bytecode(50, 50),
+                configs.into_iter().map(|c| SimulationConfig { n_steps: 100, ..c }).collect(),
+                2,
+            )
+        };
+        let batch = run_simulations_parallel(fast).unwrap();
+        assert!(batch.results.iter().all(|r| !r.timed_out && !r.failed && r.steps.len() == 100));
+    }
+
+    #[test]
+    fn test_antithetic_batch_pairs_mirrored_paths() {
+        let configs = (0..2)
+            .map(|seed| SimulationConfig {
+                n_steps: 100,
+                seed: Some(seed),
+                ..SimulationConfig::default()
+            })
+            .collect();
+        let batch = run_simulations_parallel(SimulationBatchConfig {
+            antithetic: true,
+            ..SimulationBatchConfig::new(
+                fixed_fee_bytecode(30, 30),
+                fixed_fee_bytecode(50, 50),
+                configs,
+                2,
+            )
+        })
+        .unwrap();
+
+        assert_eq!(batch.results.len(), 4);
+        for pair in batch.results.chunks(2) {
+            let (plain, mirror) = (&pair[0], &pair[1]);
+            assert_eq!(plain.seed, mirror.seed);
+            assert!(!plain.config.antithetic && mirror.config.antithetic);
+            let config = &plain.config;
+            let drift = (config.gbm_mu - 0.5 * config.gbm_sigma.powi(2)) * config.gbm_dt;
+            let returns = |path: &[f64]| -> Vec<f64> {
+                path.windows(2).map(|w| (w[1] / w[0]).ln()).collect()
+            };
+            let plain_returns = returns(&plain.fair_price_path);
+            let mirror_returns = returns(&mirror.fair_price_path);
+            assert_eq!(plain_returns.len(), 100);
+            for (r, m) in plain_returns.iter().zip(&mirror_returns) {
+                assert!((r - drift + (m - drift)).abs() < 1e-9, "{r} {m}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_results_embed_the_config_they_ran_with() {
+        let variance = HyperparameterVariance {
+            retail_mean_size_min: 10.0,
+            retail_mean_size_max: 30.0,
+            vary_retail_mean_size: true,
+            retail_arrival_rate_min: 0.4,
+            retail_arrival_rate_max: 1.2,
+            vary_retail_arrival_rate: true,
+            gbm_sigma_min: 0.0005,
+            gbm_sigma_max: 0.0015,
+            vary_gbm_sigma: true,
+        };
+        let base = SimulationConfig {
+            n_steps: 100,
+            ..SimulationConfig::default()
+        };
+        let mut configs: Vec<SimulationConfig> = (0..3).map(|seed| variance.apply(&base, seed)).collect();
+        configs.push(base.clone());
+        let (sub, norm) = (fixed_fee_bytecode(30, 30), fixed_fee_bytecode(50, 50));
+        let batch = run_simulations_parallel(SimulationBatchConfig::new(
+            sub.clone(),
+            norm.clone(),
+            configs.clone(),
+            2,
+        ))
+        .unwrap();
+
+        for (result, config) in batch.results.iter().zip(&configs) {
+            assert_eq!(result.config.seed, Some(result.seed));
+            assert_eq!(result.config.gbm_sigma, config.gbm_sigma);
+            assert_eq!(result.config.retail_arrival_rate, config.retail_arrival_rate);
+            assert_eq!(result.config.retail_mean_size, config.retail_mean_size);
 
-    // Full tests require EVM bytecode - see integration tests
+            // Re-running the embedded config alone reproduces the result
+            let rerun = run_simulation(sub.clone(), norm.clone(), result.config.clone()).unwrap();
+            assert_eq!(rerun.pnl, result.pnl);
+            assert_eq!(rerun.config, result.config);
+        }
+        // Unseeded configs record the seed they defaulted to
+        assert_eq!(batch.results[3].config.seed, Some(0));
+        assert_eq!(batch.results[3].config, SimulationConfig { seed: Some(0), ..base });
+    }
 }
@@ -1,105 +1,338 @@
 //! Simulation configuration.
 
+#[cfg(feature = "python")]
+use pyo3::exceptions::{PyAttributeError, PyTypeError};
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::types::{PyDict, PyTuple};
+use serde::{Deserialize, Serialize};
+
+use crate::amm::{FeeModel, FeeUpdateFrequency, RoundingMode, DEFAULT_MIN_RESERVE};
+use crate::market::{
+    theoretical_lvr, RetailBatching, RetailCohort, RetailFlowStyle, RetailMode, RouterMode,
+    DEFAULT_MIN_ROUTED_AMOUNT,
+};
+use crate::evm::GasLimits;
+
+/// Where the baseline ("normalizer") pool's fees come from.
+///
+/// - `Strategy`: its own strategy, like the submission (the default).
+/// - `Constant`: held at the config's `baseline_fee` on both sides.
+/// - `Mirror`: set to the submission's fees at the end of every step (and
+///   at the start of the run), holding them through the next step.
+///
+/// Outside `Strategy` mode the baseline's strategy is never asked for fees
+/// after initialization, so scoring compares the submission against a
+/// controlled counterfactual.
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BaselineMode {
+    #[default]
+    Strategy,
+    Constant,
+    Mirror,
+}
 
 /// Configuration for a simulation run.
-#[pyclass]
-#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", pyclass(get_all, set_all))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SimulationConfig {
     /// Number of simulation steps
-    #[pyo3(get, set)]
     pub n_steps: u32,
 
     /// Initial fair price
-    #[pyo3(get, set)]
     pub initial_price: f64,
 
     /// Initial X reserves
-    #[pyo3(get, set)]
     pub initial_x: f64,
 
-    /// Initial Y reserves
-    #[pyo3(get, set)]
+    /// Initial Y reserves (ignored under `sync_initial_spot`)
     pub initial_y: f64,
 
     /// GBM drift (annualized)
-    #[pyo3(get, set)]
     pub gbm_mu: f64,
 
     /// GBM volatility (annualized)
-    #[pyo3(get, set)]
     pub gbm_sigma: f64,
 
     /// GBM time step
-    #[pyo3(get, set)]
     pub gbm_dt: f64,
 
     /// Retail order arrival rate (Poisson lambda)
-    #[pyo3(get, set)]
     pub retail_arrival_rate: f64,
 
     /// Mean retail order size (lognormal mean)
-    #[pyo3(get, set)]
     pub retail_mean_size: f64,
 
     /// Lognormal sigma for retail order sizes (log-space)
-    #[pyo3(get, set)]
     pub retail_size_sigma: f64,
 
     /// Probability of buy order
-    #[pyo3(get, set)]
     pub retail_buy_prob: f64,
 
+    /// Per-step multiplier on the retail arrival rate (wraps around)
+    pub retail_arrival_profile: Option<Vec<f64>>,
+
+    /// Per-step multiplier on retail order sizes (wraps around)
+    pub retail_size_profile: Option<Vec<f64>>,
+
+    /// Number of competing arbitrageurs
+    pub n_arbitrageurs: u32,
+
+    /// Gas cost (in Y) per arbitrageur; missing entries default to 0
+    pub arb_gas_costs: Vec<f64>,
+
+    /// Per-step act probability per arbitrageur; missing entries default to 1
+    pub arb_act_probs: Vec<f64>,
+
+    /// Mispricing (bps) beyond its pool's fee band each arbitrageur needs
+    /// before trading; missing entries default to 0
+    pub arb_min_edge_bps: Vec<f64>,
+
+    /// Explicit fair price per step, replayed instead of GBM when set.
+    /// Must contain at least `n_steps` prices.
+    pub price_path: Option<Vec<f64>>,
+
+    /// Per-step volatility of a second asset whose shocks are correlated
+    /// with the fair price (None = single asset). Its price feed is named
+    /// "fair_b" and starts at `initial_price`.
+    pub asset_b_sigma: Option<f64>,
+
+    /// Shock correlation between the fair price and the second asset
+    pub asset_correlation: f64,
+
+    /// Price feed each AMM is valued against, in order (submission,
+    /// normalizer); empty = both use "fair"
+    pub reference_prices: Vec<String>,
+
+    /// Allowed fee tiers (as fractions, e.g. 0.003 = 30 bps); strategy
+    /// fees snap to the nearest tier when set
+    pub fee_tiers: Option<Vec<f64>>,
+
+    /// Whether both AMMs charge fees on the input or the output token
+    pub fee_model: FeeModel,
+
+    /// Gas limit override for afterInitialize (None = default)
+    pub gas_limit_init: Option<u64>,
+
+    /// Gas limit override for afterSwap (None = default)
+    pub gas_limit_trade: Option<u64>,
+
+    /// Gas limit override for getName (None = default)
+    pub gas_limit_name: Option<u64>,
+
+    /// Abort the simulation on the first failed afterSwap call instead
+    /// of keeping stale fees
+    pub strict_strategy: bool,
+
+    /// Abort the simulation on the first strategy fee outside the fee
+    /// bounds (`fee_min`/`fee_max`, else [0, MAX_FEE]) instead of clamping
+    /// it; violations are counted in the result either way
+    pub strict_fees: bool,
+
+    /// Quote trades with integer Wad math instead of f64 (slower, but
+    /// bit-reproducible across platforms)
+    pub wad_math: bool,
+
+    /// Retain per-step detail (e.g. volume series) in the result
+    pub verbose: bool,
+
+    /// Reserve floor: trades that would leave either reserve below this
+    /// are rejected and the pool is flagged as drained
+    pub min_reserve: f64,
+
+    /// Smoothing factor in (0, 1] of the EMA oracle fed to strategies via
+    /// `setOracle(uint256)` before each afterSwap (None = no oracle)
+    pub oracle_ema_alpha: Option<f64>,
+
+    /// How retail orders within a step are routed (sequentially, as
+    /// aggregated per-side blocks, or in a seeded random order)
+    pub retail_batching: RetailBatching,
+
+    /// Tilt of the retail buy probability toward rebalancing the pools'
+    /// inventory: `buy_prob + sensitivity * (x * p - y) / (x * p + y)`,
+    /// clamped to [0, 1] (0 = no feedback)
+    pub retail_inventory_sensitivity: f64,
+
+    /// Leading steps that trade normally but are excluded from scoring:
+    /// edge, PnL and fee baselines are re-snapshotted and volumes and
+    /// per-step averages restarted once they have run. Per-step series,
+    /// strategy failure and gas counters and `adaptation_steps` still
+    /// cover the whole run
+    pub warmup_steps: u32,
+
+    /// Probability that a step has no retail arrivals at all, giving
+    /// bursty flow with quiet spells (0 = plain Poisson arrivals)
+    pub retail_quiet_prob: f64,
+
+    /// Whether strategies update fees after every trade or once per step
+    /// with the step's last trade
+    pub fee_update_frequency: FeeUpdateFrequency,
+
+    /// Size of a sandwicher's front-run relative to each retail fill it
+    /// wraps (None = no sandwicher)
+    pub sandwich_ratio: Option<f64>,
+
+    /// Cap on any single trade as a fraction of the relevant reserve;
+    /// larger trades are clamped (None = uncapped)
+    pub max_trade_fraction: Option<f64>,
+
+    /// A second retail population trading alongside the main one, with
+    /// its own arrival rate and size distribution; it shares the profiles,
+    /// quiet probability and inventory sensitivity (None = single cohort)
+    pub retail_cohort_b: Option<RetailCohort>,
+
+    /// Start both pools at the fair price: overrides `initial_y` with
+    /// `initial_x * initial_price`, so there is no arbitrage at t=0. When
+    /// false, any gap between `initial_y / initial_x` and `initial_price`
+    /// is arbitraged away on the first step
+    pub sync_initial_spot: bool,
+
+    /// How strongly retail steers away from venues that gave it worse
+    /// fills recently, per bps of excess smoothed slippage (0 = memoryless
+    /// routing)
+    pub flow_elasticity: f64,
+
+    /// Whether quotes round natively or against the trader at WAD
+    /// granularity, as on-chain pools do
+    pub rounding_mode: RoundingMode,
+
+    /// Steps by which the price arbitrageurs trade toward trails the fair
+    /// price (0 = they see it instantly); their trades still count against
+    /// each AMM's edge at the current price
+    pub arb_price_lag: u32,
+
+    /// Where the baseline pool's fees come from
+    pub baseline_mode: BaselineMode,
+
+    /// Fee (both sides) of the baseline under `BaselineMode::Constant`, and
+    /// of the normalizer `optimal_static_fee` sweeps against
+    pub baseline_fee: f64,
+
+    /// Keep pool reserves as exact Wad amounts rather than f64, so long
+    /// runs accumulate no rounding drift in the reserves
+    pub exact_reserves: bool,
+
+    /// Whether retail orders are split across pools or sent whole to the
+    /// best-priced one
+    pub router_mode: RouterMode,
+
+    /// Smallest leg of a retail order the router executes; smaller legs
+    /// are dropped as dust. Absolute, in the leg's input token, unless
+    /// `min_routed_relative` makes it a fraction of the receiving pool's
+    /// reserve of that token (for pools far from unit scale)
+    pub min_routed_amount: f64,
+    pub min_routed_relative: bool,
+
+    /// Multiplier on the (possibly lagged) price arbitrageurs trade toward,
+    /// e.g. 1.01 for a reference 1% above the fair price (1 = unbiased);
+    /// edge and PnL are still measured at the true price
+    pub arb_price_bias: f64,
+
+    /// Lowest and highest fee (as fractions) a strategy may charge; its
+    /// fees are clamped into this band instead of [0, MAX_FEE] when set.
+    /// A negative `fee_min` allows maker rebates, paid to the trader out
+    /// of the pool's accumulated fees (and so debited from its PnL)
+    pub fee_min: Option<f64>,
+    pub fee_max: Option<f64>,
+
+    /// Whether retail orders are drawn at random or every step gets the
+    /// expected flow, independent of the seed
+    pub retail_mode: RetailMode,
+
+    /// Negate the normal draws of the GBM fair price, running the mirror
+    /// image of the path the seed gives without it
+    pub antithetic: bool,
+
+    /// Expected number of arbitrage passes per pool and step (Poisson
+    /// lambda); each pass lets every arbitrageur correct what mispricing
+    /// remains. None runs exactly one pass per step
+    pub arb_arrival_rate: Option<f64>,
+
+    /// How retail order sides respond to the fair-price return over the
+    /// last `FLOW_LOOKBACK` steps, and the buy probability tilt per unit
+    /// of that log return
+    pub retail_flow_style: RetailFlowStyle,
+    pub retail_flow_sensitivity: f64,
+
+    /// Keep a per-step result for every timestep; off skips building them
+    /// when only the final scores are needed
+    pub record_steps: bool,
+
+    /// Trade size (in X) the pools are quoted at each step to measure their
+    /// two-sided spread (`avg_spread_bps`)
+    pub spread_probe_size: f64,
+
+    /// Liquidity added to (positive deltas) or withdrawn from (negative) a
+    /// pool at the start of a step, as (step, "submission" or
+    /// "normalizer", delta_x, delta_y). Capital moved this way is valued at
+    /// the step's fair price and left out of that pool's PnL
+    pub liquidity_events: Vec<(u32, String, f64, f64)>,
+
     /// Random seed for reproducibility (None = random)
-    #[pyo3(get, set)]
     pub seed: Option<u64>,
 }
 
+/// Fields `SimulationConfig(...)` also accepts positionally, in order.
+#[cfg(feature = "python")]
+const POSITIONAL_FIELDS: [&str; 12] = [
+    "n_steps",
+    "initial_price",
+    "initial_x",
+    "initial_y",
+    "gbm_mu",
+    "gbm_sigma",
+    "gbm_dt",
+    "retail_arrival_rate",
+    "retail_mean_size",
+    "retail_size_sigma",
+    "retail_buy_prob",
+    "seed",
+];
+
+#[cfg(feature = "python")]
 #[pymethods]
 impl SimulationConfig {
+    /// Start from `SimulationConfig::default()` and set each given field,
+    /// by keyword or (for `POSITIONAL_FIELDS`) by position.
     #[new]
-    #[pyo3(signature = (
-        n_steps,
-        initial_price,
-        initial_x,
-        initial_y,
-        gbm_mu,
-        gbm_sigma,
-        gbm_dt,
-        retail_arrival_rate,
-        retail_mean_size,
-        retail_size_sigma,
-        retail_buy_prob,
-        seed
-    ))]
-    pub fn new(
-        n_steps: u32,
-        initial_price: f64,
-        initial_x: f64,
-        initial_y: f64,
-        gbm_mu: f64,
-        gbm_sigma: f64,
-        gbm_dt: f64,
-        retail_arrival_rate: f64,
-        retail_mean_size: f64,
-        retail_size_sigma: f64,
-        retail_buy_prob: f64,
-        seed: Option<u64>,
-    ) -> Self {
-        Self {
-            n_steps,
-            initial_price,
-            initial_x,
-            initial_y,
-            gbm_mu,
-            gbm_sigma,
-            gbm_dt,
-            retail_arrival_rate,
-            retail_mean_size,
-            retail_size_sigma,
-            retail_buy_prob,
-            seed,
+    #[pyo3(signature = (*args, **kwargs))]
+    fn py_new(
+        py: Python<'_>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Self> {
+        if args.len() > POSITIONAL_FIELDS.len() {
+            return Err(PyTypeError::new_err(format!(
+                "SimulationConfig() takes at most {} positional arguments ({} given)",
+                POSITIONAL_FIELDS.len(),
+                args.len()
+            )));
+        }
+        let config = Bound::new(py, Self::default())?;
+        for (name, value) in POSITIONAL_FIELDS.iter().zip(args.iter()) {
+            config.setattr(*name, value)?;
+        }
+        for (key, value) in kwargs.into_iter().flatten() {
+            let name: String = key.extract()?;
+            if POSITIONAL_FIELDS[..args.len()].contains(&name.as_str()) {
+                return Err(PyTypeError::new_err(format!(
+                    "SimulationConfig() got multiple values for argument '{name}'"
+                )));
+            }
+            // Fields are the only attributes with setters
+            match config.setattr(name.as_str(), value) {
+                Err(e) if e.is_instance_of::<PyAttributeError>(py) => {
+                    return Err(PyTypeError::new_err(format!(
+                        "SimulationConfig() got an unexpected keyword argument '{name}'"
+                    )));
+                }
+                result => result?,
+            }
         }
+        let config = config.borrow().clone();
+        Ok(config)
     }
 
     fn __repr__(&self) -> String {
@@ -110,6 +343,108 @@ impl SimulationConfig {
     }
 }
 
+impl SimulationConfig {
+    /// Strategy gas limits with this config's overrides applied.
+    pub fn gas_limits(&self) -> GasLimits {
+        let defaults = GasLimits::default();
+        GasLimits {
+            init: self.gas_limit_init.unwrap_or(defaults.init),
+            trade: self.gas_limit_trade.unwrap_or(defaults.trade),
+            name: self.gas_limit_name.unwrap_or(defaults.name),
+        }
+    }
+}
+
+impl SimulationConfig {
+    /// Initial Y reserves the pools actually start with: `initial_y`, or
+    /// `initial_x * initial_price` under `sync_initial_spot`.
+    pub fn effective_initial_y(&self) -> f64 {
+        if self.sync_initial_spot {
+            self.initial_x * self.initial_price
+        } else {
+            self.initial_y
+        }
+    }
+}
+
+impl SimulationConfig {
+    /// Theoretical LVR per unit time of a constant-product pool worth
+    /// `pool_value` (in Y) under this config's fair-price volatility.
+    pub fn theoretical_lvr(&self, pool_value: f64) -> f64 {
+        theoretical_lvr(self.gbm_sigma, pool_value)
+    }
+}
+
+impl Default for SimulationConfig {
+    /// Baseline competition settings (nominal variance midpoints, no seed).
+    fn default() -> Self {
+        Self {
+            n_steps: 10_000,
+            initial_price: 100.0,
+            initial_x: 100.0,
+            initial_y: 10_000.0,
+            gbm_mu: 0.0,
+            gbm_sigma: 0.000945,
+            gbm_dt: 1.0,
+            retail_arrival_rate: 0.8,
+            retail_mean_size: 20.0,
+            retail_size_sigma: 1.2,
+            retail_buy_prob: 0.5,
+            retail_arrival_profile: None,
+            retail_size_profile: None,
+            n_arbitrageurs: 1,
+            arb_gas_costs: Vec::new(),
+            arb_act_probs: Vec::new(),
+            arb_min_edge_bps: Vec::new(),
+            price_path: None,
+            asset_b_sigma: None,
+            asset_correlation: 0.0,
+            reference_prices: Vec::new(),
+            fee_tiers: None,
+            fee_model: FeeModel::OnInput,
+            gas_limit_init: None,
+            gas_limit_trade: None,
+            gas_limit_name: None,
+            strict_strategy: false,
+            strict_fees: false,
+            wad_math: false,
+            verbose: false,
+            min_reserve: DEFAULT_MIN_RESERVE,
+            oracle_ema_alpha: None,
+            retail_batching: RetailBatching::Sequential,
+            retail_inventory_sensitivity: 0.0,
+            warmup_steps: 0,
+            retail_quiet_prob: 0.0,
+            fee_update_frequency: FeeUpdateFrequency::PerTrade,
+            sandwich_ratio: None,
+            max_trade_fraction: None,
+            retail_cohort_b: None,
+            sync_initial_spot: false,
+            flow_elasticity: 0.0,
+            rounding_mode: RoundingMode::Native,
+            arb_price_lag: 0,
+            baseline_mode: BaselineMode::Strategy,
+            baseline_fee: 0.003,
+            exact_reserves: false,
+            router_mode: RouterMode::OptimalSplit,
+            min_routed_amount: DEFAULT_MIN_ROUTED_AMOUNT,
+            min_routed_relative: false,
+            arb_price_bias: 1.0,
+            fee_min: None,
+            fee_max: None,
+            retail_mode: RetailMode::Stochastic,
+            antithetic: false,
+            arb_arrival_rate: None,
+            retail_flow_style: RetailFlowStyle::Random,
+            retail_flow_sensitivity: 50.0,
+            record_steps: true,
+            spread_probe_size: 0.1,
+            liquidity_events: Vec::new(),
+            seed: None,
+        }
+    }
+}
+
 /// Configuration for hyperparameter variance across simulations.
 #[derive(Debug, Clone)]
 pub struct HyperparameterVariance {
@@ -128,12 +463,41 @@ pub struct HyperparameterVariance {
 
 impl HyperparameterVariance {
     /// Apply variance to create a new config based on seed.
+    ///
+    /// `seed` is split into independent `param_seed` and `path_seed`
+    /// streams (see `split_seed`), so the sampled hyperparameters are not
+    /// tied to the realized price path.
     pub fn apply(&self, base: &SimulationConfig, seed: u64) -> SimulationConfig {
-        use rand::SeedableRng;
+        let (param_seed, path_seed) = Self::split_seed(seed);
+        self.apply_with_seeds(base, param_seed, path_seed)
+    }
+
+    /// Derive the `(param_seed, path_seed)` pair `apply` uses for `seed`.
+    pub fn split_seed(seed: u64) -> (u64, u64) {
         use rand::Rng;
+        use rand::SeedableRng;
         use rand_pcg::Pcg64;
 
         let mut rng = Pcg64::seed_from_u64(seed);
+        (rng.gen(), rng.gen())
+    }
+
+    /// Apply variance with hyperparameters drawn from `param_seed` and the
+    /// simulation seeded with `path_seed`.
+    ///
+    /// Fixing `param_seed` while varying `path_seed` runs the same market
+    /// parameters over different price and order paths.
+    pub fn apply_with_seeds(
+        &self,
+        base: &SimulationConfig,
+        param_seed: u64,
+        path_seed: u64,
+    ) -> SimulationConfig {
+        use rand::Rng;
+        use rand::SeedableRng;
+        use rand_pcg::Pcg64;
+
+        let mut rng = Pcg64::seed_from_u64(param_seed);
 
         let retail_mean_size = if self.vary_retail_mean_size {
             rng.gen_range(self.retail_mean_size_min..self.retail_mean_size_max)
@@ -154,18 +518,162 @@ impl HyperparameterVariance {
         };
 
         SimulationConfig {
-            n_steps: base.n_steps,
-            initial_price: base.initial_price,
-            initial_x: base.initial_x,
-            initial_y: base.initial_y,
-            gbm_mu: base.gbm_mu,
             gbm_sigma,
-            gbm_dt: base.gbm_dt,
             retail_arrival_rate,
             retail_mean_size,
-            retail_size_sigma: base.retail_size_sigma,
-            retail_buy_prob: base.retail_buy_prob,
-            seed: Some(seed),
+            seed: Some(path_seed),
+            ..base.clone()
+        }
+    }
+
+    /// Draw `n` configs by Latin hypercube sampling.
+    ///
+    /// Each varying range is cut into `n` equal strata and every stratum
+    /// is hit by exactly one config, at a uniform point within it; the
+    /// strata are matched up across parameters by independent random
+    /// permutations. Config `i` is seeded with the path seed `apply` would
+    /// use for `seed + i`.
+    pub fn latin_hypercube(&self, base: &SimulationConfig, n: usize, seed: u64) -> Vec<SimulationConfig> {
+        use rand::seq::SliceRandom;
+        use rand::Rng;
+        use rand::SeedableRng;
+        use rand_pcg::Pcg64;
+
+        let (param_seed, _) = Self::split_seed(seed);
+        let mut rng = Pcg64::seed_from_u64(param_seed);
+        let mut stratify = |vary: bool, min: f64, max: f64, fixed: f64| -> Vec<f64> {
+            if !vary {
+                return vec![fixed; n];
+            }
+            let mut strata: Vec<usize> = (0..n).collect();
+            strata.shuffle(&mut rng);
+            strata
+                .into_iter()
+                .map(|stratum| {
+                    let u = (stratum as f64 + rng.gen::<f64>()) / n as f64;
+                    min + u * (max - min)
+                })
+                .collect()
+        };
+
+        let retail_mean_size = stratify(
+            self.vary_retail_mean_size,
+            self.retail_mean_size_min,
+            self.retail_mean_size_max,
+            base.retail_mean_size,
+        );
+        let retail_arrival_rate = stratify(
+            self.vary_retail_arrival_rate,
+            self.retail_arrival_rate_min,
+            self.retail_arrival_rate_max,
+            base.retail_arrival_rate,
+        );
+        let gbm_sigma = stratify(
+            self.vary_gbm_sigma,
+            self.gbm_sigma_min,
+            self.gbm_sigma_max,
+            base.gbm_sigma,
+        );
+
+        (0..n)
+            .map(|i| SimulationConfig {
+                gbm_sigma: gbm_sigma[i],
+                retail_arrival_rate: retail_arrival_rate[i],
+                retail_mean_size: retail_mean_size[i],
+                seed: Some(Self::split_seed(seed.wrapping_add(i as u64)).1),
+                ..base.clone()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::GBMPriceProcess;
+
+    fn variance() -> HyperparameterVariance {
+        HyperparameterVariance {
+            retail_mean_size_min: 10.0,
+            retail_mean_size_max: 30.0,
+            vary_retail_mean_size: true,
+            retail_arrival_rate_min: 0.4,
+            retail_arrival_rate_max: 1.2,
+            vary_retail_arrival_rate: true,
+            gbm_sigma_min: 0.0005,
+            gbm_sigma_max: 0.0015,
+            vary_gbm_sigma: true,
+        }
+    }
+
+    fn price_path(config: &SimulationConfig) -> Vec<f64> {
+        let mut process = GBMPriceProcess::new(
+            config.initial_price,
+            config.gbm_mu,
+            config.gbm_sigma,
+            config.gbm_dt,
+            config.seed,
+        );
+        (0..50).map(|_| process.step()).collect()
+    }
+
+    #[test]
+    fn test_param_seed_and_path_seed_are_independent() {
+        let base = SimulationConfig::default();
+        let variance = variance();
+
+        let a = variance.apply_with_seeds(&base, 7, 1);
+        let b = variance.apply_with_seeds(&base, 7, 2);
+        assert_eq!(a.retail_mean_size, b.retail_mean_size);
+        assert_eq!(a.retail_arrival_rate, b.retail_arrival_rate);
+        assert_eq!(a.gbm_sigma, b.gbm_sigma);
+        assert_ne!(price_path(&a), price_path(&b));
+
+        // A different param_seed on the same path_seed resamples the params
+        let c = variance.apply_with_seeds(&base, 8, 1);
+        assert_ne!(a.gbm_sigma, c.gbm_sigma);
+        assert_eq!(c.seed, Some(1));
+
+        // `apply` goes through the split streams
+        let (param_seed, path_seed) = HyperparameterVariance::split_seed(5);
+        assert_ne!(param_seed, path_seed);
+        let applied = variance.apply(&base, 5);
+        let split = variance.apply_with_seeds(&base, param_seed, path_seed);
+        assert_eq!(applied.gbm_sigma, split.gbm_sigma);
+        assert_eq!(applied.seed, Some(path_seed));
+    }
+
+    #[test]
+    fn test_latin_hypercube_hits_every_stratum_once() {
+        let base = SimulationConfig::default();
+        let variance = HyperparameterVariance {
+            vary_retail_arrival_rate: false,
+            ..variance()
+        };
+        let n = 16;
+        let configs = variance.latin_hypercube(&base, n, 11);
+        assert_eq!(configs.len(), n);
+
+        let sizes: Vec<f64> = configs.iter().map(|c| c.retail_mean_size).collect();
+        let sigmas: Vec<f64> = configs.iter().map(|c| c.gbm_sigma).collect();
+        let ranges = [
+            (sizes, variance.retail_mean_size_min, variance.retail_mean_size_max),
+            (sigmas, variance.gbm_sigma_min, variance.gbm_sigma_max),
+        ];
+        for (values, min, max) in ranges {
+            let mut strata: Vec<usize> = values
+                .iter()
+                .map(|v| ((v - min) / (max - min) * n as f64) as usize)
+                .collect();
+            strata.sort_unstable();
+            assert_eq!(strata, (0..n).collect::<Vec<_>>());
         }
+        assert!(configs.iter().all(|c| c.retail_arrival_rate == base.retail_arrival_rate));
+
+        // Reproducible per seed, with path seeds matching `apply`
+        let again = variance.latin_hypercube(&base, n, 11);
+        assert!(configs.iter().zip(&again).all(|(a, b)| a.gbm_sigma == b.gbm_sigma));
+        assert_eq!(configs[3].seed, variance.apply(&base, 14).seed);
+        assert_ne!(variance.latin_hypercube(&base, n, 12)[0].gbm_sigma, configs[0].gbm_sigma);
     }
 }
@@ -4,8 +4,13 @@ pub mod wad;
 pub mod trade_info;
 pub mod config;
 pub mod result;
+pub mod observation;
 
 pub use wad::Wad;
 pub use trade_info::TradeInfo;
 pub use config::SimulationConfig;
-pub use result::{LightweightSimResult, LightweightStepResult, BatchSimulationResult};
+pub use result::{
+    BatchSimulationResult, ComparisonStats, FeeStats, GasBreakdown, LightweightSimResult,
+    LightweightStepResult, PairedBatchResult, ScoreScheme, StepColumns, TieBreak,
+};
+pub use observation::{StateObservation, StepObservation};
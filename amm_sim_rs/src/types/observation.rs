@@ -0,0 +1,78 @@
+//! Observation types for step-by-step simulation.
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+/// Market state right after `reset()`, before any step has run.
+#[cfg_attr(feature = "python", pyclass(get_all))]
+#[derive(Debug, Clone)]
+pub struct StateObservation {
+    /// Number of steps run so far
+    pub timestamp: u32,
+
+    /// Current fair price
+    pub fair_price: f64,
+
+    /// Reserves (reserve_x, reserve_y) by strategy name
+    pub reserves: HashMap<String, (f64, f64)>,
+
+    /// Spot prices by strategy name
+    pub spot_prices: HashMap<String, f64>,
+
+    /// Fees (bid, ask) by strategy name
+    pub fees: HashMap<String, (f64, f64)>,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl StateObservation {
+    fn __repr__(&self) -> String {
+        format!(
+            "StateObservation(timestamp={}, fair_price={:.4})",
+            self.timestamp, self.fair_price
+        )
+    }
+}
+
+/// Market state after one `step()`.
+#[cfg_attr(feature = "python", pyclass(get_all))]
+#[derive(Debug, Clone)]
+pub struct StepObservation {
+    /// Step that was just run
+    pub timestamp: u32,
+
+    /// Fair price at this step
+    pub fair_price: f64,
+
+    /// Reserves (reserve_x, reserve_y) by strategy name
+    pub reserves: HashMap<String, (f64, f64)>,
+
+    /// Spot prices by strategy name
+    pub spot_prices: HashMap<String, f64>,
+
+    /// Fees (bid, ask) by strategy name
+    pub fees: HashMap<String, (f64, f64)>,
+
+    /// Running PnL by strategy name
+    pub pnls: HashMap<String, f64>,
+
+    /// Latest signal returned alongside fees (a strategy's optional third
+    /// return word), by strategy name; strategies returning only fees are
+    /// absent
+    pub signals: HashMap<String, f64>,
+
+    /// Whether this was the last configured step
+    pub done: bool,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl StepObservation {
+    fn __repr__(&self) -> String {
+        format!(
+            "StepObservation(timestamp={}, fair_price={:.4}, done={})",
+            self.timestamp, self.fair_price, self.done
+        )
+    }
+}
@@ -1,33 +1,60 @@
 //! Simulation result types.
 
+// pyo3 0.22's generated wrappers convert every `PyResult` error into `PyErr`
+#![cfg_attr(feature = "python", allow(clippy::useless_conversion))]
+
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
-use std::collections::HashMap;
+#[cfg(feature = "python")]
+use pyo3::types::{PyBytes, PyDict};
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::types::config::SimulationConfig;
+
+/// Version of the `BatchSimulationResult::to_bytes` encoding; bumped
+/// whenever the layout of the result types changes.
+pub const RESULT_FORMAT_VERSION: u32 = 3;
+
+/// Serialize a map in key order, so equal maps encode to equal bytes
+/// whatever their iteration order.
+fn sorted_map<K: Ord + Serialize, V: Serialize, S: Serializer>(
+    map: &HashMap<K, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
 
 /// Lightweight step result for charting (minimal memory footprint).
-#[pyclass]
-#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", pyclass(get_all))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LightweightStepResult {
     /// Simulation step number
-    #[pyo3(get)]
     pub timestamp: u32,
 
     /// Fair price at this step
-    #[pyo3(get)]
     pub fair_price: f64,
 
     /// Spot prices by strategy name
-    #[pyo3(get)]
+    #[serde(serialize_with = "sorted_map")]
     pub spot_prices: HashMap<String, f64>,
 
     /// Running PnL by strategy name
-    #[pyo3(get)]
+    #[serde(serialize_with = "sorted_map")]
     pub pnls: HashMap<String, f64>,
 
     /// Fees (bid, ask) by strategy name
-    #[pyo3(get)]
+    #[serde(serialize_with = "sorted_map")]
     pub fees: HashMap<String, (f64, f64)>,
+
+    /// Reserves (reserve_x, reserve_y) by strategy name at the end of the
+    /// step; only populated when the config is verbose
+    #[serde(serialize_with = "sorted_map")]
+    pub reserves: HashMap<String, (f64, f64)>,
 }
 
+#[cfg(feature = "python")]
 #[pymethods]
 impl LightweightStepResult {
     fn __repr__(&self) -> String {
@@ -38,82 +65,369 @@ impl LightweightStepResult {
     }
 }
 
+/// Gas spent by a strategy, split by call type and trade side.
+///
+/// Figures are total gas used per EVM call (including the 21000 intrinsic
+/// cost), the same quantity the per-call gas limits are checked against.
+#[cfg_attr(feature = "python", pyclass(get_all))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct GasBreakdown {
+    /// Gas used by afterInitialize
+    pub after_initialize: u64,
+
+    /// Total gas used by afterSwap on buys (AMM buys X)
+    pub buy_swap_gas: u64,
+
+    /// Number of afterSwap calls on buys
+    pub buy_swaps: u64,
+
+    /// Total gas used by afterSwap on sells (AMM sells X)
+    pub sell_swap_gas: u64,
+
+    /// Number of afterSwap calls on sells
+    pub sell_swaps: u64,
+}
+
+impl GasBreakdown {
+    /// Record one afterSwap call.
+    pub fn record_swap(&mut self, is_buy: bool, gas_used: u64) {
+        if is_buy {
+            self.buy_swap_gas += gas_used;
+            self.buy_swaps += 1;
+        } else {
+            self.sell_swap_gas += gas_used;
+            self.sell_swaps += 1;
+        }
+    }
+}
+
+#[cfg_attr(feature = "python", pymethods)]
+impl GasBreakdown {
+    /// Average gas per buy-side afterSwap (0 if none).
+    pub fn avg_buy_swap_gas(&self) -> f64 {
+        if self.buy_swaps == 0 {
+            return 0.0;
+        }
+        self.buy_swap_gas as f64 / self.buy_swaps as f64
+    }
+
+    /// Average gas per sell-side afterSwap (0 if none).
+    pub fn avg_sell_swap_gas(&self) -> f64 {
+        if self.sell_swaps == 0 {
+            return 0.0;
+        }
+        self.sell_swap_gas as f64 / self.sell_swaps as f64
+    }
+
+    #[cfg(feature = "python")]
+    fn __repr__(&self) -> String {
+        format!(
+            "GasBreakdown(init={}, buy_avg={:.0}, sell_avg={:.0})",
+            self.after_initialize,
+            self.avg_buy_swap_gas(),
+            self.avg_sell_swap_gas()
+        )
+    }
+}
+
+/// Range and variability of a strategy's fees across the run's steps.
+#[cfg_attr(feature = "python", pyclass(get_all))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeeStats {
+    /// Lowest bid fee
+    pub bid_min: f64,
+
+    /// Highest bid fee
+    pub bid_max: f64,
+
+    /// Mean bid fee
+    pub bid_mean: f64,
+
+    /// Population standard deviation of the bid fee
+    pub bid_stddev: f64,
+
+    /// Lowest ask fee
+    pub ask_min: f64,
+
+    /// Highest ask fee
+    pub ask_max: f64,
+
+    /// Mean ask fee
+    pub ask_mean: f64,
+
+    /// Population standard deviation of the ask fee
+    pub ask_stddev: f64,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl FeeStats {
+    fn __repr__(&self) -> String {
+        format!(
+            "FeeStats(bid={:.6}±{:.6}, ask={:.6}±{:.6})",
+            self.bid_mean, self.bid_stddev, self.ask_mean, self.ask_stddev
+        )
+    }
+}
+
+/// A strategy's final PnL split into signed contributions (in Y, at the
+/// final price) that add up to it.
+///
+/// `fee_revenue`, `impermanent_loss` and `arb_loss` are each measured on
+/// their own; `residual` is what is left of the PnL after them. It is not
+/// an error term: it carries the market move of the initial tokens and,
+/// since the loss against holding them already includes what the
+/// arbitrageurs took, gives `arb_loss` back. Like the PnL, everything is
+/// measured from the end of the warmup.
+#[cfg_attr(feature = "python", pyclass(get_all))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PnlAttribution {
+    /// Value of the fees collected outside the reserves over the scored
+    /// steps
+    pub fee_revenue: f64,
+
+    /// Reserves' value against holding the initial tokens (the result's
+    /// `impermanent_loss` negated, so negative when the pool lost)
+    pub impermanent_loss: f64,
+
+    /// Arbitrageurs' profit at the fair price (the realized LVR), negated
+    pub arb_loss: f64,
+
+    /// PnL not accounted for by the other components
+    pub residual: f64,
+}
+
+impl PnlAttribution {
+    /// Sum of the components, equal to the PnL up to rounding.
+    pub fn total(&self) -> f64 {
+        self.fee_revenue + self.impermanent_loss + self.arb_loss + self.residual
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl PnlAttribution {
+    #[pyo3(name = "total")]
+    fn py_total(&self) -> f64 {
+        self.total()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "PnlAttribution(fees={:.6}, il={:.6}, arb={:.6}, residual={:.6})",
+            self.fee_revenue, self.impermanent_loss, self.arb_loss, self.residual
+        )
+    }
+}
+
 /// Lightweight simulation result for charting.
-#[pyclass]
-#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", pyclass(get_all))]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct LightweightSimResult {
     /// Seed used for this simulation
-    #[pyo3(get)]
     pub seed: u64,
 
+    /// Config the simulation ran with, seed resolved; running it again
+    /// reproduces this result
+    pub config: SimulationConfig,
+
     /// Strategy names
-    #[pyo3(get)]
     pub strategies: Vec<String>,
 
+    /// Name each strategy's contract reported via `getName()`, by strategy
+    /// name; contracts sharing a name show the strategy name instead
+    #[serde(serialize_with = "sorted_map")]
+    pub display_names: HashMap<String, String>,
+
     /// Final PnL by strategy name
-    #[pyo3(get)]
+    #[serde(serialize_with = "sorted_map")]
     pub pnl: HashMap<String, f64>,
 
     /// Edge by strategy name (sum over trades)
-    #[pyo3(get)]
+    #[serde(serialize_with = "sorted_map")]
     pub edges: HashMap<String, f64>,
 
     /// Initial fair price
-    #[pyo3(get)]
     pub initial_fair_price: f64,
 
     /// Initial reserves by strategy name: (reserve_x, reserve_y)
-    #[pyo3(get)]
+    #[serde(serialize_with = "sorted_map")]
     pub initial_reserves: HashMap<String, (f64, f64)>,
 
     /// Step results for charting
-    #[pyo3(get)]
     pub steps: Vec<LightweightStepResult>,
 
+    /// Fair price at the start and after every step, warmup included
+    pub fair_price_path: Vec<f64>,
+
+    /// Time between steps (the config's `gbm_dt`), in the units volatility
+    /// is quoted in
+    pub gbm_dt: f64,
+
     /// Total arb volume (in Y) by strategy name
-    #[pyo3(get)]
+    #[serde(serialize_with = "sorted_map")]
     pub arb_volume_y: HashMap<String, f64>,
 
+    /// Net X the arbitrageurs bought from each AMM over the run (negative
+    /// when they sold more than they bought), by strategy name
+    #[serde(serialize_with = "sorted_map")]
+    pub arb_net_x: HashMap<String, f64>,
+
     /// Total retail volume (in Y) by strategy name
-    #[pyo3(get)]
+    #[serde(serialize_with = "sorted_map")]
     pub retail_volume_y: HashMap<String, f64>,
 
+    /// Average cost of a routed retail trade versus the fair price, in bps
+    /// (fees plus price impact), by strategy name; 0 without retail trades
+    #[serde(serialize_with = "sorted_map")]
+    pub retail_slippage_bps: HashMap<String, f64>,
+
+    /// Total profit (in Y) a sandwicher extracted around retail fills, by
+    /// strategy name; 0 when sandwiching is disabled
+    #[serde(serialize_with = "sorted_map")]
+    pub sandwich_profit: HashMap<String, f64>,
+
     /// Average fees (bid, ask) by strategy name over the simulation
-    #[pyo3(get)]
+    #[serde(serialize_with = "sorted_map")]
     pub average_fees: HashMap<String, (f64, f64)>,
+
+    /// Average per-step `|bid - ask|` by strategy name: 0 for strategies
+    /// that always quote symmetric fees, larger for directional ones
+    #[serde(serialize_with = "sorted_map")]
+    pub fee_asymmetry: HashMap<String, f64>,
+
+    /// Min, max, mean and standard deviation of the per-step bid and ask
+    /// fees by strategy name
+    #[serde(serialize_with = "sorted_map")]
+    pub fee_stats: HashMap<String, FeeStats>,
+
+    /// Step at which each strategy's fees settled: the first step from
+    /// which its bid and ask each stayed within `ADAPTATION_BAND` for
+    /// `ADAPTATION_WINDOW` steps (see the engine); the number of steps run
+    /// if they never did
+    #[serde(serialize_with = "sorted_map")]
+    pub adaptation_steps: HashMap<String, u32>,
+
+    /// Loss-versus-rebalancing realized by the arbitrageurs: their profit
+    /// at the fair price, gross of gas, by strategy name
+    #[serde(serialize_with = "sorted_map")]
+    pub realized_lvr: HashMap<String, f64>,
+
+    /// Closed-form LVR benchmark, `sigma^2 / 8` times each step's pool value
+    /// times `gbm_dt`, summed over the scored steps by strategy name; it
+    /// uses the configured volatility even when a price path is replayed
+    #[serde(serialize_with = "sorted_map")]
+    pub theoretical_lvr: HashMap<String, f64>,
+
+    /// RMS relative deviation of spot from fair price, `(spot - fair) / fair`,
+    /// over all steps by strategy name
+    #[serde(serialize_with = "sorted_map")]
+    pub tracking_error: HashMap<String, f64>,
+
+    /// Average two-sided spread, in bps of spot, each pool quoted for a
+    /// `spread_probe_size` trade at the end of every step: the buy price
+    /// minus the sell price, fees and price impact included; steps where a
+    /// side would be rejected are skipped
+    #[serde(serialize_with = "sorted_map")]
+    pub avg_spread_bps: HashMap<String, f64>,
+
+    /// Failed afterSwap calls (revert, out of gas, bad return) by strategy name
+    #[serde(serialize_with = "sorted_map")]
+    pub swap_failures: HashMap<String, u64>,
+
+    /// Strategy fee quotes with a fee outside the pool's fee bounds, by
+    /// strategy name
+    #[serde(serialize_with = "sorted_map")]
+    pub fee_violations: HashMap<String, u64>,
+
+    /// Strategy gas usage by strategy name
+    #[serde(serialize_with = "sorted_map")]
+    pub gas: HashMap<String, GasBreakdown>,
+
+    /// Impermanent loss vs holding the initial tokens, at the final price and
+    /// excluding fees, by strategy name
+    #[serde(serialize_with = "sorted_map")]
+    pub impermanent_loss: HashMap<String, f64>,
+
+    /// Fees collected outside the reserves over the scored steps (warmup
+    /// fees excluded), (fees_x, fees_y) by strategy name
+    #[serde(serialize_with = "sorted_map")]
+    pub accumulated_fees: HashMap<String, (f64, f64)>,
+
+    /// Value of the accumulated fees at the final price over the absolute
+    /// impermanent loss, by strategy name; above 1 the fees covered the IL.
+    /// Infinite when fees were earned without any IL, 0 with neither
+    #[serde(serialize_with = "sorted_map")]
+    pub fee_il_ratio: HashMap<String, f64>,
+
+    /// Final PnL split into fees, impermanent loss, arbitrage loss and the
+    /// market move, by strategy name
+    #[serde(serialize_with = "sorted_map")]
+    pub pnl_attribution: HashMap<String, PnlAttribution>,
+
+    /// Whether a trade was rejected for hitting the reserve floor, by
+    /// strategy name
+    #[serde(serialize_with = "sorted_map")]
+    pub drained: HashMap<String, bool>,
+
+    /// Per-step Y volume (arb + retail) by strategy name; only populated
+    /// when the config is verbose
+    #[serde(serialize_with = "sorted_map")]
+    pub volume_series: HashMap<String, Vec<f64>>,
+
+    /// Wall-clock time spent in the simulation loop (microseconds)
+    pub elapsed_micros: u64,
+
+    /// Whether the simulation errored instead of finishing; only batches
+    /// run with `capture_errors` return failed results, which carry the
+    /// seed, config, strategies, `error` and `failed_strategy` and leave
+    /// every other field empty
+    pub failed: bool,
+
+    /// Why the simulation failed (None when it finished)
+    pub error: Option<String>,
+
+    /// Name of the strategy whose deployment or call failed the
+    /// simulation; it loses the simulation. None when the simulation
+    /// finished or failed for another reason (an invalid config, a
+    /// timeout), in which case it is left out of win counts and scores
+    pub failed_strategy: Option<String>,
+
+    /// Whether the simulation was stopped for running past its batch's
+    /// `timeout_ms`; timed-out results are also `failed`
+    pub timed_out: bool,
 }
 
-#[pymethods]
+#[cfg_attr(feature = "python", pymethods)]
 impl LightweightSimResult {
-    /// Get the winner of this simulation.
-    fn winner(&self) -> Option<String> {
-        let names: Vec<_> = self.strategies.iter().collect();
-        if names.len() != 2 {
-            return None;
-        }
+    /// Get the winner of this simulation (None = draw).
+    ///
+    /// See `LightweightSimResult::winner_by`.
+    #[cfg(feature = "python")]
+    #[pyo3(name = "winner", signature = (tie_break = TieBreak::Draw))]
+    fn py_winner(&self, tie_break: TieBreak) -> Option<String> {
+        self.winner_by(tie_break)
+    }
 
-        let pnl_a = self.pnl.get(names[0]).copied().unwrap_or(0.0);
-        let pnl_b = self.pnl.get(names[1]).copied().unwrap_or(0.0);
-        let edge_a = self
-            .edges
-            .get(names[0])
-            .copied()
-            .unwrap_or(pnl_a);
-        let edge_b = self
-            .edges
-            .get(names[1])
-            .copied()
-            .unwrap_or(pnl_b);
-
-        if edge_a > edge_b {
-            Some(names[0].clone())
-        } else if edge_b > edge_a {
-            Some(names[1].clone())
-        } else {
-            None // Draw
+    /// Volatility of the fair price per unit time: the sample standard
+    /// deviation of its per-step log-returns over `sqrt(gbm_dt)` (0 with
+    /// fewer than two returns).
+    pub fn realized_volatility(&self) -> f64 {
+        let returns: Vec<f64> = self
+            .fair_price_path
+            .windows(2)
+            .map(|pair| (pair[1] / pair[0]).ln())
+            .collect();
+        if returns.len() < 2 || self.gbm_dt <= 0.0 {
+            return 0.0;
         }
+        let n = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / n;
+        let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        (variance / self.gbm_dt).sqrt()
     }
 
+    #[cfg(feature = "python")]
     fn __repr__(&self) -> String {
         format!(
             "LightweightSimResult(seed={}, pnl={:?})",
@@ -122,62 +436,156 @@ impl LightweightSimResult {
     }
 }
 
+impl LightweightSimResult {
+    /// Get the winner of this simulation by edge; equal edges are a draw.
+    pub fn winner(&self) -> Option<String> {
+        self.winner_by(TieBreak::Draw)
+    }
+
+    /// Get the winner of this simulation by edge, breaking exact ties
+    /// with `tie_break`. Returns None on a draw, or if the simulation
+    /// failed without a strategy to blame.
+    pub fn winner_by(&self, tie_break: TieBreak) -> Option<String> {
+        if self.strategies.len() != 2 {
+            return None;
+        }
+        let (name_a, name_b) = (&self.strategies[0], &self.strategies[1]);
+        self.compare(name_a, name_b, tie_break)
+            .map(|a_wins| if a_wins { name_a.clone() } else { name_b.clone() })
+    }
+
+    /// Edge of a strategy, falling back to its PnL (0 if unknown).
+    fn edge_or_pnl(&self, name: &str) -> f64 {
+        let pnl = self.pnl.get(name).copied().unwrap_or(0.0);
+        self.edges.get(name).copied().unwrap_or(pnl)
+    }
+
+    /// Whether the simulation counts towards win counts and scores: it
+    /// finished, or failed because of one of its strategies.
+    fn is_scored(&self) -> bool {
+        !self.failed || self.failed_strategy.is_some()
+    }
+
+    /// Whether `name_a` beats `name_b` (None = draw).
+    ///
+    /// A strategy that failed the simulation loses it.
+    fn compare(&self, name_a: &str, name_b: &str, tie_break: TieBreak) -> Option<bool> {
+        if let Some(failed) = self.failed_strategy.as_deref() {
+            if failed == name_a || failed == name_b {
+                return Some(failed == name_b);
+            }
+        }
+        if self.failed {
+            return None;
+        }
+        let pnl_a = self.pnl.get(name_a).copied().unwrap_or(0.0);
+        let pnl_b = self.pnl.get(name_b).copied().unwrap_or(0.0);
+        let edge_a = self.edge_or_pnl(name_a);
+        let edge_b = self.edge_or_pnl(name_b);
+
+        // Higher score wins
+        let (score_a, score_b) = if edge_a != edge_b {
+            (edge_a, edge_b)
+        } else {
+            match tie_break {
+                TieBreak::Draw => return None,
+                TieBreak::Pnl => (pnl_a, pnl_b),
+                TieBreak::LowerFee => (-self.mean_fee(name_a), -self.mean_fee(name_b)),
+                TieBreak::LowerGas => (-self.total_gas(name_a), -self.total_gas(name_b)),
+            }
+        };
+
+        if score_a > score_b {
+            Some(true)
+        } else if score_b > score_a {
+            Some(false)
+        } else {
+            None // Draw
+        }
+    }
+
+    /// Mean of the average bid and ask fee for a strategy.
+    fn mean_fee(&self, name: &str) -> f64 {
+        self.average_fees
+            .get(name)
+            .map_or(0.0, |(bid, ask)| (bid + ask) / 2.0)
+    }
+
+    /// Total strategy gas for a strategy.
+    fn total_gas(&self, name: &str) -> f64 {
+        self.gas.get(name).map_or(0.0, |gas| {
+            (gas.after_initialize + gas.buy_swap_gas + gas.sell_swap_gas) as f64
+        })
+    }
+}
+
+/// How to settle a simulation whose edges are exactly equal.
+///
+/// - `Draw`: the simulation is a draw (the default).
+/// - `Pnl`: higher total PnL wins.
+/// - `LowerFee`: lower average fee wins.
+/// - `LowerGas`: lower total strategy gas wins.
+///
+/// If the tie-break values are also equal the simulation is a draw.
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TieBreak {
+    #[default]
+    Draw,
+    Pnl,
+    LowerFee,
+    LowerGas,
+}
+
+/// How `BatchSimulationResult::score` awards points for a simulation.
+///
+/// - `Binary`: 1 point for a win, half a point each for a draw (the
+///   default, matching `win_counts`).
+/// - `Margin`: the winner gets the edge difference in points.
+/// - `LogMargin`: the winner gets `ln(1 + edge difference)` points, so
+///   blowouts count for more than narrow wins but with diminishing returns.
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScoreScheme {
+    #[default]
+    Binary,
+    Margin,
+    LogMargin,
+}
+
 /// Batch result containing all simulation results.
-#[pyclass]
-#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", pyclass(get_all))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BatchSimulationResult {
     /// Individual simulation results
-    #[pyo3(get)]
     pub results: Vec<LightweightSimResult>,
 
     /// Strategy names
-    #[pyo3(get)]
     pub strategies: Vec<String>,
 }
 
-#[pymethods]
+#[cfg_attr(feature = "python", pymethods)]
 impl BatchSimulationResult {
     /// Get win counts: (wins_a, wins_b, draws)
-    fn win_counts(&self) -> (u32, u32, u32) {
-        if self.strategies.len() != 2 {
-            return (0, 0, 0);
-        }
-
-        let name_a = &self.strategies[0];
-        let name_b = &self.strategies[1];
-
-        let mut wins_a = 0u32;
-        let mut wins_b = 0u32;
-        let mut draws = 0u32;
-
-        for result in &self.results {
-            let pnl_a = result.pnl.get(name_a).copied().unwrap_or(0.0);
-            let pnl_b = result.pnl.get(name_b).copied().unwrap_or(0.0);
-            let edge_a = result
-                .edges
-                .get(name_a)
-                .copied()
-                .unwrap_or(pnl_a);
-            let edge_b = result
-                .edges
-                .get(name_b)
-                .copied()
-                .unwrap_or(pnl_b);
-
-            if edge_a > edge_b {
-                wins_a += 1;
-            } else if edge_b > edge_a {
-                wins_b += 1;
-            } else {
-                draws += 1;
-            }
-        }
+    ///
+    /// See `BatchSimulationResult::win_counts_by`.
+    #[cfg(feature = "python")]
+    #[pyo3(name = "win_counts", signature = (tie_break = TieBreak::Draw))]
+    fn py_win_counts(&self, tie_break: TieBreak) -> (u32, u32, u32) {
+        self.win_counts_by(tie_break)
+    }
 
-        (wins_a, wins_b, draws)
+    /// Total points per strategy name under a scoring scheme.
+    ///
+    /// See `BatchSimulationResult::score`.
+    #[cfg(feature = "python")]
+    #[pyo3(name = "score", signature = (scheme = ScoreScheme::Binary))]
+    fn py_score(&self, scheme: ScoreScheme) -> HashMap<String, f64> {
+        self.score(scheme)
     }
 
     /// Get total PnL: (total_pnl_a, total_pnl_b)
-    fn total_pnl(&self) -> (f64, f64) {
+    pub fn total_pnl(&self) -> (f64, f64) {
         if self.strategies.len() != 2 {
             return (0.0, 0.0);
         }
@@ -196,8 +604,35 @@ impl BatchSimulationResult {
         (total_a, total_b)
     }
 
+    /// Win rate of strategy A with a Wilson 95% confidence interval.
+    ///
+    /// See `BatchSimulationResult::win_rate_ci`.
+    #[cfg(feature = "python")]
+    #[pyo3(name = "win_rate_ci", signature = (draw_weight = 0.5))]
+    fn py_win_rate_ci(&self, draw_weight: f64) -> (f64, f64, f64) {
+        self.win_rate_ci(draw_weight)
+    }
+
+    /// Get total wall-clock time across all simulations (microseconds).
+    pub fn total_elapsed_micros(&self) -> u64 {
+        self.results.iter().map(|r| r.elapsed_micros).sum()
+    }
+
+    /// Get the `n` slowest simulations as `(index, elapsed_micros)`, slowest first.
+    pub fn slowest_n(&self, n: usize) -> Vec<(usize, u64)> {
+        let mut timings: Vec<(usize, u64)> = self
+            .results
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (i, r.elapsed_micros))
+            .collect();
+        timings.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        timings.truncate(n);
+        timings
+    }
+
     /// Get the overall winner based on win count.
-    fn overall_winner(&self) -> Option<String> {
+    pub fn overall_winner(&self) -> Option<String> {
         let (wins_a, wins_b, _) = self.win_counts();
         if wins_a > wins_b {
             Some(self.strategies[0].clone())
@@ -208,6 +643,7 @@ impl BatchSimulationResult {
         }
     }
 
+    #[cfg(feature = "python")]
     fn __repr__(&self) -> String {
         let (wins_a, wins_b, draws) = self.win_counts();
         format!(
@@ -216,7 +652,655 @@ impl BatchSimulationResult {
         )
     }
 
+    /// Flatten every simulation's steps into columns.
+    ///
+    /// Returns a dict of `array.array` buffers (`"I"` for `sim` and
+    /// `timestamp`, `"d"` otherwise), one entry per step across the batch:
+    /// `sim`, `timestamp`, `fair_price`, and `spot_<name>`, `pnl_<name>`,
+    /// `bid_fee_<name>`, `ask_fee_<name>` per strategy. Wrap them with
+    /// `numpy.frombuffer` for zero-copy arrays.
+    #[cfg(feature = "python")]
+    #[pyo3(name = "to_columns")]
+    fn py_to_columns<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let array = py.import_bound("array")?.getattr("array")?;
+        let to_array = |typecode: &str, bytes: Vec<u8>| -> PyResult<Bound<'py, PyAny>> {
+            let buffer = array.call1((typecode,))?;
+            buffer.call_method1("frombytes", (PyBytes::new_bound(py, &bytes),))?;
+            Ok(buffer)
+        };
+        let u32_bytes = |values: &[u32]| values.iter().flat_map(|v| v.to_ne_bytes()).collect();
+        let f64_bytes = |values: &[f64]| values.iter().flat_map(|v| v.to_ne_bytes()).collect();
+
+        let columns = self.to_columns();
+        let dict = PyDict::new_bound(py);
+        dict.set_item("sim", to_array("I", u32_bytes(&columns.sim))?)?;
+        dict.set_item("timestamp", to_array("I", u32_bytes(&columns.timestamp))?)?;
+        dict.set_item("fair_price", to_array("d", f64_bytes(&columns.fair_price))?)?;
+        for (prefix, series) in [
+            ("spot", &columns.spot_prices),
+            ("pnl", &columns.pnls),
+            ("bid_fee", &columns.bid_fees),
+            ("ask_fee", &columns.ask_fees),
+        ] {
+            for (name, values) in series {
+                dict.set_item(format!("{prefix}_{name}"), to_array("d", f64_bytes(values))?)?;
+            }
+        }
+        Ok(dict)
+    }
+
+    #[cfg(feature = "python")]
     fn __len__(&self) -> usize {
         self.results.len()
     }
+
+    /// Encode the batch compactly (see `BatchSimulationResult::to_bytes`).
+    #[cfg(feature = "python")]
+    #[pyo3(name = "to_bytes")]
+    fn py_to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, &self.to_bytes())
+    }
+
+    /// Decode bytes written by `to_bytes`.
+    #[cfg(feature = "python")]
+    #[staticmethod]
+    #[pyo3(name = "from_bytes")]
+    fn py_from_bytes(bytes: &[u8]) -> PyResult<Self> {
+        Self::from_bytes(bytes).map_err(pyo3::exceptions::PyValueError::new_err)
+    }
+}
+
+/// A batch's step data flattened into contiguous columns, one row per
+/// step of every simulation in batch order.
+///
+/// Per-strategy columns are keyed by strategy name; a step missing a
+/// strategy holds NaN.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StepColumns {
+    /// Index of the simulation within the batch
+    pub sim: Vec<u32>,
+
+    /// Step number within the simulation
+    pub timestamp: Vec<u32>,
+
+    /// Fair price
+    pub fair_price: Vec<f64>,
+
+    /// Spot prices by strategy name
+    pub spot_prices: HashMap<String, Vec<f64>>,
+
+    /// Running PnL by strategy name
+    pub pnls: HashMap<String, Vec<f64>>,
+
+    /// Bid fees by strategy name
+    pub bid_fees: HashMap<String, Vec<f64>>,
+
+    /// Ask fees by strategy name
+    pub ask_fees: HashMap<String, Vec<f64>>,
+}
+
+impl BatchSimulationResult {
+    /// Encode the batch as `RESULT_FORMAT_VERSION` followed by a bincode
+    /// body, for archiving or content-addressed caching.
+    ///
+    /// Maps are written in key order, so equal batches always encode to
+    /// identical bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = RESULT_FORMAT_VERSION.to_le_bytes().to_vec();
+        // Plain data into a Vec: encoding cannot fail
+        bincode::serialize_into(&mut bytes, self).expect("result encoding failed");
+        bytes
+    }
+
+    /// Decode bytes written by `to_bytes` with the same format version.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let Some((version, body)) = bytes.split_first_chunk::<4>() else {
+            return Err("truncated header".into());
+        };
+        let version = u32::from_le_bytes(*version);
+        if version != RESULT_FORMAT_VERSION {
+            return Err(format!(
+                "result format version {} (expected {})",
+                version, RESULT_FORMAT_VERSION
+            ));
+        }
+        bincode::deserialize(body).map_err(|e| e.to_string())
+    }
+
+    /// Flatten every simulation's steps into columns (see `StepColumns`).
+    pub fn to_columns(&self) -> StepColumns {
+        let n_rows: usize = self.results.iter().map(|result| result.steps.len()).sum();
+        let per_strategy = || -> HashMap<String, Vec<f64>> {
+            self.strategies
+                .iter()
+                .map(|name| (name.clone(), Vec::with_capacity(n_rows)))
+                .collect()
+        };
+        let mut columns = StepColumns {
+            sim: Vec::with_capacity(n_rows),
+            timestamp: Vec::with_capacity(n_rows),
+            fair_price: Vec::with_capacity(n_rows),
+            spot_prices: per_strategy(),
+            pnls: per_strategy(),
+            bid_fees: per_strategy(),
+            ask_fees: per_strategy(),
+        };
+
+        for (sim, result) in self.results.iter().enumerate() {
+            for step in &result.steps {
+                columns.sim.push(sim as u32);
+                columns.timestamp.push(step.timestamp);
+                columns.fair_price.push(step.fair_price);
+                for name in &self.strategies {
+                    let fees = step.fees.get(name);
+                    let value =
+                        |map: &HashMap<String, f64>| map.get(name).copied().unwrap_or(f64::NAN);
+                    columns.spot_prices.get_mut(name).unwrap().push(value(&step.spot_prices));
+                    columns.pnls.get_mut(name).unwrap().push(value(&step.pnls));
+                    columns.bid_fees.get_mut(name).unwrap().push(fees.map_or(f64::NAN, |f| f.0));
+                    columns.ask_fees.get_mut(name).unwrap().push(fees.map_or(f64::NAN, |f| f.1));
+                }
+            }
+        }
+        columns
+    }
+
+    /// Get win counts: (wins_a, wins_b, draws), with equal edges as draws.
+    pub fn win_counts(&self) -> (u32, u32, u32) {
+        self.win_counts_by(TieBreak::Draw)
+    }
+
+    /// Get win counts: (wins_a, wins_b, draws), breaking exact edge ties
+    /// with `tie_break`.
+    pub fn win_counts_by(&self, tie_break: TieBreak) -> (u32, u32, u32) {
+        if self.strategies.len() != 2 {
+            return (0, 0, 0);
+        }
+
+        let name_a = &self.strategies[0];
+        let name_b = &self.strategies[1];
+
+        let mut wins_a = 0u32;
+        let mut wins_b = 0u32;
+        let mut draws = 0u32;
+
+        for result in self.results.iter().filter(|result| result.is_scored()) {
+            match result.compare(name_a, name_b, tie_break) {
+                Some(true) => wins_a += 1,
+                Some(false) => wins_b += 1,
+                None => draws += 1,
+            }
+        }
+
+        (wins_a, wins_b, draws)
+    }
+
+    /// Total points per strategy name, with each simulation's winner (by
+    /// edge, equal edges a draw) scored per `scheme`.
+    ///
+    /// A simulation failed by one strategy goes to the other, worth a
+    /// point under `Binary` and nothing under the margin schemes, which
+    /// have no edges to compare.
+    ///
+    /// Empty unless the batch has exactly two strategies.
+    pub fn score(&self, scheme: ScoreScheme) -> HashMap<String, f64> {
+        if self.strategies.len() != 2 {
+            return HashMap::new();
+        }
+
+        let name_a = &self.strategies[0];
+        let name_b = &self.strategies[1];
+
+        let mut points_a = 0.0f64;
+        let mut points_b = 0.0f64;
+
+        for result in self.results.iter().filter(|result| result.is_scored()) {
+            let margin = (result.edge_or_pnl(name_a) - result.edge_or_pnl(name_b)).abs();
+            let points = match scheme {
+                ScoreScheme::Binary => 1.0,
+                ScoreScheme::Margin => margin,
+                ScoreScheme::LogMargin => margin.ln_1p(),
+            };
+            match result.compare(name_a, name_b, TieBreak::Draw) {
+                Some(true) => points_a += points,
+                Some(false) => points_b += points,
+                None if scheme == ScoreScheme::Binary => {
+                    points_a += 0.5;
+                    points_b += 0.5;
+                }
+                None => {}
+            }
+        }
+
+        HashMap::from([(name_a.clone(), points_a), (name_b.clone(), points_b)])
+    }
+
+    /// Win rate of strategy A with a Wilson 95% confidence interval.
+    ///
+    /// Draws count as `draw_weight` of a win (0.5 = half-win, 0 = loss).
+    /// Returns `(estimate, low, high)`; all zero for an empty batch.
+    pub fn win_rate_ci(&self, draw_weight: f64) -> (f64, f64, f64) {
+        let (wins_a, wins_b, draws) = self.win_counts();
+        let n = (wins_a + wins_b + draws) as f64;
+        if n == 0.0 {
+            return (0.0, 0.0, 0.0);
+        }
+
+        let draw_weight = draw_weight.clamp(0.0, 1.0);
+        let p = (wins_a as f64 + draw_weight * draws as f64) / n;
+        let (low, high) = wilson_interval(p, n, WILSON_Z_95);
+        (p, low, high)
+    }
+}
+
+/// Two submissions each run against the same baseline on identical
+/// configs, so their per-config scores are paired (common random numbers).
+#[cfg_attr(feature = "python", pyclass(get_all))]
+#[derive(Debug, Clone)]
+pub struct PairedBatchResult {
+    /// Submission A against the baseline
+    pub a: BatchSimulationResult,
+
+    /// Submission B against the baseline
+    pub b: BatchSimulationResult,
+
+    /// Per-config submission edge of A minus that of B
+    pub edge_diffs: Vec<f64>,
+
+    /// Per-config submission PnL of A minus that of B
+    pub pnl_diffs: Vec<f64>,
+}
+
+#[cfg_attr(feature = "python", pymethods)]
+impl PairedBatchResult {
+    /// Mean paired edge difference (A - B) and its standard error.
+    pub fn mean_edge_diff(&self) -> (f64, f64) {
+        mean_and_std_error(&self.edge_diffs)
+    }
+
+    /// Mean paired PnL difference (A - B) and its standard error.
+    pub fn mean_pnl_diff(&self) -> (f64, f64) {
+        mean_and_std_error(&self.pnl_diffs)
+    }
+
+    #[cfg(feature = "python")]
+    fn __repr__(&self) -> String {
+        let (mean, std_error) = self.mean_edge_diff();
+        format!(
+            "PairedBatchResult(n={}, edge_diff={:.6} ± {:.6})",
+            self.edge_diffs.len(), mean, std_error
+        )
+    }
+}
+
+impl PairedBatchResult {
+    /// Pair up two batches run on the same configs, comparing the first
+    /// (submission) strategy of each.
+    pub fn new(a: BatchSimulationResult, b: BatchSimulationResult) -> Self {
+        let diffs = |scores: fn(&LightweightSimResult) -> &HashMap<String, f64>| -> Vec<f64> {
+            let score = |result, name: &String| scores(result).get(name).copied().unwrap_or(0.0);
+            a.results
+                .iter()
+                .zip(&b.results)
+                .map(|(result_a, result_b)| {
+                    score(result_a, &a.strategies[0]) - score(result_b, &b.strategies[0])
+                })
+                .collect()
+        };
+        let edge_diffs = diffs(|result| &result.edges);
+        let pnl_diffs = diffs(|result| &result.pnl);
+        Self { a, b, edge_diffs, pnl_diffs }
+    }
+}
+
+/// Paired t-test of one batch's submission edges against another's.
+#[cfg_attr(feature = "python", pyclass(get_all))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComparisonStats {
+    /// Number of paired simulations
+    pub n: usize,
+
+    /// Mean per-simulation edge difference (A - B)
+    pub mean_diff: f64,
+
+    /// Standard error of the mean difference
+    pub std_error: f64,
+
+    /// t-statistic, `mean_diff / std_error`
+    pub t_stat: f64,
+
+    /// Two-sided p-value under a t-distribution with `n - 1` degrees of
+    /// freedom
+    pub p_value: f64,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl ComparisonStats {
+    fn __repr__(&self) -> String {
+        format!(
+            "ComparisonStats(n={}, mean_diff={:.6}, t={:.3}, p={:.4})",
+            self.n, self.mean_diff, self.t_stat, self.p_value
+        )
+    }
+}
+
+/// Headline scores of one simulation, without its per-step or per-pool
+/// detail, for batches too large to keep full results.
+#[cfg_attr(feature = "python", pyclass(get_all))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimulationSummary {
+    /// Seed used for this simulation
+    pub seed: u64,
+
+    /// Final PnL of the submission
+    pub pnl_submission: f64,
+
+    /// Final PnL of the baseline
+    pub pnl_baseline: f64,
+
+    /// Edge of the submission
+    pub edge_submission: f64,
+
+    /// Edge of the baseline
+    pub edge_baseline: f64,
+
+    /// Index of the winner (0 = submission, 1 = baseline); None on a draw
+    /// or a failure no strategy is blamed for
+    pub winner: Option<usize>,
+
+    /// Whether the simulation failed (see `LightweightSimResult::failed`)
+    pub failed: bool,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl SimulationSummary {
+    fn __repr__(&self) -> String {
+        format!(
+            "SimulationSummary(seed={}, edge_submission={:.4}, edge_baseline={:.4})",
+            self.seed, self.edge_submission, self.edge_baseline
+        )
+    }
+}
+
+impl From<&LightweightSimResult> for SimulationSummary {
+    fn from(result: &LightweightSimResult) -> Self {
+        let score = |scores: &HashMap<String, f64>, index: usize| {
+            result
+                .strategies
+                .get(index)
+                .and_then(|name| scores.get(name))
+                .copied()
+                .unwrap_or(0.0)
+        };
+        let winner = result
+            .winner()
+            .and_then(|name| result.strategies.iter().position(|s| *s == name));
+        Self {
+            seed: result.seed,
+            pnl_submission: score(&result.pnl, 0),
+            pnl_baseline: score(&result.pnl, 1),
+            edge_submission: score(&result.edges, 0),
+            edge_baseline: score(&result.edges, 1),
+            winner,
+            failed: result.failed,
+        }
+    }
+}
+
+/// Sample mean and standard error of the mean; the error is zero with
+/// fewer than two samples.
+pub(crate) fn mean_and_std_error(samples: &[f64]) -> (f64, f64) {
+    let n = samples.len() as f64;
+    if samples.is_empty() {
+        return (0.0, 0.0);
+    }
+    let mean = samples.iter().sum::<f64>() / n;
+    if samples.len() < 2 {
+        return (mean, 0.0);
+    }
+    let variance = samples.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / (n - 1.0);
+    (mean, (variance / n).sqrt())
+}
+
+/// Two-sided 95% normal quantile.
+const WILSON_Z_95: f64 = 1.959_963_984_540_054;
+
+/// Wilson score interval for a proportion `p` observed over `n` trials.
+fn wilson_interval(p: f64, n: f64, z: f64) -> (f64, f64) {
+    let z2 = z * z;
+    let denominator = 1.0 + z2 / n;
+    let center = (p + z2 / (2.0 * n)) / denominator;
+    let margin = z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / denominator;
+    ((center - margin).max(0.0), (center + margin).min(1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch_with_timings(timings: &[u64]) -> BatchSimulationResult {
+        let results = timings
+            .iter()
+            .enumerate()
+            .map(|(i, &elapsed_micros)| LightweightSimResult {
+                seed: i as u64,
+                elapsed_micros,
+                ..Default::default()
+            })
+            .collect();
+        BatchSimulationResult {
+            results,
+            strategies: vec!["submission".into(), "normalizer".into()],
+        }
+    }
+
+    #[test]
+    fn test_timing_helpers() {
+        let batch = batch_with_timings(&[30, 120, 5, 120, 60]);
+        assert_eq!(batch.total_elapsed_micros(), 335);
+        assert_eq!(batch.slowest_n(3), vec![(1, 120), (3, 120), (4, 60)]);
+        assert_eq!(batch.slowest_n(10).len(), 5);
+    }
+
+    /// Batch where strategy A's edge beats, loses to, or ties B as given.
+    fn batch_with_outcomes(wins: usize, losses: usize, draws: usize) -> BatchSimulationResult {
+        let outcome = |edge_a: f64| LightweightSimResult {
+            edges: HashMap::from([("submission".into(), edge_a), ("normalizer".into(), 0.0)]),
+            ..Default::default()
+        };
+        let results = std::iter::repeat_with(|| outcome(1.0))
+            .take(wins)
+            .chain(std::iter::repeat_with(|| outcome(-1.0)).take(losses))
+            .chain(std::iter::repeat_with(|| outcome(0.0)).take(draws))
+            .collect();
+        BatchSimulationResult {
+            results,
+            strategies: vec!["submission".into(), "normalizer".into()],
+        }
+    }
+
+    #[test]
+    fn test_win_rate_ci() {
+        // 60/100: Wilson 95% interval is (0.5020, 0.6906)
+        let (p, low, high) = batch_with_outcomes(60, 40, 0).win_rate_ci(0.5);
+        assert!((p - 0.6).abs() < 1e-12);
+        assert!((low - 0.502_003).abs() < 1e-5, "low={low}");
+        assert!((high - 0.690_599).abs() < 1e-5, "high={high}");
+
+        // Draws count as half-wins by default, or as losses
+        let batch = batch_with_outcomes(50, 30, 20);
+        assert!((batch.win_rate_ci(0.5).0 - 0.6).abs() < 1e-12);
+        assert!((batch.win_rate_ci(0.0).0 - 0.5).abs() < 1e-12);
+
+        // Degenerate cases stay within [0, 1]
+        let (p, low, high) = batch_with_outcomes(10, 0, 0).win_rate_ci(0.5);
+        assert_eq!(p, 1.0);
+        assert!(low > 0.7 && (high - 1.0).abs() < 1e-12);
+        assert_eq!(batch_with_outcomes(0, 0, 0).win_rate_ci(0.5), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_failures_lose_or_drop_out_of_the_counts() {
+        let mut batch = batch_with_outcomes(1, 1, 1);
+        let failure = |failed_strategy: Option<&str>| LightweightSimResult {
+            strategies: batch.strategies.clone(),
+            failed: true,
+            failed_strategy: failed_strategy.map(String::from),
+            ..Default::default()
+        };
+        batch.results.extend([
+            failure(Some("submission")),
+            failure(Some("normalizer")),
+            failure(None),
+        ]);
+
+        assert_eq!(batch.win_counts(), (2, 2, 1));
+        assert_eq!(batch.score(ScoreScheme::Binary)["submission"], 2.5);
+        assert_eq!(batch.results[5].winner(), None);
+        let summary = SimulationSummary::from(&batch.results[3]);
+        assert!(summary.failed && summary.winner == Some(1));
+    }
+
+    #[test]
+    fn test_margin_scores_weigh_narrow_wins_less() {
+        let batch_winning_by = |margin: f64| BatchSimulationResult {
+            results: (0..20)
+                .map(|_| LightweightSimResult {
+                    edges: HashMap::from([("submission".into(), 5.0 + margin), ("normalizer".into(), 5.0)]),
+                    ..Default::default()
+                })
+                .collect(),
+            strategies: vec!["submission".into(), "normalizer".into()],
+        };
+        let narrow = batch_winning_by(0.01);
+        let dominant = batch_winning_by(10.0);
+
+        // Binary scoring can't tell them apart
+        assert_eq!(narrow.score(ScoreScheme::Binary), dominant.score(ScoreScheme::Binary));
+        assert_eq!(narrow.score(ScoreScheme::Binary)["submission"], 20.0);
+        assert_eq!(narrow.score(ScoreScheme::Binary)["normalizer"], 0.0);
+
+        // Margin-weighted scoring gives the narrow winner far fewer points
+        let narrow_points = narrow.score(ScoreScheme::Margin)["submission"];
+        let dominant_points = dominant.score(ScoreScheme::Margin)["submission"];
+        assert!((narrow_points - 0.2).abs() < 1e-9, "{narrow_points}");
+        assert!((dominant_points - 200.0).abs() < 1e-9, "{dominant_points}");
+        assert_eq!(narrow.score(ScoreScheme::Margin)["normalizer"], 0.0);
+
+        // Log scaling keeps the order but compresses the gap
+        let narrow_log = narrow.score(ScoreScheme::LogMargin)["submission"];
+        let dominant_log = dominant.score(ScoreScheme::LogMargin)["submission"];
+        assert!(narrow_log < dominant_log && dominant_log < dominant_points);
+
+        // Draws split a binary point and score nothing by margin
+        let draws = batch_with_outcomes(0, 0, 4);
+        assert_eq!(draws.score(ScoreScheme::Binary)["submission"], 2.0);
+        assert_eq!(draws.score(ScoreScheme::Margin)["normalizer"], 0.0);
+    }
+
+    /// Result with equal edges where A has the higher PnL, B the lower
+    /// average fee and A the lower gas.
+    fn tied_result() -> LightweightSimResult {
+        let gas = |swap_gas: u64| GasBreakdown {
+            after_initialize: 30_000,
+            buy_swap_gas: swap_gas,
+            buy_swaps: 10,
+            ..Default::default()
+        };
+        LightweightSimResult {
+            strategies: vec!["submission".into(), "normalizer".into()],
+            edges: HashMap::from([("submission".into(), 5.0), ("normalizer".into(), 5.0)]),
+            pnl: HashMap::from([("submission".into(), 12.0), ("normalizer".into(), 8.0)]),
+            average_fees: HashMap::from([
+                ("submission".into(), (0.004, 0.004)),
+                ("normalizer".into(), (0.003, 0.002)),
+            ]),
+            gas: HashMap::from([("submission".into(), gas(250_000)), ("normalizer".into(), gas(400_000))]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_winner_by_tie_break() {
+        let result = tied_result();
+        assert_eq!(result.winner(), None);
+        assert_eq!(result.winner_by(TieBreak::Draw), None);
+        assert_eq!(result.winner_by(TieBreak::Pnl).as_deref(), Some("submission"));
+        assert_eq!(result.winner_by(TieBreak::LowerFee).as_deref(), Some("normalizer"));
+        assert_eq!(result.winner_by(TieBreak::LowerGas).as_deref(), Some("submission"));
+
+        // Tie-breaks never override a decided edge
+        let mut decided = tied_result();
+        decided.edges.insert("normalizer".into(), 6.0);
+        for tie_break in [TieBreak::Draw, TieBreak::Pnl, TieBreak::LowerFee, TieBreak::LowerGas] {
+            assert_eq!(decided.winner_by(tie_break).as_deref(), Some("normalizer"));
+        }
+
+        // Equal tie-break values stay a draw
+        let mut level = tied_result();
+        level.pnl.insert("normalizer".into(), 12.0);
+        assert_eq!(level.winner_by(TieBreak::Pnl), None);
+    }
+
+    #[test]
+    fn test_win_counts_by_tie_break() {
+        let mut decided = tied_result();
+        decided.edges.insert("submission".into(), 9.0);
+        let batch = BatchSimulationResult {
+            results: vec![tied_result(), tied_result(), decided],
+            strategies: vec!["submission".into(), "normalizer".into()],
+        };
+        assert_eq!(batch.win_counts(), (1, 0, 2));
+        assert_eq!(batch.win_counts_by(TieBreak::Pnl), (3, 0, 0));
+        assert_eq!(batch.win_counts_by(TieBreak::LowerFee), (1, 2, 0));
+        assert_eq!(batch.win_counts_by(TieBreak::LowerGas), (3, 0, 0));
+    }
+
+    #[test]
+    fn test_to_columns_flattens_every_step() {
+        let names = ["submission", "normalizer"];
+        let (n_sims, n_steps) = (3, 5);
+        let result = |sim: usize| LightweightSimResult {
+            seed: sim as u64,
+            steps: (0..n_steps)
+                .map(|t| {
+                    let per_name = |value: f64| -> HashMap<String, f64> {
+                        names.iter().map(|name| (name.to_string(), value)).collect()
+                    };
+                    LightweightStepResult {
+                        timestamp: t,
+                        fair_price: 100.0 + t as f64,
+                        spot_prices: per_name(99.0),
+                        pnls: per_name(sim as f64),
+                        fees: names.iter().map(|name| (name.to_string(), (0.003, 0.004))).collect(),
+                        reserves: HashMap::new(),
+                    }
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let batch = BatchSimulationResult {
+            results: (0..n_sims).map(result).collect(),
+            strategies: names.iter().map(|name| name.to_string()).collect(),
+        };
+
+        let columns = batch.to_columns();
+        let n_rows = n_sims * n_steps as usize;
+        assert_eq!(columns.sim.len(), n_rows);
+        assert_eq!(columns.timestamp.len(), n_rows);
+        assert_eq!(columns.fair_price.len(), n_rows);
+        for name in names {
+            let series = [&columns.spot_prices, &columns.pnls, &columns.bid_fees, &columns.ask_fees];
+            for column in series {
+                assert_eq!(column[name].len(), n_rows);
+            }
+        }
+        // Rows are in batch order, steps in order within each simulation
+        assert_eq!(columns.sim[n_steps as usize], 1);
+        assert_eq!(columns.timestamp[n_steps as usize + 2], 2);
+        assert_eq!(columns.pnls["normalizer"][n_rows - 1], (n_sims - 1) as f64);
+        assert_eq!(columns.ask_fees["submission"][0], 0.004);
+    }
 }
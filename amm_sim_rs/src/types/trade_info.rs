@@ -1,9 +1,15 @@
 //! TradeInfo struct and ABI encoding for EVM calls.
 
+use serde::{Deserialize, Serialize};
+
 use crate::types::wad::{Wad, MAX_FEE};
 
+/// Length of the calldata for a `(TradeInfo)` hook, trailing sequence
+/// word included.
+pub const TRADE_CALLDATA_LEN: usize = 228;
+
 /// Information about an executed trade, passed to EVM strategies.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct TradeInfo {
     /// true if AMM bought X (trader sold X)
     pub is_buy: bool,
@@ -13,6 +19,9 @@ pub struct TradeInfo {
     pub amount_y: Wad,
     /// Simulation step number
     pub timestamp: u64,
+    /// Trades executed earlier in the run, across all pools (0 for the
+    /// first); orders the trades within a step
+    pub sequence: u64,
     /// Post-trade X reserves (WAD precision)
     pub reserve_x: Wad,
     /// Post-trade Y reserves (WAD precision)
@@ -26,6 +35,7 @@ impl TradeInfo {
         amount_x: Wad,
         amount_y: Wad,
         timestamp: u64,
+        sequence: u64,
         reserve_x: Wad,
         reserve_y: Wad,
     ) -> Self {
@@ -34,25 +44,37 @@ impl TradeInfo {
             amount_x,
             amount_y,
             timestamp,
+            sequence,
             reserve_x,
             reserve_y,
         }
     }
 
     /// Encode as ABI calldata for afterSwap function.
+    #[inline]
+    pub fn encode_calldata(&self, buffer: &mut [u8; TRADE_CALLDATA_LEN]) {
+        self.encode_calldata_for(SELECTOR_AFTER_SWAP, buffer);
+    }
+
+    /// Encode as ABI calldata for a `(TradeInfo)` hook such as afterSwap
+    /// or afterArbitrage.
     ///
-    /// Layout (196 bytes total):
-    /// - bytes 0-3: function selector (0xc2babb57)
+    /// Layout (228 bytes total):
+    /// - bytes 0-3: function selector
     /// - bytes 4-35: isBuy (bool as uint256)
     /// - bytes 36-67: amountX (uint256)
     /// - bytes 68-99: amountY (uint256)
     /// - bytes 100-131: timestamp (uint256)
     /// - bytes 132-163: reserveX (uint256)
     /// - bytes 164-195: reserveY (uint256)
+    /// - bytes 196-227: sequence (uint256)
+    ///
+    /// The sequence word trails the `TradeInfo` struct, so the selector and
+    /// the struct's ABI are unchanged: Solidity decoding ignores it, and a
+    /// strategy that wants it reads `calldataload(196)`.
     #[inline]
-    pub fn encode_calldata(&self, buffer: &mut [u8; 196]) {
-        // Function selector for afterSwap(TradeInfo)
-        buffer[0..4].copy_from_slice(&[0xc2, 0xba, 0xbb, 0x57]);
+    pub fn encode_calldata_for(&self, selector: [u8; 4], buffer: &mut [u8; TRADE_CALLDATA_LEN]) {
+        buffer[0..4].copy_from_slice(&selector);
 
         // isBuy (bool as uint256, value at byte 35)
         buffer[4..36].fill(0);
@@ -74,6 +96,9 @@ impl TradeInfo {
 
         // reserveY
         Self::encode_u256(&mut buffer[164..196], self.reserve_y.raw() as u128);
+
+        // sequence
+        Self::encode_u256(&mut buffer[196..228], self.sequence as u128);
     }
 
     /// Encode a u128 as big-endian 32 bytes.
@@ -91,9 +116,15 @@ pub const SELECTOR_AFTER_INITIALIZE: [u8; 4] = [0x83, 0x7a, 0xef, 0x47];
 /// Function selector for afterSwap(TradeInfo)
 pub const SELECTOR_AFTER_SWAP: [u8; 4] = [0xc2, 0xba, 0xbb, 0x57];
 
+/// Function selector for afterArbitrage(TradeInfo)
+pub const SELECTOR_AFTER_ARBITRAGE: [u8; 4] = [0x8f, 0xd6, 0xc8, 0x99];
+
 /// Function selector for getName()
 pub const SELECTOR_GET_NAME: [u8; 4] = [0x17, 0xd7, 0xde, 0x7c];
 
+/// Function selector for setOracle(uint256)
+pub const SELECTOR_SET_ORACLE: [u8; 4] = [0xd4, 0xfd, 0x7d, 0x22];
+
 /// Encode afterInitialize(uint256, uint256) calldata.
 #[inline]
 pub fn encode_after_initialize(initial_x: Wad, initial_y: Wad) -> [u8; 68] {
@@ -111,9 +142,41 @@ pub fn encode_after_initialize(initial_x: Wad, initial_y: Wad) -> [u8; 68] {
     buffer
 }
 
+/// Encode setOracle(uint256) calldata.
+#[inline]
+pub fn encode_set_oracle(price: Wad) -> [u8; 36] {
+    let mut buffer = [0u8; 36];
+    buffer[0..4].copy_from_slice(&SELECTOR_SET_ORACLE);
+    buffer[20..36].copy_from_slice(&(price.raw() as u128).to_be_bytes());
+    buffer
+}
+
+/// What a strategy hook returned: its fees and an optional signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StrategyOutput {
+    /// Bid fee in WAD
+    pub bid_fee: Wad,
+    /// Ask fee in WAD
+    pub ask_fee: Wad,
+    /// Third return word as a signed WAD value (e.g. a target inventory
+    /// skew); None for 2-word returns or a word outside the i128 range
+    pub signal: Option<Wad>,
+}
+
 /// Decode (uint256, uint256) return value as (bid_fee, ask_fee) in WAD.
 #[inline]
 pub fn decode_fee_pair(data: &[u8]) -> Option<(Wad, Wad)> {
+    decode_strategy_output(data).map(|output| (output.bid_fee, output.ask_fee))
+}
+
+/// Decode a `(uint256 bidFee, uint256 askFee)` or
+/// `(uint256 bidFee, uint256 askFee, int256 signal)` return value.
+///
+/// Fees follow `decode_fee_pair`; a signal that does not fit in i128 is
+/// dropped rather than failing the fees, and anything past the third word
+/// is ignored.
+#[inline]
+pub fn decode_strategy_output(data: &[u8]) -> Option<StrategyOutput> {
     if data.len() < 64 {
         return None;
     }
@@ -128,7 +191,27 @@ pub fn decode_fee_pair(data: &[u8]) -> Option<(Wad, Wad)> {
 
     let bid_i128 = i128::try_from(bid_fee).ok()?;
     let ask_i128 = i128::try_from(ask_fee).ok()?;
-    Some((Wad::new(bid_i128), Wad::new(ask_i128)))
+    let signal = data.get(64..96).and_then(decode_i256).map(Wad::new);
+    Some(StrategyOutput {
+        bid_fee: Wad::new(bid_i128),
+        ask_fee: Wad::new(ask_i128),
+        signal,
+    })
+}
+
+/// Decode big-endian 32 bytes as a two's-complement int256 (None if it
+/// does not fit in i128).
+#[inline]
+fn decode_i256(data: &[u8]) -> Option<i128> {
+    if data.len() != 32 {
+        return None;
+    }
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&data[16..32]);
+    let value = i128::from_be_bytes(bytes);
+    // Upper 16 bytes must sign-extend the lower ones
+    let extension = if value < 0 { 0xff } else { 0x00 };
+    data[0..16].iter().all(|&b| b == extension).then_some(value)
 }
 
 /// Decode big-endian 32 bytes as u128 (upper 16 bytes must be zero).
@@ -146,6 +229,56 @@ fn decode_u256(data: &[u8]) -> Option<u128> {
     Some(u128::from_be_bytes(bytes))
 }
 
+/// Decode big-endian 32 bytes as usize (None if it does not fit).
+#[inline]
+fn decode_usize(data: &[u8]) -> Option<usize> {
+    if data.len() != 32 {
+        return None;
+    }
+    // Check upper 24 bytes are zero
+    if data[0..24].iter().any(|&b| b != 0) {
+        return None;
+    }
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[24..32]);
+    usize::try_from(u64::from_be_bytes(bytes)).ok()
+}
+
+/// Longest strategy name kept by `decode_name`, in characters.
+pub const MAX_NAME_CHARS: usize = 64;
+
+/// Decode a `string` return value: an offset word, then at that offset a
+/// length word followed by the UTF-8 bytes.
+///
+/// Strategy output is untrusted, so offsets and lengths pointing outside
+/// `data` (however large) and invalid UTF-8 give None.
+pub fn decode_string(data: &[u8]) -> Option<String> {
+    String::from_utf8(decode_bytes(data)?.to_vec()).ok()
+}
+
+/// Decode a `getName()` return value for display.
+///
+/// Unlike `decode_string`, invalid UTF-8 is replaced rather than rejected;
+/// control characters are dropped, surrounding whitespace trimmed and the
+/// result cut to `MAX_NAME_CHARS`. Gives None when the data is malformed
+/// or nothing printable is left.
+pub fn decode_name(data: &[u8]) -> Option<String> {
+    let raw = String::from_utf8_lossy(decode_bytes(data)?);
+    let printable: String = raw.chars().filter(|c| !c.is_control()).collect();
+    let name: String = printable.trim().chars().take(MAX_NAME_CHARS).collect();
+    let name = name.trim_end();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Bytes of an ABI-encoded `string`/`bytes` value, bounds-checked against
+/// `data`.
+fn decode_bytes(data: &[u8]) -> Option<&[u8]> {
+    let offset = decode_usize(data.get(0..32)?)?;
+    let start = offset.checked_add(32)?;
+    let length = decode_usize(data.get(offset..start)?)?;
+    data.get(start..start.checked_add(length)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -158,11 +291,12 @@ mod tests {
             amount_x: Wad::new(WAD), // 1.0
             amount_y: Wad::new(WAD * 2), // 2.0
             timestamp: 100,
+            sequence: 7,
             reserve_x: Wad::new(WAD * 1000),
             reserve_y: Wad::new(WAD * 1000),
         };
 
-        let mut buffer = [0u8; 196];
+        let mut buffer = [0u8; TRADE_CALLDATA_LEN];
         trade.encode_calldata(&mut buffer);
 
         // Check selector
@@ -174,6 +308,8 @@ mod tests {
         // Decode and verify
         let decoded_x = decode_u256(&buffer[36..68]).unwrap();
         assert_eq!(decoded_x as i128, WAD);
+        assert_eq!(decode_u256(&buffer[100..132]), Some(100));
+        assert_eq!(decode_u256(&buffer[196..228]), Some(7));
     }
 
     #[test]
@@ -201,4 +337,114 @@ mod tests {
 
         assert!(decode_fee_pair(&data).is_none());
     }
+
+    #[test]
+    fn test_decode_strategy_output_with_and_without_signal() {
+        let fee = |bps: u64| word(bps * 100_000_000_000_000);
+
+        let pair = [fee(30), fee(50)].concat();
+        let output = decode_strategy_output(&pair).unwrap();
+        assert_eq!((output.bid_fee, output.ask_fee), (Wad::from_bps(30), Wad::from_bps(50)));
+        assert_eq!(output.signal, None);
+        assert_eq!(decode_fee_pair(&pair), Some((Wad::from_bps(30), Wad::from_bps(50))));
+
+        let triple = [fee(30), fee(50), word(WAD as u64 / 4)].concat();
+        let output = decode_strategy_output(&triple).unwrap();
+        assert_eq!((output.bid_fee, output.ask_fee), (Wad::from_bps(30), Wad::from_bps(50)));
+        assert_eq!(output.signal, Some(Wad::new(WAD / 4)));
+        assert_eq!(decode_fee_pair(&triple), decode_fee_pair(&pair));
+
+        // int256 -1 is all ones; a word overflowing i128 drops the signal
+        let negative = [fee(30), fee(50), [0xff; 32]].concat();
+        assert_eq!(decode_strategy_output(&negative).unwrap().signal, Some(Wad::new(-1)));
+        let mut huge = [0u8; 32];
+        huge[0] = 1;
+        let overflow = [fee(30), fee(50), huge].concat();
+        let output = decode_strategy_output(&overflow).unwrap();
+        assert_eq!((output.bid_fee, output.signal), (Wad::from_bps(30), None));
+    }
+
+    /// Encode `value` as a 32-byte big-endian ABI word.
+    fn word(value: u64) -> [u8; 32] {
+        let mut word = [0u8; 32];
+        word[24..32].copy_from_slice(&value.to_be_bytes());
+        word
+    }
+
+    #[test]
+    fn test_decode_string() {
+        let mut data = [word(32), word(6)].concat();
+        data.extend_from_slice(b"Golden");
+        data.resize(96, 0);
+        assert_eq!(decode_string(&data).as_deref(), Some("Golden"));
+
+        // Offsets and lengths past the end, however large, are rejected
+        let out_of_range = [(u64::MAX, 6), (u64::MAX - 31, 6), (32, u64::MAX), (32, 65), (96, 0)];
+        for (offset, length) in out_of_range {
+            let data = [word(offset), word(length), [b'a'; 32]].concat();
+            assert_eq!(decode_string(&data), None, "offset {offset}, length {length}");
+        }
+        let invalid_utf8 = [word(32), word(2), [0xff; 32]].concat();
+        assert_eq!(decode_string(&invalid_utf8), None);
+    }
+
+    #[test]
+    fn test_decode_name_is_lenient_but_bounded() {
+        let encode = |bytes: &[u8]| {
+            let mut data = [word(32), word(bytes.len() as u64)].concat();
+            data.extend_from_slice(bytes);
+            data
+        };
+        assert_eq!(decode_name(&encode(b"  Golden\n")).as_deref(), Some("Golden"));
+        assert_eq!(decode_name(&encode(b"Gold\xffen")).as_deref(), Some("Gold\u{fffd}en"));
+        assert_eq!(decode_name(&encode(b"\t\x00 ")), None);
+
+        let long = decode_name(&encode(&[b'a'; 10_000])).unwrap();
+        assert_eq!(long.chars().count(), MAX_NAME_CHARS);
+        let multibyte = decode_name(&encode("é".repeat(100).as_bytes())).unwrap();
+        assert_eq!(multibyte, "é".repeat(MAX_NAME_CHARS));
+
+        let huge_length = [word(32), word(u64::MAX), [b'a'; 32]].concat();
+        assert_eq!(decode_name(&huge_length), None);
+    }
+
+    #[test]
+    fn test_decoders_never_panic_on_random_data() {
+        use rand::{Rng, SeedableRng};
+        use rand_pcg::Pcg64;
+
+        let mut rng = Pcg64::seed_from_u64(858);
+        for _ in 0..20_000 {
+            // Words are random bytes, small values, or values near the top
+            // of the u64 range, so the decoders see plausible offsets and
+            // lengths as well as noise
+            let n_words = rng.gen_range(0..6);
+            let mut data = Vec::with_capacity(n_words * 32);
+            for _ in 0..n_words {
+                match rng.gen_range(0..3) {
+                    0 => data.extend((0..32).map(|_| rng.gen::<u8>())),
+                    1 => data.extend(word(rng.gen_range(0..256))),
+                    _ => data.extend(word(u64::MAX - rng.gen_range(0..64))),
+                }
+            }
+            data.truncate(data.len().saturating_sub(rng.gen_range(0..33)));
+
+            if let Some((bid, ask)) = decode_fee_pair(&data) {
+                assert!(bid.raw() <= MAX_FEE && ask.raw() <= MAX_FEE);
+            }
+            for start in 0..data.len().min(40) {
+                let end = (start + 32).min(data.len());
+                if let Some(value) = decode_u256(&data[start..end]) {
+                    assert_eq!(end - start, 32);
+                    assert_eq!(value.to_be_bytes(), data[start + 16..end]);
+                }
+            }
+            if let Some(name) = decode_string(&data) {
+                assert!(name.len() + 32 <= data.len());
+            }
+            if let Some(name) = decode_name(&data) {
+                assert!(name.chars().count() <= MAX_NAME_CHARS);
+            }
+        }
+    }
 }
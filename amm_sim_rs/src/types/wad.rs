@@ -7,6 +7,8 @@
 
 use std::ops::{Add, Sub, Mul, Div, Neg};
 
+use serde::{Deserialize, Serialize};
+
 /// WAD precision constant (1e18)
 pub const WAD: i128 = 1_000_000_000_000_000_000;
 
@@ -17,7 +19,7 @@ pub const BPS: i128 = 100_000_000_000_000;
 pub const MAX_FEE: i128 = 100_000_000_000_000_000;
 
 /// WAD fixed-point number with 18 decimal places.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
 pub struct Wad(pub i128);
 
 impl Wad {
@@ -52,9 +54,12 @@ impl Wad {
     }
 
     /// WAD multiplication: (a * b) / WAD
+    ///
+    /// The intermediate product is 256-bit, so this does not overflow
+    /// unless the result itself does.
     #[inline]
     pub fn wmul(self, other: Wad) -> Wad {
-        Wad((self.0 * other.0) / WAD)
+        Wad(mul_div(self.0, other.0, WAD))
     }
 
     /// WAD division: (a * WAD) / b
@@ -63,13 +68,36 @@ impl Wad {
         if other.0 == 0 {
             return Wad(0);
         }
-        Wad((self.0 * WAD) / other.0)
+        Wad(mul_div(self.0, WAD, other.0))
+    }
+
+    /// Raw (a * b) / c with a 256-bit intermediate, truncating toward zero.
+    ///
+    /// Returns zero if `c` is zero; saturates if the result overflows.
+    #[inline]
+    pub fn mul_div(self, b: Wad, c: Wad) -> Wad {
+        Wad(mul_div(self.0, b.0, c.0))
+    }
+
+    /// `mul_div`, but a positive inexact result rounds up instead.
+    #[inline]
+    pub fn mul_div_up(self, b: Wad, c: Wad) -> Wad {
+        Wad(mul_div_up(self.0, b.0, c.0))
+    }
+
+    /// `wdiv`, but a positive inexact result rounds up instead.
+    #[inline]
+    pub fn wdiv_up(self, other: Wad) -> Wad {
+        if other.0 == 0 {
+            return Wad(0);
+        }
+        Wad(mul_div_up(self.0, WAD, other.0))
     }
 
     /// Clamp fee to valid range [0, MAX_FEE].
     #[inline]
     pub fn clamp_fee(self) -> Wad {
-        Wad(self.0.max(0).min(MAX_FEE))
+        Wad(self.0.clamp(0, MAX_FEE))
     }
 
     /// Clamp to arbitrary range.
@@ -144,6 +172,78 @@ impl Wad {
     }
 }
 
+/// (a * b) / c on i128 with a 256-bit intermediate.
+///
+/// Truncates toward zero like i128 division; returns 0 for c == 0 and
+/// saturates to i128::MIN/MAX on overflow.
+fn mul_div(a: i128, b: i128, c: i128) -> i128 {
+    if c == 0 {
+        return 0;
+    }
+    let negative = (a < 0) ^ (b < 0) ^ (c < 0);
+    let (hi, lo) = widening_mul(a.unsigned_abs(), b.unsigned_abs());
+    let divisor = c.unsigned_abs();
+
+    let magnitude = if hi == 0 {
+        Some(lo / divisor)
+    } else if hi >= divisor {
+        None
+    } else {
+        // Binary long division of (hi, lo) by the divisor. The quotient fits
+        // in 128 bits because hi < divisor, and the remainder stays below
+        // divisor <= 2^127, so shifting it never overflows.
+        let mut remainder = hi;
+        let mut quotient: u128 = 0;
+        for i in (0..128).rev() {
+            remainder = (remainder << 1) | ((lo >> i) & 1);
+            quotient <<= 1;
+            if remainder >= divisor {
+                remainder -= divisor;
+                quotient |= 1;
+            }
+        }
+        Some(quotient)
+    };
+
+    match magnitude {
+        Some(m) if negative && m <= i128::MIN.unsigned_abs() => (m as i128).wrapping_neg(),
+        Some(m) if !negative && m <= i128::MAX as u128 => m as i128,
+        _ if negative => i128::MIN,
+        _ => i128::MAX,
+    }
+}
+
+/// `mul_div` rounded up when the result is positive and inexact.
+fn mul_div_up(a: i128, b: i128, c: i128) -> i128 {
+    let quotient = mul_div(a, b, c);
+    if quotient <= 0 || quotient == i128::MAX {
+        return quotient;
+    }
+    let product = widening_mul(a.unsigned_abs(), b.unsigned_abs());
+    if widening_mul(quotient as u128, c.unsigned_abs()) == product {
+        quotient
+    } else {
+        quotient + 1
+    }
+}
+
+/// Full 256-bit product of two u128 values as (high, low).
+fn widening_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+
+    let middle = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+    let lo = (lo_lo & MASK) | (middle << 64);
+    let hi = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (middle >> 64);
+    (hi, lo)
+}
+
 impl Add for Wad {
     type Output = Self;
     #[inline]
@@ -213,7 +313,7 @@ mod tests {
         assert_eq!(w.0, WAD / 2);
 
         let w = Wad::from_f64(0.0025); // 25 bps
-        assert_eq!(w.0, 25 * BPS / 10);
+        assert_eq!(w.0, 25 * BPS);
     }
 
     #[test]
@@ -241,6 +341,39 @@ mod tests {
         assert!((c.to_f64() - 3.0).abs() < 1e-10);
     }
 
+    #[test]
+    fn test_wmul_wdiv_large_values() {
+        // Raw product ~1e42 would overflow a plain i128 multiply
+        let reserve_y = Wad::from_f64(10_000.0);
+        let reserve_x = Wad::from_f64(100.0);
+        assert_eq!(reserve_y.wmul(reserve_x), Wad(1_000_000 * WAD));
+        assert_eq!(Wad(1_000_000 * WAD).wdiv(reserve_x), reserve_y);
+
+        // Exact against a small-value reference, including signs
+        for (a, b, c) in [(7, 3, 2), (-7, 3, 2), (7, -3, -2), (0, 5, 3), (5, 5, 0)] {
+            let expected = if c == 0 { 0 } else { a * b / c };
+            assert_eq!(Wad(a).mul_div(Wad(b), Wad(c)), Wad(expected));
+        }
+
+        // 256-bit intermediate: (2^120 * 2^100) / 2^110 = 2^110
+        let result = Wad(1 << 120).mul_div(Wad(1 << 100), Wad(1 << 110));
+        assert_eq!(result, Wad(1 << 110));
+
+        // Results that do not fit saturate
+        assert_eq!(Wad(i128::MAX).wmul(Wad(2 * WAD)), Wad(i128::MAX));
+        assert_eq!(Wad(i128::MIN).wmul(Wad(2 * WAD)), Wad(i128::MIN));
+    }
+
+    #[test]
+    fn test_mul_div_up_rounds_only_inexact_results() {
+        assert_eq!(Wad(10).mul_div(Wad(1), Wad(3)), Wad(3));
+        assert_eq!(Wad(10).mul_div_up(Wad(1), Wad(3)), Wad(4));
+        assert_eq!(Wad(9).mul_div_up(Wad(1), Wad(3)), Wad(3));
+        assert_eq!(Wad(i128::MAX / 2).mul_div_up(Wad(4), Wad(2)), Wad(i128::MAX - 1));
+        assert_eq!(Wad::one().wdiv_up(Wad::from_f64(3.0)), Wad(333_333_333_333_333_334));
+        assert_eq!(Wad(0).wdiv_up(Wad(7)), Wad(0));
+    }
+
     #[test]
     fn test_sqrt() {
         let a = Wad::from_f64(4.0);
@@ -249,7 +382,7 @@ mod tests {
 
         let a = Wad::from_f64(2.0);
         let s = a.sqrt();
-        assert!((s.to_f64() - 1.414213562).abs() < 1e-6);
+        assert!((s.to_f64() - std::f64::consts::SQRT_2).abs() < 1e-6);
     }
 
     #[test]
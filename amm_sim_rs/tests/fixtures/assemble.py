@@ -0,0 +1,78 @@
+"""Assemble the fixed-fee golden-path strategy fixtures.
+
+Usage: python assemble.py   (rewrites the *.hex files in this directory)
+
+Each fixture is creation code that deploys the runtime below. The runtime
+implements the strategy ABI with constant fees:
+
+    getName()                         -> "Golden"
+    afterInitialize(uint256,uint256)  -> (fee, fee)
+    afterSwap(TradeInfo)              -> (fee, fee)
+    anything else                     -> revert
+
+To add a fixture, append to FIXTURES and re-run. Changing an existing
+fixture changes the golden values in tests/golden_path.rs.
+"""
+
+from pathlib import Path
+
+WAD_BPS = 10**14
+
+SELECTOR_GET_NAME = 0x17D7DE7C
+SELECTOR_AFTER_INITIALIZE = 0x837AEF47
+SELECTOR_AFTER_SWAP = 0xC2BABB57
+
+FIXTURES = {
+    "fixed_fee_30bps.hex": 30,
+    "fixed_fee_80bps.hex": 80,
+}
+
+
+def push(value: int) -> bytes:
+    width = max(1, (value.bit_length() + 7) // 8)
+    return bytes([0x5F + width]) + value.to_bytes(width, "big")
+
+
+def runtime(fee_bps: int, name: bytes = b"Golden") -> bytes:
+    fee = fee_bps * WAD_BPS
+
+    # Selector dispatch; jump targets are patched below.
+    head = (
+        push(0) + b"\x35" + push(0xE0) + b"\x1c"        # selector = calldata[0:4]
+        + b"\x80" + push(SELECTOR_GET_NAME) + b"\x14" + b"\x61NN\x57"
+        + b"\x80" + push(SELECTOR_AFTER_INITIALIZE) + b"\x14" + b"\x61FF\x57"
+        + b"\x80" + push(SELECTOR_AFTER_SWAP) + b"\x14" + b"\x61FF\x57"
+        + push(0) + push(0) + b"\xfd"                   # revert
+    )
+    fees = (
+        b"\x5b"
+        + push(fee) + push(0) + b"\x52"                 # mem[0x00] = bid
+        + push(fee) + push(0x20) + b"\x52"              # mem[0x20] = ask
+        + push(0x40) + push(0) + b"\xf3"                # return mem[0:0x40]
+    )
+    name_word = int.from_bytes(name.ljust(32, b"\0"), "big")
+    get_name = (
+        b"\x5b"
+        + push(0x20) + push(0) + b"\x52"                # offset
+        + push(len(name)) + push(0x20) + b"\x52"        # length
+        + b"\x7f" + name_word.to_bytes(32, "big") + push(0x40) + b"\x52"
+        + push(0x60) + push(0) + b"\xf3"
+    )
+
+    fees_at = len(head)
+    name_at = len(head) + len(fees)
+    head = head.replace(b"NN", name_at.to_bytes(2, "big"))
+    head = head.replace(b"FF", fees_at.to_bytes(2, "big"))
+    return head + fees + get_name
+
+
+def creation(code: bytes) -> bytes:
+    # PUSH2 len, DUP1, PUSH1 12, PUSH1 0, CODECOPY, PUSH1 0, RETURN
+    n = len(code).to_bytes(2, "big")
+    return b"\x61" + n + b"\x80\x60\x0c\x60\x00\x39\x60\x00\xf3" + code
+
+
+if __name__ == "__main__":
+    here = Path(__file__).parent
+    for filename, fee_bps in FIXTURES.items():
+        (here / filename).write_text(creation(runtime(fee_bps)).hex() + "\n")
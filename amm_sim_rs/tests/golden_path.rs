@@ -0,0 +1,95 @@
+//! End-to-end "golden path" simulation with embedded strategy bytecode.
+//!
+//! Runs the engine, CFMM, arbitrageur, and router in pure Rust against the
+//! fixed-fee fixtures in `tests/fixtures/` (see `assemble.py` there) and
+//! pins the resulting PnL and edge values. A change to any of these
+//! numbers means simulation behavior changed.
+
+use amm_sim_rs::evm::EVMStrategy;
+use amm_sim_rs::simulation::engine::SimulationEngine;
+use amm_sim_rs::types::config::SimulationConfig;
+use amm_sim_rs::types::result::LightweightSimResult;
+
+const FIXED_FEE_30BPS: &str = include_str!("fixtures/fixed_fee_30bps.hex");
+const FIXED_FEE_80BPS: &str = include_str!("fixtures/fixed_fee_80bps.hex");
+
+fn decode_hex(hex: &str) -> Vec<u8> {
+    let hex = hex.trim();
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn strategy(hex: &str) -> EVMStrategy {
+    EVMStrategy::new(decode_hex(hex), "Default".to_string()).unwrap()
+}
+
+fn golden_config() -> SimulationConfig {
+    SimulationConfig {
+        n_steps: 500,
+        initial_price: 100.0,
+        initial_x: 100.0,
+        initial_y: 10_000.0,
+        gbm_mu: 0.0,
+        gbm_sigma: 0.001,
+        gbm_dt: 1.0,
+        retail_arrival_rate: 0.8,
+        retail_mean_size: 20.0,
+        retail_size_sigma: 1.2,
+        retail_buy_prob: 0.5,
+        seed: Some(42),
+        ..SimulationConfig::default()
+    }
+}
+
+fn run_golden() -> LightweightSimResult {
+    let mut engine = SimulationEngine::new(golden_config());
+    engine
+        .run(strategy(FIXED_FEE_30BPS), strategy(FIXED_FEE_80BPS))
+        .unwrap()
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    let tolerance = 1e-9 * expected.abs().max(1e-3);
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "expected {expected}, got {actual}"
+    );
+}
+
+#[test]
+fn fixture_reports_name() {
+    assert_eq!(strategy(FIXED_FEE_30BPS).name(), "Golden");
+}
+
+#[test]
+fn golden_path_is_deterministic() {
+    let a = run_golden();
+    let b = run_golden();
+    for name in ["submission", "normalizer"] {
+        assert_eq!(a.pnl[name], b.pnl[name]);
+        assert_eq!(a.edges[name], b.edges[name]);
+    }
+}
+
+#[test]
+fn golden_path_values() {
+    let result = run_golden();
+
+    assert_eq!(result.steps.len(), 500);
+    for (name, fee) in [("submission", 0.003), ("normalizer", 0.008)] {
+        let (bid, ask) = result.average_fees[name];
+        assert_close(bid, fee);
+        assert_close(ask, fee);
+    }
+
+    assert_close(result.pnl["submission"], -95.37196042606593);
+    assert_close(result.pnl["normalizer"], -100.03469376586509);
+    assert_close(result.edges["submission"], 28.34505110748725);
+    assert_close(result.edges["normalizer"], 23.41086726732356);
+    assert_close(result.arb_volume_y["submission"], 3693.0876573514356);
+    assert_close(result.arb_volume_y["normalizer"], 926.4480704266085);
+    assert_close(result.retail_volume_y["submission"], 6347.132299761833);
+    assert_close(result.retail_volume_y["normalizer"], 2246.9490271967074);
+}
@@ -0,0 +1,186 @@
+//! The pure-Rust entry points, usable without the `python` feature.
+//!
+//! Runs with `cargo test --no-default-features` as well as the default
+//! build.
+
+use amm_sim_rs::types::config::SimulationConfig;
+use amm_sim_rs::types::result::BatchSimulationResult;
+use amm_sim_rs::{
+    inspect_strategy, replay_rs, run_batch_rs, run_batch_summary_rs, run_seed_sweep_rs,
+    run_single_rs,
+};
+
+const FIXED_FEE_30BPS: &str = include_str!("fixtures/fixed_fee_30bps.hex");
+const FIXED_FEE_80BPS: &str = include_str!("fixtures/fixed_fee_80bps.hex");
+
+fn decode_hex(hex: &str) -> Vec<u8> {
+    let hex = hex.trim();
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn config(seed: u64) -> SimulationConfig {
+    SimulationConfig {
+        n_steps: 200,
+        seed: Some(seed),
+        ..SimulationConfig::default()
+    }
+}
+
+#[test]
+fn run_batch_rs_matches_single_runs() {
+    let configs: Vec<SimulationConfig> = (0..4).map(config).collect();
+    let batch = run_batch_rs(
+        decode_hex(FIXED_FEE_30BPS),
+        decode_hex(FIXED_FEE_80BPS),
+        configs.clone(),
+        2,
+    )
+    .unwrap();
+
+    assert_eq!(batch.results.len(), 4);
+    assert_eq!(batch.strategies, vec!["submission", "normalizer"]);
+    let (wins_a, wins_b, draws) = batch.win_counts();
+    assert_eq!(wins_a + wins_b + draws, 4);
+
+    for (result, config) in batch.results.iter().zip(configs) {
+        let single = run_single_rs(decode_hex(FIXED_FEE_30BPS), decode_hex(FIXED_FEE_80BPS), config)
+            .unwrap();
+        assert_eq!(result.seed, single.seed);
+        assert_eq!(result.pnl, single.pnl);
+        assert_eq!(result.edges, single.edges);
+    }
+}
+
+#[test]
+fn run_seed_sweep_rs_runs_one_sim_per_seed() {
+    let seeds = [7, 3, 11];
+    let sweep = run_seed_sweep_rs(
+        decode_hex(FIXED_FEE_30BPS),
+        decode_hex(FIXED_FEE_80BPS),
+        &config(0),
+        &seeds,
+        2,
+    )
+    .unwrap();
+
+    assert_eq!(sweep.results.len(), seeds.len());
+    for (result, &seed) in sweep.results.iter().zip(&seeds) {
+        assert_eq!(result.seed, seed);
+        assert_eq!(result.steps.len(), 200);
+    }
+    let single = run_single_rs(decode_hex(FIXED_FEE_30BPS), decode_hex(FIXED_FEE_80BPS), config(3)).unwrap();
+    assert_eq!(sweep.results[1].edges, single.edges);
+}
+
+#[test]
+fn replay_rs_reproduces_a_batch_result_verbosely() {
+    let configs: Vec<SimulationConfig> = (0..3).map(config).collect();
+    let batch = run_batch_rs(decode_hex(FIXED_FEE_30BPS), decode_hex(FIXED_FEE_80BPS), configs, 2)
+        .unwrap();
+    let original = &batch.results[1];
+    assert!(original.volume_series.is_empty());
+
+    let replayed =
+        replay_rs(original, decode_hex(FIXED_FEE_30BPS), decode_hex(FIXED_FEE_80BPS), true)
+            .unwrap();
+    assert_eq!(replayed.seed, original.seed);
+    assert_eq!(replayed.pnl, original.pnl);
+    assert_eq!(replayed.edges, original.edges);
+    assert!(replayed.config.verbose);
+    assert_eq!(replayed.volume_series["submission"].len(), 200);
+    for (step, original_step) in replayed.steps.iter().zip(&original.steps) {
+        assert!(original_step.reserves.is_empty());
+        assert_eq!(step.reserves.len(), 2);
+        assert_eq!(step.fees, original_step.fees);
+    }
+}
+
+#[test]
+fn run_batch_summary_rs_matches_full_results() {
+    let configs: Vec<SimulationConfig> = (0..4).map(config).collect();
+    let full = run_batch_rs(
+        decode_hex(FIXED_FEE_30BPS),
+        decode_hex(FIXED_FEE_80BPS),
+        configs.clone(),
+        2,
+    )
+    .unwrap();
+    let summaries =
+        run_batch_summary_rs(decode_hex(FIXED_FEE_30BPS), decode_hex(FIXED_FEE_80BPS), configs, 2)
+            .unwrap();
+
+    assert_eq!(summaries.len(), full.results.len());
+    for (summary, result) in summaries.iter().zip(&full.results) {
+        assert_eq!(summary.seed, result.seed);
+        assert_eq!(summary.pnl_submission, result.pnl["submission"]);
+        assert_eq!(summary.pnl_baseline, result.pnl["normalizer"]);
+        assert_eq!(summary.edge_submission, result.edges["submission"]);
+        assert_eq!(summary.edge_baseline, result.edges["normalizer"]);
+        let winner = summary.winner.map(|index| full.strategies[index].clone());
+        assert_eq!(winner, result.winner());
+    }
+
+    let unrecorded = SimulationConfig { record_steps: false, ..config(2) };
+    let result =
+        run_single_rs(decode_hex(FIXED_FEE_30BPS), decode_hex(FIXED_FEE_80BPS), unrecorded)
+            .unwrap();
+    assert!(result.steps.is_empty());
+    assert_eq!(result.pnl, full.results[2].pnl);
+    assert_eq!(result.average_fees, full.results[2].average_fees);
+}
+
+/// A small batch with wall-clock timings zeroed, so reruns compare equal.
+fn timeless_batch() -> BatchSimulationResult {
+    let configs = vec![config(1), SimulationConfig { verbose: true, ..config(2) }];
+    let mut batch =
+        run_batch_rs(decode_hex(FIXED_FEE_30BPS), decode_hex(FIXED_FEE_80BPS), configs, 2).unwrap();
+    for result in &mut batch.results {
+        result.elapsed_micros = 0;
+    }
+    batch
+}
+
+#[test]
+fn batch_result_bytes_round_trip() {
+    let batch = timeless_batch();
+    let bytes = batch.to_bytes();
+    assert_eq!(BatchSimulationResult::from_bytes(&bytes).unwrap(), batch);
+
+    // Other format versions and truncated input are refused
+    let mut future = bytes.clone();
+    future[0] += 1;
+    assert!(BatchSimulationResult::from_bytes(&future).unwrap_err().contains("version"));
+    assert!(BatchSimulationResult::from_bytes(&bytes[..bytes.len() / 2]).is_err());
+    assert!(BatchSimulationResult::from_bytes(&bytes[..2]).is_err());
+}
+
+#[test]
+fn identical_batches_encode_to_identical_bytes() {
+    // Each run builds its maps with fresh hash seeds, so only key-ordered
+    // encoding makes the bytes agree
+    let (first, second) = (timeless_batch(), timeless_batch());
+    assert_eq!(first, second);
+    assert_eq!(first.to_bytes(), second.to_bytes());
+}
+
+#[test]
+fn run_batch_rs_reports_invalid_bytecode() {
+    let err = run_batch_rs(vec![0xfe], decode_hex(FIXED_FEE_80BPS), vec![config(1)], 1);
+    assert!(err.is_err());
+}
+
+#[test]
+fn inspect_strategy_reports_initial_fees() {
+    let (name, bid_fee, ask_fee) = inspect_strategy(decode_hex(FIXED_FEE_30BPS), 100.0, 10_000.0).unwrap();
+    assert_eq!(name, "Golden");
+    assert!((bid_fee - 0.003).abs() < 1e-15, "{bid_fee}");
+    assert!((ask_fee - 0.003).abs() < 1e-15, "{ask_fee}");
+
+    let (_, bid_fee, ask_fee) = inspect_strategy(decode_hex(FIXED_FEE_80BPS), 100.0, 10_000.0).unwrap();
+    assert!((bid_fee - 0.008).abs() < 1e-15 && (ask_fee - 0.008).abs() < 1e-15);
+
+    assert!(inspect_strategy(vec![0xfe], 100.0, 10_000.0).is_err());
+}